@@ -0,0 +1,50 @@
+#import indirect_instancing::instance_struct
+
+struct UniformData {
+    @size(16)
+    columns: u32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> in_uniform: UniformData;
+
+@group(1)
+@binding(0)
+var<storage, read_write> out_instances: Instances;
+
+@group(1)
+@binding(1)
+var<uniform> instance_count: u32;
+
+@compute
+@workgroup_size(64)
+fn instances(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    // Destructure invocation index
+    let instance_idx = invocation_id.x;
+
+    // Early-out if we're out of bounds
+    if (instance_idx >= instance_count) {
+        return;
+    }
+
+    // Lay instances out in unit cells, row by row from the top left of a square grid
+    let half_extent = f32(in_uniform.columns) * 0.5 - 0.5;
+    let x = f32(instance_idx % in_uniform.columns) - half_extent;
+    let y = half_extent - f32(instance_idx / in_uniform.columns);
+
+    let transform = mat4x4<f32>(
+        vec4<f32>(1.0, 0.0, 0.0, 0.0),
+        vec4<f32>(0.0, 1.0, 0.0, 0.0),
+        vec4<f32>(0.0, 0.0, 1.0, 0.0),
+        vec4<f32>(x, y, 0.0, 1.0),
+    );
+
+    out_instances.instances[instance_idx].transform = transform;
+    out_instances.instances[instance_idx].inverse_transpose_model = mat4x4<f32>(
+        vec4<f32>(1.0, 0.0, 0.0, 0.0),
+        vec4<f32>(0.0, 1.0, 0.0, 0.0),
+        vec4<f32>(0.0, 0.0, 1.0, 0.0),
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
+    );
+}
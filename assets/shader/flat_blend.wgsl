@@ -0,0 +1,29 @@
+#import bevy_pbr::mesh_view_bindings
+#import indirect_instancing::instance_struct
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+@group(2)
+@binding(0)
+var<uniform> instances: Instances;
+#else
+@group(2)
+@binding(0)
+var<storage> instances: Instances;
+#endif
+
+struct Vertex {
+    @builtin(instance_index) instance: u32,
+    @location(0) vertex: vec3<f32>,
+};
+
+@vertex
+fn vertex(in: Vertex) -> @builtin(position) vec4<f32> {
+    let instance = instances.instances[in.instance];
+    return view.view_proj * instance.transform * vec4<f32>(in.vertex, 1.0);
+}
+
+// Unlit half-transparent red, so blend order shows in the output
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 0.0, 0.5);
+}
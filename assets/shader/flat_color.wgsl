@@ -0,0 +1,39 @@
+#import bevy_pbr::mesh_view_bindings
+#import indirect_instancing::instance_struct
+#import indirect_instancing::color_instance_struct
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+@group(2)
+@binding(0)
+var<uniform> instances: ColorInstances;
+#else
+@group(2)
+@binding(0)
+var<storage> instances: ColorInstances;
+#endif
+
+struct Vertex {
+    @builtin(instance_index) instance: u32,
+    @location(0) vertex: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vertex(in: Vertex) -> VertexOutput {
+    let instance = instances.instances[in.instance];
+
+    var out: VertexOutput;
+    out.clip_position = view.view_proj * instance.base.transform * vec4<f32>(in.vertex, 1.0);
+    out.color = instance.color;
+    return out;
+}
+
+// Unlit instance color
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
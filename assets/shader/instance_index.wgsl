@@ -0,0 +1,38 @@
+#import bevy_pbr::mesh_view_bindings
+#import indirect_instancing::instance_struct
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+@group(2)
+@binding(0)
+var<uniform> instances: Instances;
+#else
+@group(2)
+@binding(0)
+var<storage> instances: Instances;
+#endif
+
+struct Vertex {
+    @builtin(instance_index) instance: u32,
+    @location(0) vertex: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) instance: u32,
+};
+
+@vertex
+fn vertex(in: Vertex) -> VertexOutput {
+    let instance = instances.instances[in.instance];
+
+    var out: VertexOutput;
+    out.clip_position = view.view_proj * instance.transform * vec4<f32>(in.vertex, 1.0);
+    out.instance = in.instance;
+    return out;
+}
+
+// Encodes instance_index in the red channel, in linear steps of 1/8 starting from 1
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(f32(in.instance + 1u) / 8.0, 0.0, 0.0, 1.0);
+}
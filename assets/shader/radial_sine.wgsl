@@ -21,11 +21,15 @@ var<uniform> in_uniform: UniformData;
 @binding(0)
 var<storage, read_write> out_instances: ColorInstances;
 
+@group(1)
+@binding(1)
+var<uniform> instance_count: u32;
+
 @compute
 @workgroup_size(64)
 fn instances(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
     // Calculate maximum indices
-    let max_instance = arrayLength(&out_instances.instances);
+    let max_instance = instance_count;
 
     // Destructure invocation index
     let instance_idx = invocation_id.x;
@@ -0,0 +1,43 @@
+#import indirect_instancing::instance_struct
+#import indirect_instancing::indirect_struct
+#import indirect_instancing::color_instance_struct
+
+struct UniformData {
+    @size(16)
+    time: f32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> in_uniform: UniformData;
+
+@group(1)
+@binding(0)
+var<storage, read_write> out_instances: ColorInstances;
+
+@group(1)
+@binding(1)
+var<uniform> instance_count: u32;
+
+@compute
+@workgroup_size(64)
+fn instances(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    // Calculate maximum indices
+    let max_instance = instance_count;
+
+    // Destructure invocation index
+    let instance_idx = invocation_id.x;
+
+    // Early-out if we're out of bounds
+    if (instance_idx >= max_instance) {
+        return;
+    }
+
+    // Read the seeded position
+    let pos = out_instances.instances[instance_idx].base.transform[3].xyz;
+
+    // Displace it by a wave travelling across the seeded layout
+    let height = sin(in_uniform.time * 2.0 + pos.x * 0.5 + pos.z * 0.5);
+
+    out_instances.instances[instance_idx].base.transform[3].y = pos.y + height;
+}
@@ -0,0 +1,86 @@
+//! Times extracting a million instances into their material's arena
+//!
+//! Run with `cargo bench --bench extract_instances`.
+
+use std::time::Instant;
+
+use bevy::{
+    asset::AssetPlugin,
+    prelude::{
+        default, AddAsset, App, Camera, Mesh, SpatialBundle, Stage, SystemStage, Transform, World,
+    },
+    render::{
+        primitives::Frustum,
+        view::{VisibilityPlugin, VisibleEntities},
+        MainWorld,
+    },
+    time::TimePlugin,
+    transform::TransformPlugin,
+};
+
+use bevy_instancing::prelude::{
+    extract_mesh_instances, BasicMaterial, ExtractedInstances, MeshInstanceBundle,
+};
+
+const INSTANCE_COUNT: usize = 1_000_000;
+
+/// Extractions to average over
+const ITERATIONS: u32 = 20;
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugin(TimePlugin)
+        .add_plugin(AssetPlugin::default())
+        .add_asset::<Mesh>()
+        .add_plugin(TransformPlugin)
+        .add_plugin(VisibilityPlugin);
+
+    // Instances are only visible while some view can see them
+    app.world.spawn((
+        Camera::default(),
+        Frustum::default(),
+        VisibleEntities::default(),
+    ));
+
+    app.world.spawn_batch(
+        (0..INSTANCE_COUNT).map(|i| MeshInstanceBundle::<BasicMaterial> {
+            spatial_bundle: SpatialBundle::from_transform(Transform::from_xyz(
+                (i % 1000) as f32,
+                (i / 1000) as f32,
+                0.0,
+            )),
+            ..default()
+        }),
+    );
+
+    // Propagate transforms and visibility once, as a frame would before extraction
+    app.update();
+
+    let mut main_world = MainWorld::default();
+    std::mem::swap(&mut *main_world, &mut app.world);
+
+    let mut render_world = World::new();
+    render_world.insert_resource(main_world);
+    render_world.init_resource::<ExtractedInstances<BasicMaterial>>();
+
+    let mut stage =
+        SystemStage::single_threaded().with_system(extract_mesh_instances::<BasicMaterial>);
+
+    // The first extraction grows the arena, which later frames reuse
+    stage.run(&mut render_world);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        stage.run(&mut render_world);
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+
+    assert_eq!(
+        render_world
+            .resource::<ExtractedInstances<BasicMaterial>>()
+            .len(),
+        INSTANCE_COUNT
+    );
+
+    println!("Extracted {INSTANCE_COUNT} instances in {elapsed:?} per frame");
+}
@@ -0,0 +1,103 @@
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, shape::Cube, App, Assets, Camera3dBundle, Color, Commands, Component, Handle,
+        Mesh, Query, Res, ResMut, SpatialBundle, Time, Transform, With,
+    },
+    render::{primitives::Aabb, view::NoFrustumCulling},
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    InstanceCullingPlugin, MeshInstanceBundle,
+};
+
+// Test GPU instance culling against main-world Aabb overrides
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin)
+        .add_plugin(InstanceCullingPlugin::<CustomMaterial>::default());
+
+    app.add_startup_system(setup_instancing)
+        .add_system(toggle_aabb_override);
+
+    app.run()
+}
+
+/// Marker for the instance whose bounds are periodically overridden
+#[derive(Debug, Default, Copy, Clone, Component)]
+struct Overridden;
+
+/// Bounds of the default unit cube
+fn cube_aabb() -> Aabb {
+    Aabb::from_min_max(Vec3::splat(-0.5), Vec3::splat(0.5))
+}
+
+/// Bounds placed well behind the camera, so GPU culling rejects the instance despite its geometry being in view
+fn offscreen_aabb() -> Aabb {
+    Aabb::from_min_max(Vec3::new(-0.5, -0.5, 99.5), Vec3::new(0.5, 0.5, 100.5))
+}
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh_cube = meshes.add(Cube::default().into());
+
+    for (x, color, overridden) in [(-1.5, Color::WHITE, false), (1.5, Color::RED, true)] {
+        // Skip bevy's own frustum culling, so only GPU instance culling applies.
+        // Bevy doesn't compute bounds for such entities, so they're supplied here
+        let mut entity = commands.spawn((
+            NoFrustumCulling,
+            cube_aabb(),
+            ColorInstanceBundle {
+                instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                    mesh: mesh_cube.clone(),
+                    material: Handle::<CustomMaterial>::default(),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_xyz(x, 0.0, 0.0),
+                        ..default()
+                    },
+                },
+                mesh_instance_color: color.into(),
+            },
+        ));
+
+        if overridden {
+            entity.insert(Overridden);
+        }
+    }
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 3.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Alternates the red cube between its own and offscreen bounds every two seconds,
+/// so it should only be drawn every other interval
+fn toggle_aabb_override(time: Res<Time>, mut query_overridden: Query<&mut Aabb, With<Overridden>>) {
+    let overridden = (time.elapsed_seconds() / 2.0) as u32 % 2 == 0;
+
+    for mut aabb in query_overridden.iter_mut() {
+        *aabb = if overridden {
+            offscreen_aabb()
+        } else {
+            cube_aabb()
+        };
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::{Vec2, Vec3},
+    pbr::AlphaMode,
+    prelude::{
+        default, info, shape::Quad, App, Assets, Camera, Camera3dBundle, Color, Commands,
+        Component, Entity, EventWriter, Image, Local, Mesh, PluginGroup, Query, Res, ResMut,
+        Resource, SpatialBundle, Transform,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    InstanceMeta, MeshInstanceBundle,
+};
+
+/// View depths of the two quads, swapped once the first order has been drawn
+const DEPTHS: [f32; 2] = [0.0, -1.0];
+
+/// Frames to wait for each order to be drawn before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Instances in the order they were written to the blended batch's instance buffer,
+/// as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct EmittedOrder(Arc<Mutex<Vec<Entity>>>);
+
+/// Index of a quad into [`DEPTHS`]
+#[derive(Debug, Copy, Clone, Component)]
+struct BlendedQuad(usize);
+
+// Test back-to-front sorting of blended instances within a single batch,
+// by expecting the farther of two overlapping quads to be emitted first,
+// then the order to flip when their depths are swapped
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(CustomMaterialPlugin);
+
+    let emitted_order = EmittedOrder::default();
+    app.insert_resource(emitted_order.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_emitted_order);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(emitted_order)
+        .add_system_to_stage(RenderStage::Cleanup, read_emitted_order);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CustomMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let mesh = meshes.add(Quad::new(Vec2::splat(2.0)).into());
+    let material = materials.add(CustomMaterial {
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+
+    // Nearer quad spawned first, so spawn order disagrees with depth order
+    for (i, depth) in DEPTHS.iter().enumerate() {
+        commands.spawn((
+            ColorInstanceBundle {
+                instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_xyz(i as f32 * 0.5, 0.0, *depth),
+                        ..default()
+                    },
+                },
+                mesh_instance_color: Color::rgba(1.0, 0.0, 0.0, 0.5).into(),
+            },
+            BlendedQuad(i),
+        ));
+    }
+
+    let size = Extent3d {
+        width: 64,
+        height: 64,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("blend sort target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(images.add(image)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn read_emitted_order(
+    query_instance_meta: Query<&InstanceMeta<CustomMaterial>>,
+    emitted_order: Res<EmittedOrder>,
+) {
+    *emitted_order.0.lock().unwrap() = query_instance_meta
+        .iter()
+        .flat_map(|instance_meta| instance_meta.instance_batches.values())
+        .flat_map(|instance_batch| instance_batch.instance_indices.iter())
+        .map(|(entity, _)| *entity)
+        .collect();
+}
+
+/// Expects the farther quad to be emitted first, then swaps the quads' depths
+fn check_emitted_order(
+    emitted_order: Res<EmittedOrder>,
+    mut query_quads: Query<(Entity, &BlendedQuad, &mut Transform)>,
+    mut swapped: Local<bool>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    // Farthest first, i.e. most negative depth
+    let mut expected = query_quads
+        .iter()
+        .map(|(entity, _, transform)| (entity, transform.translation.z))
+        .collect::<Vec<_>>();
+    expected.sort_by(|(_, lhs), (_, rhs)| lhs.total_cmp(rhs));
+    let expected = expected
+        .into_iter()
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+
+    let emitted = emitted_order.0.lock().unwrap().clone();
+
+    if emitted == expected {
+        info!(
+            "Emitted blended quads back-to-front after {} frames",
+            *frame
+        );
+
+        if *swapped {
+            exit.send(AppExit);
+            return;
+        }
+
+        for (_, quad, mut transform) in query_quads.iter_mut() {
+            transform.translation.z = DEPTHS[1 - quad.0];
+        }
+
+        *swapped = true;
+        *frame = 0;
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected blended quads emitted as {expected:?}, got {emitted:?}");
+    }
+}
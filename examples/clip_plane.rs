@@ -0,0 +1,125 @@
+//! Demonstration of InstanceClipPlane functionality
+//!
+//! Bobs a grid of cubes through a water plane, clipping the instances below it
+//! as a planar reflection pass would.
+//!
+
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{AlphaMode, DirectionalLight, DirectionalLightBundle, PbrBundle, StandardMaterial},
+    prelude::{
+        default,
+        shape::{Cube, Plane},
+        App, Assets, Camera3dBundle, Color, Commands, Component, Mesh, Query, Res, ResMut,
+        SpatialBundle, Time, Transform,
+    },
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    InstanceClipPlane, InstanceColor, MeshInstanceBundle,
+};
+
+const GRID_SIZE: usize = 16;
+
+// Test clipping instances against a per-view plane
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing);
+
+    app.add_system(bob_instances);
+
+    app.run()
+}
+
+/// Phase offset for an instance's vertical motion
+#[derive(Debug, Default, Copy, Clone, Component)]
+struct Bob(f32);
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    // Keep only the geometry above the water
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-16.0, 8.0, 16.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        InstanceClipPlane::from_point_normal(Vec3::ZERO, Vec3::Y),
+    ));
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            // Workaround: Pointing straight up or down prevents directional shadow from rendering
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    // Water plane
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(
+            Plane {
+                size: GRID_SIZE as f32 + 4.0,
+            }
+            .into(),
+        ),
+        material: standard_materials.add(StandardMaterial {
+            base_color: Color::rgba(0.1, 0.3, 0.6, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+        ..default()
+    });
+
+    let mesh_cube = meshes.add(Cube { size: 0.8 }.into());
+
+    let half_extent = GRID_SIZE as f32 / 2.0 - 0.5;
+    let mut cubes = vec![];
+    for x in 0..GRID_SIZE {
+        for z in 0..GRID_SIZE {
+            let position = Vec3::new(x as f32 - half_extent, 0.0, z as f32 - half_extent);
+
+            cubes.push((
+                ColorInstanceBundle::<CustomMaterial> {
+                    instance_bundle: MeshInstanceBundle {
+                        mesh: mesh_cube.clone(),
+                        spatial_bundle: SpatialBundle {
+                            transform: Transform::from_translation(position),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    mesh_instance_color: InstanceColor(Color::rgb(
+                        x as f32 / GRID_SIZE as f32,
+                        0.5,
+                        z as f32 / GRID_SIZE as f32,
+                    )),
+                },
+                Bob((x + z) as f32 * 0.4),
+            ));
+        }
+    }
+
+    commands.spawn_batch(cubes);
+}
+
+fn bob_instances(time: Res<Time>, mut query: Query<(&Bob, &mut Transform)>) {
+    for (bob, mut transform) in query.iter_mut() {
+        transform.translation.y = (time.elapsed_seconds() + bob.0).sin();
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera, Camera3dBundle, Color, Commands,
+        EventWriter, Handle, Image, Local, Mesh, PluginGroup, Query, Res, ResMut, Resource,
+        SpatialBundle, Transform,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, PipelineCache, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    BatchedDraws, ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectReadback,
+    IndirectRenderingPlugin, InstanceCullingPipeline, InstanceCullingPlugin, InstanceMeta,
+    MeshInstanceBundle,
+};
+
+/// Instances of each mesh, all within view
+const INSTANCES_PER_MESH: u32 = 10;
+
+/// Frames to wait for compacted instance counts to be read back before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Instance counts of the camera's indirect draws, as last read back by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct CompactedCounts(Arc<Mutex<Vec<u32>>>);
+
+// Test compacting GPU culling, by expecting the instance counts it zeroes and counts back up
+// to match the visible instances of each mesh
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(CustomMaterialPlugin)
+    .add_plugin(InstanceCullingPlugin::<CustomMaterial>::default().compact());
+
+    let compacted_counts = CompactedCounts::default();
+    app.insert_resource(compacted_counts.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_compacted_counts);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(compacted_counts)
+        .add_system_to_stage(RenderStage::Cleanup, read_compacted_counts);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    // Meshes of the same layout, so both are drawn from one indirect buffer
+    let mesh_large = meshes.add(Cube::new(0.5).into());
+    let mesh_small = meshes.add(Cube::new(0.3).into());
+
+    for (z, mesh) in [(-0.5, mesh_large), (0.5, mesh_small)] {
+        for x in 0..INSTANCES_PER_MESH {
+            commands.spawn(ColorInstanceBundle {
+                instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                    mesh: mesh.clone(),
+                    material: Handle::<CustomMaterial>::default(),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_xyz(
+                            x as f32 - INSTANCES_PER_MESH as f32 / 2.0,
+                            0.0,
+                            z,
+                        ),
+                        ..default()
+                    },
+                },
+                mesh_instance_color: Color::WHITE.into(),
+            });
+        }
+    }
+
+    let size = Extent3d {
+        width: 64,
+        height: 64,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("compact culling target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(images.add(image)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Reads back the camera's indirect draws once the previous readback has completed,
+/// starting from the first frame queued after the compaction pipeline became ready
+fn read_compacted_counts(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    pipeline_cache: Res<PipelineCache>,
+    culling_pipeline: Res<InstanceCullingPipeline>,
+    query_instance_meta: Query<&InstanceMeta<CustomMaterial>>,
+    compacted_counts: Res<CompactedCounts>,
+    mut readback: Local<Option<IndirectReadback>>,
+    mut compacting: Local<bool>,
+) {
+    if let Some(pending) = readback.as_ref() {
+        let instance_counts = match pending.lock().unwrap().take() {
+            Some(instance_counts) => instance_counts,
+            None => return,
+        };
+
+        *compacted_counts.0.lock().unwrap() = instance_counts;
+    }
+
+    if !*compacting {
+        *compacting = pipeline_cache
+            .get_compute_pipeline(culling_pipeline.compact_pipeline)
+            .is_some();
+        return;
+    }
+
+    *readback = query_instance_meta
+        .iter()
+        .flat_map(|instance_meta| instance_meta.batched_instances.values().flatten())
+        .find_map(|batched_instances| match &batched_instances.draws {
+            BatchedDraws::Indirect(indirect_data) => {
+                Some(indirect_data.read_instance_counts(&render_device, &render_queue))
+            }
+            BatchedDraws::Direct(_) => None,
+        });
+}
+
+fn check_compacted_counts(
+    compacted_counts: Res<CompactedCounts>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let instance_counts = compacted_counts.0.lock().unwrap().clone();
+
+    if instance_counts == [INSTANCES_PER_MESH; 2] {
+        info!(
+            "Compacted {INSTANCES_PER_MESH} instances into each mesh's draw after {} frames",
+            *frame
+        );
+        exit.send(AppExit);
+    }
+
+    if *frame > MAX_FRAMES {
+        panic!(
+            "Expected {INSTANCES_PER_MESH} compacted instances per mesh, got {instance_counts:?}"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, shape::Plane, App, Assets, Camera3dBundle, Color, Commands, Component, Entity,
+        Handle, Mesh, Query, Res, ResMut, SpatialBundle, Time, Transform, With,
+    },
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    InstanceDepthBias, MeshInstanceBundle,
+};
+
+// Test per-instance slope-scaled depth bias on coplanar decals seen at a grazing angle
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing)
+        .add_system(toggle_depth_bias);
+
+    app.run()
+}
+
+/// Marker for decals whose depth bias is toggled on and off
+#[derive(Debug, Default, Copy, Clone, Component)]
+struct Decal;
+
+const DECAL_DEPTH_BIAS: InstanceDepthBias = InstanceDepthBias {
+    constant: 0.001,
+    slope_scale: 0.002,
+};
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh_ground = meshes.add(Plane { size: 200.0 }.into());
+    let mesh_decal = meshes.add(Plane { size: 2.0 }.into());
+
+    commands.spawn(ColorInstanceBundle {
+        instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+            mesh: mesh_ground,
+            material: Handle::<CustomMaterial>::default(),
+            spatial_bundle: default(),
+        },
+        mesh_instance_color: Color::GRAY.into(),
+    });
+
+    // Decals lying exactly on the ground, receding into the distance
+    for i in 0..32 {
+        commands.spawn((
+            Decal,
+            DECAL_DEPTH_BIAS,
+            ColorInstanceBundle {
+                instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                    mesh: mesh_decal.clone(),
+                    material: Handle::<CustomMaterial>::default(),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_xyz(0.0, 0.0, -(i as f32) * 3.0),
+                        ..default()
+                    },
+                },
+                mesh_instance_color: Color::ORANGE_RED.into(),
+            },
+        ));
+    }
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    // Grazing view over the ground plane
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 0.5, 6.0)
+            .looking_at(Vec3::new(0.0, 0.0, -50.0), Vec3::Y),
+        ..default()
+    });
+}
+
+/// Alternates the decals between biased and unbiased every two seconds,
+/// so distant decals should only z-fight every other interval
+fn toggle_depth_bias(
+    time: Res<Time>,
+    query_decal: Query<(Entity, Option<&InstanceDepthBias>), With<Decal>>,
+    mut commands: Commands,
+) {
+    let biased = (time.elapsed_seconds() / 2.0) as u32 % 2 == 0;
+
+    for (entity, depth_bias) in query_decal.iter() {
+        match (biased, depth_bias.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert(DECAL_DEPTH_BIAS);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<InstanceDepthBias>();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera, Camera3dBundle, Commands, Component,
+        DespawnRecursiveExt, Entity, EventWriter, Handle, Image, Local, Mesh, PluginGroup, Query,
+        Res, ResMut, Resource, SpatialBundle, Transform, With,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, InstanceMeta, MeshInstanceBundle,
+};
+
+const INSTANCE_COUNT: usize = 10;
+
+const DESPAWN_COUNT: usize = 5;
+
+/// Frames to wait for instance batches to be prepared before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Number of instances batched by the view, as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct BatchedInstanceCount(Arc<Mutex<Option<usize>>>);
+
+/// Marker for instances despawned once all instances have been batched
+#[derive(Debug, Default, Copy, Clone, Component)]
+struct Despawned;
+
+// Test that despawned instances are dropped from the render world,
+// by batching ten instances, despawning five, and expecting five to remain batched
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(BasicMaterialPlugin);
+
+    let count = BatchedInstanceCount::default();
+    app.insert_resource(count.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_batched_instance_count);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(count)
+        .add_system_to_stage(RenderStage::Cleanup, read_batched_instance_count);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let mesh_cube = meshes.add(Cube::new(0.5).into());
+
+    for i in 0..INSTANCE_COUNT {
+        let mut instance = commands.spawn(MeshInstanceBundle::<BasicMaterial> {
+            mesh: mesh_cube.clone(),
+            material: Handle::<BasicMaterial>::default(),
+            spatial_bundle: SpatialBundle {
+                transform: Transform::from_xyz(i as f32 - INSTANCE_COUNT as f32 * 0.5, 0.0, 0.0),
+                ..default()
+            },
+        });
+
+        if i < DESPAWN_COUNT {
+            instance.insert(Despawned);
+        }
+    }
+
+    let size = Extent3d {
+        width: 64,
+        height: 64,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("despawn instances target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(images.add(image)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 15.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Records the number of instances across the view's batches
+fn read_batched_instance_count(
+    query_views: Query<&InstanceMeta<BasicMaterial>>,
+    count: Res<BatchedInstanceCount>,
+) {
+    *count.0.lock().unwrap() = query_views
+        .iter()
+        .map(|instance_meta| {
+            instance_meta
+                .instance_batches
+                .values()
+                .map(|batch| batch.instances.len())
+                .sum::<usize>()
+        })
+        .max();
+}
+
+fn check_batched_instance_count(
+    count: Res<BatchedInstanceCount>,
+    query_despawned: Query<Entity, With<Despawned>>,
+    mut despawned: Local<bool>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+    mut commands: Commands,
+) {
+    *frame += 1;
+
+    let count = *count.0.lock().unwrap();
+
+    if !*despawned {
+        if count == Some(INSTANCE_COUNT) {
+            info!("All {INSTANCE_COUNT} instances batched, despawning {DESPAWN_COUNT}");
+            for entity in query_despawned.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            *despawned = true;
+        }
+    } else if count == Some(INSTANCE_COUNT - DESPAWN_COUNT) {
+        info!("Despawned instances dropped after {} frames", *frame);
+        exit.send(AppExit);
+    } else if count != Some(INSTANCE_COUNT) {
+        panic!(
+            "Expected {} batched instances after despawning, got {count:?}",
+            INSTANCE_COUNT - DESPAWN_COUNT
+        );
+    }
+
+    if *frame > MAX_FRAMES {
+        panic!("Expected batched instances to settle, got {count:?}");
+    }
+}
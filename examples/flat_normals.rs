@@ -0,0 +1,83 @@
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default,
+        shape::{Cube, Icosphere},
+        App, Assets, Camera3dBundle, Color, Commands, Handle, Mesh, ResMut, SpatialBundle,
+        Transform,
+    },
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    MeshInstanceBundle,
+};
+
+// Test instancing meshes without a normal attribute
+//
+// The front row has its normals stripped, and should be lit with faceted shading
+// from normals derived in the fragment shader, rather than failing to render.
+// The back row keeps its normals for comparison.
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing);
+
+    app.run()
+}
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let shapes: [Mesh; 2] = [
+        Cube::new(0.8).into(),
+        Icosphere {
+            radius: 0.5,
+            subdivisions: 1,
+        }
+        .into(),
+    ];
+
+    for (x, mesh) in shapes.into_iter().enumerate() {
+        let mut flat_mesh = mesh.clone();
+        flat_mesh.remove_attribute(Mesh::ATTRIBUTE_NORMAL);
+
+        for (z, mesh) in [mesh, flat_mesh].into_iter().enumerate() {
+            commands.spawn(ColorInstanceBundle {
+                instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                    mesh: meshes.add(mesh),
+                    material: Handle::<CustomMaterial>::default(),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_xyz(x as f32 * 1.5 - 0.75, 0.0, z as f32 * 1.5)
+                            .with_rotation(Quat::from_rotation_y(0.5)),
+                        ..default()
+                    },
+                },
+                mesh_instance_color: Color::WHITE.into(),
+            });
+        }
+    }
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6)
+                * Quat::from_rotation_y(0.4),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 2.5, 5.0)
+            .looking_at(Vec3::new(0.0, 0.0, 0.75), Vec3::Y),
+        ..default()
+    });
+}
@@ -0,0 +1,134 @@
+//! Demonstration of alpha-to-coverage
+//!
+//! Renders a field of crossed leaf cards whose texture alpha is converted
+//! into MSAA sample coverage, giving soft edges without sorting.
+//! Cards are lit on both sides, as their back faces aren't culled.
+//!
+
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{AlphaMode, DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, shape::Quad, App, Assets, Camera3dBundle, Color, Commands, Image, Mesh, Msaa,
+        ResMut, SpatialBundle, Transform, Vec2,
+    },
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, IndirectRenderingPlugin, MeshInstanceBundle, TextureMaterial,
+    TextureMaterialPlugin,
+};
+
+const LEAF_TEXTURE_SIZE: u32 = 64;
+const CARDS_PER_AXIS: i32 = 24;
+
+// Test alpha-to-coverage on instanced foliage cards
+fn main() {
+    let mut app = App::default();
+
+    app.insert_resource(Msaa { samples: 4 });
+
+    app.add_plugins(DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(TextureMaterialPlugin);
+
+    app.add_startup_system(setup_instancing);
+
+    app.run()
+}
+
+/// Generates a white leaf shape with a soft alpha falloff
+fn leaf_texture() -> Image {
+    let size = LEAF_TEXTURE_SIZE as f32;
+
+    let data = (0..LEAF_TEXTURE_SIZE * LEAF_TEXTURE_SIZE)
+        .flat_map(|i| {
+            let uv = Vec2::new(
+                (i % LEAF_TEXTURE_SIZE) as f32 / size,
+                (i / LEAF_TEXTURE_SIZE) as f32 / size,
+            );
+
+            // Narrow ellipse, pointed at both ends
+            let p = (uv - 0.5) * 2.0;
+            let width = (1.0 - p.y * p.y).max(0.0) * 0.5;
+            let alpha = (1.0 - (p.x.abs() / width.max(f32::EPSILON))).clamp(0.0, 1.0);
+            let alpha = (alpha * 4.0).min(1.0);
+
+            [255, 255, 255, (alpha * 255.0) as u8]
+        })
+        .collect();
+
+    Image::new(
+        Extent3d {
+            width: LEAF_TEXTURE_SIZE,
+            height: LEAF_TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut texture_materials: ResMut<Assets<TextureMaterial>>,
+    mut commands: Commands,
+) {
+    // Perspective camera
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-12.0, 6.0, 12.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            // Workaround: Pointing straight up or down prevents directional shadow from rendering
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    let mesh = meshes.add(Quad::new(Vec2::new(1.0, 2.0)).into());
+
+    // Coverage replaces blending, so cards need no sorting
+    let material = texture_materials.add(TextureMaterial {
+        texture: images.add(leaf_texture()),
+        alpha_mode: AlphaMode::Mask(0.5),
+        alpha_to_coverage: true,
+        two_sided_lighting: true,
+        cull_mode: None,
+    });
+
+    for x in -CARDS_PER_AXIS / 2..CARDS_PER_AXIS / 2 {
+        for z in -CARDS_PER_AXIS / 2..CARDS_PER_AXIS / 2 {
+            let position = Vec3::new(x as f32 * 0.5, 1.0, z as f32 * 0.5);
+            let yaw = (x * 7 + z * 13) as f32;
+
+            // Cross two cards per plant
+            for rotation in [0.0, std::f32::consts::FRAC_PI_2] {
+                commands.spawn(ColorInstanceBundle {
+                    instance_bundle: MeshInstanceBundle {
+                        mesh: mesh.clone(),
+                        material: material.clone(),
+                        spatial_bundle: SpatialBundle {
+                            transform: Transform::from_translation(position)
+                                .with_rotation(Quat::from_rotation_y(yaw + rotation)),
+                            ..default()
+                        },
+                    },
+                    mesh_instance_color: Color::rgb(0.3, 0.8, 0.2).into(),
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::{Mat4, Vec3},
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera3dBundle, Commands, Entity, EventWriter,
+        Handle, Local, Mesh, PluginGroup, Res, ResMut, Resource, SpatialBundle, Transform,
+        Visibility,
+    },
+    render::{RenderApp, RenderStage},
+    utils::HashMap,
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, ExtractedInstances, IndirectRenderingPlugin, Instance,
+    MeshInstance, MeshInstanceBundle,
+};
+
+/// Frames to wait for instances to be extracted before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// The hidden and visible instance entities
+#[derive(Debug, Copy, Clone, Resource)]
+struct TestInstances {
+    hidden: Entity,
+    visible: Entity,
+}
+
+/// Prepared inverse-transpose model matrix of each extracted instance, as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct PreparedNormalMatrices(Arc<Mutex<HashMap<Entity, Mat4>>>);
+
+// Test that a hidden instance extracted alongside a visible one
+// prepares to finite instance data, rather than the inverse of a zero matrix
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(BasicMaterialPlugin);
+
+    let matrices = PreparedNormalMatrices::default();
+    app.insert_resource(matrices.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_normal_matrices);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(matrices)
+        .add_system_to_stage(RenderStage::Cleanup, read_normal_matrices);
+
+    app.run()
+}
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh_cube = meshes.add(Cube::new(0.5).into());
+
+    let mut spawn_instance = |x: f32, is_visible: bool| {
+        commands
+            .spawn(MeshInstanceBundle::<BasicMaterial> {
+                mesh: mesh_cube.clone(),
+                material: Handle::<BasicMaterial>::default(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_xyz(x, 0.0, 0.0),
+                    visibility: Visibility { is_visible },
+                    ..default()
+                },
+            })
+            .id()
+    };
+
+    let instances = TestInstances {
+        hidden: spawn_instance(-1.0, false),
+        visible: spawn_instance(1.0, true),
+    };
+    commands.insert_resource(instances);
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 2.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Prepares each extracted instance, recording its inverse-transpose model matrix
+fn read_normal_matrices(
+    extracted_instances: Res<ExtractedInstances<BasicMaterial>>,
+    matrices: Res<PreparedNormalMatrices>,
+) {
+    *matrices.0.lock().unwrap() = extracted_instances
+        .iter()
+        .map(|(entity, extracted)| {
+            (
+                *entity,
+                MeshInstance::prepare_instance(&extracted.instance, 0).inverse_transpose_model,
+            )
+        })
+        .collect();
+}
+
+fn check_normal_matrices(
+    instances: Option<Res<TestInstances>>,
+    matrices: Res<PreparedNormalMatrices>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let instances = if let Some(instances) = instances {
+        instances
+    } else {
+        return;
+    };
+
+    let matrices = matrices.0.lock().unwrap();
+    let hidden = matrices.get(&instances.hidden);
+    let visible = matrices.get(&instances.visible);
+
+    if let (Some(hidden), Some(visible)) = (hidden, visible) {
+        assert!(
+            visible.is_finite(),
+            "Visible instance has a non-finite inverse-transpose model: {visible:?}"
+        );
+        assert!(
+            hidden.is_finite(),
+            "Hidden instance has a non-finite inverse-transpose model: {hidden:?}"
+        );
+
+        info!(
+            "Hidden and visible instances prepared finite data after {} frames",
+            *frame
+        );
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected both instances to be extracted, got hidden {hidden:?} and visible {visible:?}");
+    }
+}
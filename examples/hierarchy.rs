@@ -0,0 +1,86 @@
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, shape::Cube, App, Assets, BuildChildren, Camera3dBundle, Color, Commands,
+        Component, Handle, Mesh, Query, Res, ResMut, SpatialBundle, Time, Transform, With,
+    },
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    MeshInstanceBundle,
+};
+
+// Test instances parented to a moving, non-instanced entity
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing)
+        .add_system(rotate_parents);
+
+    app.run()
+}
+
+/// Marker for the plain (non-instanced) parent entity
+#[derive(Debug, Default, Copy, Clone, Component)]
+struct Parent;
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh_cube = meshes.add(Cube::default().into());
+
+    // The parent carries no instance components; children inherit its transform
+    // through GlobalTransform, which is what instance extraction reads
+    commands
+        .spawn((Parent, SpatialBundle::default()))
+        .with_children(|parent| {
+            for i in 0..16 {
+                let angle = i as f32 / 16.0 * std::f32::consts::TAU;
+
+                parent.spawn(ColorInstanceBundle {
+                    instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                        mesh: mesh_cube.clone(),
+                        material: Handle::<CustomMaterial>::default(),
+                        spatial_bundle: SpatialBundle {
+                            transform: Transform::from_xyz(
+                                angle.cos() * 5.0,
+                                0.0,
+                                angle.sin() * 5.0,
+                            ),
+                            ..default()
+                        },
+                    },
+                    mesh_instance_color: Color::hsl(i as f32 / 16.0 * 360.0, 1.0, 0.5).into(),
+                });
+            }
+        });
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 10.0, 15.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn rotate_parents(time: Res<Time>, mut query_parent: Query<&mut Transform, With<Parent>>) {
+    for mut transform in query_parent.iter_mut() {
+        transform.rotation = Quat::from_rotation_y(time.elapsed_seconds());
+        transform.translation.x = time.elapsed_seconds().sin() * 3.0;
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::{
+    pbr::{DirectionalLight, DirectionalLightBundle, Material, MaterialMeshBundle},
+    prelude::{
+        default, shape::Cube, App, Assets, Camera3dBundle, Commands, Mesh, ResMut, SpatialBundle,
+        Transform, Vec3,
+    },
+    reflect::TypeUuid,
+    render::render_resource::AsBindGroup,
+};
+
+use bevy_instancing::prelude::{
+    AsBatch, HybridMaterialPlugin, IndirectRenderingPlugin, MaterialInstanced, MeshInstance,
+    MeshInstanceBundle, NotInstanced,
+};
+
+/// Material usable through both bevy's material pipeline and the instanced one,
+/// rendered with each pipeline's default shaders
+#[derive(Debug, Default, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "8f5d3f3c-5b0e-4f59-a7a2-1e4c2b9d7a10"]
+struct HybridMaterial {}
+
+impl Material for HybridMaterial {}
+
+impl From<&HybridMaterial> for () {
+    fn from(_: &HybridMaterial) -> Self {}
+}
+
+impl AsBatch for HybridMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for HybridMaterial {
+    type Instance = MeshInstance;
+}
+
+// Test a single non-instanced hero mesh sharing a material with many instanced copies
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(HybridMaterialPlugin::<HybridMaterial>::default());
+
+    app.add_startup_system(setup_instancing);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<HybridMaterial>>,
+    mut commands: Commands,
+) {
+    let mesh_cube = meshes.add(Cube::default().into());
+    let material = materials.add(HybridMaterial {});
+
+    // Hero, drawn by bevy's material pipeline
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: mesh_cube.clone(),
+            material: material.clone(),
+            transform: Transform::from_scale(Vec3::splat(3.0)),
+            ..default()
+        },
+        NotInstanced,
+    ));
+
+    // Copies, drawn by the instanced pipeline
+    for x in -8..=8 {
+        for z in -8..=8 {
+            if x == 0 && z == 0 {
+                continue;
+            }
+
+            commands.spawn(MeshInstanceBundle::<HybridMaterial> {
+                mesh: mesh_cube.clone(),
+                material: material.clone(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_xyz(x as f32 * 2.0, 0.0, z as f32 * 2.0),
+                    ..default()
+                },
+            });
+        }
+    }
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 10.0, 0.0)
+            .looking_at(Vec3::new(1.0, 0.0, 1.0), Vec3::Y),
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(30.0, 30.0, 30.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
@@ -106,46 +106,55 @@ fn setup_instancing(
     let material_opaque_no_cull = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Opaque,
         cull_mode: None,
+        ..default()
     });
 
     let material_mask_no_cull = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Mask(0.5),
         cull_mode: None,
+        ..default()
     });
 
     let material_blend_no_cull = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Blend,
         cull_mode: None,
+        ..default()
     });
 
     let material_opaque_cull_front = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Opaque,
         cull_mode: Some(Face::Front),
+        ..default()
     });
 
     let material_mask_cull_front = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Mask(0.5),
         cull_mode: Some(Face::Front),
+        ..default()
     });
 
     let material_blend_cull_front = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Blend,
         cull_mode: Some(Face::Front),
+        ..default()
     });
 
     let material_opaque_cull_back = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Opaque,
         cull_mode: Some(Face::Back),
+        ..default()
     });
 
     let material_mask_cull_back = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Mask(0.5),
         cull_mode: Some(Face::Back),
+        ..default()
     });
 
     let material_blend_cull_back = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Blend,
         cull_mode: Some(Face::Back),
+        ..default()
     });
 
     let custom_materials: &[Handle<CustomMaterial>] = &[
@@ -164,24 +173,28 @@ fn setup_instancing(
         texture: asset_server.load("texture/text_1.png"),
         alpha_mode: AlphaMode::Opaque,
         cull_mode: Some(Face::Back),
+        ..default()
     });
 
     let material_texture_2 = texture_materials.add(TextureMaterial {
         texture: asset_server.load("texture/text_2.png"),
         alpha_mode: AlphaMode::Mask(0.2),
         cull_mode: Some(Face::Back),
+        ..default()
     });
 
     let material_texture_3 = texture_materials.add(TextureMaterial {
         texture: asset_server.load("texture/text_3.png"),
         alpha_mode: AlphaMode::Blend,
         cull_mode: Some(Face::Back),
+        ..default()
     });
 
     let material_texture_smiley = texture_materials.add(TextureMaterial {
         texture: asset_server.load("texture/text_smiley.png"),
         alpha_mode: AlphaMode::Opaque,
         cull_mode: Some(Face::Back),
+        ..default()
     });
 
     let texture_materials: &[Handle<TextureMaterial>] = &[
@@ -108,6 +108,7 @@ fn setup_instancing(
     let material_back = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Blend,
         cull_mode: Some(Face::Front),
+        ..default()
     });
 
     commands
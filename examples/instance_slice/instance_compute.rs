@@ -116,11 +116,13 @@ fn setup_instancing(
     let material_front = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Blend,
         cull_mode: Some(Face::Back),
+        ..default()
     });
 
     let material_back = board_materials.add(CustomMaterial {
         alpha_mode: AlphaMode::Blend,
         cull_mode: Some(Face::Front),
+        ..default()
     });
 
     commands.spawn((
@@ -0,0 +1,143 @@
+//! Demonstration of InstanceSliceSeed functionality
+//!
+//! Seeds an instance slice with a grid of cubes from the CPU,
+//! then displaces the seeded layout with a compute shader.
+//!
+
+use bevy::ecs::system::lifetimeless::Read;
+use bevy::prelude::{Camera3dBundle, Component, Query, Res};
+use bevy::render::extract_component::ExtractComponent;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::time::Time;
+use bevy::{
+    core::Name,
+    math::{Mat4, Quat, Vec3, Vec4},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{default, shape::Cube, App, Assets, Commands, Mesh, ResMut, Transform},
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    ColorMeshInstance, CustomMaterial, CustomMaterialPlugin, GpuColorMeshInstance, GpuMeshInstance,
+    IndirectRenderingPlugin, InstanceCompute, InstanceComputePlugin, InstanceSlice,
+    InstanceSliceBundle, InstanceSliceSeed, InstanceSliceSeedPlugin,
+};
+
+const GRID_SIZE: usize = 32;
+
+// Test seeding an instance slice before computing it
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_plugin(InstanceSliceSeedPlugin::<CustomMaterial>::default())
+        .add_plugin(InstanceComputePlugin::<SeededWaveInstances>::default());
+
+    app.add_startup_system(setup_instancing);
+
+    app.add_system(instance_compute_time);
+
+    app.run()
+}
+
+#[derive(Debug, Default, Copy, Clone, Component, AsBindGroup)]
+pub struct SeededWaveInstances {
+    #[uniform(0)]
+    time: f32,
+}
+
+impl From<&SeededWaveInstances> for () {
+    fn from(_: &SeededWaveInstances) -> Self {}
+}
+
+impl ExtractComponent for SeededWaveInstances {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+impl InstanceCompute for SeededWaveInstances {
+    type Instance = ColorMeshInstance;
+
+    fn shader() -> ShaderRef {
+        "shader/seeded_wave.wgsl".into()
+    }
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CustomMaterial>>,
+    mut commands: Commands,
+) {
+    // Perspective camera
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-30.0, 30.0, 30.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            // Workaround: Pointing straight up or down prevents directional shadow from rendering
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    // Lay out a grid of cubes for the compute shader to start from
+    let half_extent = GRID_SIZE as f32 / 2.0;
+    let seed = (0..GRID_SIZE * GRID_SIZE).map(|i| {
+        let (x, z) = (i % GRID_SIZE, i / GRID_SIZE);
+        let transform = Mat4::from_translation(Vec3::new(
+            x as f32 - half_extent,
+            0.0,
+            z as f32 - half_extent,
+        ));
+
+        GpuColorMeshInstance {
+            base: GpuMeshInstance {
+                transform,
+                inverse_transpose_model: transform.inverse().transpose(),
+                ..default()
+            },
+            attribute: Vec4::new(
+                x as f32 / GRID_SIZE as f32,
+                z as f32 / GRID_SIZE as f32,
+                1.0,
+                1.0,
+            ),
+        }
+    });
+
+    commands.spawn((
+        Name::new("Seeded Instance Slice"),
+        InstanceSliceBundle {
+            material: materials.add(CustomMaterial::default()),
+            mesh: meshes.add(Cube { size: 0.5 }.into()),
+            mesh_instance_slice: InstanceSlice {
+                instance_count: GRID_SIZE * GRID_SIZE,
+            },
+            ..default()
+        },
+        InstanceSliceSeed::<CustomMaterial>::new(seed),
+        SeededWaveInstances::default(),
+    ));
+}
+
+fn instance_compute_time(time: Res<Time>, mut query_uniform: Query<&mut SeededWaveInstances>) {
+    for mut uniform in query_uniform.iter_mut() {
+        uniform.time = time.elapsed_seconds();
+    }
+}
@@ -0,0 +1,105 @@
+//! Demonstration of InstanceStream functionality
+//!
+//! Appends a row of cubes to an instance slice each frame
+//! until it holds 100 instances, without rebuilding earlier ones.
+//!
+
+use bevy::prelude::{Camera3dBundle, Query};
+use bevy::{
+    core::Name,
+    math::{Mat4, Quat, Vec3, Vec4},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{default, info, shape::Cube, App, Assets, Commands, Mesh, ResMut, Transform},
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    CustomMaterial, CustomMaterialPlugin, GpuColorMeshInstance, GpuMeshInstance,
+    IndirectRenderingPlugin, InstanceSliceBundle, InstanceStream, InstanceStreamPlugin,
+};
+
+const INSTANCE_COUNT: usize = 100;
+const INSTANCES_PER_FRAME: usize = 10;
+
+// Test streaming instances into a slice
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_plugin(InstanceStreamPlugin::<CustomMaterial>::default());
+
+    app.add_startup_system(setup_instancing);
+
+    app.add_system(append_instances);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CustomMaterial>>,
+    mut commands: Commands,
+) {
+    // Perspective camera
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-15.0, 15.0, 15.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            // Workaround: Pointing straight up or down prevents directional shadow from rendering
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn((
+        Name::new("Streamed Instance Slice"),
+        InstanceSliceBundle {
+            material: materials.add(CustomMaterial::default()),
+            mesh: meshes.add(Cube { size: 0.5 }.into()),
+            ..default()
+        },
+        InstanceStream::<CustomMaterial>::default(),
+    ));
+}
+
+fn append_instances(mut query_stream: Query<&mut InstanceStream<CustomMaterial>>) {
+    for mut stream in query_stream.iter_mut() {
+        if stream.len() >= INSTANCE_COUNT {
+            continue;
+        }
+
+        let row = stream.len() / INSTANCES_PER_FRAME;
+        stream.append((0..INSTANCES_PER_FRAME).map(|column| {
+            let transform =
+                Mat4::from_translation(Vec3::new(column as f32 - 4.5, 0.0, row as f32 - 4.5));
+
+            GpuColorMeshInstance {
+                base: GpuMeshInstance {
+                    transform,
+                    inverse_transpose_model: transform.inverse().transpose(),
+                    ..default()
+                },
+                attribute: Vec4::new(
+                    column as f32 / INSTANCES_PER_FRAME as f32,
+                    row as f32 / INSTANCES_PER_FRAME as f32,
+                    1.0,
+                    1.0,
+                ),
+            }
+        }));
+
+        info!("Streamed {} instances", stream.len());
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera, Camera3dBundle, Commands, Component,
+        EventWriter, Handle, Image, Local, Mesh, PluginGroup, Query, Res, ResMut, Resource,
+        Transform, With, Without,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, BatchedDraws, DrawCall, IndirectRenderingPlugin,
+    InstanceMeta, InstanceSlice, InstanceSliceBundle, InstanceSliceRange,
+};
+
+/// Instances of the slice that stays put in front of the resized one
+const FIXED_COUNT: usize = 50;
+
+/// Instance counts the resized slice alternates between
+const RESIZE_COUNTS: [usize; 5] = [100, 300, 100, 300, 100];
+
+/// Frames to wait for each resize to be drawn before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Slice ranges and drawn instance count of the camera's batches, as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct DrawnSlices(Arc<Mutex<(Vec<InstanceSliceRange>, u32)>>);
+
+#[derive(Debug, Copy, Clone, Component)]
+struct ResizedSlice;
+
+// Test growing and shrinking an instance slice at runtime,
+// by expecting each new instance count to be allocated in place and drawn
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(BasicMaterialPlugin);
+
+    let drawn_slices = DrawnSlices::default();
+    app.insert_resource(drawn_slices.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(resize_slice);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(drawn_slices)
+        .add_system_to_stage(RenderStage::Cleanup, read_drawn_slices);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let mesh = meshes.add(Cube::new(0.5).into());
+
+    // Spawned first so it's allocated at the start of the slice region
+    commands.spawn(InstanceSliceBundle::<BasicMaterial> {
+        material: Handle::<BasicMaterial>::default(),
+        mesh: mesh.clone(),
+        mesh_instance_slice: InstanceSlice {
+            instance_count: FIXED_COUNT,
+        },
+        ..default()
+    });
+
+    commands.spawn((
+        InstanceSliceBundle::<BasicMaterial> {
+            material: Handle::<BasicMaterial>::default(),
+            mesh,
+            mesh_instance_slice: InstanceSlice {
+                instance_count: RESIZE_COUNTS[0],
+            },
+            ..default()
+        },
+        ResizedSlice,
+    ));
+
+    let size = Extent3d {
+        width: 64,
+        height: 64,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("resize slice target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(images.add(image)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn read_drawn_slices(
+    query_instance_meta: Query<&InstanceMeta<BasicMaterial>>,
+    drawn_slices: Res<DrawnSlices>,
+) {
+    let mut ranges = vec![];
+    let mut drawn = 0;
+
+    for instance_meta in query_instance_meta.iter() {
+        for instance_batch in instance_meta.instance_batches.values() {
+            ranges.extend(instance_batch.instance_slice_ranges.values().copied());
+        }
+
+        for batched_instances in instance_meta.batched_instances.values().flatten() {
+            drawn += match &batched_instances.draws {
+                BatchedDraws::Indirect(indirect_data) => indirect_data
+                    .indirects
+                    .iter()
+                    .map(|draw| draw.instance_count())
+                    .sum(),
+                BatchedDraws::Direct(draw) => draw.instance_count(),
+            };
+        }
+    }
+
+    ranges.sort_unstable_by_key(|range| range.offset);
+    *drawn_slices.0.lock().unwrap() = (ranges, drawn);
+}
+
+/// Moves on to the next instance count once the current one is drawn at the resized slice's offset
+fn resize_slice(
+    drawn_slices: Res<DrawnSlices>,
+    mut query_resized: Query<&mut InstanceSlice, With<ResizedSlice>>,
+    query_fixed: Query<&InstanceSliceRange, Without<ResizedSlice>>,
+    mut step: Local<usize>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let instance_count = match RESIZE_COUNTS.get(*step) {
+        Some(instance_count) => *instance_count as u64,
+        None => return,
+    };
+    let expected = [
+        InstanceSliceRange {
+            offset: 0,
+            instance_count: FIXED_COUNT as u64,
+        },
+        InstanceSliceRange {
+            offset: FIXED_COUNT as u64,
+            instance_count,
+        },
+    ];
+
+    let (ranges, drawn) = drawn_slices.0.lock().unwrap().clone();
+    let fixed_offsets = query_fixed
+        .iter()
+        .map(|range| range.offset)
+        .collect::<Vec<_>>();
+
+    if ranges == expected
+        && drawn as u64 == FIXED_COUNT as u64 + instance_count
+        && fixed_offsets == [0]
+    {
+        info!(
+            "Drew {instance_count} resized slice instances in place after {} frames",
+            *frame
+        );
+
+        *step += 1;
+        *frame = 0;
+
+        match RESIZE_COUNTS.get(*step) {
+            Some(instance_count) => {
+                query_resized.single_mut().instance_count = *instance_count;
+            }
+            None => exit.send(AppExit),
+        }
+
+        return;
+    }
+
+    if *frame > MAX_FRAMES {
+        panic!(
+            "Expected {expected:?} drawing {} instances, got {ranges:?} drawing {drawn}",
+            FIXED_COUNT as u64 + instance_count
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Demonstration of a single compute-driven InstanceSlice drawn by two materials
+//!
+//! The source slice is simulated with CustomMaterial,
+//! and its instances are shared with a TextureMaterial slice drawn at twice the scale.
+//!
+
+use bevy::{
+    ecs::system::lifetimeless::Read,
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, shape::Cube, App, AssetServer, Assets, Camera3dBundle, Commands, Component, Mesh,
+        Query, Res, ResMut, Transform,
+    },
+    render::{
+        extract_component::ExtractComponent,
+        render_resource::{AsBindGroup, ShaderRef},
+    },
+    time::Time,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    ColorMeshInstance, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    InstanceCompute, InstanceComputePlugin, InstanceSlice, InstanceSliceBundle,
+    SharedInstanceSlice, TextureMaterial, TextureMaterialPlugin,
+};
+
+const INSTANCE_COUNT: usize = 200;
+
+// Test sharing compute output between materials
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin)
+        .add_plugin(TextureMaterialPlugin);
+
+    app.add_plugin(InstanceComputePlugin::<RadialSineInstances>::default());
+
+    app.add_startup_system(setup_instancing);
+
+    app.add_system(instance_compute_time);
+
+    app.run()
+}
+
+#[derive(Debug, Default, Copy, Clone, Component, AsBindGroup)]
+pub struct RadialSineInstances {
+    #[uniform(0)]
+    time: f32,
+    #[uniform(0)]
+    normal: Vec3,
+    #[uniform(0)]
+    tangent: Vec3,
+    #[uniform(0)]
+    tint: Vec3,
+}
+
+impl From<&RadialSineInstances> for () {
+    fn from(_: &RadialSineInstances) -> Self {}
+}
+
+impl ExtractComponent for RadialSineInstances {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+impl InstanceCompute for RadialSineInstances {
+    type Instance = ColorMeshInstance;
+
+    fn shader() -> ShaderRef {
+        "shader/radial_sine.wgsl".into()
+    }
+}
+
+fn setup_instancing(
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut texture_materials: ResMut<Assets<TextureMaterial>>,
+    mut commands: Commands,
+) {
+    // Perspective camera
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-50.0, 50.0, 50.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    let mesh_cube = meshes.add(Cube::default().into());
+    let mesh_small_cube = meshes.add(Cube::new(0.5).into());
+
+    // Simulated slice, drawn with the default CustomMaterial
+    let source = commands
+        .spawn((
+            InstanceSliceBundle::<CustomMaterial> {
+                mesh: mesh_cube,
+                mesh_instance_slice: InstanceSlice {
+                    instance_count: INSTANCE_COUNT,
+                },
+                ..default()
+            },
+            RadialSineInstances {
+                tint: Vec3::new(1.0, 1.0, 1.0),
+                normal: Vec3::X,
+                tangent: -Vec3::Y,
+                ..default()
+            },
+        ))
+        .id();
+
+    // Same instances, drawn with a textured material and a different mesh
+    let material_texture = texture_materials.add(TextureMaterial {
+        texture: asset_server.load("texture/text_smiley.png"),
+        ..default()
+    });
+
+    commands.spawn((
+        InstanceSliceBundle {
+            material: material_texture,
+            mesh: mesh_small_cube,
+            mesh_instance_slice: InstanceSlice {
+                instance_count: INSTANCE_COUNT,
+            },
+            ..default()
+        },
+        SharedInstanceSlice(source),
+    ));
+}
+
+fn instance_compute_time(time: Res<Time>, mut query_uniform: Query<&mut RadialSineInstances>) {
+    for mut uniform in query_uniform.iter_mut() {
+        uniform.time = time.elapsed_seconds();
+    }
+}
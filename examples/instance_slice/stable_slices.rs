@@ -0,0 +1,170 @@
+//! Demonstration of stable InstanceSlice offsets
+//!
+//! Replaces the oldest of several seeded slices each second with one of a different length.
+//! Surviving slices keep their region of the instance buffer, and thus their data,
+//! while new slices reuse the ranges freed by removed ones.
+//!
+
+use bevy::prelude::{Camera3dBundle, Component, Entity, Local, Query, Res};
+use bevy::time::Time;
+use bevy::utils::HashMap;
+use bevy::{
+    core::Name,
+    math::{Mat4, Quat, Vec3, Vec4},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, info, shape::Cube, warn, App, Assets, Commands, Handle, Mesh, ResMut, Resource,
+        Transform,
+    },
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    CustomMaterial, CustomMaterialPlugin, GpuColorMeshInstance, GpuMeshInstance,
+    IndirectRenderingPlugin, InstanceSlice, InstanceSliceBundle, InstanceSliceRange,
+    InstanceSliceSeed, InstanceSliceSeedPlugin,
+};
+
+const SLICE_COUNT: usize = 8;
+
+// Test adding and removing slices without disturbing the others
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_plugin(InstanceSliceSeedPlugin::<CustomMaterial>::default());
+
+    app.add_startup_system(setup_instancing);
+
+    app.add_system(replace_oldest_slice)
+        .add_system(check_slice_offsets);
+
+    app.run()
+}
+
+#[derive(Resource)]
+struct SliceAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<CustomMaterial>,
+}
+
+/// Index of the row a slice was spawned for
+#[derive(Debug, Copy, Clone, Component)]
+struct SliceRow(usize);
+
+fn spawn_slice_row(commands: &mut Commands, slice_assets: &SliceAssets, row: usize) {
+    // Vary lengths so freed ranges don't always fit their replacements
+    let instance_count = 4 + (row * 7) % 13;
+    let z = (row % SLICE_COUNT) as f32 - SLICE_COUNT as f32 / 2.0;
+
+    let seed = (0..instance_count).map(|x| {
+        let transform = Mat4::from_translation(Vec3::new(x as f32 - 8.0, 0.0, z));
+
+        GpuColorMeshInstance {
+            base: GpuMeshInstance {
+                transform,
+                inverse_transpose_model: transform.inverse().transpose(),
+                ..default()
+            },
+            attribute: Vec4::new(
+                (row % 3) as f32 / 2.0,
+                x as f32 / instance_count as f32,
+                1.0,
+                1.0,
+            ),
+        }
+    });
+
+    commands.spawn((
+        Name::new(format!("Instance Slice Row {row}")),
+        SliceRow(row),
+        InstanceSliceBundle {
+            material: slice_assets.material.clone(),
+            mesh: slice_assets.mesh.clone(),
+            mesh_instance_slice: InstanceSlice { instance_count },
+            ..default()
+        },
+        InstanceSliceSeed::<CustomMaterial>::new(seed),
+    ));
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CustomMaterial>>,
+    mut commands: Commands,
+) {
+    // Perspective camera
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-15.0, 15.0, 15.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            // Workaround: Pointing straight up or down prevents directional shadow from rendering
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    // Share a mesh and material so all slices land in the same instance buffer
+    let slice_assets = SliceAssets {
+        mesh: meshes.add(Cube { size: 0.5 }.into()),
+        material: materials.add(CustomMaterial::default()),
+    };
+
+    for row in 0..SLICE_COUNT {
+        spawn_slice_row(&mut commands, &slice_assets, row);
+    }
+
+    commands.insert_resource(slice_assets);
+}
+
+fn replace_oldest_slice(
+    time: Res<Time>,
+    slice_assets: Res<SliceAssets>,
+    query_slice_row: Query<(Entity, &SliceRow)>,
+    mut commands: Commands,
+) {
+    let second = time.elapsed_seconds() as usize;
+    if second == (time.elapsed_seconds() - time.delta_seconds()) as usize {
+        return;
+    }
+
+    let (entity, oldest) =
+        if let Some(oldest) = query_slice_row.iter().min_by_key(|(_, SliceRow(row))| *row) {
+            oldest
+        } else {
+            return;
+        };
+
+    commands.entity(entity).despawn();
+    spawn_slice_row(&mut commands, &slice_assets, oldest.0 + SLICE_COUNT);
+}
+
+fn check_slice_offsets(
+    query_slice_range: Query<(Entity, &SliceRow, &InstanceSliceRange)>,
+    mut offsets: Local<HashMap<Entity, u64>>,
+) {
+    offsets.retain(|entity, _| query_slice_range.contains(*entity));
+
+    for (entity, SliceRow(row), slice_range) in query_slice_range.iter() {
+        match offsets.insert(entity, slice_range.offset) {
+            Some(offset) if offset != slice_range.offset => warn!(
+                "Slice row {row} moved from offset {offset} to {}",
+                slice_range.offset
+            ),
+            None => info!("Slice row {row} allocated at {slice_range:?}"),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera, Camera3dBundle, Color, Commands,
+        EventWriter, Handle, Image, Local, Mesh, PluginGroup, Query, Res, ResMut, Resource,
+        Transform,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin, InstanceId, InstanceMeta,
+    InstancedScene, InstancedSceneInstance, InstancedScenePlugin,
+};
+
+/// Instances added when the scene is spawned
+const INITIAL_COUNT: usize = 100;
+
+/// Instances removed, then added back into the freed slots
+const REMOVED_COUNT: usize = 40;
+const READDED_COUNT: usize = 20;
+
+/// Frames to wait for each change to be drawn before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Instances in the camera's batches, as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct DrawnInstances(Arc<Mutex<usize>>);
+
+// Test adding and removing instances of a pooled scene,
+// by expecting the drawn instance count to follow the scene without growing its pool
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(CustomMaterialPlugin)
+    .add_plugin(InstancedScenePlugin::<CustomMaterial>::default());
+
+    let drawn_instances = DrawnInstances::default();
+    app.insert_resource(drawn_instances.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(update_scene);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(drawn_instances)
+        .add_system_to_stage(RenderStage::Cleanup, read_drawn_instances);
+
+    app.run()
+}
+
+fn grid_transform(i: usize) -> Transform {
+    Transform::from_xyz((i % 10) as f32 - 5.0, 0.0, (i / 10) as f32 - 5.0)
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let mut scene = InstancedScene::new(
+        meshes.add(Cube::new(0.25).into()),
+        Handle::<CustomMaterial>::default(),
+    );
+
+    for i in 0..INITIAL_COUNT {
+        scene.add(grid_transform(i), Color::WHITE);
+    }
+
+    commands.spawn(scene);
+
+    let size = Extent3d {
+        width: 64,
+        height: 64,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("instanced scene target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(images.add(image)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 20.0, 20.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn read_drawn_instances(
+    query_instance_meta: Query<&InstanceMeta<CustomMaterial>>,
+    drawn_instances: Res<DrawnInstances>,
+) {
+    *drawn_instances.0.lock().unwrap() = query_instance_meta
+        .iter()
+        .flat_map(|instance_meta| instance_meta.instance_batches.values())
+        .map(|instance_batch| instance_batch.instances.len())
+        .sum();
+}
+
+/// Removes and re-adds instances once the initial scene is drawn,
+/// then clears it once the smaller scene is drawn from the same pool
+fn update_scene(
+    drawn_instances: Res<DrawnInstances>,
+    mut query_scene: Query<&mut InstancedScene<CustomMaterial>>,
+    query_pool: Query<&InstancedSceneInstance>,
+    mut removed: Local<Vec<InstanceId>>,
+    mut step: Local<usize>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let mut scene = query_scene.single_mut();
+    let drawn = *drawn_instances.0.lock().unwrap();
+    let pooled = query_pool.iter().count();
+
+    let expected = match *step {
+        0 => INITIAL_COUNT,
+        1 => INITIAL_COUNT - REMOVED_COUNT + READDED_COUNT,
+        _ => 0,
+    };
+
+    if drawn != expected || pooled != INITIAL_COUNT || scene.len() != expected {
+        if *frame > MAX_FRAMES {
+            panic!(
+                "Expected {expected} instances drawn from a pool of {INITIAL_COUNT}, \
+                got {drawn} drawn from {pooled}"
+            );
+        }
+        return;
+    }
+
+    info!(
+        "Drew {drawn} instances from a pool of {pooled} after {} frames",
+        *frame
+    );
+
+    *frame = 0;
+    *step += 1;
+
+    match *step {
+        1 => {
+            *removed = scene.ids().take(REMOVED_COUNT).collect();
+            for id in removed.iter() {
+                assert!(scene.remove(*id));
+            }
+
+            for i in 0..READDED_COUNT {
+                let id = scene.add(grid_transform(i), Color::RED);
+                assert!(!removed.contains(&id), "Reused slot kept a removed id");
+            }
+
+            for id in removed.iter() {
+                assert!(!scene.contains(*id));
+                assert!(!scene.set_transform(*id, Transform::IDENTITY));
+            }
+
+            assert_eq!(scene.capacity(), INITIAL_COUNT);
+        }
+        2 => scene.clear(),
+        _ => exit.send(AppExit),
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    prelude::{
+        default, info, App, Assets, Camera, Camera3dBundle, Color, Commands, EventWriter, Image,
+        Local, PluginGroup, Query, Res, ResMut, Resource, Transform,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    CustomMaterial, IndirectRenderingPlugin, InstanceMeta, InstancedShapes, InstancedShapesPlugin,
+};
+
+/// Shapes of each type drawn per frame, halved once they've all been seen
+const SHAPE_COUNT: usize = 1000;
+
+/// Frames to wait for each shape count to be drawn before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Slice instances of the camera's shape batches, as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct DrawnShapes(Arc<Mutex<u64>>);
+
+// Test drawing immediate-mode shapes, by expecting every shape drawn in a frame to be instanced,
+// and shapes from earlier frames to be cleared rather than accumulating
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(InstancedShapesPlugin);
+
+    let drawn_shapes = DrawnShapes::default();
+    app.insert_resource(drawn_shapes.clone());
+
+    app.add_startup_system(setup_camera);
+    app.add_system(draw_shapes);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(drawn_shapes)
+        .add_system_to_stage(RenderStage::Cleanup, read_drawn_shapes);
+
+    app.run()
+}
+
+fn setup_camera(mut images: ResMut<Assets<Image>>, mut commands: Commands) {
+    let size = Extent3d {
+        width: 64,
+        height: 64,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("instanced shapes target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(images.add(image)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 40.0, 40.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn read_drawn_shapes(
+    query_instance_meta: Query<&InstanceMeta<CustomMaterial>>,
+    drawn_shapes: Res<DrawnShapes>,
+) {
+    *drawn_shapes.0.lock().unwrap() = query_instance_meta
+        .iter()
+        .flat_map(|instance_meta| instance_meta.instance_batches.values())
+        .flat_map(|instance_batch| instance_batch.instance_slice_ranges.values())
+        .map(|range| range.instance_count)
+        .sum();
+}
+
+/// Draws a grid of cubes and spheres each frame, halving it once the full grid is drawn
+fn draw_shapes(
+    drawn_shapes: Res<DrawnShapes>,
+    mut shapes: ResMut<InstancedShapes>,
+    mut shape_count: Local<Option<usize>>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let count = *shape_count.get_or_insert(SHAPE_COUNT);
+    let drawn = *drawn_shapes.0.lock().unwrap();
+
+    if drawn == count as u64 * 2 {
+        info!(
+            "Drew {count} cubes and {count} spheres after {} frames",
+            *frame
+        );
+
+        if count == SHAPE_COUNT / 2 {
+            exit.send(AppExit);
+            return;
+        }
+
+        *shape_count = Some(SHAPE_COUNT / 2);
+        *frame = 0;
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected {} shape instances, got {drawn}", count * 2);
+    }
+
+    let count = shape_count.unwrap();
+    let side = (count as f32).sqrt().ceil() as usize;
+
+    for i in 0..count {
+        let position = Vec3::new(
+            (i % side) as f32 - side as f32 / 2.0,
+            0.0,
+            (i / side) as f32 - side as f32 / 2.0,
+        );
+        let color = Color::hsl(i as f32 / count as f32 * 360.0, 0.8, 0.5);
+
+        shapes.draw_instanced_box(
+            position - Vec3::splat(0.3),
+            position + Vec3::splat(0.3),
+            color,
+        );
+        shapes.draw_instanced_sphere(position + Vec3::Y, 0.3, color);
+    }
+}
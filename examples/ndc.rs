@@ -0,0 +1,75 @@
+use bevy::{
+    math::Vec3,
+    prelude::{
+        default, shape::Quad, App, Assets, Camera3dBundle, Commands, Mesh, ResMut, SpatialBundle,
+        Transform,
+    },
+    reflect::TypeUuid,
+    render::{render_resource::AsBindGroup, view::NoFrustumCulling},
+};
+
+use bevy_instancing::prelude::{
+    AsBatch, IndirectRenderingPlugin, InstancedMaterialPlugin, MaterialInstanced,
+    MeshInstanceBundle, NdcInstance, NoInstanceCulling,
+};
+
+/// Material that renders with the default instanced shader, positioned in NDC
+#[derive(Debug, Default, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "3c1f0e0a-1b8e-4d55-9d0c-7d9a3f2b6e41"]
+struct NdcMaterial {}
+
+impl From<&NdcMaterial> for () {
+    fn from(_: &NdcMaterial) -> Self {}
+}
+
+impl AsBatch for NdcMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for NdcMaterial {
+    type Instance = NdcInstance;
+}
+
+// Test instances positioned directly in clip space, i.e. for HUD elements
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(InstancedMaterialPlugin::<NdcMaterial>::default());
+
+    app.add_startup_system(setup_instancing);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<NdcMaterial>>,
+    mut commands: Commands,
+) {
+    let mesh_quad = meshes.add(Quad::default().into());
+    let material = materials.add(NdcMaterial {});
+
+    // A row of quads along the bottom of the screen, unaffected by the camera
+    for i in 0..8 {
+        commands.spawn((
+            MeshInstanceBundle::<NdcMaterial> {
+                mesh: mesh_quad.clone(),
+                material: material.clone(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_xyz(-0.875 + i as f32 * 0.25, -0.8, 0.5)
+                        .with_scale(Vec3::new(0.2, 0.2, 1.0)),
+                    ..default()
+                },
+            },
+            NoFrustumCulling,
+            NoInstanceCulling,
+        ));
+    }
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 10.0),
+        ..default()
+    });
+}
@@ -0,0 +1,102 @@
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{AlphaMode, DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, shape::Cube, App, Assets, Camera3dBundle, Color, Commands, Handle, Mesh, ResMut,
+        SpatialBundle, Transform,
+    },
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    MeshInstanceBundle,
+};
+
+// Compare weighted blended order-independent transparency (right)
+// against sorted alpha blending (left) for intersecting transparent cubes
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CustomMaterial>>,
+    mut commands: Commands,
+) {
+    let mesh_cube = meshes.add(Cube::default().into());
+
+    let material_sorted = materials.add(CustomMaterial {
+        alpha_mode: AlphaMode::Blend,
+        cull_mode: None,
+        ..default()
+    });
+
+    let material_oit = materials.add(CustomMaterial {
+        alpha_mode: AlphaMode::Blend,
+        cull_mode: None,
+        order_independent_transparency: true,
+    });
+
+    let colors = [
+        Color::rgba(1.0, 0.0, 0.0, 0.5),
+        Color::rgba(0.0, 1.0, 0.0, 0.5),
+        Color::rgba(0.0, 0.0, 1.0, 0.5),
+    ];
+
+    for (material, x) in [(material_sorted, -2.0), (material_oit, 2.0)] {
+        spawn_cubes(&mut commands, &mesh_cube, &material, x, &colors);
+    }
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 3.0, 8.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Spawns a set of mutually intersecting cubes, which no per-batch sort order can draw correctly
+fn spawn_cubes(
+    commands: &mut Commands,
+    mesh: &Handle<Mesh>,
+    material: &Handle<CustomMaterial>,
+    x: f32,
+    colors: &[Color],
+) {
+    for (i, color) in colors.iter().enumerate() {
+        let angle = i as f32 * std::f32::consts::TAU / colors.len() as f32;
+        let offset = Vec3::new(angle.cos(), 0.0, angle.sin()) * 0.4;
+
+        commands.spawn(ColorInstanceBundle {
+            instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_translation(Vec3::X * x + offset)
+                        .with_rotation(Quat::from_rotation_y(angle)),
+                    ..default()
+                },
+            },
+            mesh_instance_color: (*color).into(),
+        });
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::{
+    math::Vec3,
+    prelude::{default, App, Camera3dBundle, Color, Commands, SpatialBundle, Transform},
+};
+
+use bevy_instancing::prelude::{
+    IndirectRenderingPlugin, InstanceColor, InstancePointSize, PointCloudBundle, PointCloudPlugin,
+};
+
+const POINTS_PER_AXIS: usize = 64;
+
+// Test point sprites expanded from instances without mesh vertices
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(PointCloudPlugin);
+
+    app.add_startup_system(setup_instancing);
+
+    app.run()
+}
+
+fn setup_instancing(mut commands: Commands) {
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-60.0, 60.0, 60.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    // Fill a sphere with points, colored by position
+    let half_extent = POINTS_PER_AXIS as f32 / 2.0;
+    let mut points = vec![];
+    for x in 0..POINTS_PER_AXIS {
+        for y in 0..POINTS_PER_AXIS {
+            for z in 0..POINTS_PER_AXIS {
+                let position = Vec3::new(x as f32, y as f32, z as f32) - half_extent;
+                if position.length() > half_extent {
+                    continue;
+                }
+
+                let color = position / POINTS_PER_AXIS as f32 + 0.5;
+
+                points.push(PointCloudBundle {
+                    color: InstanceColor(Color::rgb(color.x, color.y, color.z)),
+                    size: InstancePointSize(0.5),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_translation(position),
+                        ..default()
+                    },
+                    ..default()
+                });
+            }
+        }
+    }
+
+    commands.spawn_batch(points);
+}
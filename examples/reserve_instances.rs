@@ -0,0 +1,194 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera, Camera3dBundle, Commands, EventWriter,
+        Handle, Image, IntoSystemDescriptor, Local, Mesh, PluginGroup, Res, ResMut, Resource,
+        SpatialBundle, Transform,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            BufferId, Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    prepare_instance_batches::{self, ViewInstanceData},
+    BasicMaterial, BasicMaterialPlugin, GpuInstances, IndirectRenderingPlugin, MeshInstanceBundle,
+};
+
+const INITIAL_COUNT: usize = 16;
+
+const FILL_COUNT: usize = 1000;
+
+/// Instances reserved ahead of time, enough for the initial and fill instances together
+const RESERVED_CAPACITY: usize = 1024;
+
+/// Frames to wait for instance batches to be prepared before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Length, capacity and buffer of the view's instance buffer, as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct InstanceBufferState(Arc<Mutex<Option<(usize, usize, BufferId)>>>);
+
+// Test that reserved instance buffers are filled in place, by reserving capacity
+// for more instances than are spawned, spawning up to it, and expecting the same buffer
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(BasicMaterialPlugin);
+
+    let state = InstanceBufferState::default();
+    app.insert_resource(state.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_instance_buffer);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(state)
+        .add_system_to_stage(
+            RenderStage::Prepare,
+            reserve_instance_buffers.before(prepare_instance_batches::system::<BasicMaterial>),
+        )
+        .add_system_to_stage(RenderStage::Cleanup, read_instance_buffer);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    spawn_instances(
+        &mut commands,
+        meshes.add(Cube::new(0.5).into()),
+        INITIAL_COUNT,
+    );
+
+    let size = Extent3d {
+        width: 64,
+        height: 64,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("reserve instances target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(images.add(image)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 15.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn spawn_instances(commands: &mut Commands, mesh: Handle<Mesh>, count: usize) {
+    for _ in 0..count {
+        commands.spawn(MeshInstanceBundle::<BasicMaterial> {
+            mesh: mesh.clone(),
+            material: Handle::<BasicMaterial>::default(),
+            spatial_bundle: SpatialBundle::default(),
+        });
+    }
+}
+
+/// Grows every instance buffer to [`RESERVED_CAPACITY`] before instances are written to it
+fn reserve_instance_buffers(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut view_instance_data: ResMut<ViewInstanceData<BasicMaterial>>,
+) {
+    for gpu_instances in view_instance_data
+        .values_mut()
+        .flat_map(|instance_data| instance_data.values_mut())
+    {
+        gpu_instances.reserve(&render_device, &render_queue, RESERVED_CAPACITY);
+    }
+}
+
+fn read_instance_buffer(
+    view_instance_data: Res<ViewInstanceData<BasicMaterial>>,
+    state: Res<InstanceBufferState>,
+) {
+    *state.0.lock().unwrap() = view_instance_data
+        .values()
+        .flat_map(|instance_data| instance_data.values())
+        .find_map(|gpu_instances| match gpu_instances {
+            GpuInstances::Storage { buffer } => buffer
+                .buffer()
+                .map(|buffer| (gpu_instances.len(), gpu_instances.capacity(), buffer.id())),
+            GpuInstances::Uniform { .. } => None,
+        });
+}
+
+fn check_instance_buffer(
+    state: Res<InstanceBufferState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut reserved_buffer: Local<Option<BufferId>>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+    mut commands: Commands,
+) {
+    *frame += 1;
+
+    let state = *state.0.lock().unwrap();
+
+    match (*reserved_buffer, state) {
+        (None, Some((INITIAL_COUNT, capacity, buffer))) if capacity >= RESERVED_CAPACITY => {
+            info!("Reserved {capacity} instances, spawning {FILL_COUNT} more");
+            spawn_instances(&mut commands, meshes.add(Cube::new(0.5).into()), FILL_COUNT);
+            *reserved_buffer = Some(buffer);
+        }
+        (Some(reserved), Some((len, _, buffer))) if buffer != reserved => {
+            panic!("Expected the reserved instance buffer to be kept, but it was recreated holding {len} instances");
+        }
+        (Some(_), Some((len, capacity, _))) if len == INITIAL_COUNT + FILL_COUNT => {
+            info!(
+                "Filled {len} of {capacity} reserved instances without reallocating after {} frames",
+                *frame
+            );
+            exit.send(AppExit);
+        }
+        _ => (),
+    }
+
+    if *frame > MAX_FRAMES {
+        panic!("Expected the reserved instance buffer to be filled, got {state:?}");
+    }
+}
@@ -0,0 +1,184 @@
+//! Demonstration of ScreenMaterial functionality
+//!
+//! Renders a spinning cube from several angles into offscreen images,
+//! and displays each feed on one of a bank of instanced monitors.
+//!
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    math::{Quat, Vec3},
+    pbr::{PbrBundle, PointLight, PointLightBundle, StandardMaterial},
+    prelude::{
+        default,
+        shape::{Cube, Quad},
+        App, Assets, Camera, Camera3d, Camera3dBundle, Color, Commands, Component, Image, Mesh,
+        Query, Res, ResMut, SpatialBundle, Time, Transform, With,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    IndirectRenderingPlugin, InstanceSeed, MeshInstanceBundle, ScreenMaterial, ScreenMaterialPlugin,
+};
+
+const FEED_COUNT: usize = 4;
+const FEED_SIZE: u32 = 256;
+const FEED_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+const MONITOR_COLUMNS: usize = 4;
+const MONITOR_ROWS: usize = 3;
+
+// Test sampling per-instance camera feeds
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(ScreenMaterialPlugin);
+
+    app.add_startup_system(setup_instancing);
+
+    app.add_system(spin_subject);
+
+    app.run()
+}
+
+/// Marks the cube filmed by the feed cameras
+#[derive(Debug, Default, Copy, Clone, Component)]
+struct Subject;
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    mut screen_materials: ResMut<Assets<ScreenMaterial>>,
+    mut commands: Commands,
+) {
+    // Subject, only visible to the feed cameras
+    let subject_layer = RenderLayers::layer(1);
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cube { size: 1.0 }.into()),
+            material: standard_materials.add(Color::rgb(0.9, 0.4, 0.1).into()),
+            ..default()
+        },
+        subject_layer,
+        Subject,
+    ));
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            ..default()
+        },
+        transform: Transform::from_xyz(2.0, 4.0, 2.0),
+        ..default()
+    });
+
+    // Feed cameras, circling the subject
+    let size = Extent3d {
+        width: FEED_SIZE,
+        height: FEED_SIZE,
+        ..default()
+    };
+
+    let feeds = (0..FEED_COUNT)
+        .map(|i| {
+            let mut image = Image {
+                texture_descriptor: TextureDescriptor {
+                    label: Some("screen feed"),
+                    size,
+                    dimension: TextureDimension::D2,
+                    format: FEED_FORMAT,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    usage: TextureUsages::TEXTURE_BINDING
+                        | TextureUsages::COPY_DST
+                        | TextureUsages::COPY_SRC
+                        | TextureUsages::RENDER_ATTACHMENT,
+                },
+                ..default()
+            };
+            image.resize(size);
+            let feed = images.add(image);
+
+            let angle = i as f32 / FEED_COUNT as f32 * std::f32::consts::TAU;
+            commands.spawn((
+                Camera3dBundle {
+                    camera: Camera {
+                        target: RenderTarget::Image(feed.clone()),
+                        priority: -1 - i as isize,
+                        ..default()
+                    },
+                    camera_3d: Camera3d {
+                        clear_color: ClearColorConfig::Custom(Color::rgb(
+                            0.1,
+                            0.1 + 0.2 * i as f32,
+                            0.3,
+                        )),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        Quat::from_rotation_y(angle) * Vec3::new(0.0, 1.5, 3.0),
+                    )
+                    .looking_at(Vec3::ZERO, Vec3::Y),
+                    ..default()
+                },
+                subject_layer,
+            ));
+
+            feed
+        })
+        .collect::<Vec<_>>();
+
+    let material = screen_materials.add(ScreenMaterial::new(
+        feeds,
+        FEED_SIZE,
+        FEED_SIZE,
+        FEED_FORMAT,
+        &mut images,
+    ));
+
+    // Monitor bank
+    let mesh_quad = meshes.add(Quad::new((1.0, 1.0).into()).into());
+
+    for x in 0..MONITOR_COLUMNS {
+        for y in 0..MONITOR_ROWS {
+            commands.spawn((
+                MeshInstanceBundle::<ScreenMaterial> {
+                    mesh: mesh_quad.clone(),
+                    material: material.clone(),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_xyz(
+                            x as f32 * 1.1 - (MONITOR_COLUMNS - 1) as f32 * 0.55,
+                            y as f32 * 1.1 - (MONITOR_ROWS - 1) as f32 * 0.55,
+                            0.0,
+                        ),
+                        ..default()
+                    },
+                },
+                InstanceSeed((x + y * MONITOR_COLUMNS) as u32),
+            ));
+        }
+    }
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn spin_subject(time: Res<Time>, mut query: Query<&mut Transform, With<Subject>>) {
+    for mut transform in query.iter_mut() {
+        transform.rotation = Quat::from_rotation_y(time.elapsed_seconds())
+            * Quat::from_rotation_x(time.elapsed_seconds() * 0.5);
+    }
+}
@@ -0,0 +1,201 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    pbr::{LightEntity, Shadow},
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera, Camera3dBundle, Color, Commands,
+        DirectionalLight, DirectionalLightBundle, EventWriter, Image, Local, Mesh, PluginGroup,
+        Query, Res, ResMut, Resource, SpatialBundle, Transform, With,
+    },
+    render::{
+        camera::RenderTarget,
+        render_phase::RenderPhase,
+        render_resource::{
+            Extent3d, PipelineCache, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages,
+        },
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, ExtractedInstances,
+    IndirectRenderingPlugin, MeshInstanceBundle, ShadowTileView,
+};
+
+/// Instances spawned in a row beneath the light
+const INSTANCE_COUNT: usize = 16;
+
+/// Frames to wait for the shadow pass to be drawn before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Shadow phase items across the light's views, as last seen by the render world
+#[derive(Debug, Default, Copy, Clone)]
+struct ShadowItems {
+    /// Items drawing an instanced batch
+    batches: usize,
+    /// Items drawing a single instance entity
+    instances: usize,
+    /// Whether every batch item's pipeline has been compiled
+    ready: bool,
+}
+
+#[derive(Debug, Default, Clone, Resource)]
+struct QueuedShadowItems(Arc<Mutex<ShadowItems>>);
+
+// Test casting shadows from instances,
+// by expecting the light's shadow phase to draw their batches in place of the instance entities
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(CustomMaterialPlugin);
+
+    let queued_shadow_items = QueuedShadowItems::default();
+    app.insert_resource(queued_shadow_items.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_shadow_items);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(queued_shadow_items)
+        .add_system_to_stage(RenderStage::Cleanup, read_shadow_items);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CustomMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let mesh = meshes.add(Cube::new(0.25).into());
+    let material = materials.add(CustomMaterial::default());
+
+    for i in 0..INSTANCE_COUNT {
+        commands.spawn(ColorInstanceBundle {
+            instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_xyz(
+                        i as f32 - INSTANCE_COUNT as f32 * 0.5,
+                        0.0,
+                        0.0,
+                    ),
+                    ..default()
+                },
+            },
+            mesh_instance_color: Color::WHITE.into(),
+        });
+    }
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(1.0, 4.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    let size = Extent3d {
+        width: 64,
+        height: 64,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("shadow casters target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(images.add(image)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn read_shadow_items(
+    pipeline_cache: Res<PipelineCache>,
+    query_light_views: Query<&RenderPhase<Shadow>, With<LightEntity>>,
+    query_batches: Query<&ShadowTileView>,
+    extracted_instances: Res<ExtractedInstances<CustomMaterial>>,
+    queued_shadow_items: Res<QueuedShadowItems>,
+) {
+    let mut shadow_items = ShadowItems {
+        ready: true,
+        ..default()
+    };
+
+    for item in query_light_views
+        .iter()
+        .flat_map(|shadow_phase| shadow_phase.items.iter())
+    {
+        if query_batches.contains(item.entity) {
+            shadow_items.batches += 1;
+            shadow_items.ready &= pipeline_cache.get_render_pipeline(item.pipeline).is_some();
+        } else if extracted_instances.contains_key(&item.entity) {
+            shadow_items.instances += 1;
+        }
+    }
+
+    *queued_shadow_items.0.lock().unwrap() = shadow_items;
+}
+
+/// Expects compiled batch draws and no per-instance draws in the light's shadow phase
+fn check_shadow_items(
+    queued_shadow_items: Res<QueuedShadowItems>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let shadow_items = *queued_shadow_items.0.lock().unwrap();
+
+    assert_eq!(
+        shadow_items.instances, 0,
+        "Instances were drawn individually into the shadow phase"
+    );
+
+    if shadow_items.batches > 0 && shadow_items.ready {
+        info!(
+            "Drew {} instanced shadow batches after {} frames",
+            shadow_items.batches, *frame
+        );
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected compiled instanced shadow batches, got {shadow_items:?}");
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default,
+        shape::{Cube, Icosphere},
+        App, Assets, Camera3dBundle, Color, Commands, Component, Entity, Handle, Mesh, Query, Res,
+        ResMut, SpatialBundle, Time, Transform, With,
+    },
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    MeshInstanceBundle, ShadowOnly,
+};
+
+// Test excluding instances from the main pass with ShadowOnly
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing)
+        .add_system(toggle_shadow_only);
+
+    app.run()
+}
+
+/// Marker for the proxy instance that toggles between visible and shadow-only
+#[derive(Debug, Default, Copy, Clone, Component)]
+struct ShadowProxy;
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh_sphere = meshes.add(
+        Icosphere {
+            radius: 0.5,
+            subdivisions: 4,
+        }
+        .into(),
+    );
+    let mesh_cube = meshes.add(Cube::new(0.9).into());
+
+    // Detailed visible mesh
+    commands.spawn(ColorInstanceBundle {
+        instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+            mesh: mesh_sphere,
+            material: Handle::<CustomMaterial>::default(),
+            spatial_bundle: SpatialBundle {
+                transform: Transform::from_xyz(-1.0, 0.0, 0.0),
+                ..default()
+            },
+        },
+        mesh_instance_color: Color::WHITE.into(),
+    });
+
+    // Low-poly proxy, drawn in red while it isn't shadow-only
+    commands.spawn((
+        ShadowProxy,
+        ShadowOnly,
+        ColorInstanceBundle {
+            instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                mesh: mesh_cube,
+                material: Handle::<CustomMaterial>::default(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_xyz(1.0, 0.0, 0.0),
+                    ..default()
+                },
+            },
+            mesh_instance_color: Color::RED.into(),
+        },
+    ));
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 3.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Alternates the proxy between shadow-only and visible every two seconds,
+/// so it should only ever show up in the color pass every other interval
+fn toggle_shadow_only(
+    time: Res<Time>,
+    query_proxy: Query<(Entity, Option<&ShadowOnly>), With<ShadowProxy>>,
+    mut commands: Commands,
+) {
+    let shadow_only = (time.elapsed_seconds() / 2.0) as u32 % 2 == 0;
+
+    for (entity, marker) in query_proxy.iter() {
+        match (shadow_only, marker.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert(ShadowOnly);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<ShadowOnly>();
+            }
+            _ => {}
+        }
+    }
+}
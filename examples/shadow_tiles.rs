@@ -0,0 +1,154 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    pbr::{
+        DirectionalLight, DirectionalLightBundle, PointLight, PointLightBundle, SpotLight,
+        SpotLightBundle,
+    },
+    prelude::{
+        default, info, shape::Cube, App, Assets, Commands, EventWriter, Handle, Local, Mesh,
+        PluginGroup, Query, Res, ResMut, Resource, SpatialBundle, Transform, With,
+    },
+    render::{RenderApp, RenderStage},
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, InstanceMeta, MeshInstanceBundle,
+    ShadowTile, ShadowTileViews,
+};
+
+/// One directional light, six point light cube faces, and one spot light
+const EXPECTED_TILES: usize = 8;
+
+/// Frames to wait for every tile to be prepared before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Number of extracted shadow tiles, and of those with instances, as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct ShadowTileState(Arc<Mutex<(usize, usize)>>);
+
+// Test that instances are prepared for every shadow tile of several lights,
+// by surrounding a point light with an instance on each axis
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(BasicMaterialPlugin);
+
+    let state = ShadowTileState::default();
+    app.insert_resource(state.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_shadow_tiles);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(state)
+        .add_system_to_stage(RenderStage::Cleanup, read_shadow_tiles);
+
+    app.run()
+}
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh = meshes.add(Cube::new(0.5).into());
+
+    for direction in [
+        Vec3::X,
+        Vec3::NEG_X,
+        Vec3::Y,
+        Vec3::NEG_Y,
+        Vec3::Z,
+        Vec3::NEG_Z,
+    ] {
+        commands.spawn(MeshInstanceBundle::<BasicMaterial> {
+            mesh: mesh.clone(),
+            material: Handle::<BasicMaterial>::default(),
+            spatial_bundle: SpatialBundle {
+                transform: Transform::from_translation(direction * 3.0),
+                ..default()
+            },
+        });
+    }
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 10.0, 0.0)
+            .looking_at(Vec3::new(1.0, 0.0, 1.0), Vec3::Y),
+        ..default()
+    });
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(SpotLightBundle {
+        spot_light: SpotLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn read_shadow_tiles(
+    shadow_tile_views: Res<ShadowTileViews>,
+    query_tiles: Query<&InstanceMeta<BasicMaterial>, With<ShadowTile>>,
+    state: Res<ShadowTileState>,
+) {
+    let prepared = shadow_tile_views
+        .0
+        .values()
+        .filter(|tile_entity| {
+            query_tiles
+                .get(**tile_entity)
+                .map(|instance_meta| !instance_meta.batched_instances.is_empty())
+                .unwrap_or_default()
+        })
+        .count();
+
+    *state.0.lock().unwrap() = (shadow_tile_views.0.len(), prepared);
+}
+
+fn check_shadow_tiles(
+    state: Res<ShadowTileState>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let (tiles, prepared) = *state.0.lock().unwrap();
+
+    if tiles == EXPECTED_TILES && prepared == EXPECTED_TILES {
+        info!(
+            "Prepared instances for all {tiles} shadow tiles after {} frames",
+            *frame
+        );
+        exit.send(AppExit);
+    }
+
+    if *frame > MAX_FRAMES {
+        panic!("Expected instances in each of {EXPECTED_TILES} shadow tiles, got {prepared} of {tiles}");
+    }
+}
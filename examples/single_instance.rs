@@ -0,0 +1,93 @@
+use bevy::{
+    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    input::Input,
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera3dBundle, Color, Commands, Handle, KeyCode,
+        Mesh, Res, ResMut, SpatialBundle, Transform,
+    },
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, ForceBatchGroup,
+    IndirectRenderingPlugin, MeshInstanceBundle, SingleInstanceSettings,
+};
+
+const GRID_SIZE: usize = 32;
+
+// Benchmark the single-instance fast path against indirect drawing
+//
+// Every instance is forced into its own batch. Frame times are logged to the console,
+// and Space toggles between direct draws and one indirect buffer per batch.
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(FrameTimeDiagnosticsPlugin)
+        .add_plugin(LogDiagnosticsPlugin::default())
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing)
+        .add_system(toggle_direct_draw);
+
+    app.run()
+}
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh_cube = meshes.add(Cube::new(0.5).into());
+
+    for x in 0..GRID_SIZE {
+        for z in 0..GRID_SIZE {
+            let position = Vec3::new(x as f32, 0.0, z as f32) - GRID_SIZE as f32 * 0.5;
+
+            commands.spawn((
+                ForceBatchGroup((x * GRID_SIZE + z) as u32),
+                ColorInstanceBundle {
+                    instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                        mesh: mesh_cube.clone(),
+                        material: Handle::<CustomMaterial>::default(),
+                        spatial_bundle: SpatialBundle {
+                            transform: Transform::from_translation(
+                                position * Vec3::new(1.0, 0.0, 1.0),
+                            ),
+                            ..default()
+                        },
+                    },
+                    mesh_instance_color: Color::rgb(
+                        x as f32 / GRID_SIZE as f32,
+                        0.5,
+                        z as f32 / GRID_SIZE as f32,
+                    )
+                    .into(),
+                },
+            ));
+        }
+    }
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 20.0, 30.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn toggle_direct_draw(keys: Res<Input<KeyCode>>, mut settings: ResMut<SingleInstanceSettings>) {
+    if keys.just_pressed(KeyCode::Space) {
+        settings.direct_draw = !settings.direct_draw;
+        info!("Single-instance direct draw: {}", settings.direct_draw);
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::{
+    pbr::{AlphaMode, DirectionalLight, DirectionalLightBundle, PbrBundle, StandardMaterial},
+    prelude::{
+        default, shape::Cube, App, Assets, Camera3dBundle, Color, Commands, Handle, Mesh, Res,
+        ResMut, Resource, Time, Transform, Vec3,
+    },
+};
+
+use bevy_instancing::prelude::{
+    IndirectRenderingPlugin, InstancedStandardMaterialPlugin, NotInstanced,
+};
+
+/// Material whose color is animated, to show instanced copies following modifications
+#[derive(Resource)]
+struct AnimatedMaterial(Handle<StandardMaterial>);
+
+// Test plain PbrBundles being instanced without changes to how they're spawned
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(InstancedStandardMaterialPlugin);
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(animate_material);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    let mesh_cube = meshes.add(Cube::default().into());
+
+    let material_metal = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.8, 0.7, 0.6),
+        metallic: 1.0,
+        perceptual_roughness: 0.3,
+        ..default()
+    });
+
+    let material_glass = materials.add(StandardMaterial {
+        base_color: Color::rgba(0.2, 0.4, 1.0, 0.5),
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+
+    let material_unlit = materials.add(StandardMaterial {
+        base_color: Color::GREEN,
+        unlit: true,
+        ..default()
+    });
+
+    // Hero, drawn by bevy's material pipeline
+    commands.spawn((
+        PbrBundle {
+            mesh: mesh_cube.clone(),
+            material: material_metal.clone(),
+            transform: Transform::from_scale(Vec3::splat(3.0)),
+            ..default()
+        },
+        NotInstanced,
+    ));
+
+    // Copies, drawn by the instanced pipeline
+    for x in -8..=8i32 {
+        for z in -8..=8 {
+            if x == 0 && z == 0 {
+                continue;
+            }
+
+            let material = match (x + z).rem_euclid(3) {
+                0 => material_metal.clone(),
+                1 => material_glass.clone(),
+                _ => material_unlit.clone(),
+            };
+
+            commands.spawn(PbrBundle {
+                mesh: mesh_cube.clone(),
+                material,
+                transform: Transform::from_xyz(x as f32 * 2.0, 0.0, z as f32 * 2.0),
+                ..default()
+            });
+        }
+    }
+
+    commands.insert_resource(AnimatedMaterial(material_unlit));
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 10.0, 0.0)
+            .looking_at(Vec3::new(1.0, 0.0, 1.0), Vec3::Y),
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(30.0, 30.0, 30.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn animate_material(
+    animated_material: Res<AnimatedMaterial>,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if let Some(material) = materials.get_mut(&animated_material.0) {
+        let t = time.elapsed_seconds().sin() * 0.5 + 0.5;
+        material.base_color = Color::rgb(1.0 - t, 1.0, t);
+    }
+}
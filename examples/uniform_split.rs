@@ -0,0 +1,86 @@
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, shape::Cube, App, Assets, Camera3dBundle, Color, Commands, Handle, Mesh, ResMut,
+        SpatialBundle, Transform,
+    },
+    render::settings::{WgpuLimits, WgpuSettings},
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    MeshInstanceBundle, UniformSplitSettings,
+};
+
+const GRID_SIZE: usize = 100;
+
+// Test the warning for batches split into many uniform buffer chunks
+fn main() {
+    let mut app = App::default();
+
+    // Force the uniform buffer fallback
+    app.insert_resource(WgpuSettings {
+        constrained_limits: Some(WgpuLimits {
+            max_storage_buffers_per_shader_stage: 0,
+            ..default()
+        }),
+        ..default()
+    });
+
+    app.insert_resource(UniformSplitSettings { warn_threshold: 2 });
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing);
+
+    app.run()
+}
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh_cube = meshes.add(Cube::new(0.5).into());
+
+    // A single batch large enough to need more than `warn_threshold` uniform chunks
+    for x in 0..GRID_SIZE {
+        for z in 0..GRID_SIZE {
+            let position = Vec3::new(x as f32, 0.0, z as f32) - GRID_SIZE as f32 * 0.5;
+
+            commands.spawn(ColorInstanceBundle {
+                instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                    mesh: mesh_cube.clone(),
+                    material: Handle::<CustomMaterial>::default(),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_translation(position * Vec3::new(1.0, 0.0, 1.0)),
+                        ..default()
+                    },
+                },
+                mesh_instance_color: Color::rgb(
+                    x as f32 / GRID_SIZE as f32,
+                    0.5,
+                    z as f32 / GRID_SIZE as f32,
+                )
+                .into(),
+            });
+        }
+    }
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 60.0, 80.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
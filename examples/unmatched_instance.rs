@@ -0,0 +1,93 @@
+use bevy::{
+    math::{Quat, Vec3},
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, shape::Cube, App, Assets, Camera3dBundle, Color, Commands, Component, Entity,
+        Handle, Mesh, Query, Res, ResMut, SpatialBundle, Time, Transform, With,
+    },
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, CustomMaterial, CustomMaterialPlugin, IndirectRenderingPlugin,
+    InstanceColor, MeshInstanceBundle,
+};
+
+// Test warnings for entities missing the components their material's instance type requires
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(bevy::DefaultPlugins)
+        .add_plugin(IndirectRenderingPlugin)
+        .add_plugin(CustomMaterialPlugin);
+
+    app.add_startup_system(setup_instancing)
+        .add_system(remove_instance_color);
+
+    app.run()
+}
+
+/// Marker for the instance that loses its color after a few seconds
+#[derive(Debug, Default, Copy, Clone, Component)]
+struct Decolored;
+
+fn setup_instancing(mut meshes: ResMut<Assets<Mesh>>, mut commands: Commands) {
+    let mesh_cube = meshes.add(Cube::default().into());
+
+    // CustomMaterial instances require an InstanceColor, so this is warned about on spawn
+    commands.spawn(MeshInstanceBundle::<CustomMaterial> {
+        mesh: mesh_cube.clone(),
+        material: Handle::<CustomMaterial>::default(),
+        spatial_bundle: SpatialBundle {
+            transform: Transform::from_xyz(-1.5, 0.0, 0.0),
+            ..default()
+        },
+    });
+
+    // Valid until its color is removed, which only debug builds warn about
+    commands.spawn((
+        Decolored,
+        ColorInstanceBundle {
+            instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                mesh: mesh_cube,
+                material: Handle::<CustomMaterial>::default(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_xyz(1.5, 0.0, 0.0),
+                    ..default()
+                },
+            },
+            mesh_instance_color: Color::RED.into(),
+        },
+    ));
+
+    // Directional Light
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 4000.,
+            ..default()
+        },
+        transform: Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2 * 0.6),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 5.0, 8.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn remove_instance_color(
+    time: Res<Time>,
+    query_decolored: Query<Entity, (With<Decolored>, With<InstanceColor>)>,
+    mut commands: Commands,
+) {
+    if time.elapsed_seconds() < 3.0 {
+        return;
+    }
+
+    for entity in query_decolored.iter() {
+        commands.entity(entity).remove::<InstanceColor>();
+    }
+}
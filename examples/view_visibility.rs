@@ -0,0 +1,153 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    math::Vec3,
+    prelude::{
+        default, info, shape::Cube, App, Assets, Camera, Camera3dBundle, Color, Commands,
+        EventWriter, Handle, Image, Local, Mesh, PluginGroup, Res, ResMut, Resource, SpatialBundle,
+        Transform,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+use bevy_instancing::prelude::{
+    prepare_instance_batches::ViewInstanceData, ColorInstanceBundle, CustomMaterial,
+    CustomMaterialPlugin, IndirectRenderingPlugin, MeshInstanceBundle,
+};
+
+/// Instances in front of each camera, and out of view of the other
+const VIEW_INSTANCE_COUNTS: [usize; 2] = [1, 3];
+
+/// Frames to wait for instance buffers to be prepared before giving up
+const MAX_FRAMES: u32 = 300;
+
+/// Sorted instance buffer lengths of each view, as last seen by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct ViewInstanceCounts(Arc<Mutex<Option<Vec<usize>>>>);
+
+// Test that each view's instance buffers only hold the instances visible to it,
+// using two headless cameras looking at disjoint sets of instances
+fn main() {
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(IndirectRenderingPlugin)
+    .add_plugin(CustomMaterialPlugin);
+
+    let counts = ViewInstanceCounts::default();
+    app.insert_resource(counts.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_view_instance_counts);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(counts)
+        .add_system_to_stage(RenderStage::Cleanup, read_view_instance_counts);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let mesh_cube = meshes.add(Cube::new(0.5).into());
+
+    for (count, x) in VIEW_INSTANCE_COUNTS.into_iter().zip([-10.0, 10.0]) {
+        for i in 0..count {
+            commands.spawn(ColorInstanceBundle {
+                instance_bundle: MeshInstanceBundle::<CustomMaterial> {
+                    mesh: mesh_cube.clone(),
+                    material: Handle::<CustomMaterial>::default(),
+                    spatial_bundle: SpatialBundle {
+                        transform: Transform::from_xyz(x, i as f32 - 1.0, 0.0),
+                        ..default()
+                    },
+                },
+                mesh_instance_color: Color::WHITE.into(),
+            });
+        }
+
+        let size = Extent3d {
+            width: 64,
+            height: 64,
+            ..default()
+        };
+
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: Some("view visibility target"),
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Bgra8UnormSrgb,
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+            },
+            ..default()
+        };
+        image.resize(size);
+
+        commands.spawn(Camera3dBundle {
+            camera: Camera {
+                target: RenderTarget::Image(images.add(image)),
+                ..default()
+            },
+            transform: Transform::from_xyz(x, 0.0, 5.0).looking_at(Vec3::new(x, 0.0, 0.0), Vec3::Y),
+            ..default()
+        });
+    }
+}
+
+/// Records the number of instances in each view's instance buffers
+fn read_view_instance_counts(
+    view_instance_data: Res<ViewInstanceData<CustomMaterial>>,
+    counts: Res<ViewInstanceCounts>,
+) {
+    let mut view_counts = view_instance_data
+        .values()
+        .map(|batches| batches.values().map(|instances| instances.len()).sum())
+        .collect::<Vec<usize>>();
+    view_counts.sort();
+
+    *counts.0.lock().unwrap() = Some(view_counts);
+}
+
+fn check_view_instance_counts(
+    counts: Res<ViewInstanceCounts>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let counts = counts.0.lock().unwrap().clone();
+
+    if counts.as_deref() == Some(&VIEW_INSTANCE_COUNTS[..]) {
+        info!("View instance buffers diverged after {} frames", *frame);
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected per-view instance counts {VIEW_INSTANCE_COUNTS:?}, got {counts:?}");
+    }
+}
@@ -1,8 +1,12 @@
 use bevy::{
     ecs::reflect::ReflectComponent,
-    prelude::{Color, Component, Deref, DerefMut, Reflect},
+    prelude::{
+        Color, Commands, Component, Deref, DerefMut, Entity, Handle, Query, Reflect, With, Without,
+    },
 };
 
+use crate::instancing::material::material_instanced::MaterialInstanced;
+
 #[derive(Debug, Default, Copy, Clone, Deref, DerefMut, Component, Reflect)]
 #[reflect(Component)]
 pub struct InstanceColor(pub Color);
@@ -18,3 +22,17 @@ impl From<InstanceColor> for Color {
         color.0
     }
 }
+
+/// Inserts a white [`InstanceColor`] on entities using material `M` that lack one,
+/// including those whose [`InstanceData::color`](crate::prelude::InstanceData::color) was cleared
+///
+/// Not added by default; add it to an app to render such entities
+/// instead of warning that they are missing components.
+pub fn insert_missing_instance_color<M: MaterialInstanced>(
+    query_material: Query<Entity, (With<Handle<M>>, Without<InstanceColor>)>,
+    mut commands: Commands,
+) {
+    for entity in query_material.iter() {
+        commands.entity(entity).insert(InstanceColor(Color::WHITE));
+    }
+}
@@ -3,59 +3,46 @@ pub mod mesh_instance_color;
 pub mod plugin;
 
 use bevy::{
-    ecs::{system::lifetimeless::Read, query::ROQueryItem},
-    math::{Mat4, Vec4},
-    prelude::{default, Component}, render::render_resource::ShaderType, 
+    ecs::{query::ROQueryItem, system::lifetimeless::Read},
+    math::Vec4,
+    render::render_resource::ShaderSize,
 };
-use crate::prelude::{GpuMeshInstance, Instance, InstanceColor, MeshInstance};
 
-#[derive(Debug, Default, Clone, PartialEq, Component)]
-pub struct ColorMeshInstance {
-    pub base: MeshInstance,
-    pub color: Vec4,
-}
-
-/// GPU-friendly data for a since mesh instance
-#[derive(Debug, Copy, Clone, PartialEq, ShaderType, Component)]
-pub struct GpuColorMeshInstance {
-    #[size(144)]
-    pub base: GpuMeshInstance,
-    #[size(16)]
-    pub color: Vec4,
-}
+use crate::prelude::{
+    GpuMeshInstance, GpuMeshInstanceWith, InstanceAttribute, InstanceColor, InstanceUniformLength,
+    MeshInstanceWith,
+};
 
-impl Default for GpuColorMeshInstance {
-    fn default() -> Self {
-        Self {
-            base: default(),
-            color: Vec4::ZERO,
-        }
-    }
-}
+/// Mesh instance with a per-instance [`InstanceColor`], stored as linear RGBA after the base instance
+pub type ColorMeshInstance = MeshInstanceWith<InstanceColor>;
 
-impl Instance for ColorMeshInstance {
-    type ExtractedInstance = Self;
-    type PreparedInstance = GpuColorMeshInstance;
+/// GPU-friendly data for a single color mesh instance
+pub type GpuColorMeshInstance = GpuMeshInstanceWith<InstanceColor>;
 
-    type Query = (<MeshInstance as Instance>::Query, Read<InstanceColor>);
+impl InstanceAttribute for InstanceColor {
+    type GpuAttribute = Vec4;
 
-    fn extract_instance<'w>(
-        (base, color): ROQueryItem<Self::Query>,
-    ) -> Self::ExtractedInstance {
-        ColorMeshInstance {
-            base: MeshInstance::extract_instance(base),
-            color: Vec4::new(color.r(), color.g(), color.b(), color.a()),
-        }
-    }
+    type Query = Read<InstanceColor>;
 
-    fn prepare_instance(instance: &Self::ExtractedInstance, mesh: u32) -> Self::PreparedInstance {
-        GpuColorMeshInstance {
-            base: MeshInstance::prepare_instance(&instance.base, mesh),
-            color: instance.color,
-        }
+    fn extract_attribute(color: ROQueryItem<Self::Query>) -> Self::GpuAttribute {
+        Vec4::new(color.r(), color.g(), color.b(), color.a())
     }
 
-    fn transform(instance: &Self::ExtractedInstance) -> Mat4 {
-        instance.base.transform
+    fn fade(color: &mut Self::GpuAttribute, fade: f32) {
+        color.w *= fade;
     }
 }
+
+// Layout is mirrored by hand in `color_instance_struct.wgsl` and the structs embedding this one
+const _: () = assert!(
+    GpuMeshInstance::SHADER_SIZE.get() == 144,
+    "GpuMeshInstance size changed, update color_instance_struct.wgsl"
+);
+const _: () = assert!(
+    GpuColorMeshInstance::SHADER_SIZE.get() == 160,
+    "GpuColorMeshInstance size changed, update the #[size] of embedding structs"
+);
+const _: () = assert!(
+    ColorMeshInstance::UNIFORM_BUFFER_LENGTH.get() == 102,
+    "Uniform buffer length changed, update the ColorInstances array in color_instance_struct.wgsl"
+);
@@ -4,7 +4,9 @@ use bevy::{
     reflect::TypeUuid,
 };
 
-use crate::prelude::InstanceColor;
+use crate::prelude::{
+    sync_instance_color, sync_instance_fade, sync_instance_tag, InstanceColor, InstanceData,
+};
 
 pub const COLOR_INSTANCE_STRUCT_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 12512679806184200914);
@@ -20,6 +22,10 @@ impl Plugin for ColorInstancePlugin {
             Shader::from_wgsl
         );
 
-        app.register_type::<InstanceColor>();
+        app.register_type::<InstanceColor>()
+            .register_type::<InstanceData>()
+            .add_system(sync_instance_color)
+            .add_system(sync_instance_tag)
+            .add_system(sync_instance_fade);
     }
 }
@@ -1,7 +0,0 @@
-//! Redundant compute-based instance counting and by-mesh sorting pipelines
-//! May be worth reimplmementing as utility strata once instance compute is generalized
-
-pub mod compute_jobs;
-pub mod node;
-pub mod pipelines;
-pub mod plugin;
@@ -1,70 +0,0 @@
-use std::borrow::Cow;
-
-use bevy::{
-    prelude::{FromWorld, Shader, World},
-    render::{
-        render_resource::{
-            BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
-            CachedComputePipelineId, ComputePipelineDescriptor, PipelineCache, ShaderStages,
-        },
-        renderer::RenderDevice,
-    },
-};
-
-use crate::prelude::INDIRECT_OFFSETS_HANDLE;
-
-pub struct IndirectOffsetsPipeline {
-    pub pipeline: CachedComputePipelineId,
-    pub bind_group_layout: BindGroupLayout,
-}
-
-impl FromWorld for IndirectOffsetsPipeline {
-    fn from_world(world: &mut World) -> Self {
-        let bind_group_layout =
-            world
-                .resource::<RenderDevice>()
-                .create_bind_group_layout(&BindGroupLayoutDescriptor {
-                    label: None,
-                    entries: &[
-                        BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: ShaderStages::COMPUTE,
-                            ty: BindingType::Buffer {
-                                ty: bevy::render::render_resource::BufferBindingType::Storage {
-                                    read_only: true,
-                                },
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        },
-                        BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: ShaderStages::COMPUTE,
-                            ty: BindingType::Buffer {
-                                ty: bevy::render::render_resource::BufferBindingType::Storage {
-                                    read_only: false,
-                                },
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        },
-                    ],
-                });
-
-        let mut pipeline_cache = world.resource_mut::<PipelineCache>();
-        let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-            label: None,
-            layout: Some(vec![bind_group_layout.clone()]),
-            shader: INDIRECT_OFFSETS_HANDLE.typed::<Shader>(),
-            shader_defs: vec![],
-            entry_point: Cow::from("indirect_offsets"),
-        });
-
-        IndirectOffsetsPipeline {
-            pipeline,
-            bind_group_layout,
-        }
-    }
-}
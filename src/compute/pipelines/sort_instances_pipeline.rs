@@ -1,82 +0,0 @@
-use std::borrow::Cow;
-
-use bevy::{
-    prelude::{FromWorld, Shader, World},
-    render::{
-        render_resource::{
-            BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
-            CachedComputePipelineId, ComputePipelineDescriptor, PipelineCache, ShaderStages,
-        },
-        renderer::RenderDevice,
-    },
-};
-
-use crate::prelude::SORT_INSTANCES_HANDLE;
-
-pub struct SortInstancesPipeline {
-    pub pipeline: CachedComputePipelineId,
-    pub bind_group_layout: BindGroupLayout,
-}
-
-impl FromWorld for SortInstancesPipeline {
-    fn from_world(world: &mut World) -> Self {
-        let bind_group_layout =
-            world
-                .resource::<RenderDevice>()
-                .create_bind_group_layout(&BindGroupLayoutDescriptor {
-                    label: None,
-                    entries: &[
-                        BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: ShaderStages::COMPUTE,
-                            ty: BindingType::Buffer {
-                                ty: bevy::render::render_resource::BufferBindingType::Storage {
-                                    read_only: true,
-                                },
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        },
-                        BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: ShaderStages::COMPUTE,
-                            ty: BindingType::Buffer {
-                                ty: bevy::render::render_resource::BufferBindingType::Storage {
-                                    read_only: false,
-                                },
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        },
-                        BindGroupLayoutEntry {
-                            binding: 2,
-                            visibility: ShaderStages::COMPUTE,
-                            ty: BindingType::Buffer {
-                                ty: bevy::render::render_resource::BufferBindingType::Storage {
-                                    read_only: false,
-                                },
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        },
-                    ],
-                });
-
-        let mut pipeline_cache = world.resource_mut::<PipelineCache>();
-        let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-            label: None,
-            layout: Some(vec![bind_group_layout.clone()]),
-            shader: SORT_INSTANCES_HANDLE.typed::<Shader>(),
-            shader_defs: vec![],
-            entry_point: Cow::from("sort_instances"),
-        });
-
-        SortInstancesPipeline {
-            pipeline,
-            bind_group_layout,
-        }
-    }
-}
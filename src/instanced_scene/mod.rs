@@ -0,0 +1,335 @@
+pub mod plugin;
+
+use bevy::prelude::{Color, Component, Entity, Handle, Mesh, Transform};
+
+use crate::prelude::MaterialInstanced;
+
+/// Handle to an instance added to an [`InstancedScene`]
+///
+/// Ids are generational, so an id whose instance was removed stays invalid
+/// even once its slot is reused by a later instance.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InstanceId {
+    index: u32,
+    generation: u32,
+}
+
+impl InstanceId {
+    /// Index of the pool slot holding this instance
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct InstancedSceneSlot {
+    /// Pooled entity drawing this slot, once spawned
+    entity: Option<Entity>,
+    generation: u32,
+    /// Transform and color of the slot's instance, if occupied
+    instance: Option<(Transform, Color)>,
+    /// Whether the slot is queued for syncing to its entity
+    changed: bool,
+}
+
+/// Pool of mesh instances sharing a mesh and material, added and removed by [`InstanceId`]
+///
+/// Each instance is drawn by a pooled entity carrying a
+/// [`ColorInstanceBundle<M>`](crate::prelude::ColorInstanceBundle).
+/// Removed instances are hidden and their slots pushed onto a free list,
+/// so later additions reuse existing entities instead of spawning and despawning them,
+/// and the pool only grows to the most instances alive at once.
+///
+/// Changes are applied to the pooled entities in [`CoreStage::PostUpdate`](bevy::prelude::CoreStage::PostUpdate)
+/// by [`InstancedScenePlugin<M>`](plugin::InstancedScenePlugin).
+/// Instance transforms are in world space, independent of the scene entity's own transform.
+/// Despawning the scene entity despawns its pool.
+#[derive(Component)]
+pub struct InstancedScene<M: MaterialInstanced> {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<M>,
+    slots: Vec<InstancedSceneSlot>,
+    /// Indices of unoccupied slots, reused last-in first-out
+    free: Vec<u32>,
+    /// Indices of slots to sync to their entities
+    changed: Vec<u32>,
+}
+
+impl<M: MaterialInstanced> InstancedScene<M> {
+    pub fn new(mesh: Handle<Mesh>, material: Handle<M>) -> Self {
+        Self {
+            mesh,
+            material,
+            slots: vec![],
+            free: vec![],
+            changed: vec![],
+        }
+    }
+
+    /// Adds an instance, reusing a free slot if there is one
+    pub fn add(&mut self, transform: Transform, color: Color) -> InstanceId {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(InstancedSceneSlot::default());
+                self.slots.len() as u32 - 1
+            }
+        };
+
+        let slot = &mut self.slots[index as usize];
+        slot.instance = Some((transform, color));
+        let generation = slot.generation;
+        self.mark_changed(index);
+
+        InstanceId { index, generation }
+    }
+
+    /// Removes the instance of `id`, returning false if it was already removed
+    pub fn remove(&mut self, id: InstanceId) -> bool {
+        let slot = match self.slot_mut(id) {
+            Some(slot) => slot,
+            None => return false,
+        };
+
+        slot.instance = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        self.mark_changed(id.index);
+
+        true
+    }
+
+    /// Removes every instance, keeping the pool's entities for reuse
+    pub fn clear(&mut self) {
+        let ids = self.ids().collect::<Vec<_>>();
+        for id in ids {
+            self.remove(id);
+        }
+    }
+
+    /// Moves the instance of `id`, returning false if it was removed
+    pub fn set_transform(&mut self, id: InstanceId, transform: Transform) -> bool {
+        match self.slot_mut(id).and_then(|slot| slot.instance.as_mut()) {
+            Some(instance) => instance.0 = transform,
+            None => return false,
+        }
+
+        self.mark_changed(id.index);
+        true
+    }
+
+    /// Recolors the instance of `id`, returning false if it was removed
+    pub fn set_color(&mut self, id: InstanceId, color: Color) -> bool {
+        match self.slot_mut(id).and_then(|slot| slot.instance.as_mut()) {
+            Some(instance) => instance.1 = color,
+            None => return false,
+        }
+
+        self.mark_changed(id.index);
+        true
+    }
+
+    /// Transform and color of the instance of `id`, if it hasn't been removed
+    pub fn get(&self, id: InstanceId) -> Option<(Transform, Color)> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.instance)
+    }
+
+    pub fn contains(&self, id: InstanceId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Ids of the scene's instances, in slot order
+    pub fn ids(&self) -> impl Iterator<Item = InstanceId> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.instance.is_some())
+            .map(|(index, slot)| InstanceId {
+                index: index as u32,
+                generation: slot.generation,
+            })
+    }
+
+    /// Number of instances in the scene
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of slots in the pool, occupied or free
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Entities drawing the pool's slots, including hidden free ones
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.slots.iter().filter_map(|slot| slot.entity)
+    }
+
+    fn slot_mut(&mut self, id: InstanceId) -> Option<&mut InstancedSceneSlot> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation && slot.instance.is_some())
+    }
+
+    fn mark_changed(&mut self, index: u32) {
+        let slot = &mut self.slots[index as usize];
+        if !slot.changed {
+            slot.changed = true;
+            self.changed.push(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, Color, Stage, SystemStage, Transform, Visibility, World};
+
+    use crate::prelude::{BasicMaterial, InstancedSceneInstance};
+
+    use super::{plugin::sync_instanced_scenes, InstancedScene};
+
+    fn scene() -> InstancedScene<BasicMaterial> {
+        InstancedScene::new(default(), default())
+    }
+
+    #[test]
+    fn removed_slots_are_reused() {
+        let mut scene = scene();
+
+        let first = scene.add(Transform::IDENTITY, Color::WHITE);
+        let second = scene.add(Transform::IDENTITY, Color::WHITE);
+        assert!(scene.remove(first));
+
+        let reused = scene.add(Transform::IDENTITY, Color::RED);
+        assert_eq!(reused.index(), first.index());
+        assert_ne!(reused.index(), second.index());
+        assert_eq!(scene.capacity(), 2);
+    }
+
+    #[test]
+    fn stale_ids_are_rejected_after_reuse() {
+        let mut scene = scene();
+
+        let stale = scene.add(Transform::IDENTITY, Color::WHITE);
+        assert!(scene.remove(stale));
+        let reused = scene.add(Transform::from_xyz(1.0, 0.0, 0.0), Color::RED);
+
+        assert!(!scene.contains(stale));
+        assert!(scene.get(stale).is_none());
+        assert!(!scene.remove(stale));
+        assert!(!scene.set_transform(stale, Transform::IDENTITY));
+        assert!(!scene.set_color(stale, Color::BLUE));
+
+        assert_eq!(
+            scene.get(reused),
+            Some((Transform::from_xyz(1.0, 0.0, 0.0), Color::RED))
+        );
+    }
+
+    #[test]
+    fn len_and_capacity_follow_adds_and_removes() {
+        let mut scene = scene();
+        assert!(scene.is_empty());
+
+        let ids = (0..4)
+            .map(|_| scene.add(Transform::IDENTITY, Color::WHITE))
+            .collect::<Vec<_>>();
+        assert_eq!(scene.len(), 4);
+        assert_eq!(scene.capacity(), 4);
+
+        assert!(scene.remove(ids[1]));
+        assert!(scene.remove(ids[2]));
+        assert_eq!(scene.len(), 2);
+        assert_eq!(scene.capacity(), 4);
+        assert_eq!(scene.ids().collect::<Vec<_>>(), vec![ids[0], ids[3]]);
+    }
+
+    #[test]
+    fn clear_keeps_the_pool() {
+        let mut scene = scene();
+
+        let ids = (0..3)
+            .map(|_| scene.add(Transform::IDENTITY, Color::WHITE))
+            .collect::<Vec<_>>();
+        scene.clear();
+
+        assert!(scene.is_empty());
+        assert_eq!(scene.capacity(), 3);
+        assert!(ids.iter().all(|id| !scene.contains(*id)));
+
+        for _ in 0..3 {
+            scene.add(Transform::IDENTITY, Color::WHITE);
+        }
+        assert_eq!(scene.capacity(), 3);
+    }
+
+    #[test]
+    fn drawn_instances_follow_adds_and_removes() {
+        let mut world = World::new();
+        let mut stage =
+            SystemStage::single_threaded().with_system(sync_instanced_scenes::<BasicMaterial>);
+
+        let scene_entity = world.spawn(scene()).id();
+
+        let drawn = |world: &mut World| {
+            world
+                .query::<(&InstancedSceneInstance, &Visibility)>()
+                .iter(world)
+                .filter(|(_, visibility)| visibility.is_visible)
+                .count()
+        };
+        let pooled =
+            |world: &mut World| world.query::<&InstancedSceneInstance>().iter(world).count();
+
+        let ids = {
+            let mut scene = world
+                .get_mut::<InstancedScene<BasicMaterial>>(scene_entity)
+                .unwrap();
+            (0..8)
+                .map(|_| scene.add(Transform::IDENTITY, Color::WHITE))
+                .collect::<Vec<_>>()
+        };
+        stage.run(&mut world);
+        assert_eq!(drawn(&mut world), 8);
+        assert_eq!(pooled(&mut world), 8);
+
+        {
+            let mut scene = world
+                .get_mut::<InstancedScene<BasicMaterial>>(scene_entity)
+                .unwrap();
+            for id in &ids[..5] {
+                assert!(scene.remove(*id));
+            }
+        }
+        stage.run(&mut world);
+        assert_eq!(drawn(&mut world), 3);
+        assert_eq!(pooled(&mut world), 8);
+
+        {
+            let mut scene = world
+                .get_mut::<InstancedScene<BasicMaterial>>(scene_entity)
+                .unwrap();
+            for _ in 0..2 {
+                scene.add(Transform::IDENTITY, Color::RED);
+            }
+        }
+        stage.run(&mut world);
+        assert_eq!(drawn(&mut world), 5);
+        assert_eq!(pooled(&mut world), 8);
+
+        world
+            .get_mut::<InstancedScene<BasicMaterial>>(scene_entity)
+            .unwrap()
+            .clear();
+        stage.run(&mut world);
+        assert_eq!(drawn(&mut world), 0);
+        assert_eq!(pooled(&mut world), 8);
+    }
+}
@@ -0,0 +1,124 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::change_detection::DetectChanges,
+    prelude::{
+        default, App, Changed, Commands, Component, CoreStage, DespawnRecursiveExt, Entity,
+        GlobalTransform, Handle, IntoSystemDescriptor, Plugin, Query, RemovedComponents,
+        SpatialBundle, Transform, Visibility, With,
+    },
+    transform::TransformSystem,
+};
+
+use crate::prelude::{
+    ColorInstanceBundle, InstanceColor, InstancedScene, MaterialInstanced, MeshInstanceBundle,
+};
+
+/// Syncs each [`InstancedScene<M>`] to its pool of instance entities
+pub struct InstancedScenePlugin<M: MaterialInstanced>(PhantomData<M>);
+
+impl<M: MaterialInstanced> Default for InstancedScenePlugin<M> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<M: MaterialInstanced> Plugin for InstancedScenePlugin<M> {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            sync_instanced_scenes::<M>.before(TransformSystem::TransformPropagate),
+        )
+        .add_system_to_stage(CoreStage::PostUpdate, despawn_instanced_scene_pools::<M>);
+    }
+}
+
+/// Marks an entity as part of the pool of the [`InstancedScene`] on `scene`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+pub struct InstancedSceneInstance {
+    pub scene: Entity,
+}
+
+/// Applies the changed slots of each [`InstancedScene<M>`] to their pooled entities,
+/// spawning an entity for each slot that doesn't have one yet
+pub fn sync_instanced_scenes<M: MaterialInstanced>(
+    mut commands: Commands,
+    mut query_scenes: Query<(Entity, &mut InstancedScene<M>), Changed<InstancedScene<M>>>,
+    mut query_instances: Query<(&mut Transform, &mut InstanceColor, &mut Visibility)>,
+) {
+    for (scene_entity, mut scene) in query_scenes.iter_mut() {
+        // Syncing isn't a change to the scene itself
+        let scene = scene.bypass_change_detection();
+
+        for index in std::mem::take(&mut scene.changed) {
+            let slot = &mut scene.slots[index as usize];
+            slot.changed = false;
+
+            if let Some(entity) = slot.entity {
+                if let Ok((mut transform, mut color, mut visibility)) =
+                    query_instances.get_mut(entity)
+                {
+                    match slot.instance {
+                        Some((instance_transform, instance_color)) => {
+                            *transform = instance_transform;
+                            color.0 = instance_color;
+                            visibility.is_visible = true;
+                        }
+                        None => visibility.is_visible = false,
+                    }
+                    continue;
+                }
+            }
+
+            // Slots are only given an entity once first occupied, or again if it was despawned
+            let (transform, color) = match slot.instance {
+                Some(instance) => instance,
+                None => {
+                    slot.entity = None;
+                    continue;
+                }
+            };
+
+            slot.entity = Some(
+                commands
+                    .spawn((
+                        ColorInstanceBundle {
+                            instance_bundle: MeshInstanceBundle::<M> {
+                                mesh: scene.mesh.clone(),
+                                material: scene.material.clone(),
+                                spatial_bundle: SpatialBundle {
+                                    transform,
+                                    // Spawned after propagation, so set to draw in place this frame
+                                    global_transform: GlobalTransform::from(transform),
+                                    ..default()
+                                },
+                            },
+                            mesh_instance_color: color.into(),
+                        },
+                        InstancedSceneInstance {
+                            scene: scene_entity,
+                        },
+                    ))
+                    .id(),
+            );
+        }
+    }
+}
+
+/// Despawns the pooled entities of removed [`InstancedScene<M>`]s
+pub fn despawn_instanced_scene_pools<M: MaterialInstanced>(
+    mut commands: Commands,
+    removed_scenes: RemovedComponents<InstancedScene<M>>,
+    query_instances: Query<(Entity, &InstancedSceneInstance), With<Handle<M>>>,
+) {
+    let removed = removed_scenes.iter().collect::<Vec<_>>();
+    if removed.is_empty() {
+        return;
+    }
+
+    for (entity, instance) in query_instances.iter() {
+        if removed.contains(&instance.scene) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,83 @@
+pub mod plugin;
+
+use bevy::{
+    math::{Vec3, Vec4},
+    prelude::{default, Color, Component, Resource, Transform},
+};
+
+use crate::prelude::{GpuColorMeshInstance, GpuMeshInstance};
+
+/// Shape drawn by the instance slice on the same entity,
+/// spawned by [`InstancedShapesPlugin`](plugin::InstancedShapesPlugin)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Component)]
+pub enum InstancedShape {
+    /// Unit cube centered on the origin
+    Cube,
+    /// Unit-diameter sphere centered on the origin
+    Sphere,
+}
+
+/// Immediate-mode shapes accumulated for the current frame
+///
+/// Shapes drawn before [`CoreStage::PostUpdate`](bevy::prelude::CoreStage::PostUpdate)
+/// are rendered that frame, then cleared; draw them again every frame to keep them on screen.
+/// Each shape type is drawn as a single instance slice with a per-instance color,
+/// so thousands of shapes cost no more entities or draw calls than one.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct InstancedShapes {
+    pub cubes: Vec<GpuColorMeshInstance>,
+    pub spheres: Vec<GpuColorMeshInstance>,
+}
+
+impl InstancedShapes {
+    /// Draws a unit cube transformed by `transform`
+    pub fn draw_instanced_cube(&mut self, transform: Transform, color: Color) {
+        self.cubes.push(shape_instance(transform, color));
+    }
+
+    /// Draws a box spanning `min` to `max`, such as an AABB
+    pub fn draw_instanced_box(&mut self, min: Vec3, max: Vec3, color: Color) {
+        self.draw_instanced_cube(
+            Transform::from_translation((min + max) * 0.5).with_scale(max - min),
+            color,
+        );
+    }
+
+    /// Draws a sphere of `radius` around `center`
+    pub fn draw_instanced_sphere(&mut self, center: Vec3, radius: f32, color: Color) {
+        self.spheres.push(shape_instance(
+            Transform::from_translation(center).with_scale(Vec3::splat(radius * 2.0)),
+            color,
+        ));
+    }
+
+    /// Number of shapes drawn so far this frame
+    pub fn len(&self) -> usize {
+        self.cubes.len() + self.spheres.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes the accumulated instances of `shape`, leaving none behind
+    pub fn take(&mut self, shape: InstancedShape) -> Vec<GpuColorMeshInstance> {
+        match shape {
+            InstancedShape::Cube => std::mem::take(&mut self.cubes),
+            InstancedShape::Sphere => std::mem::take(&mut self.spheres),
+        }
+    }
+}
+
+fn shape_instance(transform: Transform, color: Color) -> GpuColorMeshInstance {
+    let transform = transform.compute_matrix();
+
+    GpuColorMeshInstance {
+        base: GpuMeshInstance {
+            transform,
+            inverse_transpose_model: transform.inverse().transpose(),
+            ..default()
+        },
+        attribute: Vec4::new(color.r(), color.g(), color.b(), color.a()),
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::{
+    core::Name,
+    prelude::{
+        default,
+        shape::{Cube, Icosphere},
+        App, Assets, Commands, CoreStage, Handle, HandleUntyped, Mesh, Plugin, Query, ResMut,
+        Visibility,
+    },
+    reflect::TypeUuid,
+};
+
+use crate::prelude::{
+    CustomMaterial, CustomMaterialPlugin, InstanceSlice, InstanceSliceBundle, InstanceSliceSeed,
+    InstanceSliceSeedPlugin, InstancedShape, InstancedShapes,
+};
+
+pub const INSTANCED_CUBE_MESH_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Mesh::TYPE_UUID, 9089973817326376653);
+
+pub const INSTANCED_SPHERE_MESH_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Mesh::TYPE_UUID, 11302761834914659749);
+
+/// Draws the shapes accumulated in [`InstancedShapes`] each frame
+///
+/// Shapes are seeded into an instance slice per shape type using the default [`CustomMaterial`],
+/// so they require storage buffer support. Adds [`CustomMaterialPlugin`] if it isn't already.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct InstancedShapesPlugin;
+
+impl Plugin for InstancedShapesPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<CustomMaterialPlugin>() {
+            app.add_plugin(CustomMaterialPlugin);
+        }
+
+        if !app.is_plugin_added::<InstanceSliceSeedPlugin<CustomMaterial>>() {
+            app.add_plugin(InstanceSliceSeedPlugin::<CustomMaterial>::default());
+        }
+
+        let mut meshes = app.world.resource_mut::<Assets<Mesh>>();
+        meshes.set_untracked(INSTANCED_CUBE_MESH_HANDLE, Cube::new(1.0).into());
+        meshes.set_untracked(
+            INSTANCED_SPHERE_MESH_HANDLE,
+            Icosphere {
+                radius: 0.5,
+                subdivisions: 3,
+            }
+            .into(),
+        );
+
+        app.init_resource::<InstancedShapes>()
+            .add_startup_system(spawn_instanced_shapes)
+            .add_system_to_stage(CoreStage::PostUpdate, flush_instanced_shapes);
+    }
+}
+
+/// Spawns the instance slice of each [`InstancedShape`]
+pub fn spawn_instanced_shapes(mut commands: Commands) {
+    for (shape, mesh) in [
+        (InstancedShape::Cube, INSTANCED_CUBE_MESH_HANDLE),
+        (InstancedShape::Sphere, INSTANCED_SPHERE_MESH_HANDLE),
+    ] {
+        commands.spawn((
+            Name::new(format!("Instanced {shape:?} Shapes")),
+            shape,
+            InstanceSliceBundle::<CustomMaterial> {
+                material: Handle::<CustomMaterial>::default(),
+                mesh: mesh.typed(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            InstanceSliceSeed::<CustomMaterial>::default(),
+        ));
+    }
+}
+
+/// Moves this frame's shapes into their slices, clearing [`InstancedShapes`] for the next frame
+///
+/// Slices without shapes are hidden rather than drawn empty.
+pub fn flush_instanced_shapes(
+    mut shapes: ResMut<InstancedShapes>,
+    mut query_shapes: Query<(
+        &InstancedShape,
+        &mut InstanceSlice,
+        &mut InstanceSliceSeed<CustomMaterial>,
+        &mut Visibility,
+    )>,
+) {
+    for (shape, mut instance_slice, mut seed, mut visibility) in query_shapes.iter_mut() {
+        let instances = shapes.take(*shape);
+
+        // Leave idle slices untouched so their seeds aren't uploaded again
+        if instances.is_empty() && seed.instances.is_empty() {
+            continue;
+        }
+
+        visibility.is_visible = !instances.is_empty();
+        instance_slice.instance_count = instances.len();
+        seed.instances = instances;
+    }
+}
@@ -0,0 +1,14 @@
+use bevy::{prelude::Resource, render::extract_resource::ExtractResource};
+
+/// Number of distinct colors used to tint instance batches
+pub const BATCH_DEBUG_PALETTE_LENGTH: usize = 8;
+
+/// Debug overlay that flat-shades each instance batch in a distinct color
+///
+/// Instances sharing a color (and a view) are drawn with a single draw call,
+/// which makes it easy to see how materials and meshes are being split into batches.
+/// Colors repeat every [`BATCH_DEBUG_PALETTE_LENGTH`] batches.
+#[derive(Debug, Default, Copy, Clone, Resource, ExtractResource)]
+pub struct InstanceBatchDebug {
+    pub enabled: bool,
+}
@@ -0,0 +1,74 @@
+use bevy::{
+    ecs::{reflect::ReflectComponent, system::lifetimeless::Read},
+    prelude::{Component, Deref, DerefMut, Entity, Query, Res, ResMut, Resource, Vec3, Vec4, With},
+    reflect::Reflect,
+    render::{
+        extract_component::ExtractComponent,
+        render_resource::UniformBuffer,
+        renderer::{RenderDevice, RenderQueue},
+        view::{ExtractedView, VisibleEntities},
+    },
+    utils::HashMap,
+};
+
+/// World-space clip plane for instances rendered by this view, i.e. for planar reflection passes
+///
+/// Stored as `(normal, distance)`; fragments at `position` are discarded where
+/// `dot(normal, position) + distance < 0`, so the normal points toward the kept half-space.
+///
+/// Instanced fragment shaders apply it by importing `indirect_instancing::instance_clip_plane`
+/// and calling `clip_instance(world_position)`. Views without this component clip nothing.
+#[derive(Debug, Copy, Clone, PartialEq, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component)]
+pub struct InstanceClipPlane(pub Vec4);
+
+impl Default for InstanceClipPlane {
+    fn default() -> Self {
+        InstanceClipPlane(Vec4::W)
+    }
+}
+
+impl InstanceClipPlane {
+    /// Clip plane keeping the half-space `normal` points into, passing through `point`
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Self {
+        let normal = normal.normalize();
+        InstanceClipPlane(normal.extend(-normal.dot(point)))
+    }
+}
+
+impl ExtractComponent for InstanceClipPlane {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Per-view uniform buffers holding each view's [`InstanceClipPlane`]
+#[derive(Default, Resource)]
+pub struct ViewClipPlanes {
+    pub buffers: HashMap<Entity, UniformBuffer<Vec4>>,
+}
+
+#[allow(clippy::type_complexity)]
+pub fn prepare_view_clip_planes(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut view_clip_planes: ResMut<ViewClipPlanes>,
+    query_views: Query<
+        (Entity, Option<&InstanceClipPlane>),
+        (With<ExtractedView>, With<VisibleEntities>),
+    >,
+) {
+    view_clip_planes
+        .buffers
+        .retain(|entity, _| query_views.contains(*entity));
+
+    for (view_entity, clip_plane) in query_views.iter() {
+        let buffer = view_clip_planes.buffers.entry(view_entity).or_default();
+        buffer.set(clip_plane.copied().unwrap_or_default().0);
+        buffer.write_buffer(&render_device, &render_queue);
+    }
+}
@@ -0,0 +1,51 @@
+#define_import_path indirect_instancing::compact_instance_struct
+
+struct CompactInstanceData {
+    rotation: vec4<f32>,
+    translation: vec3<f32>,
+    scale: f32,
+    mesh: u32,
+    seed: u32,
+};
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+struct CompactInstances {
+    instances: array<CompactInstanceData, 341>,
+};
+#else
+struct CompactInstances {
+    instances: array<CompactInstanceData>,
+};
+#endif
+
+// Rotates `v` by the unit quaternion `q`
+fn compact_instance_rotate(q: vec4<f32>, v: vec3<f32>) -> vec3<f32> {
+    let t = 2.0 * cross(q.xyz, v);
+    return v + q.w * t + cross(q.xyz, t);
+}
+
+// Reconstructs the model matrix of a compact instance
+fn compact_instance_transform(instance: CompactInstanceData) -> mat4x4<f32> {
+    let x = compact_instance_rotate(instance.rotation, vec3<f32>(1.0, 0.0, 0.0)) * instance.scale;
+    let y = compact_instance_rotate(instance.rotation, vec3<f32>(0.0, 1.0, 0.0)) * instance.scale;
+    let z = compact_instance_rotate(instance.rotation, vec3<f32>(0.0, 0.0, 1.0)) * instance.scale;
+    return mat4x4<f32>(
+        vec4<f32>(x, 0.0),
+        vec4<f32>(y, 0.0),
+        vec4<f32>(z, 0.0),
+        vec4<f32>(instance.translation, 1.0),
+    );
+}
+
+// Transforms a local-space position into world space, without building the model matrix
+fn compact_instance_position(instance: CompactInstanceData, position: vec3<f32>) -> vec4<f32> {
+    let world = compact_instance_rotate(instance.rotation, position * instance.scale);
+    return vec4<f32>(world + instance.translation, 1.0);
+}
+
+// Transforms a local-space normal into world space
+//
+// Scale is uniform, so rotation alone stands in for the inverse-transpose model matrix
+fn compact_instance_normal(instance: CompactInstanceData, normal: vec3<f32>) -> vec3<f32> {
+    return compact_instance_rotate(instance.rotation, normal);
+}
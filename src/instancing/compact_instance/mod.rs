@@ -0,0 +1,144 @@
+use bevy::{
+    asset::load_internal_asset,
+    ecs::{query::ROQueryItem, system::lifetimeless::Read},
+    math::{Mat4, Quat, Vec3, Vec4},
+    prelude::{
+        default, Component, ComputedVisibility, Entity, GlobalTransform, Handle, HandleUntyped,
+        Mesh, Plugin, Shader,
+    },
+    reflect::TypeUuid,
+    render::render_resource::{ShaderSize, ShaderType},
+};
+
+use crate::prelude::{Instance, InstanceSeed, InstanceUniformLength};
+
+/// `indirect_instancing::compact_instance_struct`, declaring [`GpuCompactMeshInstance`]
+/// and functions reconstructing its transform in vertex shaders
+pub const COMPACT_INSTANCE_STRUCT_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6148203957716342081);
+
+/// Registers the `indirect_instancing::compact_instance_struct` shader import
+pub struct CompactInstancePlugin;
+
+impl Plugin for CompactInstancePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        load_internal_asset!(
+            app,
+            COMPACT_INSTANCE_STRUCT_HANDLE,
+            "compact_instance_struct.wgsl",
+            Shader::from_wgsl
+        );
+    }
+}
+
+/// Mesh instance stored as a translation, rotation and uniform scale
+/// rather than full model matrices, for scenes with very large instance counts
+///
+/// Prepares to 48 bytes per instance, against the 144 of a [`MeshInstance`](crate::prelude::MeshInstance).
+/// Vertex shaders rebuild the model matrix with `compact_instance_transform`
+/// from `indirect_instancing::compact_instance_struct`, and rotate normals by the quaternion alone,
+/// so no inverse is computed on the CPU.
+///
+/// Non-uniform scale can't be represented, and is replaced by the largest axis scale.
+/// Per-instance depth bias isn't stored either.
+/// The layout isn't a [`CulledInstance`](crate::prelude::CulledInstance),
+/// so [`InstanceCullingPlugin`](crate::prelude::InstanceCullingPlugin) can't be added for it.
+#[derive(Debug, Default, Clone, PartialEq, Component)]
+pub struct CompactMeshInstance {
+    pub mesh: Handle<Mesh>,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: f32,
+    pub seed: u32,
+}
+
+impl CompactMeshInstance {
+    /// Converts this instance into its GPU representation, drawing the mesh at index `mesh` of its batch
+    pub fn to_gpu(&self, mesh: u32) -> GpuCompactMeshInstance {
+        GpuCompactMeshInstance {
+            rotation: self.rotation.into(),
+            translation: self.translation,
+            scale: self.scale,
+            mesh,
+            seed: self.seed,
+        }
+    }
+}
+
+/// GPU-friendly data for a single compact mesh instance
+///
+/// Fields are ordered to pack without padding, besides the struct's trailing alignment.
+#[derive(Debug, Copy, Clone, PartialEq, ShaderType, Component)]
+pub struct GpuCompactMeshInstance {
+    pub rotation: Vec4,
+    pub translation: Vec3,
+    pub scale: f32,
+    pub mesh: u32,
+    pub seed: u32,
+}
+
+impl Default for GpuCompactMeshInstance {
+    fn default() -> Self {
+        Self {
+            rotation: Quat::IDENTITY.into(),
+            translation: Vec3::ZERO,
+            scale: 0.0,
+            mesh: default(),
+            seed: default(),
+        }
+    }
+}
+
+// Layout is mirrored by hand in `compact_instance_struct.wgsl`
+const _: () = assert!(
+    GpuCompactMeshInstance::SHADER_SIZE.get() == 48,
+    "GpuCompactMeshInstance size changed, update compact_instance_struct.wgsl"
+);
+const _: () = assert!(
+    CompactMeshInstance::UNIFORM_BUFFER_LENGTH.get() == 341,
+    "Uniform buffer length changed, update the CompactInstances array in compact_instance_struct.wgsl"
+);
+
+impl Instance for CompactMeshInstance {
+    type ExtractedInstance = Self;
+    type PreparedInstance = GpuCompactMeshInstance;
+
+    type Query = (
+        Entity,
+        Read<Handle<Mesh>>,
+        Read<GlobalTransform>,
+        Read<ComputedVisibility>,
+        Option<Read<InstanceSeed>>,
+    );
+
+    fn extract_instance(
+        (entity, mesh, transform, visibility, seed): ROQueryItem<Self::Query>,
+    ) -> Self::ExtractedInstance {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+
+        CompactMeshInstance {
+            mesh: mesh.clone_weak(),
+            translation,
+            rotation,
+            // Collapse hidden instances to a point
+            scale: if visibility.is_visible() {
+                scale.max_element()
+            } else {
+                0.0
+            },
+            seed: seed.map(|seed| seed.0).unwrap_or_else(|| entity.index()),
+        }
+    }
+
+    fn prepare_instance(instance: &Self::ExtractedInstance, mesh: u32) -> Self::PreparedInstance {
+        instance.to_gpu(mesh)
+    }
+
+    fn transform(instance: &Self::ExtractedInstance) -> Mat4 {
+        Mat4::from_scale_rotation_translation(
+            Vec3::splat(instance.scale),
+            instance.rotation,
+            instance.translation,
+        )
+    }
+}
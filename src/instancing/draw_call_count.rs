@@ -0,0 +1,87 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::{App, Plugin, Res, ResMut, Resource},
+    render::RenderApp,
+};
+
+/// Diagnostic reporting the number of draw calls issued by instanced materials each frame
+pub const INSTANCING_DRAW_CALLS: DiagnosticId =
+    DiagnosticId::from_u128(0x3b9e_6f41_d20a_4c57_a8e3_7c15_90d4_2b6e);
+
+/// GPU draw calls issued by instanced materials, across all views and phases
+///
+/// Lives in the render world. Each indirect draw, and each draw issued directly
+/// in its place, counts as one call, so the count reflects batching, the number
+/// of meshes per batch, and uniform buffer chunking. With the `multi_draw_indirect` feature,
+/// a batch's indirect draws issued together count as one call.
+#[derive(Debug, Default, Resource)]
+pub struct InstancingDrawCallCount {
+    /// Draw calls issued during the last rendered frame
+    pub last_frame: u32,
+    issued: AtomicU32,
+}
+
+impl InstancingDrawCallCount {
+    /// Records `count` draw calls issued during the current frame
+    pub fn add(&self, count: u32) {
+        self.issued.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+/// Draw call count of the last rendered frame, shared with the main world
+#[derive(Debug, Default, Clone, Resource)]
+pub struct DrawCallCountReadback(pub Arc<AtomicU32>);
+
+/// Closes the current frame's draw call count
+pub fn finish_draw_call_count(
+    mut draw_call_count: ResMut<InstancingDrawCallCount>,
+    readback: Option<Res<DrawCallCountReadback>>,
+) {
+    draw_call_count.last_frame = std::mem::take(draw_call_count.issued.get_mut());
+
+    if let Some(readback) = readback {
+        readback
+            .0
+            .store(draw_call_count.last_frame, Ordering::Relaxed);
+    }
+}
+
+/// Reports [`InstancingDrawCallCount`] through [`Diagnostics`] as [`INSTANCING_DRAW_CALLS`]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct InstancingDiagnosticsPlugin;
+
+impl Plugin for InstancingDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        let readback = DrawCallCountReadback::default();
+
+        app.insert_resource(readback.clone())
+            .add_startup_system(setup_instancing_diagnostics)
+            .add_system(report_instancing_diagnostics);
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.insert_resource(readback);
+        }
+    }
+}
+
+pub fn setup_instancing_diagnostics(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(Diagnostic::new(
+        INSTANCING_DRAW_CALLS,
+        "instancing_draw_calls",
+        20,
+    ));
+}
+
+pub fn report_instancing_diagnostics(
+    readback: Res<DrawCallCountReadback>,
+    mut diagnostics: ResMut<Diagnostics>,
+) {
+    diagnostics.add_measurement(INSTANCING_DRAW_CALLS, || {
+        readback.0.load(Ordering::Relaxed) as f64
+    });
+}
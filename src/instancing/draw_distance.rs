@@ -0,0 +1,51 @@
+use bevy::{
+    ecs::{query::QueryItem, reflect::ReflectComponent, system::lifetimeless::Read},
+    prelude::{Component, Deref, DerefMut, Reflect, Resource},
+    render::{extract_component::ExtractComponent, extract_resource::ExtractResource},
+};
+
+/// View depth beyond which instances are excluded from their batch, i.e. for a render distance setting
+///
+/// Materials can override it with [`MaterialInstanced::max_draw_distance`](crate::prelude::MaterialInstanced::max_draw_distance).
+/// Instance slices are populated on the GPU, and so are not affected.
+#[derive(Debug, Copy, Clone, PartialEq, Deref, DerefMut, Resource, ExtractResource)]
+pub struct MaxDrawDistance(pub f32);
+
+impl Default for MaxDrawDistance {
+    fn default() -> Self {
+        MaxDrawDistance(f32::INFINITY)
+    }
+}
+
+/// Depth range before [`MaxDrawDistance`] over which instances fade out rather than popping
+///
+/// Opacity is scaled through [`Instance::fade`](crate::prelude::Instance::fade),
+/// so fading is only visible for instance types carrying a color and materials using
+/// [`AlphaMode::Blend`](bevy::pbr::AlphaMode::Blend). Defaults to `0.0`, which disables fading.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Deref, DerefMut, Resource, ExtractResource)]
+pub struct DrawDistanceFade(pub f32);
+
+/// Per-instance opacity in `0.0..1.0`, multiplied into the fade of [`DrawDistanceFade`]
+///
+/// Applied through [`Instance::fade`](crate::prelude::Instance::fade) like the distance fade,
+/// so it has the same requirements to be visible.
+/// Instance slices are populated on the GPU, and so are not affected.
+#[derive(Debug, Copy, Clone, PartialEq, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component)]
+pub struct InstanceFade(pub f32);
+
+impl Default for InstanceFade {
+    fn default() -> Self {
+        InstanceFade(1.0)
+    }
+}
+
+impl ExtractComponent for InstanceFade {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
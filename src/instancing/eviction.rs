@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    prelude::{Entity, Resource},
+    render::extract_resource::ExtractResource,
+};
+
+/// Controls how long per-view instance buffers outlive their view
+#[derive(Debug, Copy, Clone, Resource, ExtractResource)]
+pub struct InstanceEvictionSettings {
+    /// Number of consecutive frames a view can go without rendering
+    /// before its instance and indirect buffers are released
+    ///
+    /// Retaining buffers for a short while avoids reallocating them
+    /// when a camera is toggled off and back on.
+    pub idle_frames: u32,
+}
+
+impl Default for InstanceEvictionSettings {
+    fn default() -> Self {
+        Self { idle_frames: 60 }
+    }
+}
+
+/// Tracks how many consecutive frames each view has been inactive
+#[derive(Debug, Default, Clone)]
+pub struct ViewIdleFrames(BTreeMap<Entity, u32>);
+
+impl ViewIdleFrames {
+    /// Updates idle counters for `views`, given which of them are active this frame,
+    /// and returns the views that have been idle for longer than `idle_frames`
+    pub fn evict(
+        &mut self,
+        views: impl IntoIterator<Item = Entity>,
+        is_active: impl Fn(Entity) -> bool,
+        idle_frames: u32,
+    ) -> Vec<Entity> {
+        let mut evicted = vec![];
+
+        for view in views {
+            if is_active(view) {
+                self.0.remove(&view);
+                continue;
+            }
+
+            let idle = self.0.entry(view).or_default();
+            *idle += 1;
+
+            if *idle > idle_frames {
+                self.0.remove(&view);
+                evicted.push(view);
+            }
+        }
+
+        evicted
+    }
+}
@@ -0,0 +1,356 @@
+use bevy::{prelude::World, render::render_resource::CommandEncoder};
+
+#[cfg(feature = "gpu_timestamps")]
+pub use timestamps::*;
+
+/// Writes the start timestamp of the named GPU span,
+/// if the `gpu_timestamps` feature is enabled and `GpuTimingPlugin` has been added
+#[allow(unused_variables)]
+pub fn begin_gpu_span(world: &World, encoder: &mut CommandEncoder, span: &str) {
+    #[cfg(feature = "gpu_timestamps")]
+    if let Some(timestamps) = world.get_resource::<GpuTimestamps>() {
+        timestamps.write_timestamp(encoder, span, false);
+    }
+}
+
+/// Writes the end timestamp of the named GPU span,
+/// if the `gpu_timestamps` feature is enabled and `GpuTimingPlugin` has been added
+#[allow(unused_variables)]
+pub fn end_gpu_span(world: &World, encoder: &mut CommandEncoder, span: &str) {
+    #[cfg(feature = "gpu_timestamps")]
+    if let Some(timestamps) = world.get_resource::<GpuTimestamps>() {
+        timestamps.write_timestamp(encoder, span, true);
+    }
+}
+
+#[cfg(feature = "gpu_timestamps")]
+mod timestamps {
+    use std::sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    };
+
+    use bevy::{
+        core_pipeline::core_3d,
+        diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+        prelude::{warn, App, FromWorld, Plugin, Res, ResMut, Resource, World},
+        render::{
+            render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+            render_resource::{
+                Buffer, BufferDescriptor, BufferUsages, CommandEncoder, MapMode, WgpuFeatures,
+            },
+            renderer::{RenderContext, RenderDevice, RenderQueue},
+            RenderApp, RenderStage,
+        },
+    };
+
+    use super::{begin_gpu_span, end_gpu_span};
+
+    /// Maximum number of distinct GPU spans that can be timed
+    pub const MAX_GPU_SPANS: u32 = 32;
+
+    /// Diagnostic id of the first GPU span, subsequent spans use consecutive ids
+    pub const GPU_SPAN_DIAGNOSTIC_BASE: u128 = 0x5f0c_31d2_8a4e_4b7e_9d26_1c8b_0000_0000;
+
+    /// GPU span covering the core 3D main pass, including the instanced draws
+    pub const MAIN_PASS_SPAN: &str = "main_pass";
+
+    const TIMESTAMP_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+    const GPU_TIMING_RESOLVE: &str = "gpu_timing_resolve";
+    const GPU_MAIN_PASS_BEGIN: &str = "gpu_main_pass_begin";
+    const GPU_MAIN_PASS_END: &str = "gpu_main_pass_end";
+
+    /// Returns the [`DiagnosticId`] of the `index`th registered GPU span
+    pub fn gpu_span_diagnostic_id(index: usize) -> DiagnosticId {
+        DiagnosticId::from_u128(GPU_SPAN_DIAGNOSTIC_BASE + index as u128)
+    }
+
+    /// Measures GPU time spent in the instance compute and culling passes and the main pass
+    /// using timestamp queries, and reports it in milliseconds through [`Diagnostics`]
+    ///
+    /// Requires the [`WgpuFeatures::TIMESTAMP_QUERY`] feature to be requested in `WgpuSettings`,
+    /// otherwise timing is disabled with a warning. When multiple cameras are active,
+    /// the main pass span reports the last one to render.
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct GpuTimingPlugin;
+
+    impl Plugin for GpuTimingPlugin {
+        fn build(&self, app: &mut App) {
+            let measurements = GpuSpanMeasurements::default();
+
+            app.insert_resource(measurements.clone())
+                .add_system(report_gpu_spans);
+
+            let render_app = app.sub_app_mut(RenderApp);
+            render_app
+                .insert_resource(measurements)
+                .init_resource::<GpuTimestamps>()
+                .add_system_to_stage(RenderStage::Cleanup, map_gpu_timestamps);
+
+            let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+
+            render_graph.add_node(GPU_TIMING_RESOLVE, GpuTimestampResolveNode);
+            render_graph
+                .add_node_edge(
+                    bevy::render::main_graph::node::CAMERA_DRIVER,
+                    GPU_TIMING_RESOLVE,
+                )
+                .unwrap();
+
+            if let Some(core_3d) = render_graph.get_sub_graph_mut(core_3d::graph::NAME) {
+                core_3d.add_node(GPU_MAIN_PASS_BEGIN, GpuSpanNode { end: false });
+                core_3d.add_node(GPU_MAIN_PASS_END, GpuSpanNode { end: true });
+                core_3d
+                    .add_node_edge(GPU_MAIN_PASS_BEGIN, core_3d::graph::node::MAIN_PASS)
+                    .unwrap();
+                core_3d
+                    .add_node_edge(core_3d::graph::node::MAIN_PASS, GPU_MAIN_PASS_END)
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Span timings read back from the GPU, awaiting submission to [`Diagnostics`]
+    #[derive(Debug, Default, Clone, Resource)]
+    pub struct GpuSpanMeasurements(pub Arc<Mutex<Vec<(usize, String, f64)>>>);
+
+    /// Timestamp query set and its readback buffers
+    #[derive(Resource)]
+    pub struct GpuTimestamps {
+        query_set: Option<wgpu::QuerySet>,
+        resolve_buffer: Buffer,
+        readback_buffer: Buffer,
+        period: f32,
+        spans: Mutex<Vec<String>>,
+        /// Bitmask of spans written since the last resolve
+        written: AtomicU64,
+        /// Bitmask of spans copied into the readback buffer, awaiting mapping
+        resolved: AtomicU64,
+        mapping: Arc<AtomicBool>,
+    }
+
+    impl FromWorld for GpuTimestamps {
+        fn from_world(world: &mut World) -> Self {
+            let render_device = world.resource::<RenderDevice>();
+            let render_queue = world.resource::<RenderQueue>();
+
+            let query_set = if render_device
+                .features()
+                .contains(WgpuFeatures::TIMESTAMP_QUERY)
+            {
+                Some(
+                    render_device
+                        .wgpu_device()
+                        .create_query_set(&wgpu::QuerySetDescriptor {
+                            label: Some("instancing timestamps"),
+                            ty: wgpu::QueryType::Timestamp,
+                            count: MAX_GPU_SPANS * 2,
+                        }),
+                )
+            } else {
+                warn!("TIMESTAMP_QUERY is not enabled, GPU timing will be unavailable");
+                None
+            };
+
+            let size = (MAX_GPU_SPANS * 2) as u64 * TIMESTAMP_SIZE;
+
+            let resolve_buffer = render_device.create_buffer(&BufferDescriptor {
+                label: Some("timestamp resolve buffer"),
+                size,
+                usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+
+            let readback_buffer = render_device.create_buffer(&BufferDescriptor {
+                label: Some("timestamp readback buffer"),
+                size,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+
+            GpuTimestamps {
+                query_set,
+                resolve_buffer,
+                readback_buffer,
+                period: render_queue.get_timestamp_period(),
+                spans: Mutex::new(vec![MAIN_PASS_SPAN.to_string()]),
+                written: AtomicU64::new(0),
+                resolved: AtomicU64::new(0),
+                mapping: Arc::default(),
+            }
+        }
+    }
+
+    impl GpuTimestamps {
+        /// Returns the index of the named span, registering it if necessary
+        fn span_index(&self, span: &str) -> Option<usize> {
+            let mut spans = self.spans.lock().unwrap();
+
+            if let Some(index) = spans.iter().position(|name| name == span) {
+                return Some(index);
+            }
+
+            if spans.len() >= MAX_GPU_SPANS as usize {
+                return None;
+            }
+
+            spans.push(span.to_string());
+            Some(spans.len() - 1)
+        }
+
+        pub fn write_timestamp(&self, encoder: &mut CommandEncoder, span: &str, end: bool) {
+            let query_set = if let Some(query_set) = &self.query_set {
+                query_set
+            } else {
+                return;
+            };
+
+            let index = if let Some(index) = self.span_index(span) {
+                index
+            } else {
+                return;
+            };
+
+            encoder.write_timestamp(query_set, index as u32 * 2 + end as u32);
+
+            if end {
+                self.written.fetch_or(1 << index, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Writes the start or end timestamp of the main pass span
+    struct GpuSpanNode {
+        end: bool,
+    }
+
+    impl Node for GpuSpanNode {
+        fn run(
+            &self,
+            _graph: &mut RenderGraphContext,
+            render_context: &mut RenderContext,
+            world: &World,
+        ) -> Result<(), NodeRunError> {
+            if self.end {
+                end_gpu_span(world, &mut render_context.command_encoder, MAIN_PASS_SPAN);
+            } else {
+                begin_gpu_span(world, &mut render_context.command_encoder, MAIN_PASS_SPAN);
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Resolves this frame's timestamps into the readback buffer
+    struct GpuTimestampResolveNode;
+
+    impl Node for GpuTimestampResolveNode {
+        fn run(
+            &self,
+            _graph: &mut RenderGraphContext,
+            render_context: &mut RenderContext,
+            world: &World,
+        ) -> Result<(), NodeRunError> {
+            let timestamps = world.resource::<GpuTimestamps>();
+
+            let query_set = if let Some(query_set) = &timestamps.query_set {
+                query_set
+            } else {
+                return Ok(());
+            };
+
+            // The readback buffer is still in use by the previous frame
+            if timestamps.mapping.load(Ordering::Acquire) {
+                return Ok(());
+            }
+
+            let written = timestamps.written.swap(0, Ordering::Relaxed);
+            if written == 0 {
+                return Ok(());
+            }
+
+            let size = (MAX_GPU_SPANS * 2) as u64 * TIMESTAMP_SIZE;
+            render_context.command_encoder.resolve_query_set(
+                query_set,
+                0..MAX_GPU_SPANS * 2,
+                &timestamps.resolve_buffer,
+                0,
+            );
+            render_context.command_encoder.copy_buffer_to_buffer(
+                &timestamps.resolve_buffer,
+                0,
+                &timestamps.readback_buffer,
+                0,
+                size,
+            );
+
+            timestamps.resolved.store(written, Ordering::Relaxed);
+
+            Ok(())
+        }
+    }
+
+    /// Maps the readback buffer once the frame's timestamps have been submitted,
+    /// converting them into per-span durations
+    pub fn map_gpu_timestamps(
+        render_device: Res<RenderDevice>,
+        timestamps: Res<GpuTimestamps>,
+        measurements: Res<GpuSpanMeasurements>,
+    ) {
+        let resolved = timestamps.resolved.swap(0, Ordering::Relaxed);
+        if resolved == 0 {
+            return;
+        }
+
+        timestamps.mapping.store(true, Ordering::Release);
+
+        let spans = timestamps.spans.lock().unwrap().clone();
+        let period = timestamps.period as f64;
+        let buffer = timestamps.readback_buffer.clone();
+        let mapping = timestamps.mapping.clone();
+        let measurements = measurements.0.clone();
+
+        render_device.map_buffer(
+            &timestamps.readback_buffer.slice(..),
+            MapMode::Read,
+            move |status| {
+                if status.is_ok() {
+                    {
+                        let data = buffer.slice(..).get_mapped_range();
+                        let data: &[u64] = bytemuck::cast_slice(&data);
+
+                        let mut measurements = measurements.lock().unwrap();
+                        for (index, name) in spans.into_iter().enumerate() {
+                            if resolved & (1 << index) == 0 {
+                                continue;
+                            }
+
+                            let ticks = data[index * 2 + 1].wrapping_sub(data[index * 2]);
+                            measurements.push((index, name, ticks as f64 * period / 1_000_000.0));
+                        }
+                    }
+                    buffer.unmap();
+                }
+
+                mapping.store(false, Ordering::Release);
+            },
+        );
+    }
+
+    /// Forwards GPU span timings to [`Diagnostics`], registering new spans as they appear
+    pub fn report_gpu_spans(
+        measurements: Res<GpuSpanMeasurements>,
+        mut diagnostics: ResMut<Diagnostics>,
+    ) {
+        for (index, name, milliseconds) in measurements.0.lock().unwrap().drain(..) {
+            let id = gpu_span_diagnostic_id(index);
+
+            if diagnostics.get(id).is_none() {
+                diagnostics.add(Diagnostic::new(id, format!("gpu {name}"), 20).with_suffix("ms"));
+            }
+
+            diagnostics.add_measurement(id, || milliseconds);
+        }
+    }
+}
@@ -5,34 +5,41 @@ use std::{borrow::Cow, hash::Hash};
 use bevy::prelude::Resource;
 use bevy::{
     asset::load_internal_asset,
+    ecs::{query::QueryItem, reflect::ReflectComponent, system::lifetimeless::Read},
     prelude::{
-        debug, default, App, AssetServer, Commands, Entity, FromWorld, HandleUntyped, Image,
-        Plugin, Query, Res, ResMut, Shader, World,
+        debug, default, App, AssetServer, ChangeTrackers, Commands, Component, Deref, DerefMut,
+        Entity, FromWorld, HandleUntyped, Image, Plugin, Query, Res, ResMut, Shader, World,
     },
-    reflect::TypeUuid,
+    reflect::{Reflect, TypeUuid},
     render::{
         extract_component::{ExtractComponent, ExtractComponentPlugin},
         render_asset::RenderAssets,
         render_graph::{Node, NodeLabel, RenderGraph},
         render_resource::{
             AsBindGroup, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
-            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType,
-            BufferBinding, BufferBindingType, ComputePassDescriptor, ComputePipelineDescriptor,
-            PipelineCache, PreparedBindGroup, ShaderRef, ShaderStages, SpecializedComputePipeline,
-            SpecializedComputePipelines,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+            BufferBinding, BufferBindingType, BufferDescriptor, BufferUsages,
+            ComputePassDescriptor, ComputePipelineDescriptor, PipelineCache, PreparedBindGroup,
+            ShaderRef, ShaderStages, ShaderType, SpecializedComputePipeline,
+            SpecializedComputePipelines, UniformBuffer,
         },
-        renderer::RenderDevice,
+        renderer::{RenderDevice, RenderQueue},
         texture::FallbackImage,
-        RenderApp, RenderStage,
+        Extract, RenderApp, RenderStage,
     },
+    utils::{HashMap, HashSet},
 };
 use bevy::{prelude::Handle, render::render_resource::CachedComputePipelineId};
 
-use crate::prelude::{InstanceSliceRange, InstanceSliceTarget};
+use crate::prelude::{InstanceSliceRange, InstanceSliceTarget, SHARED_INSTANCE_SLICE_NODE};
 
-use super::render::instance::Instance;
+use super::{
+    gpu_timing::{begin_gpu_span, end_gpu_span},
+    render::instance::Instance,
+};
 
-struct InstanceComputeLabel<T>(PhantomData<T>);
+/// Render graph label of the compute node added by [`InstanceComputePlugin<T>`]
+pub struct InstanceComputeLabel<T>(PhantomData<T>);
 
 impl<T> Default for InstanceComputeLabel<T> {
     fn default() -> Self {
@@ -57,8 +64,59 @@ impl<T> Into<NodeLabel> for InstanceComputeLabel<T> {
 pub const INSTANCE_COMPUTE_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 3197649561934630342);
 
-#[derive(Debug, Default, Copy, Clone)]
-pub struct InstanceComputePlugin<T: InstanceCompute>(PhantomData<T>);
+/// Runs the compute shader of `T` over its instance slices
+///
+/// By default the compute node runs before [`CAMERA_DRIVER`](bevy::render::main_graph::node::CAMERA_DRIVER).
+/// Use [`InstanceComputePlugin::after`] and [`InstanceComputePlugin::before`] to order it against
+/// other nodes of the main render graph, which must already have been added.
+///
+/// Slices are computed every frame unless [`InstanceComputePlugin::compute_on_change`] is used.
+#[derive(Debug, Clone)]
+pub struct InstanceComputePlugin<T: InstanceCompute> {
+    pub after: Vec<NodeLabel>,
+    pub before: Vec<NodeLabel>,
+    pub compute_on_change: bool,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: InstanceCompute> Default for InstanceComputePlugin<T> {
+    fn default() -> Self {
+        Self {
+            after: vec![],
+            before: vec![bevy::render::main_graph::node::CAMERA_DRIVER.into()],
+            compute_on_change: false,
+            _phantom: default(),
+        }
+    }
+}
+
+impl<T: InstanceCompute> InstanceComputePlugin<T> {
+    /// Runs the compute node after the given node
+    pub fn after(mut self, label: impl Into<NodeLabel>) -> Self {
+        self.after.push(label.into());
+        self
+    }
+
+    /// Runs the compute node before the given node, as well as before `CAMERA_DRIVER`
+    pub fn before(mut self, label: impl Into<NodeLabel>) -> Self {
+        self.before.push(label.into());
+        self
+    }
+
+    /// Only computes slices whose `T` component changed since the last frame
+    ///
+    /// The output of each computed slice is kept in a persistent buffer,
+    /// and copied back into the slice on frames where `T` is unchanged instead of dispatching.
+    /// Slices are also recomputed when their instance count changes.
+    /// Grouped slices are recomputed together whenever any slice of their group changes.
+    ///
+    /// Only suitable for shaders whose output depends on `T` alone,
+    /// rather than on time or the previous contents of the slice.
+    pub fn compute_on_change(mut self) -> Self {
+        self.compute_on_change = true;
+        self
+    }
+}
 
 impl<T> Plugin for InstanceComputePlugin<T>
 where
@@ -81,17 +139,42 @@ where
             .init_resource::<SpecializedComputePipelines<InstanceComputePipeline<T>>>()
             .add_system_to_stage(RenderStage::Queue, queue_compute_instances::<T>);
 
+        if self.compute_on_change {
+            render_app
+                .init_resource::<InstanceComputeCache<T>>()
+                .add_system_to_stage(RenderStage::Extract, extract_instance_compute_changes::<T>);
+        }
+
         let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
         render_graph.add_node(
             InstanceComputeLabel::<T>::default(),
             InstanceComputeNode::<T>::default(),
         );
-        render_graph
-            .add_node_edge(
-                InstanceComputeLabel::<T>::default(),
-                bevy::render::main_graph::node::CAMERA_DRIVER,
-            )
-            .unwrap();
+
+        for label in self.after.iter() {
+            render_graph
+                .add_node_edge(label.clone(), InstanceComputeLabel::<T>::default())
+                .unwrap();
+        }
+
+        for label in self.before.iter() {
+            render_graph
+                .add_node_edge(InstanceComputeLabel::<T>::default(), label.clone())
+                .unwrap();
+        }
+
+        // Shared slices copy compute output, so must see this frame's
+        if render_graph
+            .get_node_state(SHARED_INSTANCE_SLICE_NODE)
+            .is_ok()
+        {
+            render_graph
+                .add_node_edge(
+                    InstanceComputeLabel::<T>::default(),
+                    SHARED_INSTANCE_SLICE_NODE,
+                )
+                .unwrap();
+        }
     }
 }
 
@@ -143,16 +226,29 @@ impl<T: InstanceCompute> FromWorld for InstanceComputePipeline<T> {
         let instance_bind_group_layout =
             render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
                 label: Some("instance buffer bind group"),
-                entries: &[BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::COMPUTE,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                    // Slice instance count
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(u32::min_size()),
+                        },
+                        count: None,
+                    },
+                ],
             });
 
         let asset_server = world.resource::<AssetServer>();
@@ -179,8 +275,27 @@ impl<T: InstanceCompute> Default for InstanceComputeNode<T> {
     }
 }
 
+/// Compute work queued for `T` during the current frame
 #[derive(Resource)]
-struct InstanceComputeQueue<T: InstanceCompute>(Vec<InstanceComputeJob<T>>);
+pub struct InstanceComputeQueue<T: InstanceCompute> {
+    jobs: Vec<InstanceComputeJob<T>>,
+    /// Copies from cached output into unchanged slices
+    restores: Vec<InstanceComputeCopy>,
+    /// Copies from freshly computed slices into their cached output
+    stores: Vec<InstanceComputeCopy>,
+}
+
+impl<T: InstanceCompute> InstanceComputeQueue<T> {
+    /// Number of compute dispatches queued this frame
+    pub fn dispatch_count(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Number of slices restored from their cached output this frame instead of being computed
+    pub fn restore_count(&self) -> usize {
+        self.restores.len()
+    }
+}
 
 struct InstanceComputeJob<T: InstanceCompute> {
     pipeline: CachedComputePipelineId,
@@ -189,6 +304,75 @@ struct InstanceComputeJob<T: InstanceCompute> {
     instance_count: u64,
 }
 
+struct InstanceComputeCopy {
+    source: Buffer,
+    source_offset: u64,
+    destination: Buffer,
+    destination_offset: u64,
+    size: u64,
+}
+
+impl InstanceComputeCopy {
+    fn encode(&self, render_context: &mut bevy::render::renderer::RenderContext) {
+        render_context.command_encoder.copy_buffer_to_buffer(
+            &self.source,
+            self.source_offset,
+            &self.destination,
+            self.destination_offset,
+            self.size,
+        );
+    }
+}
+
+/// Last computed output of a slice, see [`InstanceComputePlugin::compute_on_change`]
+pub struct InstanceComputeCacheBuffer {
+    pub buffer: Buffer,
+    pub instance_count: u64,
+}
+
+/// Slice output kept across frames by [`InstanceComputePlugin::compute_on_change`]
+#[derive(Resource)]
+pub struct InstanceComputeCache<T: InstanceCompute> {
+    /// Slices whose `T` changed since the previous frame
+    pub changed: HashSet<Entity>,
+    pub buffers: HashMap<Entity, InstanceComputeCacheBuffer>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: InstanceCompute> Default for InstanceComputeCache<T> {
+    fn default() -> Self {
+        Self {
+            changed: default(),
+            buffers: default(),
+            _phantom: default(),
+        }
+    }
+}
+
+impl<T: InstanceCompute> InstanceComputeCache<T> {
+    /// Whether the slice needs computing, rather than restoring from its cached output
+    fn is_dirty(&self, entity: Entity, range: &InstanceSliceRange) -> bool {
+        self.changed.contains(&entity)
+            || self
+                .buffers
+                .get(&entity)
+                .map(|cached| cached.instance_count != range.instance_count)
+                .unwrap_or(true)
+    }
+}
+
+/// Records which slices had their `T` changed in the main world since the previous frame
+pub fn extract_instance_compute_changes<T: InstanceCompute>(
+    mut cache: ResMut<InstanceComputeCache<T>>,
+    query_instance_compute: Extract<Query<(Entity, ChangeTrackers<T>)>>,
+) {
+    cache.changed = query_instance_compute
+        .iter()
+        .filter(|(_, trackers)| trackers.is_changed())
+        .map(|(entity, _)| entity)
+        .collect();
+}
+
 const WORKGROUP_SIZE: u64 = 64;
 
 impl<T> Node for InstanceComputeNode<T>
@@ -204,8 +388,19 @@ where
         debug!("InstanceComputeNode::run");
         let pipeline_cache = world.resource::<PipelineCache>();
 
-        let compute_jobs = &world.resource::<InstanceComputeQueue<T>>().0;
-        for compute_job in compute_jobs {
+        let span = format!(
+            "compute {}",
+            bevy::utils::get_short_name(std::any::type_name::<T>())
+        );
+        begin_gpu_span(world, &mut render_context.command_encoder, &span);
+
+        let compute_queue = world.resource::<InstanceComputeQueue<T>>();
+
+        for restore in compute_queue.restores.iter() {
+            restore.encode(render_context);
+        }
+
+        for compute_job in compute_queue.jobs.iter() {
             if let Some(instance_pipeline) =
                 pipeline_cache.get_compute_pipeline(compute_job.pipeline)
             {
@@ -221,42 +416,226 @@ where
                 pass.set_bind_group(0, &compute_job.uniform_bind_group.bind_group, &[]);
                 pass.set_bind_group(1, &compute_job.instance_bind_group, &[]);
 
+                // Round up, so trailing instances past the last full workgroup are computed
                 let instance_workgroups =
-                    (compute_job.instance_count / WORKGROUP_SIZE).max(1) as u32;
+                    compute_job.instance_count.div_ceil(WORKGROUP_SIZE) as u32;
 
                 pass.set_pipeline(instance_pipeline);
                 pass.dispatch_workgroups(instance_workgroups, 1, 1);
             }
         }
 
+        for store in compute_queue.stores.iter() {
+            store.encode(render_context);
+        }
+
+        end_gpu_span(world, &mut render_context.command_encoder, &span);
+
         Ok(())
     }
 }
 
+/// Dispatches the compute shader of contiguous instance slices sharing a group at once
+///
+/// Slices with equal groups, compute pipelines and instance buffers are merged
+/// into a single dispatch over their combined range whenever their ranges are adjacent,
+/// using the uniform data of the first slice in the range.
+/// Slices without this component are always dispatched individually.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component)]
+pub struct InstanceComputeGroup(pub u32);
+
+impl ExtractComponent for InstanceComputeGroup {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn queue_compute_instances<T>(
     pipeline: Res<InstanceComputePipeline<T>>,
     render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
     mut pipeline_cache: ResMut<PipelineCache>,
     mut compute_pipelines: ResMut<SpecializedComputePipelines<InstanceComputePipeline<T>>>,
     render_images: Res<RenderAssets<Image>>,
     fallback_image: Res<FallbackImage>,
-    query_instance_slice: Query<(Entity, &T, &InstanceSliceRange, &InstanceSliceTarget)>,
+    mut compute_cache: Option<ResMut<InstanceComputeCache<T>>>,
+    query_instance_slice: Query<(
+        Entity,
+        &T,
+        &InstanceSliceRange,
+        &InstanceSliceTarget,
+        Option<&InstanceComputeGroup>,
+    )>,
     mut commands: Commands,
 ) where
     T: InstanceCompute,
     T::Data: Clone + PartialEq + Eq + Hash + for<'a> From<&'a T>,
 {
     debug!("queue_compute_instances");
-    let mut instance_compute_queue = vec![];
 
+    if let Some(compute_cache) = compute_cache.as_mut() {
+        compute_cache
+            .buffers
+            .retain(|entity, _| query_instance_slice.contains(*entity));
+    }
+
+    let is_dirty = |entity: Entity, range: &InstanceSliceRange| {
+        compute_cache
+            .as_ref()
+            .map(|compute_cache| compute_cache.is_dirty(entity, range))
+            .unwrap_or(true)
+    };
+
+    // Dispatch ungrouped slices individually,
+    // and collect grouped ones by the state their dispatches would share.
+    // Each dispatch keeps the slices it covers, so their output can be cached
+    let mut dispatches = vec![];
+    let mut unchanged_slices = vec![];
+    let mut grouped_slices = HashMap::<_, Vec<_>>::default();
     for (
         instance_slice_entity,
         instance_compute_uniform,
         instance_slice_range,
         instance_slice_buffer,
+        group,
     ) in query_instance_slice.iter()
     {
         debug!("Instance slice {instance_slice_entity:?}");
+        let compute_pipeline = compute_pipelines.specialize(
+            &mut pipeline_cache,
+            &pipeline,
+            instance_compute_uniform.into(),
+        );
+
+        let slice = (
+            instance_slice_buffer,
+            *instance_slice_range,
+            instance_compute_uniform,
+        );
+
+        if let Some(group) = group {
+            grouped_slices
+                .entry((*group, compute_pipeline, instance_slice_buffer.buffer.id()))
+                .or_default()
+                .push((instance_slice_entity, slice));
+        } else if is_dirty(instance_slice_entity, instance_slice_range) {
+            dispatches.push((
+                compute_pipeline,
+                slice,
+                vec![(instance_slice_entity, *instance_slice_range)],
+            ));
+        } else {
+            unchanged_slices.push((instance_slice_entity, slice));
+        }
+    }
+
+    // Merge adjacent ranges of grouped slices
+    for ((_, compute_pipeline, _), mut slices) in grouped_slices {
+        if !slices
+            .iter()
+            .any(|(entity, (_, range, _))| is_dirty(*entity, range))
+        {
+            unchanged_slices.extend(slices);
+            continue;
+        }
+
+        slices.sort_by_key(|(_, (_, range, _))| range.offset);
+
+        let mut slices = slices.into_iter();
+        let (entity, mut merged) = slices.next().unwrap();
+        let mut members = vec![(entity, merged.1)];
+        for (entity, slice) in slices {
+            if slice.1.offset == merged.1.offset + merged.1.instance_count {
+                merged.1.instance_count += slice.1.instance_count;
+                members.push((entity, slice.1));
+            } else {
+                dispatches.push((compute_pipeline, merged, std::mem::take(&mut members)));
+                merged = slice;
+                members.push((entity, slice.1));
+            }
+        }
+        dispatches.push((compute_pipeline, merged, members));
+    }
+
+    // Restore unchanged slices from their last computed output
+    let restores = unchanged_slices
+        .into_iter()
+        .map(
+            |(entity, (instance_slice_buffer, instance_slice_range, _))| {
+                let cached = &compute_cache.as_ref().unwrap().buffers[&entity];
+                InstanceComputeCopy {
+                    source: cached.buffer.clone(),
+                    source_offset: 0,
+                    destination: instance_slice_buffer.buffer.clone(),
+                    destination_offset: instance_slice_range.offset
+                        * instance_slice_buffer.instance_size,
+                    size: instance_slice_range.instance_count * instance_slice_buffer.instance_size,
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+
+    let mut instance_compute_queue = vec![];
+    let mut stores = vec![];
+    for (
+        compute_pipeline,
+        (instance_slice_buffer, instance_slice_range, instance_compute_uniform),
+        members,
+    ) in dispatches
+    {
+        if let Some(compute_cache) = compute_cache.as_mut() {
+            // Output computed by a pipeline that isn't ready yet wouldn't be valid
+            let ready = pipeline_cache
+                .get_compute_pipeline(compute_pipeline)
+                .is_some();
+
+            for (entity, range) in members {
+                if !ready {
+                    compute_cache.buffers.remove(&entity);
+                    continue;
+                }
+
+                let size = range.instance_count * instance_slice_buffer.instance_size;
+                let cached = match compute_cache.buffers.get(&entity) {
+                    Some(cached) if cached.instance_count == range.instance_count => {
+                        cached.buffer.clone()
+                    }
+                    _ => {
+                        let buffer = render_device.create_buffer(&BufferDescriptor {
+                            label: Some("instance compute cache buffer"),
+                            size,
+                            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+                            mapped_at_creation: false,
+                        });
+
+                        compute_cache.buffers.insert(
+                            entity,
+                            InstanceComputeCacheBuffer {
+                                buffer: buffer.clone(),
+                                instance_count: range.instance_count,
+                            },
+                        );
+
+                        buffer
+                    }
+                };
+
+                stores.push(InstanceComputeCopy {
+                    source: instance_slice_buffer.buffer.clone(),
+                    source_offset: range.offset * instance_slice_buffer.instance_size,
+                    destination: cached,
+                    destination_offset: 0,
+                    size,
+                });
+            }
+        }
+
         let uniform_bind_group = match instance_compute_uniform.as_bind_group(
             &pipeline.uniform_bind_group_layout,
             &render_device,
@@ -267,46 +646,61 @@ pub fn queue_compute_instances<T>(
             Err(_) => panic!("Failed to create uniform bind group"),
         };
 
+        // Workgroups may overshoot the slice, so shaders can early-out against its real length
+        let mut instance_count = UniformBuffer::from(instance_slice_range.instance_count as u32);
+        instance_count.write_buffer(&render_device, &render_queue);
+
         let instance_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
             label: None,
             layout: &pipeline.instance_bind_group_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: BindingResource::Buffer(BufferBinding {
-                    buffer: &instance_slice_buffer.buffer,
-                    offset: std::mem::size_of::<<T::Instance as Instance>::PreparedInstance>()
-                        as u64
-                        * instance_slice_range.offset,
-                    size: NonZeroU64::new(
-                        std::mem::size_of::<<T::Instance as Instance>::PreparedInstance>() as u64
-                            * instance_slice_range.instance_count,
-                    ),
-                }),
-            }],
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::Buffer(BufferBinding {
+                        buffer: &instance_slice_buffer.buffer,
+                        offset: std::mem::size_of::<<T::Instance as Instance>::PreparedInstance>()
+                            as u64
+                            * instance_slice_range.offset,
+                        size: NonZeroU64::new(
+                            std::mem::size_of::<<T::Instance as Instance>::PreparedInstance>()
+                                as u64
+                                * instance_slice_range.instance_count,
+                        ),
+                    }),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: instance_count.binding().unwrap(),
+                },
+            ],
         });
 
-        let pipeline = compute_pipelines.specialize(
-            &mut pipeline_cache,
-            &pipeline,
-            instance_compute_uniform.into(),
-        );
-
         debug!(
             "Queueing InstanceComputeJob for {} cells",
             instance_slice_range.instance_count
         );
 
         instance_compute_queue.push(InstanceComputeJob {
-            pipeline,
+            pipeline: compute_pipeline,
             uniform_bind_group,
             instance_bind_group,
             instance_count: instance_slice_range.instance_count,
         });
     }
 
-    commands.insert_resource(InstanceComputeQueue(instance_compute_queue));
+    commands.insert_resource(InstanceComputeQueue {
+        jobs: instance_compute_queue,
+        restores,
+        stores,
+    });
 }
 
+/// Component driving the compute shader of an [`InstanceSlice`](crate::prelude::InstanceSlice)
+///
+/// The shader is dispatched in workgroups of 64 over the slice, with `T`'s bind group in group 0.
+/// Group 1 holds the slice's instances as a storage buffer in binding 0,
+/// and its instance count as a `u32` uniform in binding 1; since the last workgroup
+/// may extend past the end of the slice, invocations beyond it should return early.
 pub trait InstanceCompute: AsBindGroup + ExtractComponent {
     type Instance: Instance;
 
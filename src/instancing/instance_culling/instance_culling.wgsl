@@ -0,0 +1,114 @@
+struct InstanceCulling {
+    planes: array<vec4<f32>, 5>,
+    instance_stride: u32,
+    first_instance: u32,
+    instance_count: u32,
+    indirect_stride: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> culling: InstanceCulling;
+
+// Prepared instances viewed as raw words,
+// with the transform matrix at a 16 byte offset into each instance
+@group(0) @binding(1)
+var<storage, read_write> instances: array<f32>;
+
+// Local-space bounding sphere of each CPU-prepared instance as center and radius
+@group(0) @binding(2)
+var<storage, read> instance_bounds: array<vec4<f32>>;
+
+#ifdef COMPACT
+// Copy of the CPU-prepared instances taken before culling,
+// from which visible instances are compacted into `instances`
+@group(0) @binding(3)
+var<storage, read> source_instances: array<f32>;
+
+// Index of the indirect draw covering each CPU-prepared instance
+@group(0) @binding(4)
+var<storage, read> instance_draws: array<u32>;
+
+// Indirect draws viewed as raw words, with the instance count second and the base instance last
+@group(0) @binding(5)
+var<storage, read_write> indirects: array<atomic<u32>>;
+#endif
+
+let TRANSFORM_OFFSET: u32 = 4u;
+
+fn load_word(i: u32) -> f32 {
+#ifdef COMPACT
+    return source_instances[i];
+#else
+    return instances[i];
+#endif
+}
+
+fn load_column(base: u32, column: u32) -> vec4<f32> {
+    let i = base + column * 4u;
+    return vec4<f32>(load_word(i), load_word(i + 1u), load_word(i + 2u), load_word(i + 3u));
+}
+
+@compute
+@workgroup_size(64)
+fn cull(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    if invocation_id.x >= culling.instance_count {
+        return;
+    }
+
+#ifdef COMPACT
+    let index = invocation_id.x;
+#else
+    let index = culling.first_instance + invocation_id.x;
+#endif
+
+    let base = index * culling.instance_stride + TRANSFORM_OFFSET;
+
+    let x = load_column(base, 0u);
+    let y = load_column(base, 1u);
+    let z = load_column(base, 2u);
+    let w = load_column(base, 3u);
+
+    // Already invisible
+    if all(w == vec4<f32>(0.0)) {
+        return;
+    }
+
+    let bounds = instance_bounds[invocation_id.x];
+
+    let scale = max(length(x.xyz), max(length(y.xyz), length(z.xyz)));
+    // Scale the instance's bounds by its largest axis scale,
+    // so heavily scaled instances are culled conservatively
+    let radius = bounds.w * scale;
+    let center = vec4<f32>((x * bounds.x + y * bounds.y + z * bounds.z + w).xyz, 1.0);
+
+    var visible = true;
+    for (var i = 0u; i < 5u; i = i + 1u) {
+        if dot(culling.planes[i], center) + radius <= 0.0 {
+            visible = false;
+        }
+    }
+
+#ifdef COMPACT
+    if !visible {
+        return;
+    }
+
+    // Append the instance to its draw's visible instances
+    let draw = instance_draws[invocation_id.x] * culling.indirect_stride;
+    let slot = atomicAdd(&indirects[draw + 1u], 1u);
+    let target_index = atomicLoad(&indirects[draw + culling.indirect_stride - 1u]) + slot;
+
+    let source_base = index * culling.instance_stride;
+    let target_base = target_index * culling.instance_stride;
+    for (var i = 0u; i < culling.instance_stride; i = i + 1u) {
+        instances[target_base + i] = source_instances[source_base + i];
+    }
+#else
+    if !visible {
+        // Zero the transform to flag the instance invisible
+        for (var i = 0u; i < 16u; i = i + 1u) {
+            instances[base + i] = 0.0;
+        }
+    }
+#endif
+}
@@ -0,0 +1,755 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::num::NonZeroU64;
+
+use bevy::{
+    asset::load_internal_asset,
+    math::Vec4,
+    prelude::{
+        debug, default, App, Commands, Entity, FromWorld, HandleUntyped, Plugin, Query, Res,
+        ResMut, Resource, Shader, With, World,
+    },
+    reflect::TypeUuid,
+    render::{
+        primitives::Frustum,
+        render_graph::{Node, NodeLabel, RenderGraph},
+        render_resource::{
+            BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+            BufferBinding, BufferBindingType, BufferDescriptor, BufferUsages,
+            CachedComputePipelineId, ComputePassDescriptor, ComputePipelineDescriptor,
+            PipelineCache, ShaderSize, ShaderStages, ShaderType, StorageBuffer, UniformBuffer,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        view::{ExtractedView, VisibleEntities},
+        RenderApp, RenderStage,
+    },
+};
+
+use crate::instancing::{
+    gpu_timing::{begin_gpu_span, end_gpu_span},
+    indirect::{DrawCall, DrawIndexedIndirect, DrawIndirect, IndirectDraw},
+    material::{
+        material_instanced::MaterialInstanced,
+        plugin::{
+            BatchedDraws, BatchedInstances, GpuAlphaMode, GpuIndirectBufferData, GpuInstances,
+            InstanceBatchKey, InstanceMeta,
+        },
+        systems::prepare_instance_batches::ViewInstanceData,
+    },
+    render::instance::Instance,
+};
+
+struct InstanceCullingLabel<M>(PhantomData<M>);
+
+impl<M> Default for InstanceCullingLabel<M> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<M> From<InstanceCullingLabel<M>> for Cow<'static, str> {
+    fn from(_: InstanceCullingLabel<M>) -> Self {
+        Cow::Owned(format!(
+            "instance_culling::<{}>",
+            std::any::type_name::<M>()
+        ))
+    }
+}
+
+impl<M> From<InstanceCullingLabel<M>> for NodeLabel {
+    fn from(label: InstanceCullingLabel<M>) -> Self {
+        NodeLabel::Name(label.into())
+    }
+}
+
+pub const INSTANCE_CULLING_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 7416205853191645032);
+
+/// Byte offset of the transform matrix into each prepared instance,
+/// as read and zeroed by the culling shader's `TRANSFORM_OFFSET` word
+pub const CULLED_TRANSFORM_OFFSET: u64 = 16;
+
+/// Prepared instance layout that [`InstanceCullingPlugin`] can cull
+///
+/// The culling shader treats instances as raw words, reading the model matrix
+/// [`CULLED_TRANSFORM_OFFSET`] bytes in, as laid out by [`GpuMeshInstance`](crate::prelude::GpuMeshInstance).
+/// Implementations should derive [`CulledInstance::TRANSFORM_OFFSET`] from their [`ShaderType`] layout,
+/// so a mismatch fails to compile rather than culling garbage.
+pub trait CulledInstance: ShaderType + ShaderSize {
+    /// Byte offset of the model matrix into the instance's GPU layout
+    const TRANSFORM_OFFSET: u64;
+}
+
+/// Compile-time check of a [`CulledInstance`]'s layout against the culling shader
+struct CulledLayout<P>(PhantomData<P>);
+
+impl<P: CulledInstance> CulledLayout<P> {
+    const MATCHES_SHADER: () = assert!(
+        P::TRANSFORM_OFFSET == CULLED_TRANSFORM_OFFSET,
+        "Instance culling expects the transform 16 bytes into each prepared instance"
+    );
+}
+
+/// Culls the CPU-prepared instances of material `M` against each view frustum on the GPU.
+///
+/// Runs before the camera driver, and flags instances whose bounding sphere lies
+/// outside the frustum by zeroing their transform in the view's instance buffer.
+/// Each instance's sphere encloses its main-world [`Aabb`](bevy::render::primitives::Aabb),
+/// falling back to its mesh's bounds, and is scaled by its largest axis scale,
+/// so heavily scaled instances are culled conservatively.
+/// The instanced vertex shader collapses zero-transform instances to a degenerate
+/// point, so culled instances are skipped by the rasterizer without the need
+/// for compaction or an indirect count buffer.
+///
+/// Only storage-backed instance batches are culled, and instance slices are left
+/// untouched since their contents are produced on the GPU.
+///
+/// [`InstanceCullingPlugin::compact`] packs visible instances together instead,
+/// writing the surviving count of each mesh into its indirect draw.
+#[derive(Debug, Copy, Clone)]
+pub struct InstanceCullingPlugin<M: MaterialInstanced> {
+    pub compact: bool,
+    _phantom: PhantomData<M>,
+}
+
+impl<M: MaterialInstanced> Default for InstanceCullingPlugin<M> {
+    fn default() -> Self {
+        Self {
+            compact: false,
+            _phantom: default(),
+        }
+    }
+}
+
+impl<M: MaterialInstanced> InstanceCullingPlugin<M> {
+    /// Compacts the visible instances of each mesh to the front of its range of the instance buffer,
+    /// and writes their number into the `instance_count` of its indirect draw,
+    /// so culled instances cost no vertex shader invocations at all
+    ///
+    /// Visible instances land in arbitrary order, so `instance_index` no longer identifies
+    /// an instance through [`InstanceIndices`](crate::prelude::InstanceIndices).
+    /// Blended batches, which rely on their depth order, and batches drawn directly
+    /// rather than through an indirect buffer are culled without compaction.
+    pub fn compact(mut self) -> Self {
+        self.compact = true;
+        self
+    }
+}
+
+/// Render world copy of [`InstanceCullingPlugin`]'s settings for material `M`
+#[derive(Debug, Copy, Clone, Resource)]
+pub struct InstanceCullingSettings<M: MaterialInstanced> {
+    pub compact: bool,
+    _phantom: PhantomData<M>,
+}
+
+impl<M: MaterialInstanced> Plugin for InstanceCullingPlugin<M>
+where
+    <M::Instance as Instance>::PreparedInstance: CulledInstance,
+{
+    fn build(&self, app: &mut App) {
+        #[allow(clippy::let_unit_value)]
+        let () = CulledLayout::<<M::Instance as Instance>::PreparedInstance>::MATCHES_SHADER;
+
+        load_internal_asset!(
+            app,
+            INSTANCE_CULLING_SHADER_HANDLE,
+            "instance_culling.wgsl",
+            Shader::from_wgsl
+        );
+
+        let render_app = app.sub_app_mut(RenderApp);
+
+        if !render_app
+            .world
+            .contains_resource::<InstanceCullingPipeline>()
+        {
+            render_app.init_resource::<InstanceCullingPipeline>();
+        }
+
+        render_app
+            .insert_resource(InstanceCullingSettings::<M> {
+                compact: self.compact,
+                _phantom: default(),
+            })
+            .init_resource::<InstanceCullingBuffers<M>>()
+            .add_system_to_stage(RenderStage::Queue, queue_instance_culling::<M>);
+
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node(
+            InstanceCullingLabel::<M>::default(),
+            InstanceCullingNode::<M>::default(),
+        );
+        render_graph
+            .add_node_edge(
+                InstanceCullingLabel::<M>::default(),
+                bevy::render::main_graph::node::CAMERA_DRIVER,
+            )
+            .unwrap();
+    }
+}
+
+/// Per-dispatch culling parameters
+#[derive(Debug, Default, Copy, Clone, ShaderType)]
+pub struct InstanceCullingUniform {
+    /// Inward-facing frustum planes, excluding the far plane
+    pub planes: [Vec4; 5],
+    /// Size of a single prepared instance in 32-bit words
+    pub instance_stride: u32,
+    /// Index of the first CPU-prepared instance, following the batch's instance slices
+    pub first_instance: u32,
+    pub instance_count: u32,
+    /// Size of a single indirect draw in 32-bit words, when compacting
+    pub indirect_stride: u32,
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct InstanceCullingPipeline {
+    pub bind_group_layout: BindGroupLayout,
+    pub pipeline: CachedComputePipelineId,
+    /// Layout of compacting dispatches, adding the source instances,
+    /// each instance's indirect draw and the indirect buffer
+    pub compact_bind_group_layout: BindGroupLayout,
+    pub compact_pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for InstanceCullingPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let storage_entry = |binding: u32, read_only: bool| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let entries = [
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(InstanceCullingUniform::min_size()),
+                },
+                count: None,
+            },
+            storage_entry(1, false),
+            storage_entry(2, true),
+            storage_entry(3, true),
+            storage_entry(4, true),
+            storage_entry(5, false),
+        ];
+
+        let bind_group_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("instance culling bind group"),
+                entries: &entries[..3],
+            });
+
+        let compact_bind_group_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("instance culling compact bind group"),
+                entries: &entries,
+            });
+
+        let mut pipeline_cache = world.resource_mut::<PipelineCache>();
+        let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some("instance culling".into()),
+            layout: Some(vec![bind_group_layout.clone()]),
+            shader: INSTANCE_CULLING_SHADER_HANDLE.typed(),
+            shader_defs: vec![],
+            entry_point: Cow::from("cull"),
+        });
+
+        let compact_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some("instance culling compact".into()),
+            layout: Some(vec![compact_bind_group_layout.clone()]),
+            shader: INSTANCE_CULLING_SHADER_HANDLE.typed(),
+            shader_defs: vec!["COMPACT".into()],
+            entry_point: Cow::from("cull"),
+        });
+
+        InstanceCullingPipeline {
+            bind_group_layout,
+            pipeline,
+            compact_bind_group_layout,
+            compact_pipeline,
+        }
+    }
+}
+
+/// Culling buffers of each view's batches, kept between frames and rewritten in place
+#[derive(Resource)]
+pub struct InstanceCullingBuffers<M: MaterialInstanced> {
+    pub buffers: BTreeMap<Entity, BTreeMap<InstanceBatchKey<M>, BatchCullingBuffers>>,
+}
+
+impl<M: MaterialInstanced> Default for InstanceCullingBuffers<M> {
+    fn default() -> Self {
+        Self { buffers: default() }
+    }
+}
+
+#[derive(Default)]
+pub struct BatchCullingBuffers {
+    pub uniform: UniformBuffer<InstanceCullingUniform>,
+    pub instance_bounds: StorageBuffer<Vec<Vec4>>,
+    pub instance_draws: StorageBuffer<Vec<u32>>,
+    /// Copy of the culled instances when compacting,
+    /// recreated at the exact size needed whenever the batch outgrows it
+    pub compact_source: Option<Buffer>,
+}
+
+#[derive(Resource)]
+struct InstanceCullingQueue<M: MaterialInstanced> {
+    jobs: Vec<InstanceCullingJob>,
+    _phantom: PhantomData<M>,
+}
+
+struct InstanceCullingJob {
+    bind_group: BindGroup,
+    instance_count: u32,
+    /// Copy of the culled instances, read from while compacting them back into the instance buffer
+    compact_source: Option<InstanceCullingSource>,
+}
+
+struct InstanceCullingSource {
+    instance_buffer: Buffer,
+    offset: u64,
+    buffer: Buffer,
+    /// Size of the copied instances, which may not fill `buffer`
+    size: u64,
+}
+
+const WORKGROUP_SIZE: u32 = 64;
+
+struct InstanceCullingNode<M>(PhantomData<M>);
+
+impl<M: MaterialInstanced> Default for InstanceCullingNode<M> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<M: MaterialInstanced> Node for InstanceCullingNode<M> {
+    fn run(
+        &self,
+        _graph: &mut bevy::render::render_graph::RenderGraphContext,
+        render_context: &mut bevy::render::renderer::RenderContext,
+        world: &World,
+    ) -> Result<(), bevy::render::render_graph::NodeRunError> {
+        debug!("InstanceCullingNode::run");
+
+        let queue = if let Some(queue) = world.get_resource::<InstanceCullingQueue<M>>() {
+            queue
+        } else {
+            return Ok(());
+        };
+
+        if queue.jobs.is_empty() {
+            return Ok(());
+        }
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let culling_pipeline = world.resource::<InstanceCullingPipeline>();
+        let pipeline = pipeline_cache.get_compute_pipeline(culling_pipeline.pipeline);
+        let compact_pipeline =
+            pipeline_cache.get_compute_pipeline(culling_pipeline.compact_pipeline);
+
+        let span = format!(
+            "culling {}",
+            bevy::utils::get_short_name(std::any::type_name::<M>())
+        );
+        begin_gpu_span(world, &mut render_context.command_encoder, &span);
+
+        // Snapshot instances to be compacted, as they're overwritten in place
+        for compact_source in queue.jobs.iter().flat_map(|job| &job.compact_source) {
+            render_context.command_encoder.copy_buffer_to_buffer(
+                &compact_source.instance_buffer,
+                compact_source.offset,
+                &compact_source.buffer,
+                0,
+                compact_source.size,
+            );
+        }
+
+        {
+            let mut pass = render_context
+                .command_encoder
+                .begin_compute_pass(&ComputePassDescriptor::default());
+
+            for job in queue.jobs.iter() {
+                let pipeline = if job.compact_source.is_some() {
+                    compact_pipeline
+                } else {
+                    pipeline
+                };
+
+                let pipeline = if let Some(pipeline) = pipeline {
+                    pipeline
+                } else {
+                    continue;
+                };
+
+                debug!("Culling {} instances", job.instance_count);
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, &job.bind_group, &[]);
+                pass.dispatch_workgroups(job.instance_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+            }
+        }
+
+        end_gpu_span(world, &mut render_context.command_encoder, &span);
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_instance_culling<M: MaterialInstanced>(
+    pipeline: Res<InstanceCullingPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    settings: Res<InstanceCullingSettings<M>>,
+    view_instance_data: Res<ViewInstanceData<M>>,
+    mut culling_buffers: ResMut<InstanceCullingBuffers<M>>,
+    query_views: Query<(Entity, &ExtractedView, &InstanceMeta<M>), With<VisibleEntities>>,
+    mut commands: Commands,
+) where
+    <M::Instance as Instance>::PreparedInstance: CulledInstance,
+{
+    debug!("queue_instance_culling");
+
+    let instance_stride = <M::Instance as Instance>::PreparedInstance::min_size().get();
+
+    // Instance counts are only zeroed for compaction once it's certain to run
+    let compact = settings.compact
+        && pipeline_cache
+            .get_compute_pipeline(pipeline.compact_pipeline)
+            .is_some();
+
+    // Drop the buffers of views and batches that no longer exist
+    culling_buffers.buffers.retain(|view_entity, buffers| {
+        match view_instance_data.get(view_entity) {
+            Some(instance_data) if query_views.contains(*view_entity) => {
+                buffers.retain(|key, _| instance_data.contains_key(key));
+                true
+            }
+            _ => false,
+        }
+    });
+
+    let mut jobs = vec![];
+
+    for (view_entity, view, instance_meta) in query_views.iter() {
+        let instance_data = if let Some(instance_data) = view_instance_data.get(&view_entity) {
+            instance_data
+        } else {
+            continue;
+        };
+
+        let transform = view.transform.compute_matrix();
+        let frustum = Frustum::from_view_projection(
+            &(view.projection * transform.inverse()),
+            &view.transform.translation(),
+            &view.transform.back(),
+            f32::MAX,
+        );
+
+        let mut planes = [Vec4::ZERO; 5];
+        for (plane, frustum_plane) in planes.iter_mut().zip(frustum.planes.iter()) {
+            *plane = frustum_plane.normal_d();
+        }
+
+        for (key, gpu_instances) in instance_data.iter() {
+            let instance_buffer = match gpu_instances {
+                GpuInstances::Storage { buffer } => buffer.buffer(),
+                GpuInstances::Uniform { .. } => None,
+            };
+
+            let instance_buffer = if let Some(instance_buffer) = instance_buffer {
+                instance_buffer
+            } else {
+                continue;
+            };
+
+            // CPU-prepared instances follow the batch's instance slices
+            let first_instance = instance_meta
+                .instance_batches
+                .get(key)
+                .map(|batch| batch.instance_slice_len())
+                .unwrap_or_default();
+
+            let instance_count = gpu_instances.len() as u64 - first_instance;
+            if instance_count == 0 {
+                continue;
+            }
+
+            // Local-space bounding sphere of each CPU-prepared instance
+            let instance_bounds =
+                if let Some(instance_batch) = instance_meta.instance_batches.get(key) {
+                    &instance_batch.bounds
+                } else {
+                    continue;
+                };
+
+            if instance_bounds.len() as u64 != instance_count {
+                continue;
+            }
+
+            let buffers = culling_buffers
+                .buffers
+                .entry(view_entity)
+                .or_default()
+                .entry(key.clone())
+                .or_default();
+
+            buffers.instance_bounds.set(instance_bounds.clone());
+            buffers
+                .instance_bounds
+                .write_buffer(&render_device, &render_queue);
+
+            // Compact batches drawn through an indirect buffer, unless they rely on depth order
+            let compact = if compact && key.material_key.alpha_mode != GpuAlphaMode::Blend {
+                match instance_meta.batched_instances.get(key).map(Vec::as_slice) {
+                    Some(
+                        [BatchedInstances {
+                            draws: BatchedDraws::Indirect(indirect_data),
+                            ..
+                        }],
+                    ) => instance_draws(&indirect_data.indirects, first_instance, instance_count)
+                        .map(|instance_draws| (indirect_data, instance_draws)),
+                    // Direct draws have no instance count to compact into,
+                    // so their culled instances are zeroed in place instead
+                    _ => None,
+                }
+            } else {
+                None
+            };
+
+            let indirect_stride = match &compact {
+                Some((indirect_data, _)) => indirect_stride(indirect_data),
+                None => 0,
+            };
+
+            buffers.uniform.set(InstanceCullingUniform {
+                planes,
+                instance_stride: (instance_stride / 4) as u32,
+                first_instance: first_instance as u32,
+                instance_count: instance_count as u32,
+                indirect_stride: (indirect_stride / 4) as u32,
+            });
+            buffers.uniform.write_buffer(&render_device, &render_queue);
+
+            let instance_binding = BindingResource::Buffer(BufferBinding {
+                buffer: instance_buffer,
+                offset: 0,
+                size: NonZeroU64::new(instance_stride * (first_instance + instance_count)),
+            });
+
+            let (bind_group, compact_source) = match compact {
+                Some((indirect_data, instance_draws)) => {
+                    // Visible instances are counted back up from zero as they're compacted
+                    for (i, indirect) in indirect_data.indirects.iter().enumerate() {
+                        if indirect.base_instance() as u64 >= first_instance {
+                            render_queue.write_buffer(
+                                &indirect_data.buffer,
+                                (i as u64 * indirect_stride) + 4,
+                                &0u32.to_le_bytes(),
+                            );
+                        }
+                    }
+
+                    buffers.instance_draws.set(instance_draws);
+                    buffers
+                        .instance_draws
+                        .write_buffer(&render_device, &render_queue);
+
+                    let source_size = instance_stride * instance_count;
+                    let source = match &buffers.compact_source {
+                        Some(source) if source.size() >= source_size => source.clone(),
+                        _ => {
+                            let source = render_device.create_buffer(&BufferDescriptor {
+                                label: Some("instance culling source buffer"),
+                                size: source_size,
+                                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                                mapped_at_creation: false,
+                            });
+                            buffers.compact_source = Some(source.clone());
+                            source
+                        }
+                    };
+
+                    let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                        label: Some("instance culling compact bind group"),
+                        layout: &pipeline.compact_bind_group_layout,
+                        entries: &[
+                            BindGroupEntry {
+                                binding: 0,
+                                resource: buffers.uniform.binding().unwrap(),
+                            },
+                            BindGroupEntry {
+                                binding: 1,
+                                resource: instance_binding,
+                            },
+                            BindGroupEntry {
+                                binding: 2,
+                                resource: buffers.instance_bounds.binding().unwrap(),
+                            },
+                            BindGroupEntry {
+                                binding: 3,
+                                resource: source.as_entire_binding(),
+                            },
+                            BindGroupEntry {
+                                binding: 4,
+                                resource: buffers.instance_draws.binding().unwrap(),
+                            },
+                            BindGroupEntry {
+                                binding: 5,
+                                resource: indirect_data.buffer.as_entire_binding(),
+                            },
+                        ],
+                    });
+
+                    (
+                        bind_group,
+                        Some(InstanceCullingSource {
+                            instance_buffer: instance_buffer.clone(),
+                            offset: instance_stride * first_instance,
+                            buffer: source,
+                            size: source_size,
+                        }),
+                    )
+                }
+                None => (
+                    render_device.create_bind_group(&BindGroupDescriptor {
+                        label: Some("instance culling bind group"),
+                        layout: &pipeline.bind_group_layout,
+                        entries: &[
+                            BindGroupEntry {
+                                binding: 0,
+                                resource: buffers.uniform.binding().unwrap(),
+                            },
+                            BindGroupEntry {
+                                binding: 1,
+                                resource: instance_binding,
+                            },
+                            BindGroupEntry {
+                                binding: 2,
+                                resource: buffers.instance_bounds.binding().unwrap(),
+                            },
+                        ],
+                    }),
+                    None,
+                ),
+            };
+
+            jobs.push(InstanceCullingJob {
+                bind_group,
+                instance_count: instance_count as u32,
+                compact_source,
+            });
+        }
+    }
+
+    commands.insert_resource(InstanceCullingQueue::<M> {
+        jobs,
+        _phantom: default(),
+    });
+}
+
+/// Index of the indirect draw covering each CPU-prepared instance,
+/// or `None` if the batch's draws don't cover them exactly
+fn instance_draws(
+    indirects: &[IndirectDraw],
+    first_instance: u64,
+    instance_count: u64,
+) -> Option<Vec<u32>> {
+    let mut instance_draws = vec![u32::MAX; instance_count as usize];
+
+    for (i, indirect) in indirects.iter().enumerate() {
+        // Instance slice draws precede the CPU-prepared instances
+        let base_instance = indirect.base_instance() as u64;
+        if base_instance < first_instance {
+            continue;
+        }
+
+        let start = (base_instance - first_instance) as usize;
+        let end = start + indirect.instance_count() as usize;
+        instance_draws.get_mut(start..end)?.fill(i as u32);
+    }
+
+    if instance_draws.contains(&u32::MAX) {
+        return None;
+    }
+
+    Some(instance_draws)
+}
+
+/// Size of each draw in an indirect buffer, in bytes
+fn indirect_stride(indirect_data: &GpuIndirectBufferData) -> u64 {
+    match indirect_data.indirects.first() {
+        Some(IndirectDraw::Indexed(_)) => std::mem::size_of::<DrawIndexedIndirect>() as u64,
+        Some(IndirectDraw::NonIndexed(_)) | None => std::mem::size_of::<DrawIndirect>() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        instancing::indirect::{DrawIndirect, IndirectDraw},
+        prelude::{GpuColorMeshInstance, GpuMeshInstance, GpuPbrMeshInstance},
+    };
+
+    use super::{instance_draws, CulledInstance, CULLED_TRANSFORM_OFFSET};
+
+    fn draw(base_instance: u32, instance_count: u32) -> IndirectDraw {
+        IndirectDraw::NonIndexed(DrawIndirect {
+            vertex_count: 3,
+            instance_count,
+            base_vertex: 0,
+            base_instance,
+        })
+    }
+
+    #[test]
+    fn culled_instances_match_the_shader_layout() {
+        assert_eq!(GpuMeshInstance::TRANSFORM_OFFSET, CULLED_TRANSFORM_OFFSET);
+        assert_eq!(
+            GpuColorMeshInstance::TRANSFORM_OFFSET,
+            CULLED_TRANSFORM_OFFSET
+        );
+        assert_eq!(
+            GpuPbrMeshInstance::TRANSFORM_OFFSET,
+            CULLED_TRANSFORM_OFFSET
+        );
+    }
+
+    #[test]
+    fn instance_draws_map_instances_to_draws() {
+        // A slice draw precedes the two meshes' CPU-prepared instances
+        let indirects = [draw(0, 4), draw(4, 2), draw(6, 3)];
+
+        assert_eq!(instance_draws(&indirects, 4, 5), Some(vec![1, 1, 2, 2, 2]));
+    }
+
+    #[test]
+    fn instance_draws_reject_gaps() {
+        // Instance 6 isn't covered by any draw
+        assert_eq!(instance_draws(&[draw(4, 2), draw(7, 2)], 4, 5), None);
+
+        // Nor is the last instance
+        assert_eq!(instance_draws(&[draw(4, 2), draw(6, 2)], 4, 5), None);
+
+        // Draws overrunning the instances don't cover them exactly either
+        assert_eq!(instance_draws(&[draw(4, 6)], 4, 5), None);
+    }
+}
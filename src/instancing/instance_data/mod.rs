@@ -0,0 +1,224 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{default, Changed, Color, Commands, Component, Entity, Query, Reflect},
+};
+
+use crate::prelude::{InstanceColor, InstanceFade, InstanceSeed};
+
+/// Consolidated optional per-instance data
+///
+/// Bundles the per-instance extras into a single component so they can be managed together.
+/// Fields left as `None` fall back to the defaults of the corresponding instance type.
+///
+/// `color`, `tag` and `fade` are mirrored into [`InstanceColor`], [`InstanceSeed`] and [`InstanceFade`] respectively.
+/// Clearing one removes the component it was mirrored into, while components inserted by hand are left alone.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct InstanceData {
+    /// Mirrored into [`InstanceColor`]
+    pub color: Option<Color>,
+    /// Not mirrored yet, since no instance type carries an emissive color.
+    /// Deferred until one does, i.e. a PBR instance with per-instance emissive
+    pub emissive: Option<Color>,
+    /// Mirrored into [`InstanceSeed`], i.e. to select a [`ScreenMaterial`](crate::prelude::ScreenMaterial) feed
+    pub tag: Option<u32>,
+    /// Mirrored into [`InstanceFade`], scaling the instance's opacity
+    pub fade: Option<f32>,
+}
+
+impl InstanceData {
+    pub fn new() -> Self {
+        default()
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn with_emissive(mut self, emissive: Color) -> Self {
+        self.emissive = Some(emissive);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: u32) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+
+    pub fn with_fade(mut self, fade: f32) -> Self {
+        self.fade = Some(fade);
+        self
+    }
+}
+
+/// Marks a component of type `T` as mirrored from [`InstanceData`],
+/// so that clearing its field only removes it when it wasn't inserted by hand
+#[derive(Debug, Component)]
+pub struct MirroredInstanceData<T>(PhantomData<T>);
+
+impl<T> Default for MirroredInstanceData<T> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+/// Inserts `value` on `entity` when set, or removes a previously mirrored one when cleared
+fn mirror_instance_data<T: Component>(
+    commands: &mut Commands,
+    entity: Entity,
+    value: Option<T>,
+    mirrored: bool,
+) {
+    match value {
+        Some(value) => {
+            commands
+                .entity(entity)
+                .insert((value, MirroredInstanceData::<T>::default()));
+        }
+        None if mirrored => {
+            commands
+                .entity(entity)
+                .remove::<(T, MirroredInstanceData<T>)>();
+        }
+        None => (),
+    }
+}
+
+/// Mirrors [`InstanceData::color`] into the [`InstanceColor`] component read by color instances
+///
+/// Color instances need one to be drawn, which
+/// [`insert_missing_instance_color`](crate::prelude::insert_missing_instance_color) restores as white
+/// once a mirrored color is cleared.
+#[allow(clippy::type_complexity)]
+pub fn sync_instance_color(
+    query_instance_data: Query<
+        (
+            Entity,
+            &InstanceData,
+            Option<&MirroredInstanceData<InstanceColor>>,
+        ),
+        Changed<InstanceData>,
+    >,
+    mut commands: Commands,
+) {
+    for (entity, instance_data, mirrored) in query_instance_data.iter() {
+        mirror_instance_data(
+            &mut commands,
+            entity,
+            instance_data.color.map(InstanceColor),
+            mirrored.is_some(),
+        );
+    }
+}
+
+/// Mirrors [`InstanceData::tag`] into the [`InstanceSeed`] exposed to instanced shaders
+#[allow(clippy::type_complexity)]
+pub fn sync_instance_tag(
+    query_instance_data: Query<
+        (
+            Entity,
+            &InstanceData,
+            Option<&MirroredInstanceData<InstanceSeed>>,
+        ),
+        Changed<InstanceData>,
+    >,
+    mut commands: Commands,
+) {
+    for (entity, instance_data, mirrored) in query_instance_data.iter() {
+        mirror_instance_data(
+            &mut commands,
+            entity,
+            instance_data.tag.map(InstanceSeed),
+            mirrored.is_some(),
+        );
+    }
+}
+
+/// Mirrors [`InstanceData::fade`] into the [`InstanceFade`] applied when instances are prepared
+#[allow(clippy::type_complexity)]
+pub fn sync_instance_fade(
+    query_instance_data: Query<
+        (
+            Entity,
+            &InstanceData,
+            Option<&MirroredInstanceData<InstanceFade>>,
+        ),
+        Changed<InstanceData>,
+    >,
+    mut commands: Commands,
+) {
+    for (entity, instance_data, mirrored) in query_instance_data.iter() {
+        mirror_instance_data(
+            &mut commands,
+            entity,
+            instance_data.fade.map(InstanceFade),
+            mirrored.is_some(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Color, Stage, SystemStage, World};
+
+    use crate::prelude::{InstanceColor, InstanceFade, InstanceSeed};
+
+    use super::{sync_instance_color, sync_instance_fade, sync_instance_tag, InstanceData};
+
+    #[test]
+    fn cleared_color_removes_instance_color() {
+        let mut world = World::new();
+        let mut stage = SystemStage::single_threaded().with_system(sync_instance_color);
+
+        let entity = world.spawn(InstanceData::new().with_color(Color::RED)).id();
+        stage.run(&mut world);
+        assert_eq!(world.get::<InstanceColor>(entity).unwrap().0, Color::RED);
+
+        world.get_mut::<InstanceData>(entity).unwrap().color = None;
+        stage.run(&mut world);
+        assert!(world.get::<InstanceColor>(entity).is_none());
+    }
+
+    #[test]
+    fn cleared_fields_keep_components_inserted_by_hand() {
+        let mut world = World::new();
+        let mut stage = SystemStage::parallel()
+            .with_system(sync_instance_color)
+            .with_system(sync_instance_tag)
+            .with_system(sync_instance_fade);
+
+        let entity = world
+            .spawn((
+                InstanceData::new(),
+                InstanceColor(Color::BLUE),
+                InstanceSeed(3),
+                InstanceFade(0.5),
+            ))
+            .id();
+        stage.run(&mut world);
+
+        world.get_mut::<InstanceData>(entity).unwrap().emissive = Some(Color::WHITE);
+        stage.run(&mut world);
+
+        assert_eq!(world.get::<InstanceColor>(entity).unwrap().0, Color::BLUE);
+        assert_eq!(world.get::<InstanceSeed>(entity).unwrap().0, 3);
+        assert_eq!(world.get::<InstanceFade>(entity).unwrap().0, 0.5);
+    }
+
+    #[test]
+    fn fade_is_mirrored_into_instance_fade() {
+        let mut world = World::new();
+        let mut stage = SystemStage::single_threaded().with_system(sync_instance_fade);
+
+        let entity = world.spawn(InstanceData::new().with_fade(0.25)).id();
+        stage.run(&mut world);
+        assert_eq!(world.get::<InstanceFade>(entity).unwrap().0, 0.25);
+
+        world.get_mut::<InstanceData>(entity).unwrap().fade = None;
+        stage.run(&mut world);
+        assert!(world.get::<InstanceFade>(entity).is_none());
+    }
+}
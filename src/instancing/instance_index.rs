@@ -0,0 +1,69 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    prelude::{Entity, Res, ResMut, Resource},
+    render::extract_resource::ExtractResource,
+    utils::HashMap,
+};
+
+/// Controls publishing of each instance's shader `instance_index` to [`InstanceIndices`]
+///
+/// Disabled by default, since publishing walks every batch of every view each frame.
+#[derive(Debug, Default, Copy, Clone, Resource, ExtractResource)]
+pub struct InstanceIndexSettings {
+    pub enabled: bool,
+}
+
+/// The `instance_index` each instance was drawn with by each view, one frame behind the render world
+///
+/// Within an instance batch, `instance_index` is the instance's position in the batch's instance buffer,
+/// which holds the batch's instance slices first, followed by its CPU-prepared instances
+/// in draw order, i.e. grouped by mesh and sorted by depth.
+/// Sorting a view's entities by their index therefore recovers the order they are drawn in.
+/// Instances expanded into several prepared instances are indexed by the first of them.
+///
+/// Under the uniform buffer fallback, each chunk of a batch is bound separately,
+/// so indices restart at zero every [`UNIFORM_BUFFER_LENGTH`](crate::prelude::InstanceUniformLength) instances.
+///
+/// Only CPU-prepared instances are published; the instances of an
+/// [`InstanceSlice`](crate::prelude::InstanceSlice) are indexed from its
+/// [`InstanceSliceRange`](crate::prelude::InstanceSliceRange) offset.
+/// Indices are only stable for as long as the batch's instances and their depth order are,
+/// so shaders needing a persistent identity should use [`InstanceSeed`](crate::prelude::InstanceSeed).
+///
+/// Requires [`InstanceIndexSettings::enabled`].
+#[derive(Debug, Default, Clone, Resource)]
+pub struct InstanceIndices(pub HashMap<Entity, HashMap<Entity, u32>>);
+
+impl InstanceIndices {
+    /// The `instance_index` of `entity` as drawn by `view`
+    pub fn get(&self, view: Entity, entity: Entity) -> Option<u32> {
+        self.0
+            .get(&view)
+            .and_then(|indices| indices.get(&entity))
+            .copied()
+    }
+
+    /// The `instance_index` of each instance drawn by `view`
+    pub fn view(&self, view: Entity) -> Option<&HashMap<Entity, u32>> {
+        self.0.get(&view)
+    }
+}
+
+/// Instance indices published by the render world, awaiting mirroring to the main world
+#[derive(Debug, Default, Clone, Resource)]
+pub struct PublishedInstanceIndices(pub Arc<Mutex<HashMap<Entity, HashMap<Entity, u32>>>>);
+
+/// Mirrors the previous frame's published instance indices into [`InstanceIndices`]
+pub fn sync_instance_indices(
+    published: Res<PublishedInstanceIndices>,
+    mut instance_indices: ResMut<InstanceIndices>,
+) {
+    let published = std::mem::take(&mut *published.0.lock().unwrap());
+
+    if published.is_empty() && instance_indices.0.is_empty() {
+        return;
+    }
+
+    instance_indices.0 = published;
+}
@@ -0,0 +1,202 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    prelude::{
+        debug, default, App, ChangeTrackers, Commands, Component, Entity, Plugin, Query, Res,
+        ResMut, Resource,
+    },
+    render::{
+        render_resource::{
+            encase, Buffer, BufferInitDescriptor, BufferUsages, CommandEncoderDescriptor,
+            ShaderSize,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        Extract, RenderApp, RenderStage,
+    },
+    utils::HashMap,
+};
+
+use crate::prelude::{Instance, InstanceSliceRange, InstanceSliceTarget, MaterialInstanced};
+
+type PreparedInstance<M> = <<M as MaterialInstanced>::Instance as Instance>::PreparedInstance;
+
+/// Seeds the instance slices of material `M` with [`InstanceSliceSeed<M>`] data
+pub struct InstanceSliceSeedPlugin<M: MaterialInstanced>(PhantomData<M>);
+
+impl<M: MaterialInstanced> Default for InstanceSliceSeedPlugin<M> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<M: MaterialInstanced> Plugin for InstanceSliceSeedPlugin<M> {
+    fn build(&self, app: &mut App) {
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<InstanceSliceSeedBuffers<M>>()
+                .add_system_to_stage(RenderStage::Extract, extract_instance_slice_seeds::<M>)
+                .add_system_to_stage(RenderStage::Queue, queue_instance_slice_seeds::<M>);
+        }
+    }
+}
+
+/// Initial instance data for the [`InstanceSlice`](crate::prelude::InstanceSlice) on the same entity
+///
+/// Slice contents are rebuilt each frame, so the seed is copied into the slice's range
+/// of the instance buffer before every [`InstanceCompute`](crate::prelude::InstanceCompute) dispatch,
+/// letting compute shaders read it as their input layout.
+/// Instances beyond the slice's `instance_count` are ignored, and any remainder stays zeroed.
+///
+/// The seed is only uploaded to the GPU when changed.
+/// Requires [`InstanceSliceSeedPlugin<M>`].
+#[derive(Component)]
+pub struct InstanceSliceSeed<M: MaterialInstanced> {
+    pub instances: Vec<PreparedInstance<M>>,
+}
+
+impl<M: MaterialInstanced> Default for InstanceSliceSeed<M> {
+    fn default() -> Self {
+        Self {
+            instances: default(),
+        }
+    }
+}
+
+impl<M: MaterialInstanced> InstanceSliceSeed<M> {
+    pub fn new(instances: impl IntoIterator<Item = PreparedInstance<M>>) -> Self {
+        Self {
+            instances: instances.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ExtractedInstanceSliceSeed<M: MaterialInstanced> {
+    /// Seed instances, if changed since the last extraction
+    pub changed: Option<Vec<PreparedInstance<M>>>,
+}
+
+#[allow(clippy::type_complexity)]
+pub fn extract_instance_slice_seeds<M: MaterialInstanced>(
+    query_instance_seed: Extract<
+        Query<(
+            Entity,
+            &InstanceSliceSeed<M>,
+            ChangeTrackers<InstanceSliceSeed<M>>,
+        )>,
+    >,
+    mut commands: Commands,
+) {
+    let extracted = query_instance_seed
+        .iter()
+        .map(|(entity, seed, seed_tracker)| {
+            (
+                entity,
+                ExtractedInstanceSliceSeed::<M> {
+                    changed: seed_tracker.is_changed().then(|| seed.instances.clone()),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    commands.insert_or_spawn_batch(extracted);
+}
+
+/// Persistent GPU copy of a seed's instances
+pub struct InstanceSliceSeedBuffer {
+    pub buffer: Buffer,
+    pub len: usize,
+}
+
+#[derive(Resource)]
+pub struct InstanceSliceSeedBuffers<M: MaterialInstanced> {
+    pub buffers: HashMap<Entity, InstanceSliceSeedBuffer>,
+    _phantom: PhantomData<M>,
+}
+
+impl<M: MaterialInstanced> Default for InstanceSliceSeedBuffers<M> {
+    fn default() -> Self {
+        Self {
+            buffers: default(),
+            _phantom: default(),
+        }
+    }
+}
+
+/// Uploads changed seeds, then copies each seed into its slice of the instance buffer
+#[allow(clippy::type_complexity)]
+pub fn queue_instance_slice_seeds<M: MaterialInstanced>(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut seed_buffers: ResMut<InstanceSliceSeedBuffers<M>>,
+    query_instance_seed: Query<(
+        Entity,
+        &ExtractedInstanceSliceSeed<M>,
+        Option<&InstanceSliceRange>,
+        Option<&InstanceSliceTarget>,
+    )>,
+) {
+    let stride = PreparedInstance::<M>::SHADER_SIZE.get();
+
+    seed_buffers
+        .buffers
+        .retain(|entity, _| query_instance_seed.contains(*entity));
+
+    let mut command_encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("instance slice seed encoder"),
+    });
+
+    for (entity, seed, slice_range, slice_target) in query_instance_seed.iter() {
+        if let Some(instances) = &seed.changed {
+            if instances.is_empty() {
+                seed_buffers.buffers.remove(&entity);
+            } else {
+                debug!(
+                    "Uploading {} seed instances for {entity:?}",
+                    instances.len()
+                );
+
+                let mut bytes = encase::StorageBuffer::new(Vec::<u8>::new());
+                bytes.write(instances).unwrap();
+
+                let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+                    label: Some("instance slice seed buffer"),
+                    contents: &bytes.into_inner(),
+                    usage: BufferUsages::COPY_SRC,
+                });
+
+                seed_buffers.buffers.insert(
+                    entity,
+                    InstanceSliceSeedBuffer {
+                        buffer,
+                        len: instances.len(),
+                    },
+                );
+            }
+        }
+
+        let seed_buffer = if let Some(seed_buffer) = seed_buffers.buffers.get(&entity) {
+            seed_buffer
+        } else {
+            continue;
+        };
+
+        let (slice_range, slice_target) = match (slice_range, slice_target) {
+            (Some(slice_range), Some(slice_target)) => (slice_range, slice_target),
+            _ => continue,
+        };
+
+        let instance_count = slice_range.instance_count.min(seed_buffer.len as u64);
+        if instance_count > 0 {
+            command_encoder.copy_buffer_to_buffer(
+                &seed_buffer.buffer,
+                0,
+                &slice_target.buffer,
+                slice_range.offset * stride,
+                instance_count * stride,
+            );
+        }
+    }
+
+    render_queue.submit([command_encoder.finish()]);
+}
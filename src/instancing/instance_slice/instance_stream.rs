@@ -0,0 +1,342 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    prelude::{
+        debug, default, App, Changed, Commands, Component, CoreStage, Entity, Plugin, Query, Res,
+        ResMut, Resource,
+    },
+    render::{
+        render_resource::{
+            encase, Buffer, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, ShaderSize,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        Extract, RenderApp, RenderStage,
+    },
+    utils::HashMap,
+};
+
+use crate::prelude::{
+    Instance, InstanceSlice, InstanceSliceRange, InstanceSliceTarget, MaterialInstanced,
+};
+
+type PreparedInstance<M> = <<M as MaterialInstanced>::Instance as Instance>::PreparedInstance;
+
+/// Streams append-only instances into an [`InstanceSliceBundle`](crate::prelude::InstanceSliceBundle) using material `M`
+pub struct InstanceStreamPlugin<M: MaterialInstanced>(PhantomData<M>);
+
+impl<M: MaterialInstanced> Default for InstanceStreamPlugin<M> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<M: MaterialInstanced> Plugin for InstanceStreamPlugin<M> {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::First, clear_pending_instance_streams::<M>)
+            .add_system_to_stage(CoreStage::PostUpdate, sync_instance_stream_slices::<M>);
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<InstanceStreamBuffers<M>>()
+                .add_system_to_stage(RenderStage::Extract, extract_instance_streams::<M>)
+                .add_system_to_stage(RenderStage::Queue, queue_instance_streams::<M>);
+        }
+    }
+}
+
+/// Append-only instance data for the [`InstanceSlice`] on the same entity
+///
+/// Appended instances are uploaded once into a persistent GPU buffer,
+/// which is copied into the slice's range of the instance buffer each frame
+/// instead of being rebuilt on the CPU. The slice's `instance_count` is kept in sync.
+///
+/// Requires [`InstanceStreamPlugin<M>`] and storage buffer support.
+#[derive(Component)]
+pub struct InstanceStream<M: MaterialInstanced> {
+    pending: Vec<PreparedInstance<M>>,
+    len: usize,
+}
+
+impl<M: MaterialInstanced> Default for InstanceStream<M> {
+    fn default() -> Self {
+        Self {
+            pending: default(),
+            len: default(),
+        }
+    }
+}
+
+impl<M: MaterialInstanced> InstanceStream<M> {
+    /// Appends instances after those already streamed
+    pub fn append(&mut self, instances: impl IntoIterator<Item = PreparedInstance<M>>) {
+        let pending_len = self.pending.len();
+        self.pending.extend(instances);
+        self.len += self.pending.len() - pending_len;
+    }
+
+    /// Instances appended since the stream was last extracted
+    pub fn pending(&self) -> &[PreparedInstance<M>] {
+        &self.pending
+    }
+
+    /// Total number of instances streamed
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Clears instances uploaded during the previous frame
+pub fn clear_pending_instance_streams<M: MaterialInstanced>(
+    mut query: Query<&mut InstanceStream<M>>,
+) {
+    for mut stream in query.iter_mut() {
+        if !stream.pending.is_empty() {
+            stream.pending.clear();
+        }
+    }
+}
+
+/// Grows each stream's [`InstanceSlice`] to fit its instances
+pub fn sync_instance_stream_slices<M: MaterialInstanced>(
+    mut query: Query<(&InstanceStream<M>, &mut InstanceSlice), Changed<InstanceStream<M>>>,
+) {
+    for (stream, mut instance_slice) in query.iter_mut() {
+        if instance_slice.instance_count != stream.len {
+            instance_slice.instance_count = stream.len;
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ExtractedInstanceStream<M: MaterialInstanced> {
+    pub appended: Vec<PreparedInstance<M>>,
+    pub len: usize,
+}
+
+pub fn extract_instance_streams<M: MaterialInstanced>(
+    query_instance_stream: Extract<Query<(Entity, &InstanceStream<M>)>>,
+    mut commands: Commands,
+) {
+    let extracted = query_instance_stream
+        .iter()
+        .map(|(entity, stream)| {
+            (
+                entity,
+                ExtractedInstanceStream::<M> {
+                    appended: stream.pending.clone(),
+                    len: stream.len,
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    commands.insert_or_spawn_batch(extracted);
+}
+
+/// Persistent GPU copy of a stream's instances
+#[derive(Default)]
+pub struct InstanceStreamBuffer {
+    pub buffer: Option<Buffer>,
+    pub capacity: usize,
+    pub len: usize,
+}
+
+#[derive(Resource)]
+pub struct InstanceStreamBuffers<M: MaterialInstanced> {
+    pub buffers: HashMap<Entity, InstanceStreamBuffer>,
+    _phantom: PhantomData<M>,
+}
+
+impl<M: MaterialInstanced> Default for InstanceStreamBuffers<M> {
+    fn default() -> Self {
+        Self {
+            buffers: default(),
+            _phantom: default(),
+        }
+    }
+}
+
+/// Uploads newly appended instances, then copies each stream into its slice of the instance buffer
+#[allow(clippy::type_complexity)]
+pub fn queue_instance_streams<M: MaterialInstanced>(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut stream_buffers: ResMut<InstanceStreamBuffers<M>>,
+    query_instance_stream: Query<(
+        Entity,
+        &ExtractedInstanceStream<M>,
+        Option<&InstanceSliceRange>,
+        Option<&InstanceSliceTarget>,
+    )>,
+) {
+    let stride = PreparedInstance::<M>::SHADER_SIZE.get();
+
+    stream_buffers
+        .buffers
+        .retain(|entity, _| query_instance_stream.contains(*entity));
+
+    let mut command_encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("instance stream encoder"),
+    });
+
+    for (entity, stream, slice_range, slice_target) in query_instance_stream.iter() {
+        let stream_buffer = stream_buffers.buffers.entry(entity).or_default();
+
+        let base = stream.len - stream.appended.len();
+        if base != stream_buffer.len {
+            // The stream was replaced, so instances before this frame's can't be recovered
+            debug!("Instance stream {entity:?} out of sync, resetting");
+            *stream_buffer = default();
+        }
+
+        if stream.len > stream_buffer.capacity {
+            let capacity = stream.len.next_power_of_two();
+            debug!("Growing instance stream {entity:?} to {capacity}");
+
+            let buffer = render_device.create_buffer(&BufferDescriptor {
+                label: Some("instance stream buffer"),
+                size: capacity as u64 * stride,
+                usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            if let Some(old_buffer) = &stream_buffer.buffer {
+                command_encoder.copy_buffer_to_buffer(
+                    old_buffer,
+                    0,
+                    &buffer,
+                    0,
+                    stream_buffer.len as u64 * stride,
+                );
+            }
+
+            stream_buffer.buffer = Some(buffer);
+            stream_buffer.capacity = capacity;
+        }
+
+        let buffer = if let Some(buffer) = &stream_buffer.buffer {
+            buffer
+        } else {
+            continue;
+        };
+
+        if !stream.appended.is_empty() {
+            let mut bytes = encase::StorageBuffer::new(Vec::<u8>::new());
+            bytes.write(&stream.appended).unwrap();
+            render_queue.write_buffer(buffer, base as u64 * stride, &bytes.into_inner());
+        }
+
+        stream_buffer.len = stream.len;
+
+        let (slice_range, slice_target) = match (slice_range, slice_target) {
+            (Some(slice_range), Some(slice_target)) => (slice_range, slice_target),
+            _ => continue,
+        };
+
+        let instance_count = slice_range.instance_count.min(stream.len as u64);
+        if instance_count > 0 {
+            command_encoder.copy_buffer_to_buffer(
+                buffer,
+                0,
+                &slice_target.buffer,
+                slice_range.offset * stride,
+                instance_count * stride,
+            );
+        }
+    }
+
+    render_queue.submit([command_encoder.finish()]);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        prelude::{default, Stage, SystemStage, World},
+        render::MainWorld,
+    };
+
+    use crate::prelude::{BasicMaterial, InstanceSlice, MeshInstance};
+
+    use super::{
+        clear_pending_instance_streams, extract_instance_streams, sync_instance_stream_slices,
+        ExtractedInstanceStream, InstanceStream,
+    };
+
+    #[test]
+    fn appended_instances_follow_the_stream_across_frames() {
+        let mut world = World::new();
+        let mut render_world = World::new();
+
+        let mut first = SystemStage::single_threaded()
+            .with_system(clear_pending_instance_streams::<BasicMaterial>);
+        let mut post_update = SystemStage::single_threaded()
+            .with_system(sync_instance_stream_slices::<BasicMaterial>);
+        let mut extract =
+            SystemStage::single_threaded().with_system(extract_instance_streams::<BasicMaterial>);
+
+        let entity = world
+            .spawn((
+                InstanceStream::<BasicMaterial>::default(),
+                InstanceSlice { instance_count: 0 },
+            ))
+            .id();
+
+        let mut streamed = 0;
+        for appended in [40, 0, 25, 35] {
+            first.run(&mut world);
+
+            world
+                .get_mut::<InstanceStream<BasicMaterial>>(entity)
+                .unwrap()
+                .append((streamed..streamed + appended).map(|i| {
+                    MeshInstance {
+                        seed: i,
+                        ..default()
+                    }
+                    .to_gpu(0)
+                }));
+            streamed += appended;
+
+            post_update.run(&mut world);
+
+            let stream = world.get::<InstanceStream<BasicMaterial>>(entity).unwrap();
+            assert_eq!(stream.len(), streamed as usize);
+            assert_eq!(stream.pending().len(), appended as usize);
+            assert_eq!(
+                world.get::<InstanceSlice>(entity).unwrap().instance_count,
+                streamed as usize
+            );
+
+            let mut main_world = MainWorld::default();
+            std::mem::swap(&mut *main_world, &mut world);
+            render_world.insert_resource(main_world);
+            extract.run(&mut render_world);
+            let mut main_world = render_world.remove_resource::<MainWorld>().unwrap();
+            std::mem::swap(&mut *main_world, &mut world);
+
+            let extracted = render_world
+                .get::<ExtractedInstanceStream<BasicMaterial>>(entity)
+                .unwrap();
+            assert_eq!(extracted.len, streamed as usize);
+            assert_eq!(
+                extracted
+                    .appended
+                    .iter()
+                    .map(|instance| instance.seed)
+                    .collect::<Vec<_>>(),
+                (streamed - appended..streamed).collect::<Vec<_>>()
+            );
+        }
+
+        assert_eq!(streamed, 100);
+
+        first.run(&mut world);
+        let stream = world.get::<InstanceStream<BasicMaterial>>(entity).unwrap();
+        assert_eq!(stream.len(), 100);
+        assert!(stream.pending().is_empty());
+    }
+}
@@ -1,14 +1,31 @@
+pub mod instance_seed;
 pub mod instance_slice_bundle;
+pub mod instance_stream;
+pub mod shared_instance_slice;
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
 
 use bevy::{
     ecs::{reflect::ReflectComponent, system::lifetimeless::Read},
-    prelude::Component,
+    prelude::{debug, Commands, Component, Entity, Query, Res, Resource, With},
     reflect::Reflect,
-    render::{extract_component::ExtractComponent, render_resource::Buffer},
+    render::{
+        extract_component::ExtractComponent,
+        render_resource::{Buffer, BufferBindingType},
+        renderer::RenderDevice,
+    },
+    utils::HashMap,
 };
 
 /// Allocates a contiguous slice of the instance buffer corresponding to a given mesh and material
 /// Used to reserve space for compute-driven instances
+///
+/// `instance_count` can be changed at runtime without respawning the slice.
+/// Slices keep their offset across frames unless they grow past an allocated range,
+/// see [`InstanceSliceAllocator`].
 #[derive(Debug, Default, Copy, Clone, Component, Reflect)]
 #[reflect(Component)]
 pub struct InstanceSlice {
@@ -25,13 +42,322 @@ impl ExtractComponent for InstanceSlice {
     }
 }
 
-#[derive(Debug, Copy, Clone, Component)]
+/// Region of the instance buffer allocated to an [`InstanceSlice`], in instances
+///
+/// Inserted on render world slice entities during prepare, and mirrored back onto
+/// main world slice entities one frame later, i.e. for readback or debugging.
+/// Removed from main world entities whose slice wasn't allocated during the previous frame.
+/// When multiple views draw a slice, the range reflects the last view to be prepared.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
 pub struct InstanceSliceRange {
     pub offset: u64,
     pub instance_count: u64,
 }
 
+/// Slice ranges allocated by the render world, awaiting mirroring to the main world
+#[derive(Debug, Default, Clone, Resource)]
+pub struct AllocatedInstanceSliceRanges(pub Arc<Mutex<HashMap<Entity, InstanceSliceRange>>>);
+
+/// Mirrors the previous frame's [`InstanceSliceRange`]s onto main world slice entities
+pub fn sync_instance_slice_ranges(
+    allocated_ranges: Res<AllocatedInstanceSliceRanges>,
+    mut query_instance_slice: Query<(Entity, Option<&mut InstanceSliceRange>), With<InstanceSlice>>,
+    mut commands: Commands,
+) {
+    let mut allocated_ranges = std::mem::take(&mut *allocated_ranges.0.lock().unwrap());
+
+    for (entity, slice_range) in query_instance_slice.iter_mut() {
+        match (allocated_ranges.remove(&entity), slice_range) {
+            (Some(allocated), Some(mut slice_range)) => {
+                if *slice_range != allocated {
+                    *slice_range = allocated;
+                }
+            }
+            (Some(allocated), None) => {
+                commands.entity(entity).insert(allocated);
+            }
+            (None, Some(_)) => {
+                commands.entity(entity).remove::<InstanceSliceRange>();
+            }
+            (None, None) => (),
+        }
+    }
+}
+
+/// Whether `render_device` supports the storage buffers [`InstanceSlice`]s are written through
+///
+/// On devices limited to uniform buffers, i.e. WebGL2, slices are allocated
+/// but never receive an [`InstanceSliceTarget`], so nothing computes, seeds or streams into them.
+/// [`RenderDevice`] is available in the main world too, so setup can branch on this.
+pub fn storage_buffers_available(render_device: &RenderDevice) -> bool {
+    matches!(
+        render_device.get_supported_read_only_binding_type(1),
+        BufferBindingType::Storage { .. }
+    )
+}
+
 #[derive(Debug, Clone, Component)]
 pub struct InstanceSliceTarget {
     pub buffer: Buffer,
+    /// Size of a single prepared instance within `buffer`, in bytes
+    pub instance_size: u64,
+}
+
+/// Free-list allocator assigning [`InstanceSliceRange`]s within the slice region of an instance batch
+///
+/// Slices keep their range across frames for as long as they exist and aren't resized,
+/// so compute shaders writing to fixed offsets aren't disturbed by other slices being added or removed.
+/// Shrinking a slice keeps its offset, as does growing it into unused instances that follow it;
+/// otherwise growing moves the slice to a new range. Instances gained by growing start out zeroed,
+/// and those lost by shrinking stop being drawn.
+#[derive(Debug, Default, Clone)]
+pub struct InstanceSliceAllocator {
+    ranges: BTreeMap<Entity, InstanceSliceRange>,
+    /// Unused ranges within the region as (offset, instance count), sorted by offset
+    free: Vec<(u64, u64)>,
+    len: u64,
+}
+
+impl InstanceSliceAllocator {
+    /// Length of the slice region in instances, including any unused ranges
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Updates allocations to match `slices`, returning the range of each
+    ///
+    /// Ranges of slices missing from `slices` are released for reuse.
+    pub fn allocate(
+        &mut self,
+        slices: impl IntoIterator<Item = (Entity, u64)>,
+    ) -> BTreeMap<Entity, InstanceSliceRange> {
+        let slices = slices.into_iter().collect::<BTreeMap<_, _>>();
+
+        // Release removed slices and shrink or evict resized ones
+        let mut grown = vec![];
+        let ranges = std::mem::take(&mut self.ranges);
+        for (entity, mut range) in ranges {
+            match slices.get(&entity) {
+                Some(instance_count) if *instance_count <= range.instance_count => {
+                    self.release(
+                        range.offset + instance_count,
+                        range.instance_count - instance_count,
+                    );
+                    range.instance_count = *instance_count;
+                    self.ranges.insert(entity, range);
+                }
+                Some(instance_count) => {
+                    self.release(range.offset, range.instance_count);
+                    grown.push((entity, range.offset, *instance_count));
+                }
+                None => self.release(range.offset, range.instance_count),
+            }
+        }
+
+        // Grow slices in place where the instances following them are unused,
+        // before new slices can claim them
+        for (entity, offset, instance_count) in grown {
+            if self.claim(offset, instance_count) {
+                debug!("Grew {entity:?} in place to {instance_count} slice instances at {offset}");
+
+                self.ranges.insert(
+                    entity,
+                    InstanceSliceRange {
+                        offset,
+                        instance_count,
+                    },
+                );
+            }
+        }
+
+        // Allocate new and moved slices, preferring the first unused range that fits
+        for (entity, instance_count) in slices {
+            if self.ranges.contains_key(&entity) {
+                continue;
+            }
+
+            let offset = if let Some(i) = self
+                .free
+                .iter()
+                .position(|(_, free_count)| *free_count >= instance_count)
+            {
+                let (offset, free_count) = self.free[i];
+                if free_count == instance_count {
+                    self.free.remove(i);
+                } else {
+                    self.free[i] = (offset + instance_count, free_count - instance_count);
+                }
+                offset
+            } else {
+                let offset = self.len;
+                self.len += instance_count;
+                offset
+            };
+
+            debug!("Allocated {instance_count} slice instances at {offset} for {entity:?}");
+
+            self.ranges.insert(
+                entity,
+                InstanceSliceRange {
+                    offset,
+                    instance_count,
+                },
+            );
+        }
+
+        self.ranges.clone()
+    }
+
+    /// Marks `instance_count` instances at `offset` as used, if they're unused or past the region's end
+    fn claim(&mut self, offset: u64, instance_count: u64) -> bool {
+        if offset >= self.len {
+            self.release(self.len, offset - self.len);
+            self.len = self.len.max(offset + instance_count);
+            return true;
+        }
+
+        let i = match self.free.iter().position(|(free_offset, free_count)| {
+            *free_offset <= offset && offset + instance_count <= free_offset + free_count
+        }) {
+            Some(i) => i,
+            None => return false,
+        };
+
+        let (free_offset, free_count) = self.free.remove(i);
+        let end = offset + instance_count;
+        let free_end = free_offset + free_count;
+
+        if end < free_end {
+            self.free.insert(i, (end, free_end - end));
+        }
+
+        if free_offset < offset {
+            self.free.insert(i, (free_offset, offset - free_offset));
+        }
+
+        true
+    }
+
+    fn release(&mut self, offset: u64, instance_count: u64) {
+        if instance_count == 0 {
+            return;
+        }
+
+        let i = self
+            .free
+            .partition_point(|(free_offset, _)| *free_offset < offset);
+        self.free.insert(i, (offset, instance_count));
+
+        // Coalesce with the following range, then the preceding one
+        if i + 1 < self.free.len() && self.free[i].0 + self.free[i].1 == self.free[i + 1].0 {
+            self.free[i].1 += self.free.remove(i + 1).1;
+        }
+
+        if i > 0 && self.free[i - 1].0 + self.free[i - 1].1 == self.free[i].0 {
+            self.free[i - 1].1 += self.free.remove(i).1;
+        }
+
+        // Trim the region when its tail is unused
+        if let Some((free_offset, free_count)) = self.free.last().copied() {
+            if free_offset + free_count == self.len {
+                self.free.pop();
+                self.len = free_offset;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Entity;
+
+    use super::{InstanceSliceAllocator, InstanceSliceRange};
+
+    fn range(offset: u64, instance_count: u64) -> InstanceSliceRange {
+        InstanceSliceRange {
+            offset,
+            instance_count,
+        }
+    }
+
+    #[test]
+    fn tail_slice_grows_in_place() {
+        let a = Entity::from_raw(0);
+        let mut allocator = InstanceSliceAllocator::default();
+
+        for _ in 0..2 {
+            assert_eq!(allocator.allocate([(a, 100)])[&a], range(0, 100));
+            assert_eq!(allocator.len(), 100);
+
+            assert_eq!(allocator.allocate([(a, 300)])[&a], range(0, 300));
+            assert_eq!(allocator.len(), 300);
+        }
+
+        assert!(allocator.free.is_empty());
+    }
+
+    #[test]
+    fn blocked_slice_moves_to_grow() {
+        let a = Entity::from_raw(0);
+        let b = Entity::from_raw(1);
+        let mut allocator = InstanceSliceAllocator::default();
+
+        let ranges = allocator.allocate([(a, 100), (b, 100)]);
+        assert_eq!(ranges[&a], range(0, 100));
+        assert_eq!(ranges[&b], range(100, 100));
+
+        let ranges = allocator.allocate([(a, 300), (b, 100)]);
+        assert_eq!(ranges[&a], range(200, 300));
+        assert_eq!(ranges[&b], range(100, 100));
+        assert_eq!(allocator.free, [(0, 100)]);
+        assert_eq!(allocator.len(), 500);
+    }
+
+    #[test]
+    fn shrunk_slice_keeps_offset() {
+        let a = Entity::from_raw(0);
+        let b = Entity::from_raw(1);
+        let mut allocator = InstanceSliceAllocator::default();
+
+        allocator.allocate([(a, 300), (b, 100)]);
+
+        let ranges = allocator.allocate([(a, 100), (b, 100)]);
+        assert_eq!(ranges[&a], range(0, 100));
+        assert_eq!(ranges[&b], range(300, 100));
+        assert_eq!(allocator.free, [(100, 200)]);
+
+        // Growing back into the released instances keeps the offset too
+        let ranges = allocator.allocate([(a, 300), (b, 100)]);
+        assert_eq!(ranges[&a], range(0, 300));
+        assert!(allocator.free.is_empty());
+    }
+
+    #[test]
+    fn released_ranges_coalesce() {
+        let [a, b, c, d] = [0, 1, 2, 3].map(Entity::from_raw);
+        let mut allocator = InstanceSliceAllocator::default();
+
+        allocator.allocate([(a, 100), (b, 100), (c, 100), (d, 100)]);
+
+        allocator.allocate([(b, 100), (d, 100)]);
+        assert_eq!(allocator.free, [(0, 100), (200, 100)]);
+
+        allocator.allocate([(d, 100)]);
+        assert_eq!(allocator.free, [(0, 300)]);
+
+        let e = Entity::from_raw(4);
+        let ranges = allocator.allocate([(d, 100), (e, 300)]);
+        assert_eq!(ranges[&e], range(0, 300));
+        assert!(allocator.free.is_empty());
+
+        // Releasing the tail trims the region
+        allocator.allocate([(e, 300)]);
+        assert_eq!(allocator.len(), 300);
+        assert!(allocator.free.is_empty());
+    }
 }
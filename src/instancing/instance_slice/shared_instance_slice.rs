@@ -0,0 +1,179 @@
+use bevy::{
+    ecs::system::lifetimeless::Read,
+    prelude::{
+        debug, warn, App, Commands, Component, Entity, Local, Plugin, Query, Resource, World,
+    },
+    render::{
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        render_graph::{Node, RenderGraph},
+        render_resource::Buffer,
+        RenderApp, RenderStage,
+    },
+    utils::HashSet,
+};
+
+use crate::prelude::{InstanceSliceRange, InstanceSliceTarget};
+
+/// Render graph node copying shared instance slices, see [`SharedInstanceSlice`]
+///
+/// [`InstanceComputePlugin`](crate::prelude::InstanceComputePlugin) nodes run before it,
+/// so copies see the current frame's compute output.
+pub const SHARED_INSTANCE_SLICE_NODE: &str = "shared_instance_slice";
+
+/// Draws the instances of another entity's [`InstanceSlice`](crate::prelude::InstanceSlice)
+/// with this entity's material and mesh
+///
+/// Lets a single compute-driven slice be shaded by several materials,
+/// i.e. the same simulated positions drawn both as lit geometry and as a textured overlay.
+/// The entity must itself be an [`InstanceSliceBundle`](crate::prelude::InstanceSliceBundle),
+/// whose slice receives a copy of the source slice's instances each frame,
+/// after compute nodes have run and before any view is drawn.
+///
+/// Both materials must share the same prepared instance layout, and draw from different batches.
+/// Instances beyond the shorter of the two slices are left untouched.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+pub struct SharedInstanceSlice(pub Entity);
+
+impl ExtractComponent for SharedInstanceSlice {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Copies [`SharedInstanceSlice`]s from their source slices on the GPU
+///
+/// Copies are plain buffer-to-buffer transfers, so no pipeline or bind group is involved.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SharedInstanceSlicePlugin;
+
+impl Plugin for SharedInstanceSlicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ExtractComponentPlugin::<SharedInstanceSlice>::default());
+
+        let render_app = if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+        } else {
+            return;
+        };
+
+        render_app.add_system_to_stage(RenderStage::Queue, queue_shared_instance_slices);
+
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node(SHARED_INSTANCE_SLICE_NODE, SharedInstanceSliceNode);
+        render_graph
+            .add_node_edge(
+                SHARED_INSTANCE_SLICE_NODE,
+                bevy::render::main_graph::node::CAMERA_DRIVER,
+            )
+            .unwrap();
+    }
+}
+
+#[derive(Resource)]
+struct SharedInstanceSliceQueue(Vec<SharedInstanceSliceJob>);
+
+/// Copy of a source slice's instances into a shared slice, in bytes
+struct SharedInstanceSliceJob {
+    source: Buffer,
+    source_offset: u64,
+    destination: Buffer,
+    destination_offset: u64,
+    size: u64,
+}
+
+/// Queues a copy from each [`SharedInstanceSlice`]'s source slice into its own
+#[allow(clippy::type_complexity)]
+pub fn queue_shared_instance_slices(
+    query_shared_slice: Query<(
+        Entity,
+        &SharedInstanceSlice,
+        &InstanceSliceRange,
+        &InstanceSliceTarget,
+    )>,
+    query_source_slice: Query<(&InstanceSliceRange, &InstanceSliceTarget)>,
+    mut warned: Local<HashSet<Entity>>,
+    mut commands: Commands,
+) {
+    let mut jobs = vec![];
+
+    for (entity, shared_slice, range, target) in query_shared_slice.iter() {
+        let (source_range, source_target) =
+            if let Ok(source) = query_source_slice.get(shared_slice.0) {
+                source
+            } else {
+                debug!("Shared instance slice {entity:?} has no allocated source");
+                continue;
+            };
+
+        if source_target.instance_size != target.instance_size {
+            if warned.insert(entity) {
+                warn!(
+                    "Shared instance slice {entity:?} has a different instance layout from its source {:?}, and will not be copied",
+                    shared_slice.0
+                );
+            }
+            continue;
+        }
+
+        if source_target.buffer.id() == target.buffer.id() {
+            if warned.insert(entity) {
+                warn!(
+                    "Shared instance slice {entity:?} is batched with its source {:?}, and will not be copied",
+                    shared_slice.0
+                );
+            }
+            continue;
+        }
+
+        let instance_count = range.instance_count.min(source_range.instance_count);
+        if instance_count == 0 {
+            continue;
+        }
+
+        jobs.push(SharedInstanceSliceJob {
+            source: source_target.buffer.clone(),
+            source_offset: source_range.offset * target.instance_size,
+            destination: target.buffer.clone(),
+            destination_offset: range.offset * target.instance_size,
+            size: instance_count * target.instance_size,
+        });
+    }
+
+    warned.retain(|entity| query_shared_slice.contains(*entity));
+
+    commands.insert_resource(SharedInstanceSliceQueue(jobs));
+}
+
+struct SharedInstanceSliceNode;
+
+impl Node for SharedInstanceSliceNode {
+    fn run(
+        &self,
+        _graph: &mut bevy::render::render_graph::RenderGraphContext,
+        render_context: &mut bevy::render::renderer::RenderContext,
+        world: &World,
+    ) -> Result<(), bevy::render::render_graph::NodeRunError> {
+        let queue = if let Some(queue) = world.get_resource::<SharedInstanceSliceQueue>() {
+            queue
+        } else {
+            return Ok(());
+        };
+
+        for job in queue.0.iter() {
+            debug!("Copying {} shared instance slice bytes", job.size);
+            render_context.command_encoder.copy_buffer_to_buffer(
+                &job.source,
+                job.source_offset,
+                &job.destination,
+                job.destination_offset,
+                job.size,
+            );
+        }
+
+        Ok(())
+    }
+}
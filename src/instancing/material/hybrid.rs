@@ -0,0 +1,72 @@
+use std::{fmt::Debug, hash::Hash, marker::PhantomData};
+
+use bevy::{
+    pbr::{Material, MaterialPlugin},
+    prelude::{
+        default, App, Commands, Entity, Handle, IntoSystemDescriptor, Plugin, Query, Res, With,
+    },
+    render::{render_resource::ShaderType, RenderApp, RenderStage},
+};
+
+use crate::instancing::{
+    material::{
+        material_instanced::MaterialInstanced,
+        plugin::InstancedMaterialPlugin,
+        systems::{prepare_batched_instances, prepare_instance_slice_targets},
+    },
+    mesh_instance::ExtractedInstances,
+    render::instance::Instance,
+};
+
+/// Renders a material through both bevy's [`MaterialPlugin`] and [`InstancedMaterialPlugin`]
+///
+/// Entities are instanced by default. Entities marked [`NotInstanced`](crate::prelude::NotInstanced) are left
+/// to bevy's material pipeline, which allows a single hero object to share
+/// a material asset with many instanced copies.
+///
+/// Instanced entities are hidden from bevy's material pipeline once they've been batched,
+/// so each entity is drawn exactly once. Likewise, they cast shadows through their instanced batches alone,
+/// while [`NotInstanced`](crate::prelude::NotInstanced) entities cast them through bevy's shadow pass.
+pub struct HybridMaterialPlugin<M>(PhantomData<M>);
+
+impl<M> Default for HybridMaterialPlugin<M> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<M> Plugin for HybridMaterialPlugin<M>
+where
+    M: Material + MaterialInstanced,
+    <M as bevy::render::render_resource::AsBindGroup>::Data: Debug + Clone + Hash + PartialEq + Eq,
+    <M::Instance as Instance>::PreparedInstance: ShaderType,
+{
+    fn build(&self, app: &mut App) {
+        // Added first, since bevy's plugin unconditionally registers handle extraction
+        app.add_plugin(MaterialPlugin::<M>::default())
+            .add_plugin(InstancedMaterialPlugin::<M>::default());
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_system_to_stage(
+                RenderStage::Prepare,
+                hide_instances_from_material_pipeline::<M>
+                    .after(prepare_batched_instances::system::<M>)
+                    .after(prepare_instance_slice_targets::system::<M>),
+            );
+        }
+    }
+}
+
+/// Strips the material handle from instanced entities so bevy's material pipeline doesn't queue them
+pub fn hide_instances_from_material_pipeline<M: MaterialInstanced>(
+    extracted_instances: Res<ExtractedInstances<M>>,
+    query_instance: Query<Entity, With<Handle<M>>>,
+    mut commands: Commands,
+) {
+    for entity in query_instance
+        .iter()
+        .filter(|entity| extracted_instances.contains_key(entity))
+    {
+        commands.entity(entity).remove::<Handle<M>>();
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::render::{
+    render_resource::{
+        encase::{self, private::WriteInto},
+        BindingResource, Buffer, BufferBinding, BufferDescriptor, BufferInitDescriptor,
+        BufferUsages, ShaderSize, ShaderType,
+    },
+    renderer::{RenderDevice, RenderQueue},
+};
+
+/// Storage buffer of prepared instances
+///
+/// Equivalent to bevy's `StorageBuffer<Vec<T>>`, but also usable as a copy source,
+/// so compute output can be copied out of instance slices.
+///
+/// The buffer is recreated at the exact size needed whenever written instances outgrow it;
+/// [`InstanceStorageBuffer::reserve`] grows it ahead of time instead.
+pub struct InstanceStorageBuffer<T: ShaderType + ShaderSize + WriteInto> {
+    instances: Vec<T>,
+    scratch: encase::StorageBuffer<Vec<u8>>,
+    buffer: Option<Buffer>,
+    /// Size of `buffer`, in bytes
+    capacity: usize,
+}
+
+impl<T: ShaderType + ShaderSize + WriteInto> Default for InstanceStorageBuffer<T> {
+    fn default() -> Self {
+        Self {
+            instances: vec![],
+            scratch: encase::StorageBuffer::new(vec![]),
+            buffer: None,
+            capacity: 0,
+        }
+    }
+}
+
+impl<T: ShaderType + ShaderSize + WriteInto> InstanceStorageBuffer<T> {
+    const USAGE: BufferUsages = BufferUsages::STORAGE
+        .union(BufferUsages::COPY_DST)
+        .union(BufferUsages::COPY_SRC);
+
+    pub fn buffer(&self) -> Option<&Buffer> {
+        self.buffer.as_ref()
+    }
+
+    pub fn binding(&self) -> Option<BindingResource<'_>> {
+        Some(BindingResource::Buffer(BufferBinding {
+            buffer: self.buffer()?,
+            offset: 0,
+            size: None,
+        }))
+    }
+
+    pub fn get(&self) -> &Vec<T> {
+        &self.instances
+    }
+
+    pub fn get_mut(&mut self) -> &mut Vec<T> {
+        &mut self.instances
+    }
+
+    /// Number of instances the buffer can hold before it has to be recreated
+    pub fn capacity(&self) -> usize {
+        self.capacity / T::SHADER_SIZE.get() as usize
+    }
+
+    /// Grows the buffer to hold at least `instance_count` instances,
+    /// so writing up to that many doesn't recreate it
+    ///
+    /// Current instances are written to the new buffer, but GPU-written slice contents are not
+    /// carried over. Reserve before `prepare_instance_batches` of the buffer's material runs,
+    /// so that instance slices target the new buffer within the same frame.
+    pub fn reserve(
+        &mut self,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+        instance_count: usize,
+    ) {
+        let size = T::SHADER_SIZE.get() as usize * instance_count;
+        if size <= self.capacity {
+            return;
+        }
+
+        self.buffer = Some(render_device.create_buffer(&BufferDescriptor {
+            label: Some("instance buffer"),
+            size: size as u64,
+            usage: Self::USAGE,
+            mapped_at_creation: false,
+        }));
+        self.capacity = size;
+
+        self.write_buffer(render_device, render_queue);
+    }
+
+    /// Writes the instances to the GPU, recreating the buffer if they no longer fit
+    pub fn write_buffer(&mut self, render_device: &RenderDevice, render_queue: &RenderQueue) {
+        self.scratch.write(&self.instances).unwrap();
+
+        let size = self.scratch.as_ref().len();
+
+        if self.capacity < size {
+            self.buffer = Some(
+                render_device.create_buffer_with_data(&BufferInitDescriptor {
+                    label: Some("instance buffer"),
+                    usage: Self::USAGE,
+                    contents: self.scratch.as_ref(),
+                }),
+            );
+            self.capacity = size;
+        } else if let Some(buffer) = &self.buffer {
+            render_queue.write_buffer(buffer, 0, self.scratch.as_ref());
+        }
+    }
+}
@@ -3,22 +3,38 @@ use std::{hash::Hash, marker::PhantomData};
 use bevy::{
     asset::{AssetServer, Handle},
     ecs::{prelude::World, world::FromWorld},
-    pbr::MeshPipelineKey,
-    prelude::Resource,
+    pbr::{MeshPipelineKey, ShadowPipeline, SHADOW_FORMAT},
+    prelude::{warn, Resource},
     render::{
         mesh::MeshVertexBufferLayout,
         render_resource::{
-            BindGroupLayout, RenderPipelineDescriptor, Shader, SpecializedMeshPipeline,
+            BindGroupLayout, CompareFunction, DepthBiasState, FrontFace, MultisampleState,
+            RenderPipelineDescriptor, Shader, SpecializedMeshPipeline,
             SpecializedMeshPipelineError,
         },
         renderer::RenderDevice,
+        settings::WgpuFeatures,
     },
 };
 
-use crate::prelude::{InstancedMeshPipeline, MaterialInstanced};
+use crate::prelude::{
+    oit_color_targets, Instance, InstancedMeshPipeline, MaterialInstanced,
+    BATCH_DEBUG_SHADER_HANDLE, MAX_EXTRA_BIND_GROUPS,
+};
 
 pub struct InstancedMaterialPipelineKey<M: MaterialInstanced> {
     pub mesh_key: MeshPipelineKey,
+    pub depth_compare: CompareFunction,
+    pub write_depth: bool,
+    pub alpha_to_coverage: bool,
+    /// Targets the order-independent transparency pass, see [`OrderIndependent3d`](crate::prelude::OrderIndependent3d)
+    pub order_independent: bool,
+    /// Palette index to flat-shade this batch with, if [`InstanceBatchDebug`](crate::prelude::InstanceBatchDebug) is enabled
+    pub batch_debug: Option<usize>,
+    /// Flips triangle winding for instances with a negative-determinant transform
+    pub mirrored: bool,
+    /// Draws depth-only into a light's shadow map, see [`DrawInstancedShadow`](crate::prelude::DrawInstancedShadow)
+    pub shadow: bool,
     pub material_key: M::Data,
 }
 
@@ -29,6 +45,13 @@ where
     fn clone(&self) -> Self {
         Self {
             mesh_key: self.mesh_key.clone(),
+            depth_compare: self.depth_compare,
+            write_depth: self.write_depth,
+            alpha_to_coverage: self.alpha_to_coverage,
+            order_independent: self.order_independent,
+            batch_debug: self.batch_debug,
+            mirrored: self.mirrored,
+            shadow: self.shadow,
             material_key: self.material_key.clone(),
         }
     }
@@ -39,7 +62,15 @@ where
     M::Data: PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.mesh_key == other.mesh_key && self.material_key == other.material_key
+        self.mesh_key == other.mesh_key
+            && self.depth_compare == other.depth_compare
+            && self.write_depth == other.write_depth
+            && self.alpha_to_coverage == other.alpha_to_coverage
+            && self.order_independent == other.order_independent
+            && self.batch_debug == other.batch_debug
+            && self.mirrored == other.mirrored
+            && self.shadow == other.shadow
+            && self.material_key == other.material_key
     }
 }
 
@@ -51,6 +82,13 @@ where
 {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.mesh_key.hash(state);
+        self.depth_compare.hash(state);
+        self.write_depth.hash(state);
+        self.alpha_to_coverage.hash(state);
+        self.order_independent.hash(state);
+        self.batch_debug.hash(state);
+        self.mirrored.hash(state);
+        self.shadow.hash(state);
         self.material_key.hash(state);
     }
 }
@@ -59,6 +97,12 @@ where
 pub struct InstancedMaterialPipeline<M: MaterialInstanced> {
     pub instanced_mesh_pipeline: InstancedMeshPipeline,
     pub material_layout: BindGroupLayout,
+    /// View layout of bevy's shadow passes, which bind the view uniform alone
+    pub shadow_view_layout: BindGroupLayout,
+    /// Layouts returned by [`MaterialInstanced::extra_bind_group_layouts`]
+    pub extra_layouts: Vec<BindGroupLayout>,
+    /// Whether [`MaterialInstanced::conservative_rasterization`] is requested and supported
+    pub conservative_rasterization: bool,
     pub vertex_shader: Option<Handle<Shader>>,
     pub fragment_shader: Option<Handle<Shader>>,
     marker: PhantomData<M>,
@@ -86,12 +130,81 @@ where
             descriptor.fragment.as_mut().unwrap().shader = fragment_shader.clone();
         }
 
+        let instance_shader_defs = <M::Instance as Instance>::shader_defs();
+        descriptor
+            .vertex
+            .shader_defs
+            .extend(instance_shader_defs.iter().cloned());
+        descriptor
+            .fragment
+            .as_mut()
+            .unwrap()
+            .shader_defs
+            .extend(instance_shader_defs);
+
         // MeshPipeline::specialize's current implementation guarantees that the returned
         // specialized descriptor has a populated layout
         let descriptor_layout = descriptor.layout.as_mut().unwrap();
         descriptor_layout.insert(1, self.material_layout.clone());
+        descriptor_layout.extend(self.extra_layouts.iter().cloned());
+
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            depth_stencil.depth_compare = key.depth_compare;
+            depth_stencil.depth_write_enabled &= key.write_depth;
+        }
+
+        descriptor.primitive.conservative = self.conservative_rasterization;
+
+        if M::vertexless() {
+            descriptor.vertex.buffers.clear();
+        }
+
+        // Coverage is only meaningful with multiple samples
+        descriptor.multisample.alpha_to_coverage_enabled =
+            key.alpha_to_coverage && descriptor.multisample.count > 1;
+
+        if key.order_independent {
+            let fragment = descriptor.fragment.as_mut().unwrap();
+            fragment
+                .shader_defs
+                .push("ORDER_INDEPENDENT_TRANSPARENCY".into());
+            fragment.targets = oit_color_targets();
+        }
+
+        if key.mirrored {
+            descriptor.primitive.front_face = match descriptor.primitive.front_face {
+                FrontFace::Ccw => FrontFace::Cw,
+                FrontFace::Cw => FrontFace::Ccw,
+            };
+        }
 
         M::specialize(self, &mut descriptor, key.material_key, layout)?;
+
+        if let Some(batch_debug) = key.batch_debug {
+            let fragment = descriptor.fragment.as_mut().unwrap();
+            fragment.shader = BATCH_DEBUG_SHADER_HANDLE.typed();
+            fragment.entry_point = "fragment".into();
+            fragment
+                .shader_defs
+                .push(format!("BATCH_DEBUG_{batch_debug}"));
+        }
+
+        // Depth-only, matching the state of bevy's shadow pipeline
+        if key.shadow {
+            descriptor.layout.as_mut().unwrap()[0] = self.shadow_view_layout.clone();
+            descriptor.vertex.shader_defs.push("SHADOW_PASS".into());
+            descriptor.fragment = None;
+            descriptor.primitive.cull_mode = None;
+            descriptor.multisample = MultisampleState::default();
+
+            if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+                depth_stencil.format = SHADOW_FORMAT;
+                depth_stencil.depth_compare = CompareFunction::GreaterEqual;
+                depth_stencil.depth_write_enabled = true;
+                depth_stencil.bias = DepthBiasState::default();
+            }
+        }
+
         Ok(descriptor)
     }
 }
@@ -101,10 +214,35 @@ impl<M: MaterialInstanced> FromWorld for InstancedMaterialPipeline<M> {
         let asset_server = world.resource::<AssetServer>();
         let render_device = world.resource::<RenderDevice>();
         let material_layout = M::bind_group_layout(render_device);
+        let extra_layouts = M::extra_bind_group_layouts(render_device);
+
+        assert!(
+            extra_layouts.len() <= MAX_EXTRA_BIND_GROUPS,
+            "{} declares {} extra bind groups, but at most {} are supported",
+            std::any::type_name::<M>(),
+            extra_layouts.len(),
+            MAX_EXTRA_BIND_GROUPS
+        );
+
+        let mut conservative_rasterization = M::conservative_rasterization();
+        if conservative_rasterization
+            && !render_device
+                .features()
+                .contains(WgpuFeatures::CONSERVATIVE_RASTERIZATION)
+        {
+            warn!(
+                "{} requests conservative rasterization, but CONSERVATIVE_RASTERIZATION is not enabled",
+                std::any::type_name::<M>()
+            );
+            conservative_rasterization = false;
+        }
 
         InstancedMaterialPipeline {
             instanced_mesh_pipeline: world.resource::<InstancedMeshPipeline>().clone(),
             material_layout,
+            shadow_view_layout: world.resource::<ShadowPipeline>().view_layout.clone(),
+            extra_layouts,
+            conservative_rasterization,
             vertex_shader: match M::vertex_shader(asset_server) {
                 bevy::render::render_resource::ShaderRef::Default => None,
                 bevy::render::render_resource::ShaderRef::Handle(handle) => Some(handle),
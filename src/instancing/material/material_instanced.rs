@@ -1,15 +1,24 @@
+use std::ops::Range;
+
 use bevy::asset::AssetServer;
 use bevy::pbr::AlphaMode;
 use bevy::reflect::TypeUuid;
-use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::render::render_resource::{AsBindGroup, BindGroupLayout, CompareFunction, ShaderRef};
 use bevy::render::{
     mesh::MeshVertexBufferLayout,
     render_resource::{RenderPipelineDescriptor, SpecializedMeshPipelineError},
+    renderer::RenderDevice,
 };
 
 use crate::prelude::{Instance, InstancedMaterialPipeline};
 
 pub trait AsBatch {
+    /// Key identifying materials that can be drawn in the same batch
+    ///
+    /// Materials with equal keys are merged, and drawn using the bind group and pipeline key
+    /// of one of them, so the key must capture all material state that affects either.
+    /// It should be derived from material content rather than handle identity,
+    /// so that duplicate material assets share a batch.
     type BatchKey: std::fmt::Debug + PartialOrd + Ord + Clone + Send + Sync + for<'a> From<&'a Self>;
 }
 
@@ -51,6 +60,106 @@ pub trait MaterialInstanced:
         0.0
     }
 
+    #[inline]
+    /// Returns the depth comparison used when testing this material's fragments against the depth buffer.
+    /// Defaults to [`CompareFunction::Greater`], matching the reversed-Z mesh pipeline.
+    ///
+    /// [`CompareFunction::Always`] can be used to draw markers on top of other geometry.
+    fn depth_compare(&self) -> CompareFunction {
+        CompareFunction::Greater
+    }
+
+    #[inline]
+    /// Returns the band of normalized device depth this material's instances are drawn into.
+    /// Defaults to the full `0.0..1.0`.
+    ///
+    /// Clip-space depth is remapped linearly into the band through the viewport's depth range,
+    /// so it applies to any vertex shader, and instances stay ordered among themselves.
+    /// Bevy uses reversed Z, so `0.0` is the far plane, and i.e. `0.0..0.1` compresses
+    /// background instances into the far tenth of the depth buffer, for depth-layered compositing.
+    /// Materials with different bands are never batched together.
+    fn depth_range(&self) -> Range<f32> {
+        0.0..1.0
+    }
+
+    #[inline]
+    /// Returns whether this material's fragments are written to the depth buffer.
+    /// Defaults to `true`.
+    ///
+    /// Returning `false` still tests depth, which suits decals that shouldn't occlude
+    /// one another or geometry drawn after them.
+    fn write_depth(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    /// Returns whether this material's fragment alpha is converted into MSAA sample coverage.
+    /// Defaults to `false`, and has no effect unless [`Msaa`](bevy::prelude::Msaa) uses more than one sample.
+    ///
+    /// Suits masked geometry such as foliage cards, which then blend smoothly without sorting.
+    fn alpha_to_coverage(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    /// Returns whether this material is drawn with weighted blended order-independent transparency
+    /// rather than sorted blending. Defaults to `false`, and only applies with [`AlphaMode::Blend`](bevy::pbr::AlphaMode::Blend).
+    ///
+    /// The fragment shader must return `OitOutput` from `indirect_instancing::oit`
+    /// when `ORDER_INDEPENDENT_TRANSPARENCY` is defined, as [`CustomMaterial`](crate::prelude::CustomMaterial) does.
+    fn order_independent_transparency(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    /// Returns whether this material's instances are drawn into the shadow maps of shadow-casting lights.
+    /// Defaults to `true`.
+    ///
+    /// Shadows are drawn depth-only with the material's vertex shader, which only has the view uniform
+    /// bound in group 0 and is specialized with the `SHADOW_PASS` shader def.
+    /// Individual instances can be excluded with bevy's `NotShadowCaster`.
+    fn cast_shadows(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    /// Overrides the global [`MaxDrawDistance`](crate::prelude::MaxDrawDistance) for instances using this material.
+    /// Defaults to [`None`].
+    fn max_draw_distance(&self) -> Option<f32> {
+        None
+    }
+
+    /// Whether this material's pipelines rasterize conservatively,
+    /// covering every pixel a triangle touches, i.e. for GPU voxelization.
+    ///
+    /// Requires [`Features::CONSERVATIVE_RASTERIZATION`](bevy::render::settings::WgpuFeatures::CONSERVATIVE_RASTERIZATION)
+    /// to be enabled via [`WgpuSettings`](bevy::render::settings::WgpuSettings), and is ignored with a warning otherwise.
+    /// Defaults to `false`.
+    fn conservative_rasterization() -> bool {
+        false
+    }
+
+    /// Whether this material's pipelines bind no vertex buffer,
+    /// with vertex shaders relying on `instance_index` and `vertex_index` alone,
+    /// i.e. for point sprites or procedural geometry.
+    ///
+    /// Instances still need a mesh, which only determines the vertex or index count of each draw.
+    /// Defaults to `false`.
+    fn vertexless() -> bool {
+        false
+    }
+
+    /// Returns layouts for additional bind groups used by this material's shaders,
+    /// i.e. a lookup texture shared between all materials of this type.
+    ///
+    /// These are bound after the instance bind group, starting at group 3,
+    /// and their bind groups are provided via [`InstancedMaterialExtraBindGroups`](crate::prelude::InstancedMaterialExtraBindGroups).
+    /// At most [`MAX_EXTRA_BIND_GROUPS`](crate::prelude::MAX_EXTRA_BIND_GROUPS) may be declared. Defaults to none.
+    #[allow(unused_variables)]
+    fn extra_bind_group_layouts(render_device: &RenderDevice) -> Vec<BindGroupLayout> {
+        vec![]
+    }
+
     /// Specializes the given `descriptor` according to the given `key`.
     #[allow(unused_variables)]
     fn specialize(
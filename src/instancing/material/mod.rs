@@ -1,3 +1,5 @@
+pub mod hybrid;
+pub mod instance_storage_buffer;
 pub mod instanced_material_pipeline;
 pub mod plugin;
 pub mod set_instanced_material_bind_group;
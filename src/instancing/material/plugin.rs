@@ -16,28 +16,34 @@ use bevy::{
             SystemParamItem,
         },
     },
-    pbr::{AlphaMode, SetMeshViewBindGroup},
+    math::{UVec4, Vec4},
+    pbr::{AlphaMode, RenderLightSystems, SetMeshViewBindGroup, SetShadowViewBindGroup, Shadow},
     prelude::{
-        debug, default, AssetEvent, Assets, Commands, Deref, DerefMut, Entity, EventReader, Handle,
-        Image, IntoSystemDescriptor, Local, Mesh, Res, ResMut, Resource,
+        debug, default, AssetEvent, Assets, Commands, CoreStage, Deref, DerefMut, Entity,
+        EventReader, Handle, Image, IntoSystemDescriptor, Local, Mesh, Res, ResMut, Resource,
     },
     render::{
+        camera::ExtractedCamera,
         extract_component::ExtractComponentPlugin,
+        extract_resource::ExtractResource,
         mesh::{Indices, MeshVertexBufferLayout, PrimitiveTopology},
+        primitives::Aabb,
         render_asset::{PrepareAssetLabel, RenderAssets},
         render_phase::{
             AddRenderCommand, EntityRenderCommand, RenderCommandResult, SetItemPipeline,
             TrackedRenderPass,
         },
         render_resource::{
-            AsBindGroupError, BufferBindingType, IndexFormat, OwnedBindingResource, ShaderType,
-            SpecializedMeshPipelines, StorageBuffer, UniformBuffer,
+            AsBindGroupError, BufferBindingType, BufferDescriptor, BufferUsages,
+            CommandEncoderDescriptor, CompareFunction, IndexFormat, MapMode, OwnedBindingResource,
+            ShaderType, SpecializedMeshPipelines, UniformBuffer,
         },
         renderer::RenderQueue,
         texture::FallbackImage,
+        view::ExtractedView,
         Extract, RenderApp, RenderStage,
     },
-    utils::{HashMap, HashSet},
+    utils::{FloatOrd, HashMap, HashSet},
 };
 use bevy::{
     prelude::Component,
@@ -48,14 +54,18 @@ use bevy::{
 };
 
 use crate::prelude::{
-    extract_mesh_instances, Instance, InstanceSliceRange, InstancedMaterialPipeline,
-    MaterialInstanced, SetInstancedMaterialBindGroup,
+    extract_mesh_instances, prepare_view_clip_planes, ExtractedInstances, IndirectRenderingPlugin,
+    Instance, InstanceSliceRange, InstanceStorageBuffer, InstancedMaterialExtraBindGroups,
+    InstancedMaterialPipeline, InstancingDrawCallCount, MaterialInstanced, OrderIndependent3d,
+    SetInstancedMaterialBindGroup, SetInstancedMaterialExtraBindGroups, ShadowTileView,
 };
 
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::Debug,
     hash::Hash,
+    ops::Range,
+    sync::{Arc, Mutex},
 };
 
 use std::marker::PhantomData;
@@ -64,14 +74,16 @@ use super::systems::{
     extract_instanced_meshes, extract_instanced_view_meta,
     prepare_batched_instances::{self, ViewIndirectData},
     prepare_instance_batches::{self, ViewInstanceData},
-    prepare_instance_slice_targets,
+    prepare_instance_indices, prepare_instance_slice_targets,
     prepare_material_batches::{self, MaterialBatches},
     prepare_mesh_batches, prepare_view_instance_slices, prepare_view_instances,
-    queue_instanced_materials,
+    queue_instanced_materials, queue_instanced_shadows, warn_unmatched_instances,
 };
 
 /// Adds the necessary ECS resources and render logic to enable rendering entities using the given [`SpecializedMaterial`]
 /// asset type (which includes [`Material`] types).
+///
+/// [`IndirectRenderingPlugin`] must be added beforehand.
 pub struct InstancedMaterialPlugin<M: MaterialInstanced>(PhantomData<M>);
 
 impl<M: MaterialInstanced> Default for InstancedMaterialPlugin<M> {
@@ -86,23 +98,38 @@ where
     <M::Instance as Instance>::PreparedInstance: ShaderType,
 {
     fn build(&self, app: &mut App) {
-        app.add_asset::<M>()
-            .add_plugin(ExtractComponentPlugin::<Handle<M>>::default());
+        assert!(
+            app.is_plugin_added::<IndirectRenderingPlugin>(),
+            "IndirectRenderingPlugin must be added before InstancedMaterialPlugin<{}>",
+            std::any::type_name::<M>()
+        );
+
+        app.add_asset::<M>();
+
+        if !app.is_plugin_added::<ExtractComponentPlugin<Handle<M>>>() {
+            app.add_plugin(ExtractComponentPlugin::<Handle<M>>::default());
+        }
 
         if !app.is_plugin_added::<ExtractComponentPlugin<Handle<Mesh>>>() {
             app.add_plugin(ExtractComponentPlugin::<Handle<Mesh>>::default());
         }
 
+        app.add_system_to_stage(CoreStage::PostUpdate, warn_unmatched_instances::system::<M>);
+
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app
                 .add_render_command::<Transparent3d, DrawInstanced<M>>()
                 .add_render_command::<Opaque3d, DrawInstanced<M>>()
                 .add_render_command::<AlphaMask3d, DrawInstanced<M>>()
+                .add_render_command::<OrderIndependent3d, DrawInstanced<M>>()
+                .add_render_command::<Shadow, DrawInstancedShadow<M>>()
                 .init_resource::<InstancedMaterialPipeline<M>>()
                 .init_resource::<ExtractedMaterials<M>>()
                 .init_resource::<RenderMeshes>()
                 .init_resource::<RenderMaterials<M>>()
+                .init_resource::<InstancedMaterialExtraBindGroups<M>>()
                 .init_resource::<MaterialBatches<M>>()
+                .init_resource::<ExtractedInstances<M>>()
                 .init_resource::<ViewInstanceData<M>>()
                 .init_resource::<ViewIndirectData<M>>()
                 .init_resource::<SpecializedMeshPipelines<InstancedMaterialPipeline<M>>>()
@@ -136,7 +163,8 @@ where
                 .add_system_to_stage(
                     RenderStage::Prepare,
                     prepare_batched_instances::system::<M>
-                        .after(prepare_instance_batches::system::<M>),
+                        .after(prepare_instance_batches::system::<M>)
+                        .after(prepare_view_clip_planes),
                 )
                 .add_system_to_stage(
                     RenderStage::Prepare,
@@ -153,7 +181,16 @@ where
                     prepare_instance_slice_targets::system::<M>
                         .after(prepare_batched_instances::system::<M>),
                 )
-                .add_system_to_stage(RenderStage::Queue, queue_instanced_materials::system::<M>);
+                .add_system_to_stage(
+                    RenderStage::Prepare,
+                    prepare_instance_indices::system::<M>
+                        .after(prepare_instance_batches::system::<M>),
+                )
+                .add_system_to_stage(RenderStage::Queue, queue_instanced_materials::system::<M>)
+                .add_system_to_stage(
+                    RenderStage::Queue,
+                    queue_instanced_shadows::system::<M>.after(RenderLightSystems::QueueShadows),
+                );
         }
     }
 }
@@ -168,13 +205,7 @@ pub struct InstancedMeshKey {
 
 impl PartialOrd for InstancedMeshKey {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match (self.primitive_topology as usize).partial_cmp(&(other.primitive_topology as usize)) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
-        self.index_format
-            .map(|index_format| index_format as usize)
-            .partial_cmp(&other.index_format.map(|index_format| index_format as usize))
+        Some(self.cmp(other))
     }
 }
 
@@ -184,9 +215,20 @@ impl Ord for InstancedMeshKey {
             core::cmp::Ordering::Equal => {}
             ord => return ord,
         }
-        self.index_format
+        match self
+            .index_format
             .map(|index_format| index_format as usize)
             .cmp(&other.index_format.map(|index_format| index_format as usize))
+        {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        // Meshes are concatenated into a shared vertex buffer bound with a single stride,
+        // so those with different attributes must be batched separately.
+        // Attribute ids determine their formats, and thus the interleaved layout
+        self.layout
+            .attribute_ids()
+            .cmp(other.layout.attribute_ids())
     }
 }
 
@@ -194,6 +236,8 @@ impl Ord for InstancedMeshKey {
 pub enum GpuIndexBufferData {
     Indexed {
         indices: Indices,
+        /// Length of `indices`, used to size index buffers and indirect draws
+        index_count: u32,
         index_format: IndexFormat,
     },
     NonIndexed {
@@ -210,6 +254,8 @@ pub struct GpuInstancedMesh {
     pub primitive_topology: PrimitiveTopology,
     pub layout: MeshVertexBufferLayout,
     pub key: InstancedMeshKey,
+    /// Local-space bounds, if the mesh has a position attribute
+    pub aabb: Option<Aabb>,
 }
 
 #[derive(Debug, Clone, Deref, DerefMut, Resource)]
@@ -263,6 +309,8 @@ pub enum GpuAlphaMode {
     Opaque,
     Mask,
     Blend,
+    /// [`AlphaMode::Blend`] drawn with weighted blended order-independent transparency
+    OrderIndependent,
 }
 
 impl From<AlphaMode> for GpuAlphaMode {
@@ -278,6 +326,10 @@ impl From<AlphaMode> for GpuAlphaMode {
 /// Unique key describing a set of mutually incompatible materials
 pub struct InstancedMaterialBatchKey<M: MaterialInstanced> {
     pub alpha_mode: GpuAlphaMode,
+    pub depth_compare: CompareFunction,
+    pub write_depth: bool,
+    pub alpha_to_coverage: bool,
+    pub depth_range: [FloatOrd; 2],
     pub key: M::BatchKey,
 }
 
@@ -285,6 +337,10 @@ impl<M: MaterialInstanced> Clone for InstancedMaterialBatchKey<M> {
     fn clone(&self) -> Self {
         Self {
             alpha_mode: self.alpha_mode.clone(),
+            depth_compare: self.depth_compare,
+            write_depth: self.write_depth,
+            alpha_to_coverage: self.alpha_to_coverage,
+            depth_range: self.depth_range,
             key: self.key.clone(),
         }
     }
@@ -292,7 +348,12 @@ impl<M: MaterialInstanced> Clone for InstancedMaterialBatchKey<M> {
 
 impl<M: MaterialInstanced> PartialEq for InstancedMaterialBatchKey<M> {
     fn eq(&self, other: &Self) -> bool {
-        self.alpha_mode == other.alpha_mode && self.key == other.key
+        self.alpha_mode == other.alpha_mode
+            && self.depth_compare == other.depth_compare
+            && self.write_depth == other.write_depth
+            && self.alpha_to_coverage == other.alpha_to_coverage
+            && self.depth_range == other.depth_range
+            && self.key == other.key
     }
 }
 
@@ -307,6 +368,22 @@ where
             Some(core::cmp::Ordering::Equal) => {}
             ord => return ord,
         }
+        match (self.depth_compare as usize).partial_cmp(&(other.depth_compare as usize)) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
+        match self.write_depth.partial_cmp(&other.write_depth) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
+        match self.alpha_to_coverage.partial_cmp(&other.alpha_to_coverage) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
+        match self.depth_range.partial_cmp(&other.depth_range) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
         self.key.partial_cmp(&other.key)
     }
 }
@@ -320,6 +397,22 @@ where
             core::cmp::Ordering::Equal => {}
             ord => return ord,
         }
+        match (self.depth_compare as usize).cmp(&(other.depth_compare as usize)) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        match self.write_depth.cmp(&other.write_depth) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        match self.alpha_to_coverage.cmp(&other.alpha_to_coverage) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        match self.depth_range.cmp(&other.depth_range) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
         self.key.cmp(&other.key)
     }
 }
@@ -331,6 +424,10 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("InstancedMaterialKey")
             .field("alpha_mode", &self.alpha_mode)
+            .field("depth_compare", &self.depth_compare)
+            .field("write_depth", &self.write_depth)
+            .field("alpha_to_coverage", &self.alpha_to_coverage)
+            .field("depth_range", &self.depth_range)
             .field("key", &self.key)
             .finish()
     }
@@ -338,8 +435,19 @@ where
 
 /// Unique key describing a set of mutually incompatible instances
 pub struct InstanceBatchKey<M: MaterialInstanced> {
+    /// Explicit draw order layer, dominating depth-based sorting
+    pub layer: i32,
     pub mesh_key: InstancedMeshKey,
     pub material_key: InstancedMaterialBatchKey<M>,
+    /// Whether instances have a negative-determinant transform,
+    /// and thus need their triangle winding flipped
+    pub mirrored: bool,
+    /// User-specified partition from [`ForceBatchGroup`](crate::prelude::ForceBatchGroup)
+    pub group: u32,
+    /// Whether instances are marked [`AlwaysOnTop`](crate::prelude::AlwaysOnTop)
+    pub always_on_top: bool,
+    /// Whether instances are marked [`ShadowOnly`](crate::prelude::ShadowOnly)
+    pub shadow_only: bool,
 }
 
 impl<M: MaterialInstanced> Component for InstanceBatchKey<M> {
@@ -352,15 +460,26 @@ where
 {
     fn clone(&self) -> Self {
         Self {
+            layer: self.layer,
             mesh_key: self.mesh_key.clone(),
             material_key: self.material_key.clone(),
+            mirrored: self.mirrored,
+            group: self.group,
+            always_on_top: self.always_on_top,
+            shadow_only: self.shadow_only,
         }
     }
 }
 
 impl<M: MaterialInstanced> PartialEq for InstanceBatchKey<M> {
     fn eq(&self, other: &Self) -> bool {
-        self.mesh_key == other.mesh_key && self.material_key == other.material_key
+        self.layer == other.layer
+            && self.mesh_key == other.mesh_key
+            && self.material_key == other.material_key
+            && self.mirrored == other.mirrored
+            && self.group == other.group
+            && self.always_on_top == other.always_on_top
+            && self.shadow_only == other.shadow_only
     }
 }
 
@@ -371,11 +490,31 @@ where
     M::BatchKey: PartialOrd,
 {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match self.layer.partial_cmp(&other.layer) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
         match self.mesh_key.partial_cmp(&other.mesh_key) {
             Some(core::cmp::Ordering::Equal) => {}
             ord => return ord,
         }
-        self.material_key.partial_cmp(&other.material_key)
+        match self.material_key.partial_cmp(&other.material_key) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
+        match self.mirrored.partial_cmp(&other.mirrored) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
+        match self.group.partial_cmp(&other.group) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
+        match self.always_on_top.partial_cmp(&other.always_on_top) {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
+        self.shadow_only.partial_cmp(&other.shadow_only)
     }
 }
 
@@ -384,11 +523,31 @@ where
     M::BatchKey: Ord,
 {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match self.layer.cmp(&other.layer) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
         match self.mesh_key.cmp(&other.mesh_key) {
             core::cmp::Ordering::Equal => {}
             ord => return ord,
         }
-        self.material_key.cmp(&other.material_key)
+        match self.material_key.cmp(&other.material_key) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        match self.mirrored.cmp(&other.mirrored) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        match self.group.cmp(&other.group) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        match self.always_on_top.cmp(&other.always_on_top) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        self.shadow_only.cmp(&other.shadow_only)
     }
 }
 
@@ -398,25 +557,67 @@ where
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("InstanceKey")
+            .field("layer", &self.layer)
             .field("mesh_key", &self.mesh_key)
             .field("material_key", &self.material_key)
+            .field("mirrored", &self.mirrored)
+            .field("group", &self.group)
+            .field("always_on_top", &self.always_on_top)
+            .field("shadow_only", &self.shadow_only)
             .finish()
     }
 }
 
 const MAX_UNIFORM_BUFFER_LENGTH: usize = MeshInstance::UNIFORM_BUFFER_LENGTH.get() as usize;
 
+/// Controls diagnostics for the uniform buffer fallback used when storage buffers are unavailable
+#[derive(Debug, Copy, Clone, Resource, ExtractResource)]
+pub struct UniformSplitSettings {
+    /// Warn once per material when a batch is split into more than this many uniform chunks,
+    /// each of which is drawn with its own bind group and indirect buffer
+    pub warn_threshold: usize,
+}
+
+impl Default for UniformSplitSettings {
+    fn default() -> Self {
+        Self { warn_threshold: 8 }
+    }
+}
+
+/// Controls the fast path for batches containing a single instance
+#[derive(Debug, Copy, Clone, Resource, ExtractResource)]
+pub struct SingleInstanceSettings {
+    /// Draw single-instance batches with a plain `draw` / `draw_indexed` call,
+    /// skipping the construction and upload of an indirect buffer
+    pub direct_draw: bool,
+}
+
+impl Default for SingleInstanceSettings {
+    fn default() -> Self {
+        Self { direct_draw: true }
+    }
+}
+
 pub enum GpuInstances<M: MaterialInstanced> {
     Uniform {
         buffers: Vec<
             UniformBuffer<[<M::Instance as Instance>::PreparedInstance; MAX_UNIFORM_BUFFER_LENGTH]>,
         >,
+        /// Number of instances stored across `buffers`, excluding the padding of the last one
+        len: usize,
     },
     Storage {
-        buffer: StorageBuffer<Vec<<M::Instance as Instance>::PreparedInstance>>,
+        buffer: InstanceStorageBuffer<<M::Instance as Instance>::PreparedInstance>,
     },
 }
 
+/// Number of uniform buffers (and thus draw calls per mesh batch) needed
+/// to hold `instance_count` instances of `M` on devices without storage buffer support
+pub fn uniform_chunk_count<M: MaterialInstanced>(instance_count: usize) -> usize {
+    let chunk_length = <M::Instance as InstanceUniformLength>::UNIFORM_BUFFER_LENGTH.get() as usize;
+    instance_count.div_ceil(chunk_length)
+}
+
 impl<M: MaterialInstanced> GpuInstances<M> {
     pub fn new(buffer_binding_type: BufferBindingType) -> Self {
         match buffer_binding_type {
@@ -426,18 +627,24 @@ impl<M: MaterialInstanced> GpuInstances<M> {
     }
 
     pub fn uniform() -> Self {
-        Self::Uniform { buffers: default() }
+        Self::Uniform {
+            buffers: default(),
+            len: 0,
+        }
     }
 
     pub fn storage() -> Self {
         Self::Storage {
-            buffer: StorageBuffer::default(),
+            buffer: InstanceStorageBuffer::default(),
         }
     }
 
     pub fn clear(&mut self) {
         match self {
-            Self::Uniform { buffers } => buffers.clear(),
+            Self::Uniform { buffers, len } => {
+                buffers.clear();
+                *len = 0;
+            }
             Self::Storage { buffer } => buffer.get_mut().clear(),
         }
     }
@@ -446,7 +653,9 @@ impl<M: MaterialInstanced> GpuInstances<M> {
         self.clear();
 
         match self {
-            Self::Uniform { buffers } => {
+            Self::Uniform { buffers, len } => {
+                *len = instances.len();
+
                 for chunk in instances.chunks(
                     <M::Instance as InstanceUniformLength>::UNIFORM_BUFFER_LENGTH.get() as usize,
                 ) {
@@ -473,9 +682,38 @@ impl<M: MaterialInstanced> GpuInstances<M> {
         }
     }
 
+    /// Removes instances rejected by `retain`, packing the remainder densely
+    /// while preserving their relative order.
+    ///
+    /// `prepare_instance_batches` rebuilds instance data from live entities every frame,
+    /// so its output is already dense; this is for data that is updated in-place,
+    /// where removals would otherwise leave gaps in the buffer.
+    pub fn compact(
+        &mut self,
+        mut retain: impl FnMut(&<M::Instance as Instance>::PreparedInstance) -> bool,
+    ) {
+        match self {
+            Self::Uniform { buffers, len } => {
+                let chunk_length =
+                    <M::Instance as InstanceUniformLength>::UNIFORM_BUFFER_LENGTH.get() as usize;
+
+                let instances = buffers
+                    .iter()
+                    .flat_map(|buffer| buffer.get().iter().take(chunk_length))
+                    .take(*len)
+                    .filter(|instance| retain(instance))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                self.set(instances);
+            }
+            Self::Storage { buffer } => buffer.get_mut().retain(retain),
+        }
+    }
+
     pub fn write_buffer(&mut self, render_device: &RenderDevice, render_queue: &RenderQueue) {
         match self {
-            Self::Uniform { buffers } => {
+            Self::Uniform { buffers, .. } => {
                 for buffer in buffers {
                     buffer.write_buffer(render_device, render_queue)
                 }
@@ -484,9 +722,38 @@ impl<M: MaterialInstanced> GpuInstances<M> {
         }
     }
 
+    /// Number of instances that fit in the current GPU buffers before they're recreated
+    ///
+    /// Uniform buffers hold whole chunks, so this is [`GpuInstances::len`]
+    /// rounded up to the chunk length.
+    pub fn capacity(&self) -> usize {
+        match self {
+            Self::Uniform { buffers, .. } => {
+                buffers.len()
+                    * <M::Instance as InstanceUniformLength>::UNIFORM_BUFFER_LENGTH.get() as usize
+            }
+            Self::Storage { buffer } => buffer.capacity(),
+        }
+    }
+
+    /// Grows a storage buffer to hold at least `instance_count` instances ahead of time,
+    /// see [`InstanceStorageBuffer::reserve`]
+    ///
+    /// Does nothing for uniform buffers, which are allocated per chunk as needed.
+    pub fn reserve(
+        &mut self,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+        instance_count: usize,
+    ) {
+        if let Self::Storage { buffer } = self {
+            buffer.reserve(render_device, render_queue, instance_count);
+        }
+    }
+
     pub fn len(&self) -> usize {
         match self {
-            Self::Uniform { buffers } => buffers.len() * 128,
+            Self::Uniform { len, .. } => *len,
             Self::Storage { buffer } => buffer.get().len(),
         }
     }
@@ -499,14 +766,38 @@ impl<M: MaterialInstanced> GpuInstances<M> {
 pub struct InstanceBatch<M: MaterialInstanced> {
     pub instances: BTreeSet<Entity>,
     pub instance_slice_ranges: BTreeMap<Entity, InstanceSliceRange>,
+    /// Local-space bounding sphere of each CPU-prepared instance as center and radius,
+    /// in instance buffer order
+    pub bounds: Vec<Vec4>,
+    /// Each CPU-prepared instance with its index into the batch's instance buffer,
+    /// which is the `instance_index` it is drawn with, in instance buffer order
+    ///
+    /// Instances expanded into several prepared instances are listed by the index of the first.
+    pub instance_indices: Vec<(Entity, u32)>,
+    /// View-space Z of the batch's nearest CPU-prepared instance, including material depth bias,
+    /// as measured by the view's rangefinder; zero for batches of instance slices only
+    pub view_distance: f32,
     pub _phantom: PhantomData<M>,
 }
 
+impl<M: MaterialInstanced> InstanceBatch<M> {
+    /// Length of the slice region at the start of the batch's instance buffer,
+    /// after which CPU-prepared instances begin
+    pub fn instance_slice_len(&self) -> u64 {
+        self.instance_slice_ranges
+            .values()
+            .map(|range| range.offset + range.instance_count)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
 impl<M: MaterialInstanced> Debug for InstanceBatch<M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("InstanceBatch")
             .field("instances", &self.instances)
             .field("instance_slice_ranges", &self.instance_slice_ranges)
+            .field("view_distance", &self.view_distance)
             .finish()
     }
 }
@@ -554,12 +845,106 @@ pub struct GpuIndirectBufferData {
     pub buffer: Buffer,
 }
 
+/// CPU-side copy of the `instance_count` values of an indirect buffer,
+/// populated once the corresponding readback has been mapped
+pub type IndirectReadback = Arc<Mutex<Option<Vec<u32>>>>;
+
+impl GpuIndirectBufferData {
+    /// Size in bytes of the indirect draws stored in `buffer`
+    pub fn size(&self) -> u64 {
+        self.indirects
+            .iter()
+            .map(|indirect| match indirect {
+                IndirectDraw::Indexed(_) => std::mem::size_of::<DrawIndexedIndirect>(),
+                IndirectDraw::NonIndexed(_) => std::mem::size_of::<DrawIndirect>(),
+            } as u64)
+            .sum()
+    }
+
+    /// Copies the indirect buffer into a mappable staging buffer and reads back
+    /// the `instance_count` of each draw, including any modifications made by compute passes.
+    ///
+    /// The returned handle is populated once the map completes on a subsequent device poll,
+    /// which usually means the next frame. Intended for debugging and profiling.
+    pub fn read_instance_counts(
+        &self,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+    ) -> IndirectReadback {
+        let readback = IndirectReadback::default();
+
+        let size = self.size();
+        if size == 0 {
+            *readback.lock().unwrap() = Some(vec![]);
+            return readback;
+        }
+
+        let staging_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("indirect readback buffer"),
+            size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("indirect readback"),
+        });
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &staging_buffer, 0, size);
+        render_queue.submit([encoder.finish()]);
+
+        // instance_count immediately follows vertex_count in both indirect layouts
+        let mut offsets = vec![];
+        let mut offset = 0;
+        for indirect in self.indirects.iter() {
+            offsets.push(offset + std::mem::size_of::<u32>());
+            offset += match indirect {
+                IndirectDraw::Indexed(_) => std::mem::size_of::<DrawIndexedIndirect>(),
+                IndirectDraw::NonIndexed(_) => std::mem::size_of::<DrawIndirect>(),
+            };
+        }
+
+        let result = readback.clone();
+        let buffer = staging_buffer.clone();
+        render_device.map_buffer(&staging_buffer.slice(..), MapMode::Read, move |status| {
+            if status.is_err() {
+                return;
+            }
+
+            let instance_counts = {
+                let data = buffer.slice(..).get_mapped_range();
+                offsets
+                    .into_iter()
+                    .map(|offset| {
+                        *bytemuck::from_bytes::<u32>(
+                            &data[offset..offset + std::mem::size_of::<u32>()],
+                        )
+                    })
+                    .collect()
+            };
+            buffer.unmap();
+
+            *result.lock().unwrap() = Some(instance_counts);
+        });
+
+        readback
+    }
+}
+
+/// The draw calls issued for a set of [`BatchedInstances`]
+#[derive(Debug, Clone)]
+pub enum BatchedDraws {
+    /// Draws read from an indirect buffer
+    Indirect(GpuIndirectBufferData),
+    /// A single draw issued directly, used for batches containing exactly one instance
+    Direct(IndirectDraw),
+}
+
 /// The data necessary to render one set of mutually compatible instances
 #[derive(Component)]
 pub struct BatchedInstances {
     pub vertex_buffer: Buffer,
     pub index_buffer: Option<(Buffer, IndexFormat)>,
-    pub indirect_buffer: GpuIndirectBufferData,
+    pub draws: BatchedDraws,
     pub bind_group: BindGroup,
 }
 
@@ -567,6 +952,17 @@ pub type DrawInstanced<M> = (
     SetItemPipeline,
     SetMeshViewBindGroup<0>,
     SetInstancedMaterialBindGroup<M, 1>,
+    SetInstancedMaterialExtraBindGroups<M, 3>,
+    DrawBatchedInstances<M>,
+);
+
+/// Draws a batch depth-only into the shadow map of the light view it was queued for,
+/// using the instances prepared for the light's [`ShadowTile`](crate::prelude::ShadowTile)
+pub type DrawInstancedShadow<M> = (
+    SetItemPipeline,
+    SetShadowViewBindGroup<0>,
+    SetInstancedMaterialBindGroup<M, 1>,
+    SetInstancedMaterialExtraBindGroups<M, 3>,
     DrawBatchedInstances<M>,
 );
 
@@ -576,39 +972,109 @@ pub struct DrawBatchedInstances<M: MaterialInstanced>(PhantomData<M>);
 impl<M: MaterialInstanced> EntityRenderCommand for DrawBatchedInstances<M> {
     type Param = (
         SRes<RenderDevice>,
+        SRes<InstancingDrawCallCount>,
         SQuery<Read<InstanceMeta<M>>>,
         SQuery<Read<InstanceBatchKey<M>>>,
+        SQuery<(Read<ExtractedView>, Option<Read<ExtractedCamera>>)>,
+        SQuery<Read<ShadowTileView>>,
     );
     #[inline]
     fn render<'w>(
         view: Entity,
         item: Entity,
-        (render_device, instance_meta, query_instance_batch_key): SystemParamItem<
-            'w,
-            '_,
-            Self::Param,
-        >,
+        (
+            render_device,
+            draw_call_count,
+            instance_meta,
+            query_instance_batch_key,
+            query_view,
+            query_shadow_tile_view,
+        ): SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
         debug!("DrawInstanceBatch {item:?}");
+        let batch_key = query_instance_batch_key.get(item).unwrap();
+
+        // Shadow batches draw the instances prepared for their tile, rather than for bevy's shadow view
+        let shadow_tile_view = query_shadow_tile_view.get_inner(item).ok();
         let batched_instances = instance_meta
-            .get_inner(view)
+            .get_inner(shadow_tile_view.map_or(view, |tile_view| tile_view.0))
             .unwrap()
             .batched_instances
-            .get(query_instance_batch_key.get(item).unwrap())
+            .get(batch_key)
             .unwrap();
 
+        // Remap depth into the material's band through the viewport,
+        // restoring the camera's own range once the batch is drawn.
+        // Shadow maps keep their full range, so bands only affect cameras
+        let [FloatOrd(band_start), FloatOrd(band_end)] = batch_key.material_key.depth_range;
+        let depth_band = if shadow_tile_view.is_none() && (band_start != 0.0 || band_end != 1.0) {
+            let (extracted_view, extracted_camera) = query_view.get_inner(view).unwrap();
+            let camera_depth = extracted_camera
+                .and_then(|camera| camera.viewport.as_ref())
+                .map(|viewport| viewport.depth.clone())
+                .unwrap_or(0.0..1.0);
+
+            set_viewport_depth(
+                pass,
+                extracted_view.viewport,
+                remap_depth_range(&camera_depth, band_start..band_end),
+            );
+
+            Some((extracted_view.viewport, camera_depth))
+        } else {
+            None
+        };
+
         for (i, batch) in batched_instances.into_iter().enumerate() {
             debug!("Batch {}", i);
             pass.set_bind_group(2, &batch.bind_group, &[]);
 
-            pass.set_vertex_buffer(0, batch.vertex_buffer.slice(..));
+            if !M::vertexless() {
+                pass.set_vertex_buffer(0, batch.vertex_buffer.slice(..));
+            }
 
             if let Some((index_buffer, index_format)) = &batch.index_buffer {
                 pass.set_index_buffer(index_buffer.slice(..), 0, *index_format);
             }
 
-            for (i, indirect) in batch.indirect_buffer.indirects.iter().enumerate() {
+            let indirect_buffer = match &batch.draws {
+                BatchedDraws::Indirect(indirect_buffer) => indirect_buffer,
+                BatchedDraws::Direct(draw) => {
+                    debug!("Drawing single instance directly: {draw:#?}");
+                    draw_direct(pass, draw);
+                    draw_call_count.add(1);
+                    continue;
+                }
+            };
+
+            // Collapse the batch's indirect draws into a single call where supported
+            if cfg!(feature = "multi_draw_indirect")
+                && render_device.features().contains(
+                    bevy::render::render_resource::WgpuFeatures::MULTI_DRAW_INDIRECT
+                        | bevy::render::render_resource::WgpuFeatures::INDIRECT_FIRST_INSTANCE,
+                )
+            {
+                let count = indirect_buffer.indirects.len() as u32;
+                match indirect_buffer.indirects.first() {
+                    Some(IndirectDraw::Indexed(_)) => {
+                        debug!("Drawing {count} indexed indirects in one call");
+                        pass.multi_draw_indexed_indirect(&indirect_buffer.buffer, 0, count);
+                    }
+                    Some(IndirectDraw::NonIndexed(_)) => {
+                        debug!("Drawing {count} indirects in one call");
+                        pass.multi_draw_indirect(&indirect_buffer.buffer, 0, count);
+                    }
+                    None => continue,
+                }
+
+                draw_call_count.add(1);
+                continue;
+            }
+
+            draw_call_count.add(indirect_buffer.indirects.len() as u32);
+
+            for (i, indirect) in indirect_buffer.indirects.iter().enumerate() {
                 if render_device
                     .features()
                     .contains(bevy::render::render_resource::WgpuFeatures::INDIRECT_FIRST_INSTANCE)
@@ -617,60 +1083,85 @@ impl<M: MaterialInstanced> EntityRenderCommand for DrawBatchedInstances<M> {
                         IndirectDraw::Indexed(_) => {
                             debug!("Drawing indexed indirect {i:?}: {indirect:#?}");
                             pass.draw_indexed_indirect(
-                                &batch.indirect_buffer.buffer,
+                                &indirect_buffer.buffer,
                                 (i * std::mem::size_of::<DrawIndexedIndirect>()) as u64,
                             );
                         }
                         IndirectDraw::NonIndexed(_) => {
                             debug!("Drawing indirect {i:?}: {indirect:#?}");
                             pass.draw_indirect(
-                                &batch.indirect_buffer.buffer,
+                                &indirect_buffer.buffer,
                                 (i * std::mem::size_of::<DrawIndirect>()) as u64,
                             );
                         }
                     }
                 } else {
-                    match indirect {
-                        IndirectDraw::Indexed(draw) => {
-                            debug!("Drawing indexed direct {i:?}: {draw:#?}");
-
-                            let DrawIndexedIndirect {
-                                vertex_count,
-                                instance_count,
-                                base_index,
-                                vertex_offset,
-                                base_instance,
-                            } = *draw;
-
-                            pass.draw_indexed(
-                                base_index..base_index + vertex_count,
-                                vertex_offset,
-                                base_instance..base_instance + instance_count,
-                            );
-                        }
-                        IndirectDraw::NonIndexed(draw) => {
-                            debug!("Drawing direct {i:?}: {indirect:#?}");
-                            let DrawIndirect {
-                                vertex_count,
-                                instance_count,
-                                base_vertex,
-                                base_instance,
-                            } = *draw;
-
-                            pass.draw(
-                                base_vertex..base_vertex + vertex_count,
-                                base_instance..base_instance + instance_count,
-                            );
-                        }
-                    }
+                    debug!("Drawing direct {i:?}: {indirect:#?}");
+                    draw_direct(pass, indirect);
                 }
             }
         }
 
+        if let Some((viewport, camera_depth)) = depth_band {
+            set_viewport_depth(pass, viewport, camera_depth);
+        }
+
         RenderCommandResult::Success
     }
 }
 
+/// Maps `band`, a fraction of the full depth range, into `camera_depth`
+fn remap_depth_range(camera_depth: &Range<f32>, band: Range<f32>) -> Range<f32> {
+    let extent = camera_depth.end - camera_depth.start;
+    camera_depth.start + band.start * extent..camera_depth.start + band.end * extent
+}
+
+/// Sets the pass viewport to the view's rect, `viewport`, over the given depth range
+fn set_viewport_depth(pass: &mut TrackedRenderPass, viewport: UVec4, depth: Range<f32>) {
+    pass.set_viewport(
+        viewport.x as f32,
+        viewport.y as f32,
+        viewport.z as f32,
+        viewport.w as f32,
+        depth.start,
+        depth.end,
+    );
+}
+
+/// Issues `indirect` as a regular draw call
+fn draw_direct(pass: &mut TrackedRenderPass, indirect: &IndirectDraw) {
+    match indirect {
+        IndirectDraw::Indexed(draw) => {
+            let DrawIndexedIndirect {
+                vertex_count,
+                instance_count,
+                base_index,
+                vertex_offset,
+                base_instance,
+            } = *draw;
+
+            pass.draw_indexed(
+                base_index..base_index + vertex_count,
+                vertex_offset,
+                base_instance..base_instance + instance_count,
+            );
+        }
+        IndirectDraw::NonIndexed(draw) => {
+            let DrawIndirect {
+                vertex_count,
+                instance_count,
+                base_vertex,
+                base_instance,
+            } = *draw;
+
+            pass.draw(
+                base_vertex..base_vertex + vertex_count,
+                base_instance..base_instance + instance_count,
+            );
+        }
+    }
+}
+
 /// Common [`Material`] properties, calculated for a specific material instance.
 pub struct MaterialProperties {
     /// The [`AlphaMode`] of this material.
@@ -678,6 +1169,41 @@ pub struct MaterialProperties {
     /// Add a bias to the view depth of the mesh which can be used to force a specific render order
     /// for meshes with equal depth, to avoid z-fighting.
     pub depth_bias: f32,
+    /// The depth comparison function used by this material's pipeline.
+    pub depth_compare: CompareFunction,
+    /// Whether this material's pipeline writes to the depth buffer.
+    pub write_depth: bool,
+    /// Whether this material's pipeline converts fragment alpha into MSAA sample coverage.
+    pub alpha_to_coverage: bool,
+    /// The band of normalized device depth this material's instances are drawn into.
+    pub depth_range: Range<f32>,
+    /// Whether this material's blended fragments use order-independent transparency.
+    pub order_independent_transparency: bool,
+    /// View depth beyond which instances using this material aren't drawn,
+    /// overriding [`MaxDrawDistance`](crate::prelude::MaxDrawDistance).
+    pub max_draw_distance: Option<f32>,
+    /// Whether this material's instances are drawn into shadow maps.
+    pub cast_shadows: bool,
+}
+
+impl MaterialProperties {
+    /// The [`GpuAlphaMode`] this material is batched and queued with
+    pub fn gpu_alpha_mode(&self) -> GpuAlphaMode {
+        match GpuAlphaMode::from(self.alpha_mode) {
+            GpuAlphaMode::Blend if self.order_independent_transparency => {
+                GpuAlphaMode::OrderIndependent
+            }
+            alpha_mode => alpha_mode,
+        }
+    }
+
+    /// Key-friendly equivalent of `depth_range`
+    pub fn depth_range_key(&self) -> [FloatOrd; 2] {
+        [
+            FloatOrd(self.depth_range.start),
+            FloatOrd(self.depth_range.end),
+        ]
+    }
 }
 
 /// Data prepared for a [`Material`] instance.
@@ -833,6 +1359,48 @@ fn prepare_material<M: MaterialInstanced>(
         properties: MaterialProperties {
             alpha_mode: material.alpha_mode(),
             depth_bias: material.depth_bias(),
+            depth_compare: material.depth_compare(),
+            write_depth: material.write_depth(),
+            alpha_to_coverage: material.alpha_to_coverage(),
+            depth_range: material.depth_range(),
+            order_independent_transparency: material.order_independent_transparency(),
+            max_draw_distance: material.max_draw_distance(),
+            cast_shadows: material.cast_shadows(),
         },
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+
+    use crate::prelude::{
+        uniform_chunk_count, ColorMeshInstance, CustomMaterial, GpuInstances, InstanceUniformLength,
+    };
+
+    #[test]
+    fn uniform_chunk_count_rounds_up() {
+        let chunk_length = ColorMeshInstance::UNIFORM_BUFFER_LENGTH.get() as usize;
+
+        assert_eq!(uniform_chunk_count::<CustomMaterial>(0), 0);
+        assert_eq!(uniform_chunk_count::<CustomMaterial>(1), 1);
+        assert_eq!(uniform_chunk_count::<CustomMaterial>(chunk_length), 1);
+        assert_eq!(uniform_chunk_count::<CustomMaterial>(chunk_length + 1), 2);
+        assert_eq!(uniform_chunk_count::<CustomMaterial>(chunk_length * 2), 2);
+    }
+
+    #[test]
+    fn uniform_instances_len_excludes_padding() {
+        let mut instances = GpuInstances::<CustomMaterial>::uniform();
+        instances.set(vec![default(); 3]);
+
+        assert_eq!(instances.len(), 3);
+        assert_eq!(
+            instances.capacity(),
+            ColorMeshInstance::UNIFORM_BUFFER_LENGTH.get() as usize
+        );
+
+        instances.compact(|_| false);
+        assert!(instances.is_empty());
+    }
+}
@@ -7,11 +7,14 @@ use bevy::{
             SystemParamItem,
         },
     },
-    prelude::debug,
-    render::render_phase::{EntityRenderCommand, RenderCommandResult, TrackedRenderPass},
+    prelude::{debug, default, Resource},
+    render::{
+        render_phase::{EntityRenderCommand, RenderCommandResult, TrackedRenderPass},
+        render_resource::BindGroup,
+    },
 };
 
-use crate::prelude::MaterialInstanced;
+use crate::prelude::{InstancedMaterialPipeline, MaterialInstanced};
 
 use std::marker::PhantomData;
 
@@ -41,3 +44,77 @@ impl<M: MaterialInstanced, const I: usize> EntityRenderCommand
         RenderCommandResult::Success
     }
 }
+
+/// Maximum number of extra bind groups a [`MaterialInstanced`] may declare.
+///
+/// The default wgpu limits only allow 4 bind groups per pipeline,
+/// so using more than one requires raising `WgpuSettings::limits.max_bind_groups`.
+pub const MAX_EXTRA_BIND_GROUPS: usize = 2;
+
+/// Bind groups matching [`MaterialInstanced::extra_bind_group_layouts`],
+/// created against [`InstancedMaterialPipeline::extra_layouts`](crate::prelude::InstancedMaterialPipeline)
+/// and inserted into the render world before [`RenderStage::Render`](bevy::render::RenderStage::Render).
+#[derive(Resource)]
+pub struct InstancedMaterialExtraBindGroups<M: MaterialInstanced> {
+    pub bind_groups: Vec<BindGroup>,
+    _phantom: PhantomData<M>,
+}
+
+impl<M: MaterialInstanced> Default for InstancedMaterialExtraBindGroups<M> {
+    fn default() -> Self {
+        Self {
+            bind_groups: default(),
+            _phantom: default(),
+        }
+    }
+}
+
+impl<M: MaterialInstanced> InstancedMaterialExtraBindGroups<M> {
+    pub fn new(bind_groups: Vec<BindGroup>) -> Self {
+        Self {
+            bind_groups,
+            _phantom: default(),
+        }
+    }
+}
+
+/// Binds [`InstancedMaterialExtraBindGroups`] in order, starting at index `I`
+pub struct SetInstancedMaterialExtraBindGroups<M: MaterialInstanced, const I: usize>(
+    PhantomData<M>,
+);
+
+impl<M: MaterialInstanced, const I: usize> EntityRenderCommand
+    for SetInstancedMaterialExtraBindGroups<M, I>
+{
+    type Param = (
+        SRes<InstancedMaterialPipeline<M>>,
+        SRes<InstancedMaterialExtraBindGroups<M>>,
+    );
+    fn render<'w>(
+        _view: Entity,
+        _item: Entity,
+        (pipeline, extra_bind_groups): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        debug!(
+            "SetInstancedMaterialExtraBindGroups<{}, {}>",
+            std::any::type_name::<M>(),
+            I
+        );
+
+        let bind_groups = &extra_bind_groups.into_inner().bind_groups;
+        if bind_groups.len() < pipeline.extra_layouts.len() {
+            return RenderCommandResult::Failure;
+        }
+
+        for (i, bind_group) in bind_groups
+            .iter()
+            .take(pipeline.extra_layouts.len())
+            .enumerate()
+        {
+            pass.set_bind_group(I + i, bind_group, &[]);
+        }
+
+        RenderCommandResult::Success
+    }
+}
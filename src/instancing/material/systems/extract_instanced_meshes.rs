@@ -10,6 +10,10 @@ use crate::instancing::material::plugin::{
     GpuInstancedMesh, RenderMeshes, InstancedMeshKey, GpuIndexBufferData,
 };
 
+/// Copies created and modified meshes into [`RenderMeshes`]
+///
+/// Mesh data is read from the main world's [`Assets<Mesh>`],
+/// so a mesh removed from it is also removed from the render world.
 pub fn system(
     mut events: Extract<EventReader<AssetEvent<Mesh>>>,
     mut render_meshes: ResMut<RenderMeshes>,
@@ -42,6 +46,7 @@ pub fn system(
                 |indices| -> GpuIndexBufferData {
                     GpuIndexBufferData::Indexed {
                         indices: indices.clone(),
+                        index_count: indices.len() as u32,
                         index_format: mesh.indices().unwrap().into(),
                     }
                 },
@@ -69,6 +74,7 @@ pub fn system(
                     index_buffer_data,
                     primitive_topology: mesh.primitive_topology(),
                     layout: mesh_vertex_buffer_layout,
+                    aabb: mesh.compute_aabb(),
                 },
             ))
         }
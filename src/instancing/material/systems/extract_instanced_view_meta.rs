@@ -1,12 +1,16 @@
 use bevy::{
-    prelude::{debug, Commands, Entity, Query},
+    prelude::{debug, Commands, Entity, Query, With},
     render::{view::VisibleEntities, Extract},
 };
 
-use crate::instancing::material::{material_instanced::MaterialInstanced, plugin::InstanceMeta};
+use crate::instancing::{
+    material::{material_instanced::MaterialInstanced, plugin::InstanceMeta},
+    shadow_tile::ShadowTile,
+};
 
 pub fn system<M: MaterialInstanced>(
     query_views: Extract<Query<(Entity, &VisibleEntities)>>,
+    query_shadow_tiles: Extract<Query<Entity, With<ShadowTile>>>,
     mut commands: Commands,
 ) {
     debug!("{}", std::any::type_name::<M>());
@@ -17,4 +21,9 @@ pub fn system<M: MaterialInstanced>(
 
         commands.insert_or_spawn_batch([(view_entity, (InstanceMeta::<M>::default(),))])
     }
+
+    // Shadow tiles only gain their visible entities once extracted
+    for view_entity in query_shadow_tiles.iter() {
+        commands.insert_or_spawn_batch([(view_entity, (InstanceMeta::<M>::default(),))])
+    }
 }
@@ -2,9 +2,12 @@ pub mod extract_instanced_meshes;
 pub mod extract_instanced_view_meta;
 pub mod prepare_batched_instances;
 pub mod prepare_instance_batches;
+pub mod prepare_instance_indices;
 pub mod prepare_material_batches;
 pub mod prepare_mesh_batches;
 pub mod prepare_view_instance_slices;
 pub mod prepare_view_instances;
 pub mod queue_instanced_materials;
+pub mod queue_instanced_shadows;
 pub mod prepare_instance_slice_targets;
+pub mod warn_unmatched_instances;
@@ -1,12 +1,16 @@
-use std::{collections::BTreeMap, num::NonZeroU64};
+use std::{
+    collections::BTreeMap,
+    num::NonZeroU64,
+    ops::{Deref, DerefMut},
+};
 
 use bevy::{
     prelude::{
-        debug, default, info, info_span, Deref, DerefMut, Entity, Handle, Mesh, Query, Res, ResMut,
+        debug, default, info, info_span, warn, Entity, Handle, Local, Mesh, Query, Res, ResMut,
         Resource, With,
     },
     render::{
-        render_resource::{BufferVec, ShaderSize},
+        render_resource::{BufferVec, ShaderSize, UniformBuffer},
         renderer::{RenderDevice, RenderQueue},
         view::{ExtractedView, VisibleEntities},
     },
@@ -17,30 +21,53 @@ use bevy::render::render_resource::{
 };
 
 use crate::instancing::{
+    clip_plane::ViewClipPlanes,
+    eviction::{InstanceEvictionSettings, ViewIdleFrames},
     indirect::{DrawCall, DrawOffsets, IndirectDraw},
     instance_slice::InstanceSlice,
     material::{
         instanced_material_pipeline::InstancedMaterialPipeline,
         material_instanced::MaterialInstanced,
         plugin::{
-            BatchedInstances, GpuIndexBufferData, GpuIndirectBufferData, GpuInstances,
-            InstanceBatchKey, InstanceMeta, RenderMeshes,
+            BatchedDraws, BatchedInstances, GpuIndexBufferData, GpuIndirectBufferData,
+            GpuInstances, InstanceBatchKey, InstanceMeta, RenderMeshes, SingleInstanceSettings,
+            UniformSplitSettings,
         },
     },
-    render::instance::{Instance, InstanceUniformLength},
+    mesh_instance::ExtractedInstances,
+    render::instance::{expanded_len, Instance, InstanceUniformLength},
 };
 
 use super::{prepare_instance_batches::ViewInstanceData, prepare_mesh_batches::MeshBatches};
 
-#[derive(Deref, DerefMut, Resource)]
+#[derive(Resource)]
 pub struct ViewIndirectData<M: MaterialInstanced> {
     pub indirect_data: BTreeMap<Entity, BTreeMap<InstanceBatchKey<M>, Vec<BufferVec<u8>>>>,
+    /// Total instance count of each batch, as exposed to its shaders
+    pub instance_counts: BTreeMap<Entity, BTreeMap<InstanceBatchKey<M>, UniformBuffer<u32>>>,
+    pub idle_frames: ViewIdleFrames,
+}
+
+impl<M: MaterialInstanced> Deref for ViewIndirectData<M> {
+    type Target = BTreeMap<Entity, BTreeMap<InstanceBatchKey<M>, Vec<BufferVec<u8>>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.indirect_data
+    }
+}
+
+impl<M: MaterialInstanced> DerefMut for ViewIndirectData<M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.indirect_data
+    }
 }
 
 impl<M: MaterialInstanced> Default for ViewIndirectData<M> {
     fn default() -> Self {
         Self {
             indirect_data: default(),
+            instance_counts: default(),
+            idle_frames: default(),
         }
     }
 }
@@ -53,18 +80,18 @@ pub fn system<M: MaterialInstanced>(
     render_queue: Res<RenderQueue>,
     mesh_batches: Res<MeshBatches>,
     view_instance_data: Res<ViewInstanceData<M>>,
+    view_clip_planes: Res<ViewClipPlanes>,
+    uniform_split_settings: Res<UniformSplitSettings>,
+    single_instance_settings: Res<SingleInstanceSettings>,
     mut view_indirect_data: ResMut<ViewIndirectData<M>>,
-    query_instance: Query<(
-        Entity,
-        &Handle<M>,
-        &Handle<Mesh>,
-        &<M::Instance as Instance>::ExtractedInstance,
-    )>,
+    extracted_instances: Res<ExtractedInstances<M>>,
+    query_instance: Query<(Entity, &Handle<M>, &Handle<Mesh>)>,
     query_instance_slice: Query<(Entity, &Handle<M>, &Handle<Mesh>, &InstanceSlice)>,
     mut query_instance_meta: Query<
         (Entity, &mut InstanceMeta<M>),
         (With<ExtractedView>, With<VisibleEntities>),
     >,
+    mut warned_uniform_split: Local<bool>,
 ) {
     debug!("{}", std::any::type_name::<M>());
 
@@ -80,7 +107,20 @@ pub fn system<M: MaterialInstanced>(
                 continue;
             };
 
-        let view_indirect_data = view_indirect_data.entry(view_entity).or_default();
+        let clip_plane = if let Some(clip_plane) = view_clip_planes.buffers.get(&view_entity) {
+            clip_plane.binding().unwrap()
+        } else {
+            continue;
+        };
+
+        let ViewIndirectData {
+            indirect_data,
+            instance_counts,
+            ..
+        } = &mut *view_indirect_data;
+        let view_indirect_data = indirect_data.entry(view_entity).or_default();
+        let view_instance_counts = instance_counts.entry(view_entity).or_default();
+        view_instance_counts.retain(|key, _| instance_meta.instance_batches.contains_key(key));
 
         // Process batches
         for key in instance_meta
@@ -112,45 +152,50 @@ pub fn system<M: MaterialInstanced>(
 
                 let instance_batch = instance_meta.instance_batches.get(&key).unwrap();
 
-                for mesh in query_instance.iter().filter_map(|(entity, _, mesh, _)| {
+                for (mesh, instance) in query_instance.iter().filter_map(|(entity, _, mesh)| {
                     if instance_batch.instances.contains(&entity) {
-                        Some(mesh)
+                        extracted_instances
+                            .get(&entity)
+                            .map(|extracted| (mesh, &extracted.instance))
                     } else {
                         None
                     }
                 }) {
-                    *mesh_instance_counts.get_mut(mesh).unwrap() += 1;
-                }
-
-                for (mesh, instance_slice) in
-                    query_instance_slice
-                        .iter()
-                        .filter_map(|(entity, _, mesh, instance_slice)| {
-                            if instance_batch.instance_slice_ranges.contains_key(&entity) {
-                                Some((mesh, instance_slice))
-                            } else {
-                                None
-                            }
-                        })
-                {
-                    *mesh_instance_counts.get_mut(mesh).unwrap() += instance_slice.instance_count;
+                    *mesh_instance_counts.get_mut(mesh).unwrap() +=
+                        expanded_len::<M::Instance>(instance);
                 }
 
                 debug!("Mesh instance counts: {mesh_instance_counts:?}");
                 mesh_instance_counts
             });
 
-            // Calculate instance offsets for indirect data
-            let (mesh_instance_offsets, _) = info_span!("Mesh instance offsets").in_scope(|| {
-                mesh_instance_counts.iter().fold(
-                    (BTreeMap::<&Handle<Mesh>, usize>::new(), 0),
-                    |(mut offsets, mut offset), (mesh, count)| {
-                        offsets.insert(mesh, offset);
-                        offset += count;
-                        (offsets, offset)
-                    },
-                )
-            });
+            // Collect instance slice ranges, which are drawn individually
+            // since the slice region may contain unused ranges
+            let instance_batch = instance_meta.instance_batches.get(&key).unwrap();
+            let instance_slice_len = instance_batch.instance_slice_len() as usize;
+            let mesh_instance_slices = query_instance_slice
+                .iter()
+                .filter_map(|(entity, _, mesh, _)| {
+                    instance_batch
+                        .instance_slice_ranges
+                        .get(&entity)
+                        .map(|range| (mesh, *range))
+                })
+                .collect::<Vec<_>>();
+
+            // Calculate instance offsets for indirect data,
+            // with CPU instances following the slice region
+            let (mesh_instance_offsets, instance_count) = info_span!("Mesh instance offsets")
+                .in_scope(|| {
+                    mesh_instance_counts.iter().fold(
+                        (BTreeMap::<&Handle<Mesh>, usize>::new(), instance_slice_len),
+                        |(mut offsets, mut offset), (mesh, count)| {
+                            offsets.insert(mesh, offset);
+                            offset += count;
+                            (offsets, offset)
+                        },
+                    )
+                });
 
             // Calculate vertex offsets for indirect data
             let (mesh_vertex_offsets, _) = info_span!("Mesh vertex offsets").in_scope(|| {
@@ -162,7 +207,9 @@ pub fn system<M: MaterialInstanced>(
                         let gpu_mesh = render_meshes.get(mesh).unwrap();
 
                         offset += match &gpu_mesh.index_buffer_data {
-                            GpuIndexBufferData::Indexed { indices, .. } => indices.len(),
+                            GpuIndexBufferData::Indexed { index_count, .. } => {
+                                *index_count as usize
+                            }
                             GpuIndexBufferData::NonIndexed { vertex_count } => {
                                 *vertex_count as usize
                             }
@@ -176,40 +223,91 @@ pub fn system<M: MaterialInstanced>(
             // Create bind group
             let instance_buffer_data = view_instance_data.get(&key).unwrap();
 
-            // Build indirect buffer
-            let indirect_buffers = view_indirect_data.entry(key.clone()).or_default();
+            let mesh_indirect = mesh_batch
+                .meshes
+                .iter()
+                .zip(mesh_batch.indirect_data.iter())
+                .collect::<BTreeMap<_, _>>();
+
+            let draw = |mut indirect: IndirectDraw,
+                        instance_count: usize,
+                        draw_offset: usize,
+                        instance_offset: usize| {
+                if instance_count > 0 {
+                    indirect.set_instance_count(instance_count as u32);
+                    indirect.set_offsets(match indirect {
+                        IndirectDraw::Indexed(_) => DrawOffsets::Indexed {
+                            base_index: draw_offset as u32,
+                            vertex_offset: 0,
+                        },
+                        IndirectDraw::NonIndexed(_) => DrawOffsets::NonIndexed {
+                            base_vertex: draw_offset as u32,
+                        },
+                    });
+                    indirect.set_base_instance(instance_offset as u32);
+                    Some(indirect)
+                } else {
+                    None
+                }
+            };
 
-            let mut indirect_buffer_data = info_span!("Create indirect buffer").in_scope(|| {
-                let indirect_data = mesh_batch
-                    .indirect_data
+            // Batches holding a single CPU instance are drawn directly,
+            // skipping the construction and upload of an indirect buffer.
+            // The instance itself stays in the batch's instance buffer, since the
+            // material's bind group layout is fixed to storage or uniform instances,
+            // and culling zeroes its transform in place rather than compacting it
+            // into a draw count, so a culled direct draw collapses to a degenerate point
+            let single_instance = if single_instance_settings.direct_draw
+                && instance_slice_len == 0
+                && instance_count == 1
+            {
+                mesh_instance_counts
                     .iter()
-                    .zip(
-                        mesh_instance_counts.values().zip(
-                            mesh_vertex_offsets
-                                .values()
-                                .zip(mesh_instance_offsets.values()),
-                        ),
-                    )
-                    .flat_map(
-                        |(mut indirect, (instance_count, (draw_offset, instance_offset)))| {
-                            if *instance_count > 0 {
-                                indirect.set_instance_count(*instance_count as u32);
-                                indirect.set_offsets(match indirect {
-                                    IndirectDraw::Indexed(_) => DrawOffsets::Indexed {
-                                        base_index: *draw_offset as u32,
-                                        vertex_offset: 0,
-                                    },
-                                    IndirectDraw::NonIndexed(_) => DrawOffsets::NonIndexed {
-                                        base_vertex: *draw_offset as u32,
-                                    },
-                                });
-                                indirect.set_base_instance(*instance_offset as u32);
-                                Some(indirect)
-                            } else {
-                                None
-                            }
-                        },
+                    .find(|(_, count)| **count == 1)
+                    .and_then(|(mesh, count)| {
+                        draw(
+                            mesh_indirect[mesh],
+                            *count,
+                            mesh_vertex_offsets[mesh],
+                            mesh_instance_offsets[mesh],
+                        )
+                    })
+            } else {
+                None
+            };
+
+            let mut batched_draws = if let Some(single_instance) = single_instance {
+                debug!("Drawing single instance directly");
+                view_indirect_data.remove(&key);
+                vec![BatchedDraws::Direct(single_instance)]
+            } else {
+                // Build indirect buffer
+                let indirect_buffers = view_indirect_data.entry(key.clone()).or_default();
+
+                info_span!("Create indirect buffer").in_scope(|| {
+                let slice_indirect_data = mesh_instance_slices.iter().flat_map(|(mesh, range)| {
+                    draw(
+                        mesh_indirect[mesh],
+                        range.instance_count as usize,
+                        mesh_vertex_offsets[mesh],
+                        range.offset as usize,
                     )
+                });
+
+                let instance_indirect_data =
+                    mesh_instance_counts
+                        .iter()
+                        .flat_map(|(mesh, instance_count)| {
+                            draw(
+                                mesh_indirect[mesh],
+                                *instance_count,
+                                mesh_vertex_offsets[mesh],
+                                mesh_instance_offsets[mesh],
+                            )
+                        });
+
+                let indirect_data = slice_indirect_data
+                    .chain(instance_indirect_data)
                     .collect::<Vec<_>>();
 
                 debug!("Indirect data: {indirect_data:#?}");
@@ -264,25 +362,49 @@ pub fn system<M: MaterialInstanced>(
 
                         if indirect.instance_count() > 0 {
                             indirect.set_base_instance(offset as u32);
-                            offset = indirect.instance_count() as isize;
+                            offset += indirect.instance_count() as isize;
                             debug!("Remainder indirect:\n{indirect:#?}");
                             current_split.push(indirect);
                         }
                     }
+
+                    // Every chunk costs a draw and a bind group, which adds up quickly on WebGL
+                    if split_data.len() > uniform_split_settings.warn_threshold
+                        && !*warned_uniform_split
+                    {
+                        *warned_uniform_split = true;
+                        warn!(
+                            "Instance batch of {} split into {} uniform buffer chunks of {total} instances, \
+                            each drawn separately. Consider reducing its instance count, \
+                            or enabling storage buffers if the platform supports them.",
+                            std::any::type_name::<M>(),
+                            split_data.len(),
+                        );
+                    }
                 } else {
                     split_data.push(indirect_data);
                 }
 
                 debug!("Split data: {split_data:#?}");
 
+                // Storage-backed indirect buffers can have their instance counts
+                // written by culling compaction
+                let indirect_usage = match instance_buffer_data {
+                    GpuInstances::Storage { .. } => BufferUsages::INDIRECT
+                        | BufferUsages::STORAGE
+                        | BufferUsages::COPY_DST
+                        | BufferUsages::COPY_SRC,
+                    GpuInstances::Uniform { .. } => BufferUsages::INDIRECT
+                        | BufferUsages::COPY_DST
+                        | BufferUsages::COPY_SRC,
+                };
+
                 split_data
                     .into_iter()
                     .enumerate()
                     .map(|(i, data)| {
                         if indirect_buffers.len() < i + 1 {
-                            indirect_buffers.push(BufferVec::new(
-                                BufferUsages::INDIRECT | BufferUsages::COPY_DST,
-                            ));
+                            indirect_buffers.push(BufferVec::new(indirect_usage));
                         }
 
                         let indirect_buffer = &mut indirect_buffers[i];
@@ -303,22 +425,27 @@ pub fn system<M: MaterialInstanced>(
 
                         indirect_buffer.write_buffer(&render_device, &render_queue);
 
-                        GpuIndirectBufferData {
+                        BatchedDraws::Indirect(GpuIndirectBufferData {
                             indirects: data,
                             buffer: indirect_buffer.buffer().unwrap().clone(),
-                        }
+                        })
                     })
                     .collect::<Vec<_>>()
-            });
+                })
+            };
+
+            // Expose the batch's total instance count to its shaders
+            let instance_count_buffer = view_instance_counts.entry(key.clone()).or_default();
+            instance_count_buffer.set(instance_count as u32);
+            instance_count_buffer.write_buffer(&render_device, &render_queue);
+            let instance_count = instance_count_buffer.binding().unwrap();
 
             let mut batches = vec![];
 
             match instance_buffer_data {
-                GpuInstances::Uniform { buffers } => {
+                GpuInstances::Uniform { buffers, .. } => {
                     info!("Buffers: {}", buffers.len());
-                    for (i, (buffer, indirect)) in
-                        buffers.into_iter().zip(indirect_buffer_data).enumerate()
-                    {
+                    for (i, (buffer, draws)) in buffers.into_iter().zip(batched_draws).enumerate() {
                         info!("BatchedInstances {i:}");
                         let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
                             label: Some("instance bind group"),
@@ -334,13 +461,19 @@ pub fn system<M: MaterialInstanced>(
                                         NonZeroU64::new(<M::Instance as InstanceUniformLength>::UNIFORM_BUFFER_LENGTH.get() * <M::Instance as Instance>::PreparedInstance::SHADER_SIZE.get()).unwrap(),
                                     ),
                                 }),
+                            }, BindGroupEntry {
+                                binding: 1,
+                                resource: clip_plane.clone(),
+                            }, BindGroupEntry {
+                                binding: 2,
+                                resource: instance_count.clone(),
                             }],
                         });
 
                         batches.push(BatchedInstances {
                             vertex_buffer: vertex_buffer.clone(),
                             index_buffer: index_buffer.clone(),
-                            indirect_buffer: indirect,
+                            draws,
                             bind_group,
                         });
                     }
@@ -351,16 +484,26 @@ pub fn system<M: MaterialInstanced>(
                         layout: &instanced_material_pipeline
                             .instanced_mesh_pipeline
                             .bind_group_layout,
-                        entries: &[BindGroupEntry {
-                            binding: 0,
-                            resource: buffer.binding().unwrap(),
-                        }],
+                        entries: &[
+                            BindGroupEntry {
+                                binding: 0,
+                                resource: buffer.binding().unwrap(),
+                            },
+                            BindGroupEntry {
+                                binding: 1,
+                                resource: clip_plane.clone(),
+                            },
+                            BindGroupEntry {
+                                binding: 2,
+                                resource: instance_count,
+                            },
+                        ],
                     });
 
                     batches.push(BatchedInstances {
                         vertex_buffer,
                         index_buffer,
-                        indirect_buffer: indirect_buffer_data.remove(0),
+                        draws: batched_draws.remove(0),
                         bind_group,
                     });
                 }
@@ -374,20 +517,24 @@ pub fn system<M: MaterialInstanced>(
 }
 
 pub fn prune_indirect_data<M: MaterialInstanced>(
+    eviction_settings: Res<InstanceEvictionSettings>,
     mut view_indirect_data: ResMut<ViewIndirectData<M>>,
     query_instance_meta: Query<
         (Entity, &mut InstanceMeta<M>),
         (With<ExtractedView>, With<VisibleEntities>),
     >,
 ) {
-    // Prune indirect data for views with no batches
-    for entity in view_indirect_data.keys().cloned().collect::<Vec<_>>() {
-        if !query_instance_meta
-            .iter()
-            .any(|(view_entity, _)| view_entity == entity)
-        {
-            info!("View {entity:?} has no instance meta, pruning indirect data");
-            view_indirect_data.remove(&entity);
-        }
+    // Prune indirect data for views that have been inactive for too long
+    let views = view_indirect_data.keys().cloned().collect::<Vec<_>>();
+    let evicted = view_indirect_data.idle_frames.evict(
+        views,
+        |entity| query_instance_meta.contains(entity),
+        eviction_settings.idle_frames,
+    );
+
+    for entity in evicted {
+        info!("View {entity:?} has been inactive, pruning indirect data");
+        view_indirect_data.remove(&entity);
+        view_indirect_data.instance_counts.remove(&entity);
     }
 }
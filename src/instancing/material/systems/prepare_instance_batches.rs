@@ -1,11 +1,13 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::{Deref, DerefMut},
+};
 
 use bevy::{
-    prelude::{
-        debug, default, info, Deref, DerefMut, Entity, Handle, Mesh, Query, Res, ResMut, Resource,
-        With,
-    },
+    math::{Vec3, Vec3A, Vec4},
+    prelude::{debug, default, info, Entity, Handle, Mesh, Query, Res, ResMut, Resource, With},
     render::{
+        primitives::{Frustum, Sphere},
         renderer::{RenderDevice, RenderQueue},
         view::{ExtractedView, VisibleEntities},
     },
@@ -13,59 +15,144 @@ use bevy::{
 };
 
 use crate::instancing::{
-    instance_slice::{InstanceSlice, InstanceSliceRange},
+    draw_distance::{DrawDistanceFade, InstanceFade, MaxDrawDistance},
+    eviction::{InstanceEvictionSettings, ViewIdleFrames},
+    instance_slice::{InstanceSlice, InstanceSliceAllocator},
     material::{
         material_instanced::MaterialInstanced,
         plugin::{
             GpuAlphaMode, GpuInstances, InstanceBatch, InstanceBatchKey, InstanceMeta,
-            InstancedMaterialBatchKey, RenderMaterials, RenderMeshes,
+            InstancedMaterialBatchKey, InstancedMeshKey, RenderMaterials, RenderMeshes,
         },
         systems::prepare_mesh_batches::MeshBatch,
     },
-    render::instance::Instance,
+    mesh_instance::{
+        ExtractedInstances, ExtractedMeshInstance, ForceBatchGroup, NoInstanceCulling, ShadowOnly,
+    },
+    render::instance::{expanded_len, Instance},
+    shadow_tile::ShadowTile,
+    sort::{depth_key, radix_sort_by_key, AlwaysOnTop, InstanceLayer, InstanceSortSettings},
 };
 
 use super::prepare_mesh_batches::MeshBatches;
 
-#[derive(Deref, DerefMut, Resource)]
+#[derive(Resource)]
 pub struct ViewInstanceData<M: MaterialInstanced> {
     pub instance_data: BTreeMap<Entity, BTreeMap<InstanceBatchKey<M>, GpuInstances<M>>>,
+    /// Persistent instance slice allocations for each view and batch
+    pub slice_allocators: BTreeMap<Entity, BTreeMap<InstanceBatchKey<M>, InstanceSliceAllocator>>,
+    pub idle_frames: ViewIdleFrames,
+}
+
+impl<M: MaterialInstanced> Deref for ViewInstanceData<M> {
+    type Target = BTreeMap<Entity, BTreeMap<InstanceBatchKey<M>, GpuInstances<M>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.instance_data
+    }
+}
+
+impl<M: MaterialInstanced> DerefMut for ViewInstanceData<M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.instance_data
+    }
 }
 
 impl<M: MaterialInstanced> Default for ViewInstanceData<M> {
     fn default() -> Self {
         Self {
             instance_data: default(),
+            slice_allocators: default(),
+            idle_frames: default(),
         }
     }
 }
 
+/// Key batching an instance or instance slice with those it can share draws with
+fn instance_batch_key<M: MaterialInstanced>(
+    mesh_key: InstancedMeshKey,
+    material_key: InstancedMaterialBatchKey<M>,
+    layer: Option<&InstanceLayer>,
+    group: Option<&ForceBatchGroup>,
+    mirrored: bool,
+    always_on_top: bool,
+    shadow_only: bool,
+) -> InstanceBatchKey<M> {
+    InstanceBatchKey {
+        layer: layer.copied().unwrap_or_default().0,
+        mesh_key,
+        material_key,
+        mirrored,
+        group: group.copied().unwrap_or_default().0,
+        always_on_top,
+        shadow_only,
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn system<M: MaterialInstanced>(
     render_device: Res<RenderDevice>,
     render_queue: Res<RenderQueue>,
     render_meshes: Res<RenderMeshes>,
     render_materials: Res<RenderMaterials<M>>,
     mesh_batches: Res<MeshBatches>,
+    sort_settings: Res<InstanceSortSettings>,
+    max_draw_distance: Res<MaxDrawDistance>,
+    draw_distance_fade: Res<DrawDistanceFade>,
+    extracted_instances: Res<ExtractedInstances<M>>,
     mut view_instance_data: ResMut<ViewInstanceData<M>>,
-    mut query_views: Query<(Entity, &ExtractedView, &mut InstanceMeta<M>), With<VisibleEntities>>,
+    mut query_views: Query<
+        (
+            Entity,
+            &ExtractedView,
+            Option<&ShadowTile>,
+            &mut InstanceMeta<M>,
+        ),
+        With<VisibleEntities>,
+    >,
     query_instance: Query<(
         Entity,
         &Handle<M>,
         &Handle<Mesh>,
-        &<M::Instance as Instance>::ExtractedInstance,
+        Option<&InstanceLayer>,
+        Option<&ForceBatchGroup>,
+        Option<&AlwaysOnTop>,
+        Option<&ShadowOnly>,
+        Option<&NoInstanceCulling>,
+        Option<&InstanceFade>,
+    )>,
+    query_instance_slice: Query<(
+        Entity,
+        &Handle<M>,
+        &Handle<Mesh>,
+        &InstanceSlice,
+        Option<&InstanceLayer>,
+        Option<&ForceBatchGroup>,
+        Option<&AlwaysOnTop>,
+        Option<&ShadowOnly>,
     )>,
-    query_instance_slice: Query<(Entity, &Handle<M>, &Handle<Mesh>, &InstanceSlice)>,
 ) {
     debug!("{}", std::any::type_name::<M>());
 
     let render_meshes = &render_meshes.instanced_meshes;
 
-    for (view_entity, view, mut instance_meta) in query_views.iter_mut() {
+    for (view_entity, view, shadow_tile, mut instance_meta) in query_views.iter_mut() {
         debug!("View {view_entity:?}");
 
-        // Fetch view rangefinder for sorting
+        // Fetch view rangefinder for sorting.
+        // It measures view-space Z from the view transform alone,
+        // so ordering stays sane for custom projections such as oblique near planes
         let rangefinder = view.rangefinder3d();
 
+        // Far plane excluded, since draw distance is limited separately
+        let view_transform = view.transform.compute_matrix();
+        let frustum = Frustum::from_view_projection(
+            &(view.projection * view_transform.inverse()),
+            &view.transform.translation(),
+            &view.transform.back(),
+            f32::MAX,
+        );
+
         let span = bevy::prelude::info_span!("Batch instances by key");
         let mut keyed_instances = span.in_scope(|| {
             // Batch instances by key
@@ -77,17 +164,37 @@ pub fn system<M: MaterialInstanced>(
                         Entity,
                         &Handle<M>,
                         &<M::Instance as Instance>::ExtractedInstance,
+                        f32,
+                        Vec4,
+                        f32,
                     ),
                 )>,
             >::new();
 
-            for (entity, material_handle, mesh_handle, instance) in instance_meta
+            for (
+                entity,
+                material_handle,
+                mesh_handle,
+                layer,
+                group,
+                on_top,
+                shadow_only,
+                no_culling,
+                instance_fade,
+            ) in instance_meta
                 .instances
                 .iter()
                 .flat_map(|entity| query_instance.get(*entity))
             {
                 debug!("Instance {entity:?}");
 
+                let ExtractedMeshInstance { instance, aabb } =
+                    if let Some(extracted) = extracted_instances.get(&entity) {
+                        extracted
+                    } else {
+                        continue;
+                    };
+
                 let mesh = if let Some(mesh) = render_meshes.get(mesh_handle) {
                     mesh
                 } else {
@@ -98,25 +205,83 @@ pub fn system<M: MaterialInstanced>(
 
                 let mesh_key = mesh.key.clone();
 
+                // Bounding sphere for GPU culling, preferring the instance's main-world Aabb
+                let bounds = match aabb.as_ref().or(mesh.aabb.as_ref()) {
+                    Some(aabb) if no_culling.is_none() => {
+                        Vec3::from(aabb.center).extend(aabb.half_extents.length())
+                    }
+                    // Unbounded instances are never culled
+                    _ => Vec4::new(0.0, 0.0, 0.0, f32::MAX),
+                };
+
                 let material = if let Some(material) = render_materials.get(material_handle) {
                     material
                 } else {
                     continue;
                 };
 
+                // Shadow tiles only take instances that cast shadows
+                if shadow_tile.is_some() && !material.properties.cast_shadows {
+                    continue;
+                }
+
                 debug!("Material valid");
 
-                let alpha_mode = GpuAlphaMode::from(material.properties.alpha_mode);
+                let alpha_mode = material.properties.gpu_alpha_mode();
                 let material_key = InstancedMaterialBatchKey {
                     alpha_mode,
+                    depth_compare: material.properties.depth_compare,
+                    write_depth: material.properties.write_depth,
+                    alpha_to_coverage: material.properties.alpha_to_coverage,
+                    depth_range: material.properties.depth_range_key(),
                     key: material.batch_key.clone(),
                 };
 
-                let mesh_z = rangefinder.distance(&<M::Instance as Instance>::transform(instance))
-                    + material.properties.depth_bias;
+                let transform = <M::Instance as Instance>::transform(instance);
+
+                // Cull instances whose bounding sphere, scaled by the instance's largest axis scale,
+                // lies outside the view frustum
+                if bounds.w < f32::MAX {
+                    let scale = transform
+                        .x_axis
+                        .truncate()
+                        .length()
+                        .max(transform.y_axis.truncate().length())
+                        .max(transform.z_axis.truncate().length());
+
+                    let sphere = Sphere {
+                        center: transform.transform_point3a(Vec3A::from(bounds.truncate())),
+                        radius: bounds.w * scale,
+                    };
+
+                    if !frustum.intersects_sphere(&sphere, false) {
+                        continue;
+                    }
+                }
+
+                let view_z = rangefinder.distance(&transform);
+
+                // View-space Z is negative in front of the camera
+                let max_distance = material
+                    .properties
+                    .max_draw_distance
+                    .unwrap_or(max_draw_distance.0);
+                if -view_z > max_distance {
+                    continue;
+                }
+
+                // Fade out over the last stretch of the draw distance
+                let distance_fade = if draw_distance_fade.0 > 0.0 {
+                    ((max_distance + view_z) / draw_distance_fade.0).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                let fade = distance_fade * instance_fade.map_or(1.0, |fade| fade.0.clamp(0.0, 1.0));
+
+                let mesh_z = view_z + material.properties.depth_bias;
 
                 let dist = mesh_z
-                    * if alpha_mode == GpuAlphaMode::Blend {
+                    * if alpha_mode == GpuAlphaMode::Blend || on_top.is_some() {
                         // Back-to-front ordering
                         1.0
                     } else {
@@ -124,22 +289,51 @@ pub fn system<M: MaterialInstanced>(
                         -1.0
                     };
 
-                let key = InstanceBatchKey {
+                let key = instance_batch_key(
                     mesh_key,
                     material_key,
-                };
+                    layer,
+                    group,
+                    transform.determinant() < 0.0,
+                    on_top.is_some(),
+                    shadow_only.is_some(),
+                );
 
                 keyed_instances.entry(key).or_default().push((
                     (mesh_handle, FloatOrd(dist)),
-                    (entity, material_handle, instance),
+                    (entity, material_handle, instance, fade, bounds, mesh_z),
                 ));
             }
 
             keyed_instances
         });
 
-        for instances in keyed_instances.values_mut() {
-            instances.sort_unstable_by(|(lhs_key, _), (rhs_key, _)| lhs_key.cmp(rhs_key))
+        // Instances are grouped by mesh to give each mesh a contiguous indirect draw,
+        // and ordered by depth within each mesh's run, back-to-front for blended batches.
+        // Ties are broken by entity, since visible entities arrive in no particular order
+        // and equal-depth blended instances would otherwise swap places between frames
+        for (key, instances) in keyed_instances.iter_mut() {
+            if instances.len() >= sort_settings.radix_threshold {
+                // Large batch, sort by quantized (mesh index, depth) key in linear time,
+                // after a stable pass by entity to order ties
+                let MeshBatch { meshes, .. } = mesh_batches.get(&key.mesh_key).unwrap();
+                let mesh_indices = meshes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, mesh)| (mesh, i as u64))
+                    .collect::<BTreeMap<_, _>>();
+
+                radix_sort_by_key(instances, |(_, (entity, ..))| entity.to_bits());
+                radix_sort_by_key(instances, |((mesh_handle, dist), _)| {
+                    (mesh_indices[mesh_handle] << 32) | depth_key(dist.0) as u64
+                });
+            } else {
+                instances.sort_unstable_by(
+                    |(lhs_key, (lhs_entity, ..)), (rhs_key, (rhs_entity, ..))| {
+                        lhs_key.cmp(rhs_key).then(lhs_entity.cmp(rhs_entity))
+                    },
+                )
+            }
         }
 
         debug!("Keyed instances: {:#?}", keyed_instances.values());
@@ -150,7 +344,16 @@ pub fn system<M: MaterialInstanced>(
             let mut keyed_instance_slices =
                 BTreeMap::<InstanceBatchKey<M>, Vec<(Entity, &Handle<M>, &InstanceSlice)>>::new();
 
-            for (entity, material_handle, mesh_handle, instance_slice) in instance_meta
+            for (
+                entity,
+                material_handle,
+                mesh_handle,
+                instance_slice,
+                layer,
+                group,
+                on_top,
+                shadow_only,
+            ) in instance_meta
                 .instance_slices
                 .iter()
                 .flat_map(|entity| query_instance_slice.get(*entity))
@@ -170,16 +373,30 @@ pub fn system<M: MaterialInstanced>(
                     continue;
                 };
 
-                let alpha_mode = GpuAlphaMode::from(material.properties.alpha_mode);
+                // Shadow tiles only take instances that cast shadows
+                if shadow_tile.is_some() && !material.properties.cast_shadows {
+                    continue;
+                }
+
+                let alpha_mode = material.properties.gpu_alpha_mode();
                 let material_key = InstancedMaterialBatchKey {
                     alpha_mode,
+                    depth_compare: material.properties.depth_compare,
+                    write_depth: material.properties.write_depth,
+                    alpha_to_coverage: material.properties.alpha_to_coverage,
+                    depth_range: material.properties.depth_range_key(),
                     key: material.batch_key.clone(),
                 };
 
-                let key = InstanceBatchKey {
+                let key = instance_batch_key(
                     mesh_key,
                     material_key,
-                };
+                    layer,
+                    group,
+                    false,
+                    on_top.is_some(),
+                    shadow_only.is_some(),
+                );
 
                 keyed_instance_slices.entry(key).or_default().push((
                     entity,
@@ -208,80 +425,76 @@ pub fn system<M: MaterialInstanced>(
             BTreeMap::<InstanceBatchKey<M>, Vec<<M::Instance as Instance>::PreparedInstance>>::new(
             );
 
-        let span = bevy::prelude::info_span!("Populate instances");
-        span.in_scope(|| {
-            debug!("Populating instances");
-            // Populate instances
-            for (key, instances) in keyed_instances.iter() {
-                debug!("{key:#?}");
-                // Collect instance data
-                let data = instances
-                    .iter()
-                    .map(|((mesh_handle, _), (_, _, instance))| {
-                        let MeshBatch { meshes, .. } = mesh_batches.get(&key.mesh_key).unwrap();
-
-                        <M::Instance as Instance>::prepare_instance(
-                            instance,
-                            meshes.iter().position(|mesh| mesh == *mesh_handle).unwrap() as u32,
-                        )
-                    })
-                    .collect::<Vec<_>>();
-
-                instance_buffer_data
-                    .entry(key.clone())
-                    .or_default()
-                    .extend(data);
-            }
-        });
-
         let span = bevy::prelude::info_span!("Create instance slice ranges");
         let mut keyed_instance_slice_ranges = span.in_scope(|| {
             debug!("Creating instance slice ranges");
+            let slice_allocators = view_instance_data
+                .slice_allocators
+                .entry(view_entity)
+                .or_default();
+
+            // Drop allocators for batches that no longer contain slices
+            slice_allocators.retain(|key, _| keyed_instance_slices.contains_key(key));
+
             // Create instance slice ranges
             keyed_instance_slices
                 .iter()
                 .map(|(key, instance_slices)| {
-                    let instance_buffer_data_len =
-                        instance_buffer_data.entry(key.clone()).or_default().len();
-
-                    // Collect CPU instance slice data
-                    let mut offset = instance_buffer_data_len;
-                    let mut instance_slice_ranges = BTreeMap::<Entity, InstanceSliceRange>::new();
-                    for (entity, _, instance_slice) in instance_slices {
-                        debug!("Generating InstanceSliceRange for {entity:?}");
-                        // Generate instance slice range
-                        instance_slice_ranges.insert(
-                            *entity,
-                            InstanceSliceRange {
-                                offset: offset as u64,
-                                instance_count: instance_slice.instance_count as u64,
-                            },
-                        );
-
-                        offset += instance_slice.instance_count;
-                    }
+                    let slice_allocator = slice_allocators.entry(key.clone()).or_default();
+
+                    let instance_slice_ranges = slice_allocator.allocate(
+                        instance_slices.iter().map(|(entity, _, instance_slice)| {
+                            (*entity, instance_slice.instance_count as u64)
+                        }),
+                    );
 
                     debug!("Instance slice ranges: {instance_slice_ranges:?}");
 
+                    // Slices occupy the start of the buffer,
+                    // so their offsets don't depend on the CPU instance count
+                    instance_buffer_data.insert(
+                        key.clone(),
+                        (0..slice_allocator.len()).map(|_| default()).collect(),
+                    );
+
                     (key.clone(), instance_slice_ranges)
                 })
                 .collect::<BTreeMap<_, _>>()
         });
 
-        let span = bevy::prelude::info_span!("Populate instance slices");
+        let span = bevy::prelude::info_span!("Populate instances");
         span.in_scope(|| {
-            // Populate instance slices
-            for (key, instance_slices) in keyed_instance_slices.iter() {
+            debug!("Populating instances");
+            // Populate instances
+            for (key, instances) in keyed_instances.iter() {
+                debug!("{key:#?}");
                 // Collect instance data
-                let instance_count: usize = instance_slices
+                let data = instances
                     .iter()
-                    .map(|(_, _, instance_slice)| instance_slice.instance_count)
-                    .sum();
+                    .flat_map(|((mesh_handle, _), (_, _, instance, fade, _, _))| {
+                        let MeshBatch { meshes, .. } = mesh_batches.get(&key.mesh_key).unwrap();
+                        let mesh = meshes.iter().position(|mesh| mesh == *mesh_handle).unwrap();
+
+                        (0..expanded_len::<M::Instance>(instance)).map(move |i| {
+                            let mut prepared = <M::Instance as Instance>::prepare_expanded_instance(
+                                instance,
+                                mesh as u32,
+                                i,
+                            );
+
+                            if *fade < 1.0 {
+                                <M::Instance as Instance>::fade(&mut prepared, *fade);
+                            }
+
+                            prepared
+                        })
+                    })
+                    .collect::<Vec<_>>();
 
                 instance_buffer_data
                     .entry(key.clone())
                     .or_default()
-                    .extend((0..instance_count).map(|_| default()));
+                    .extend(data);
             }
         });
 
@@ -304,24 +517,65 @@ pub fn system<M: MaterialInstanced>(
             instance_meta
                 .instance_batches
                 .extend(view_instance_data.keys().map(|key| {
-                    let instances = keyed_instances
+                    let instance_slice_ranges =
+                        keyed_instance_slice_ranges.remove(key).unwrap_or_default();
+
+                    // CPU-prepared instances follow the slice region
+                    let instance_slice_len = instance_slice_ranges
+                        .values()
+                        .map(|range| range.offset + range.instance_count)
+                        .max()
+                        .unwrap_or_default() as u32;
+
+                    let (instances, instance_indices, bounds, view_distance) = keyed_instances
                         .remove(key)
                         .map(|instances| {
-                            instances
+                            // Depth of the batch's nearest instance, for sorting against other batches.
+                            // View-space Z is negative in front of the camera, so the nearest is the greatest
+                            let view_distance = instances
+                                .iter()
+                                .map(|(_, (_, _, _, _, _, mesh_z))| *mesh_z)
+                                .fold(f32::MIN, f32::max);
+
+                            let bounds = instances
+                                .iter()
+                                .flat_map(|(_, (_, _, instance, _, bounds, _))| {
+                                    std::iter::repeat_n(
+                                        *bounds,
+                                        expanded_len::<M::Instance>(instance),
+                                    )
+                                })
+                                .collect::<Vec<_>>();
+
+                            let instance_indices = instances
+                                .iter()
+                                .scan(
+                                    instance_slice_len,
+                                    |index, (_, (entity, _, instance, _, _, _))| {
+                                        let first = *index;
+                                        *index += expanded_len::<M::Instance>(instance) as u32;
+                                        Some((*entity, first))
+                                    },
+                                )
+                                .collect::<Vec<_>>();
+
+                            let instances = instances
                                 .into_iter()
-                                .map(|((_, _), (instance, _, _))| instance)
-                                .collect::<BTreeSet<_>>()
+                                .map(|((_, _), (instance, _, _, _, _, _))| instance)
+                                .collect::<BTreeSet<_>>();
+
+                            (instances, instance_indices, bounds, view_distance)
                         })
                         .unwrap_or_default();
 
-                    let instance_slice_ranges =
-                        keyed_instance_slice_ranges.remove(&key).unwrap_or_default();
-
                     (
                         key.clone(),
                         InstanceBatch::<M> {
                             instances,
                             instance_slice_ranges,
+                            instance_indices,
+                            bounds,
+                            view_distance,
                             _phantom: default(),
                         },
                     )
@@ -331,20 +585,85 @@ pub fn system<M: MaterialInstanced>(
 }
 
 pub fn prune_instance_data<M: MaterialInstanced>(
+    eviction_settings: Res<InstanceEvictionSettings>,
     mut view_instance_data: ResMut<ViewInstanceData<M>>,
     query_instance_meta: Query<
         (Entity, &mut InstanceMeta<M>),
         (With<ExtractedView>, With<VisibleEntities>),
     >,
 ) {
-    // Prune indirect data for views with no batches
-    for entity in view_instance_data.keys().cloned().collect::<Vec<_>>() {
-        if !query_instance_meta
-            .iter()
-            .any(|(view_entity, _)| view_entity == entity)
-        {
-            info!("View {entity:?} has no instance meta, pruning instance data");
-            view_instance_data.remove(&entity);
+    // Prune instance data for views that have been inactive for too long
+    let views = view_instance_data.keys().cloned().collect::<Vec<_>>();
+    let evicted = view_instance_data.idle_frames.evict(
+        views,
+        |entity| query_instance_meta.contains(entity),
+        eviction_settings.idle_frames,
+    );
+
+    for entity in evicted {
+        info!("View {entity:?} has been inactive, pruning instance data");
+        view_instance_data.remove(&entity);
+        view_instance_data.slice_allocators.remove(&entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use bevy::{
+        prelude::{shape::Cube, Mesh},
+        render::render_resource::CompareFunction,
+        utils::FloatOrd,
+    };
+
+    use crate::prelude::{
+        BasicMaterial, ForceBatchGroup, GpuAlphaMode, InstanceBatchKey, InstancedMaterialBatchKey,
+        InstancedMeshKey,
+    };
+
+    use super::instance_batch_key;
+
+    fn batch_key(group: Option<ForceBatchGroup>) -> InstanceBatchKey<BasicMaterial> {
+        let mesh = Mesh::from(Cube::default());
+
+        instance_batch_key(
+            InstancedMeshKey {
+                primitive_topology: mesh.primitive_topology(),
+                layout: mesh.get_mesh_vertex_buffer_layout(),
+                index_format: mesh.indices().map(Into::into),
+            },
+            InstancedMaterialBatchKey {
+                alpha_mode: GpuAlphaMode::Opaque,
+                depth_compare: CompareFunction::Greater,
+                write_depth: true,
+                alpha_to_coverage: false,
+                depth_range: [FloatOrd(0.0), FloatOrd(1.0)],
+                key: (),
+            },
+            None,
+            group.as_ref(),
+            false,
+            false,
+            false,
+        )
+    }
+
+    #[test]
+    fn force_batch_groups_split_equal_instances() {
+        let mut batches = BTreeMap::<InstanceBatchKey<BasicMaterial>, Vec<u32>>::new();
+        for (instance, group) in [(0, None), (1, Some(ForceBatchGroup(1))), (2, None)] {
+            batches.entry(batch_key(group)).or_default().push(instance);
         }
+
+        assert_ne!(
+            batch_key(Some(ForceBatchGroup(1))),
+            batch_key(Some(ForceBatchGroup(2)))
+        );
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            batches.values().cloned().collect::<Vec<_>>(),
+            vec![vec![0, 2], vec![1]]
+        );
     }
 }
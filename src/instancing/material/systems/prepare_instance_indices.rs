@@ -0,0 +1,68 @@
+use bevy::{
+    prelude::{Entity, Query, Res, With},
+    render::view::{ExtractedView, VisibleEntities},
+};
+
+use crate::instancing::{
+    instance_index::{InstanceIndexSettings, PublishedInstanceIndices},
+    material::{
+        material_instanced::MaterialInstanced,
+        plugin::{GpuInstances, InstanceMeta},
+    },
+    render::instance::InstanceUniformLength,
+};
+
+use super::prepare_instance_batches::ViewInstanceData;
+
+/// Publishes the `instance_index` of each CPU-prepared instance,
+/// see [`InstanceIndices`](crate::prelude::InstanceIndices)
+#[allow(clippy::type_complexity)]
+pub fn system<M: MaterialInstanced>(
+    settings: Res<InstanceIndexSettings>,
+    published: Res<PublishedInstanceIndices>,
+    view_instance_data: Res<ViewInstanceData<M>>,
+    query_views: Query<(Entity, &InstanceMeta<M>), (With<ExtractedView>, With<VisibleEntities>)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    let chunk_length = <M::Instance as InstanceUniformLength>::UNIFORM_BUFFER_LENGTH.get() as u32;
+
+    let mut published = published.0.lock().unwrap();
+
+    for (view_entity, instance_meta) in query_views.iter() {
+        let view_instance_data =
+            if let Some(view_instance_data) = view_instance_data.get(&view_entity) {
+                view_instance_data
+            } else {
+                continue;
+            };
+
+        let indices = published.entry(view_entity).or_default();
+
+        for (key, instance_batch) in instance_meta.instance_batches.iter() {
+            // Uniform chunks are bound individually, so instance_index restarts with each
+            let uniform = matches!(
+                view_instance_data.get(key),
+                Some(GpuInstances::Uniform { .. })
+            );
+
+            indices.extend(
+                instance_batch
+                    .instance_indices
+                    .iter()
+                    .map(|(entity, index)| {
+                        (
+                            *entity,
+                            if uniform {
+                                index % chunk_length
+                            } else {
+                                *index
+                            },
+                        )
+                    }),
+            );
+        }
+    }
+}
@@ -3,21 +3,27 @@ use bevy::{
     render::view::{ExtractedView, VisibleEntities},
 };
 
+use bevy::render::render_resource::ShaderSize;
+
 use crate::instancing::{
-    instance_slice::InstanceSliceTarget,
+    instance_slice::{AllocatedInstanceSliceRanges, InstanceSliceTarget},
     material::{
         material_instanced::MaterialInstanced,
         plugin::{GpuInstances, InstanceMeta},
     },
+    render::instance::Instance,
 };
 
 use super::prepare_instance_batches::ViewInstanceData;
 
 pub fn system<M: MaterialInstanced>(
     view_instance_data: Res<ViewInstanceData<M>>,
+    allocated_ranges: Res<AllocatedInstanceSliceRanges>,
     query_views: Query<(Entity, &InstanceMeta<M>), (With<ExtractedView>, With<VisibleEntities>)>,
     mut commands: Commands,
 ) {
+    let mut allocated_ranges = allocated_ranges.0.lock().unwrap();
+
     for (view_entity, instance_meta) in query_views.iter() {
         debug!("\tView {view_entity:?}");
         let view_instance_data =
@@ -30,6 +36,14 @@ pub fn system<M: MaterialInstanced>(
         for key in instance_meta.instance_batches.keys() {
             let instance_buffer_data = view_instance_data.get(key).unwrap();
 
+            // Slices are written through storage buffers, so on devices without them
+            // they're allocated but left untargeted; `IndirectRenderingPlugin` warns about this once
+            let buffer = if let GpuInstances::Storage { buffer } = &instance_buffer_data {
+                buffer.buffer()
+            } else {
+                None
+            };
+
             for (entity, slice_range) in instance_meta
                 .instance_batches
                 .get(&key)
@@ -37,16 +51,18 @@ pub fn system<M: MaterialInstanced>(
                 .instance_slice_ranges
                 .iter()
             {
-                commands.entity(*entity).insert((
-                    *slice_range,
-                    InstanceSliceTarget {
-                        buffer: if let GpuInstances::Storage { buffer } = &instance_buffer_data {
-                            buffer.buffer().unwrap().clone()
-                        } else {
-                            panic!("InstanceSlice cannot be used with non-storage buffers")
-                        },
-                    },
-                ));
+                allocated_ranges.insert(*entity, *slice_range);
+
+                let mut slice = commands.entity(*entity);
+                slice.insert(*slice_range);
+
+                if let Some(buffer) = buffer {
+                    slice.insert(InstanceSliceTarget {
+                        buffer: buffer.clone(),
+                        instance_size: <M::Instance as Instance>::PreparedInstance::SHADER_SIZE
+                            .get(),
+                    });
+                }
             }
         }
     }
@@ -9,7 +9,7 @@ use bevy::prelude::{debug, Res, ResMut, Resource};
 
 use crate::instancing::material::{
     material_instanced::MaterialInstanced,
-    plugin::{GpuAlphaMode, InstancedMaterialBatchKey, MaterialBatch, RenderMaterials},
+    plugin::{InstancedMaterialBatchKey, MaterialBatch, RenderMaterials},
 };
 
 #[derive(Resource)]
@@ -65,22 +65,34 @@ pub fn system<M: MaterialInstanced>(
 
     debug!("{}", std::any::type_name::<M>());
 
-    // Batch materials by key
-    **material_batches = render_materials
-        .iter()
-        .flat_map(|(material_handle, material)| {
-            Some((
-                InstancedMaterialBatchKey {
-                    alpha_mode: GpuAlphaMode::from(material.properties.alpha_mode),
-                    key: material.batch_key.clone(),
-                },
-                MaterialBatch {
-                    material: material_handle.clone_weak(),
-                    pipeline_key: material.pipeline_key.clone(),
-                },
-            ))
-        })
-        .collect();
+    // Batch materials by key, merging materials with equal keys into a single batch
+    material_batches.clear();
+    for (material_handle, material) in render_materials.iter() {
+        let key = InstancedMaterialBatchKey {
+            alpha_mode: material.properties.gpu_alpha_mode(),
+            depth_compare: material.properties.depth_compare,
+            write_depth: material.properties.write_depth,
+            alpha_to_coverage: material.properties.alpha_to_coverage,
+            depth_range: material.properties.depth_range_key(),
+            key: material.batch_key.clone(),
+        };
+
+        // Pick the lowest handle as the batch's representative,
+        // so it doesn't depend on map iteration order
+        if let Some(material_batch) = material_batches.get(&key) {
+            if material_batch.material <= *material_handle {
+                continue;
+            }
+        }
+
+        material_batches.insert(
+            key,
+            MaterialBatch {
+                material: material_handle.clone_weak(),
+                pipeline_key: material.pipeline_key.clone(),
+            },
+        );
+    }
 
     debug!("Material batches: {:#?}", material_batches);
 }
@@ -67,138 +67,169 @@ pub fn system(
         keyed_meshes
     });
 
-    // Generate vertex, index, and indirect data for each batch
+    // Generate vertex, index, and indirect data for each batch,
+    // replacing the previous set wholesale so that batches for modified or removed meshes
+    // don't retain stale handles, and their GPU buffers are dropped
     info_span!("Batch meshes").in_scope(|| {
-        mesh_batches.extend({
-            keyed_meshes.into_iter().map(|(key, meshes)| {
-                let vertex_data = info_span!("Vertex data").in_scope(|| {
-                    let mut vertex_data =
-                        BufferVec::new(BufferUsages::VERTEX | BufferUsages::COPY_DST);
-
-                    let bytes = meshes
-                        .iter()
-                        .flat_map(|mesh| render_meshes.get(mesh))
-                        .flat_map(|mesh| mesh.vertex_buffer_data.iter())
-                        .copied()
-                        .collect::<Vec<_>>();
-
-                    vertex_data.reserve(bytes.len(), &render_device);
-
-                    for byte in bytes {
-                        vertex_data.push(byte);
-                    }
-
-                    vertex_data.write_buffer(&render_device, &render_queue);
-
-                    vertex_data
-                });
-
-                let index_data = info_span!("Index data").in_scope(|| {
-                    let mut base_index = 0;
-                    let indices = meshes.iter().fold(None, |acc, mesh| {
-                        let mesh = render_meshes.get(mesh).unwrap();
-
-                        let out = match &mesh.index_buffer_data {
-                            GpuIndexBufferData::Indexed { indices, .. } => Some(match acc {
-                                Some(acc_indices) => match (acc_indices, indices) {
-                                    (Indices::U16(lhs), Indices::U16(rhs)) => Indices::U16(
-                                        lhs.iter()
-                                            .copied()
-                                            .chain(rhs.iter().map(|idx| base_index as u16 + *idx))
-                                            .collect(),
-                                    ),
-                                    (Indices::U32(lhs), Indices::U32(rhs)) => Indices::U32(
-                                        lhs.iter()
-                                            .copied()
-                                            .chain(rhs.iter().map(|idx| base_index as u32 + *idx))
-                                            .collect(),
-                                    ),
-                                    _ => panic!("Mismatched index format"),
-                                },
-                                None => indices.clone(),
-                            }),
-                            GpuIndexBufferData::NonIndexed { .. } => None,
-                        };
+        mesh_batches.mesh_batches = {
+            keyed_meshes
+                .into_iter()
+                .map(|(key, meshes)| {
+                    let vertex_data = info_span!("Vertex data").in_scope(|| {
+                        let mut vertex_data =
+                            BufferVec::new(BufferUsages::VERTEX | BufferUsages::COPY_DST);
 
-                        base_index += mesh.vertex_count;
+                        let bytes = meshes
+                            .iter()
+                            .flat_map(|mesh| render_meshes.get(mesh))
+                            .flat_map(|mesh| mesh.vertex_buffer_data.iter())
+                            .copied()
+                            .collect::<Vec<_>>();
 
-                        out
-                    });
+                        vertex_data.reserve(bytes.len(), &render_device);
 
-                    indices.map(|indices| {
-                        let bytes: Vec<u8> = match indices {
-                            Indices::U16(indices) => bytemuck::cast_slice(&indices).to_vec(),
-                            Indices::U32(indices) => bytemuck::cast_slice(&indices).to_vec(),
-                        };
+                        for byte in bytes {
+                            vertex_data.push(byte);
+                        }
 
-                        let mut index_data =
-                            BufferVec::new(BufferUsages::INDEX | BufferUsages::COPY_DST);
+                        vertex_data.write_buffer(&render_device, &render_queue);
 
-                        index_data.reserve(bytes.len(), &render_device);
+                        vertex_data
+                    });
 
-                        for byte in bytes {
-                            index_data.push(byte);
-                        }
+                    let index_data = info_span!("Index data").in_scope(|| {
+                        let index_count = meshes
+                            .iter()
+                            .map(
+                                |mesh| match &render_meshes.get(mesh).unwrap().index_buffer_data {
+                                    GpuIndexBufferData::Indexed { index_count, .. } => {
+                                        *index_count as usize
+                                    }
+                                    GpuIndexBufferData::NonIndexed { .. } => 0,
+                                },
+                            )
+                            .sum::<usize>();
+
+                        let mut base_index = 0;
+                        let indices = meshes.iter().fold(None, |acc, mesh| {
+                            let mesh = render_meshes.get(mesh).unwrap();
+
+                            let out = match &mesh.index_buffer_data {
+                                GpuIndexBufferData::Indexed { indices, .. } => Some(match acc {
+                                    Some(acc_indices) => match (acc_indices, indices) {
+                                        (Indices::U16(lhs), Indices::U16(rhs)) => Indices::U16(
+                                            lhs.iter()
+                                                .copied()
+                                                .chain(
+                                                    rhs.iter().map(|idx| base_index as u16 + *idx),
+                                                )
+                                                .collect(),
+                                        ),
+                                        (Indices::U32(lhs), Indices::U32(rhs)) => Indices::U32(
+                                            lhs.iter()
+                                                .copied()
+                                                .chain(
+                                                    rhs.iter().map(|idx| base_index as u32 + *idx),
+                                                )
+                                                .collect(),
+                                        ),
+                                        _ => panic!("Mismatched index format"),
+                                    },
+                                    None => indices.clone(),
+                                }),
+                                GpuIndexBufferData::NonIndexed { .. } => None,
+                            };
+
+                            base_index += mesh.vertex_count;
+
+                            out
+                        });
+
+                        indices.map(|indices| {
+                            let (bytes, index_size): (Vec<u8>, usize) = match indices {
+                                Indices::U16(indices) => {
+                                    (bytemuck::cast_slice(&indices).to_vec(), 2)
+                                }
+                                Indices::U32(indices) => {
+                                    (bytemuck::cast_slice(&indices).to_vec(), 4)
+                                }
+                            };
+
+                            debug_assert_eq!(
+                                bytes.len(),
+                                index_count * index_size,
+                                "Mesh index counts out of sync with their indices"
+                            );
+
+                            let mut index_data =
+                                BufferVec::new(BufferUsages::INDEX | BufferUsages::COPY_DST);
+
+                            index_data.reserve(index_count * index_size, &render_device);
+
+                            for byte in bytes {
+                                index_data.push(byte);
+                            }
+
+                            index_data.write_buffer(&render_device, &render_queue);
+
+                            index_data
+                        })
+                    });
 
-                        index_data.write_buffer(&render_device, &render_queue);
-
-                        index_data
-                    })
-                });
-
-                let mut base_index = 0u32;
-                let indirect_data =
-                    info_span!("Indirect data").in_scope(|| match key.index_format {
-                        Some(_) => GpuIndirectData::Indexed {
-                            buffer: meshes
-                                .iter()
-                                .map(|mesh| {
-                                    match &render_meshes.get(mesh).unwrap().index_buffer_data {
-                                        GpuIndexBufferData::Indexed { indices, .. } => {
-                                            base_index += indices.len() as u32;
-
-                                            DrawIndexedIndirect {
-                                                vertex_count: indices.len() as u32,
-                                                ..default()
+                    let mut base_index = 0u32;
+                    let indirect_data =
+                        info_span!("Indirect data").in_scope(|| match key.index_format {
+                            Some(_) => GpuIndirectData::Indexed {
+                                buffer: meshes
+                                    .iter()
+                                    .map(|mesh| {
+                                        match &render_meshes.get(mesh).unwrap().index_buffer_data {
+                                            GpuIndexBufferData::Indexed { index_count, .. } => {
+                                                base_index += index_count;
+
+                                                DrawIndexedIndirect {
+                                                    vertex_count: *index_count,
+                                                    ..default()
+                                                }
                                             }
+                                            _ => panic!("Mismatched GpuIndexBufferData"),
                                         }
-                                        _ => panic!("Mismatched GpuIndexBufferData"),
-                                    }
-                                })
-                                .collect::<Vec<_>>(),
-                        },
-                        None => GpuIndirectData::NonIndexed {
-                            buffer: meshes
-                                .iter()
-                                .map(|mesh| {
-                                    match &render_meshes.get(mesh).unwrap().index_buffer_data {
-                                        GpuIndexBufferData::NonIndexed { vertex_count } => {
-                                            base_index += vertex_count;
-
-                                            DrawIndirect {
-                                                vertex_count: *vertex_count,
-                                                ..default()
+                                    })
+                                    .collect::<Vec<_>>(),
+                            },
+                            None => GpuIndirectData::NonIndexed {
+                                buffer: meshes
+                                    .iter()
+                                    .map(|mesh| {
+                                        match &render_meshes.get(mesh).unwrap().index_buffer_data {
+                                            GpuIndexBufferData::NonIndexed { vertex_count } => {
+                                                base_index += vertex_count;
+
+                                                DrawIndirect {
+                                                    vertex_count: *vertex_count,
+                                                    ..default()
+                                                }
                                             }
+                                            _ => panic!("Mismatched GpuIndexBufferData"),
                                         }
-                                        _ => panic!("Mismatched GpuIndexBufferData"),
-                                    }
-                                })
-                                .collect::<Vec<_>>(),
+                                    })
+                                    .collect::<Vec<_>>(),
+                            },
+                        });
+
+                    debug!("Mesh batch {key:#?}: {meshes:#?}");
+
+                    (
+                        key.clone(),
+                        MeshBatch {
+                            meshes,
+                            vertex_data,
+                            index_data,
+                            indirect_data,
                         },
-                    });
-
-                debug!("Mesh batch {key:#?}: {meshes:#?}");
-
-                (
-                    key.clone(),
-                    MeshBatch {
-                        meshes,
-                        vertex_data,
-                        index_data,
-                        indirect_data,
-                    },
-                )
-            })
-        })
+                    )
+                })
+                .collect()
+        }
     });
 }
@@ -1,22 +1,23 @@
 use bevy::{
-    prelude::{debug, Entity, Handle, Query, With},
+    prelude::{debug, Entity, Handle, Query, Res, With},
     render::view::{ExtractedView, VisibleEntities},
 };
 
 use crate::instancing::{
     material::{material_instanced::MaterialInstanced, plugin::InstanceMeta},
-    render::instance::Instance,
+    mesh_instance::ExtractedInstances,
 };
 
+/// Collects the instances of material `M` visible to each view
+///
+/// Inclusion is driven by each view's own [`VisibleEntities`], which bevy populates per camera
+/// from frustum culling against each instance's `Aabb` and from `RenderLayers`.
+/// Instances are thus batched into the instance buffers of the views that can see them only,
+/// and buffers diverge between views with different visibility.
 pub fn system<M: MaterialInstanced>(
     mut query_views: Query<(Entity, &VisibleEntities, &mut InstanceMeta<M>), With<ExtractedView>>,
-    query_instance: Query<
-        Entity,
-        (
-            With<Handle<M>>,
-            With<<M::Instance as Instance>::ExtractedInstance>,
-        ),
-    >,
+    extracted_instances: Res<ExtractedInstances<M>>,
+    query_instance: Query<Entity, With<Handle<M>>>,
 ) {
     debug!("{}", std::any::type_name::<M>());
 
@@ -27,7 +28,9 @@ pub fn system<M: MaterialInstanced>(
             .entities
             .iter()
             .copied()
-            .filter(|entity| query_instance.get(*entity).is_ok())
+            .filter(|entity| {
+                extracted_instances.contains_key(entity) && query_instance.contains(*entity)
+            })
             .collect::<Vec<_>>();
     }
 }
@@ -1,50 +1,104 @@
 use std::hash::Hash;
 
 use bevy::{
-    core_pipeline::core_3d::{AlphaMask3d, Opaque3d, Transparent3d},
+    core_pipeline::{
+        core_3d::{AlphaMask3d, Opaque3d, Transparent3d},
+        tonemapping::Tonemapping,
+    },
     pbr::MeshPipelineKey,
-    prelude::{debug, error, Commands, Entity, Msaa, Query, Res, ResMut, With},
+    prelude::{debug, error, Commands, Entity, Msaa, Query, Res, ResMut, With, Without},
     render::{
         render_phase::{DrawFunctions, RenderPhase},
-        render_resource::{PipelineCache, SpecializedMeshPipelines},
+        render_resource::{
+            CachedRenderPipelineId, CompareFunction, PipelineCache, SpecializedMeshPipelines,
+        },
         view::{ExtractedView, VisibleEntities},
     },
+    utils::{FloatOrd, HashMap},
 };
 
-use crate::instancing::material::{
-    instanced_material_pipeline::{InstancedMaterialPipeline, InstancedMaterialPipelineKey},
-    material_instanced::MaterialInstanced,
-    plugin::{DrawInstanced, GpuAlphaMode, InstanceMeta},
+use crate::instancing::{
+    material::{
+        instanced_material_pipeline::{InstancedMaterialPipeline, InstancedMaterialPipelineKey},
+        material_instanced::MaterialInstanced,
+        plugin::{DrawInstanced, GpuAlphaMode, InstanceMeta},
+    },
+    oit::OrderIndependent3d,
+    shadow_tile::ShadowTile,
 };
 
+use crate::instancing::batch_debug::{InstanceBatchDebug, BATCH_DEBUG_PALETTE_LENGTH};
+
 use super::prepare_material_batches::MaterialBatches;
 
-#[allow(clippy::too_many_arguments)]
+/// Phase distance separating consecutive [`InstanceLayer`](crate::prelude::InstanceLayer)s,
+/// beyond which batch view distances are clamped so layers dominate depth ordering
+const LAYER_DISTANCE: f32 = (1 << 16) as f32;
+
+/// Transparent phase distance of [`AlwaysOnTop`](crate::prelude::AlwaysOnTop) batches,
+/// beyond any regular layer and view distance
+const ALWAYS_ON_TOP_DISTANCE: f32 = (1u64 << 32) as f32;
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn system<M: MaterialInstanced>(
     material_batches: Res<MaterialBatches<M>>,
     opaque_draw_functions: Res<DrawFunctions<Opaque3d>>,
     alpha_mask_draw_functions: Res<DrawFunctions<AlphaMask3d>>,
     transparent_draw_functions: Res<DrawFunctions<Transparent3d>>,
+    order_independent_draw_functions: Res<DrawFunctions<OrderIndependent3d>>,
     instanced_material_pipeline: Res<InstancedMaterialPipeline<M>>,
     msaa: Res<Msaa>,
+    batch_debug: Res<InstanceBatchDebug>,
     mut pipelines: ResMut<SpecializedMeshPipelines<InstancedMaterialPipeline<M>>>,
     mut pipeline_cache: ResMut<PipelineCache>,
-    query_view: Query<(Entity, &InstanceMeta<M>), (With<ExtractedView>, With<VisibleEntities>)>,
+    query_view: Query<
+        (
+            Entity,
+            &ExtractedView,
+            Option<&Tonemapping>,
+            &InstanceMeta<M>,
+        ),
+        (With<VisibleEntities>, Without<ShadowTile>),
+    >,
     mut query_opaque_3d: Query<&mut RenderPhase<Opaque3d>>,
     mut query_alpha_mask_3d: Query<&mut RenderPhase<AlphaMask3d>>,
     mut query_transparent_3d: Query<&mut RenderPhase<Transparent3d>>,
+    mut query_order_independent_3d: Query<&mut RenderPhase<OrderIndependent3d>>,
     mut commands: Commands,
 ) where
     M::Data: Clone + Hash + PartialEq + Eq,
 {
     debug!("{}", std::any::type_name::<M>());
 
-    for (view_entity, instance_meta) in query_view.iter() {
+    for (view_entity, view, tonemapping, instance_meta) in query_view.iter() {
         debug!("\tView {view_entity:?}");
 
-        for key in instance_meta.batched_instances.keys() {
+        // Match the view's target format so HDR cameras receive HDR output
+        let mut view_key =
+            MeshPipelineKey::from_msaa_samples(msaa.samples) | MeshPipelineKey::from_hdr(view.hdr);
+
+        // Tonemap in the fragment shader when the view does, to match bevy's own meshes
+        if let Some(Tonemapping::Enabled { deband_dither }) = tonemapping {
+            if !view.hdr {
+                view_key |= MeshPipelineKey::TONEMAP_IN_SHADER;
+
+                if *deband_dither {
+                    view_key |= MeshPipelineKey::DEBAND_DITHER;
+                }
+            }
+        }
+
+        let mut opaque_items = vec![];
+        let mut alpha_mask_items = vec![];
+
+        for (i, key) in instance_meta.batched_instances.keys().enumerate() {
             debug!("{key:#?}");
 
+            // Shadow-only batches stay prepared for shadow passes, but are kept out of the main pass
+            if key.shadow_only {
+                continue;
+            }
+
             // Spawn entity
             let material = material_batches
                 .get(&key.material_key)
@@ -54,8 +108,16 @@ pub fn system<M: MaterialInstanced>(
 
             let batch_entity = commands.spawn((material, key.clone())).id();
 
+            // Instances drawn on top blend over everything else,
+            // so they're always routed to the transparent phase
+            let alpha_mode = if key.always_on_top {
+                GpuAlphaMode::Blend
+            } else {
+                key.material_key.alpha_mode
+            };
+
             // Queue draw function
-            let draw_function = match key.material_key.alpha_mode {
+            let draw_function = match alpha_mode {
                 GpuAlphaMode::Opaque => opaque_draw_functions.read().get_id::<DrawInstanced<M>>(),
                 GpuAlphaMode::Mask => alpha_mask_draw_functions
                     .read()
@@ -63,16 +125,17 @@ pub fn system<M: MaterialInstanced>(
                 GpuAlphaMode::Blend => transparent_draw_functions
                     .read()
                     .get_id::<DrawInstanced<M>>(),
+                GpuAlphaMode::OrderIndependent => order_independent_draw_functions
+                    .read()
+                    .get_id::<DrawInstanced<M>>(),
             }
             .unwrap();
 
-            let msaa_key = MeshPipelineKey::from_msaa_samples(msaa.samples);
-
             let mut mesh_key =
                 MeshPipelineKey::from_primitive_topology(key.mesh_key.primitive_topology)
-                    | msaa_key;
+                    | view_key;
 
-            if let GpuAlphaMode::Blend = key.material_key.alpha_mode {
+            if let GpuAlphaMode::Blend | GpuAlphaMode::OrderIndependent = alpha_mode {
                 mesh_key |= MeshPipelineKey::TRANSPARENT_MAIN_PASS;
             }
 
@@ -83,6 +146,19 @@ pub fn system<M: MaterialInstanced>(
                 &instanced_material_pipeline,
                 InstancedMaterialPipelineKey {
                     mesh_key,
+                    depth_compare: if key.always_on_top {
+                        CompareFunction::Always
+                    } else {
+                        key.material_key.depth_compare
+                    },
+                    write_depth: key.material_key.write_depth && !key.always_on_top,
+                    alpha_to_coverage: key.material_key.alpha_to_coverage,
+                    order_independent: alpha_mode == GpuAlphaMode::OrderIndependent,
+                    batch_debug: batch_debug
+                        .enabled
+                        .then_some(i % BATCH_DEBUG_PALETTE_LENGTH),
+                    mirrored: key.mirrored,
+                    shadow: false,
                     material_key: material_batch.pipeline_key.clone(),
                 },
                 &key.mesh_key.layout,
@@ -96,26 +172,40 @@ pub fn system<M: MaterialInstanced>(
                 }
             };
 
-            let distance = 0.0;
-            match key.material_key.alpha_mode {
+            // Transparent batches are sorted back-to-front by the view-space Z of their nearest instance,
+            // which is negative in front of the camera like that of bevy's own phase items.
+            // Opaque batches are only ordered by layer, so they can be grouped by pipeline below
+            let view_distance = instance_meta
+                .instance_batches
+                .get(key)
+                .map(|batch| batch.view_distance)
+                .unwrap_or_default()
+                .clamp(-LAYER_DISTANCE + 1.0, 0.0);
+
+            // Lower layers are drawn first; opaque phases sort by descending distance
+            // for front-to-back ordering, so their layer is negated
+            let mut layer_distance = key.layer as f32 * LAYER_DISTANCE;
+            if key.always_on_top {
+                layer_distance += ALWAYS_ON_TOP_DISTANCE;
+            }
+
+            match alpha_mode {
                 GpuAlphaMode::Opaque => {
                     debug!("\t\tQueuing opaque instanced draw {batch_entity:?}");
-                    let mut opaque_phase = query_opaque_3d.get_mut(view_entity).unwrap();
-                    opaque_phase.add(Opaque3d {
+                    opaque_items.push(Opaque3d {
                         entity: batch_entity,
                         draw_function,
                         pipeline,
-                        distance,
+                        distance: -layer_distance,
                     });
                 }
                 GpuAlphaMode::Mask => {
                     debug!("\t\tQueuing masked instanced draw {batch_entity:?}");
-                    let mut alpha_mask_phase = query_alpha_mask_3d.get_mut(view_entity).unwrap();
-                    alpha_mask_phase.add(AlphaMask3d {
+                    alpha_mask_items.push(AlphaMask3d {
                         entity: batch_entity,
                         draw_function,
                         pipeline,
-                        distance,
+                        distance: -layer_distance,
                     });
                 }
                 GpuAlphaMode::Blend => {
@@ -125,10 +215,57 @@ pub fn system<M: MaterialInstanced>(
                         entity: batch_entity,
                         draw_function,
                         pipeline,
-                        distance,
+                        distance: view_distance + layer_distance,
                     });
                 }
+                GpuAlphaMode::OrderIndependent => {
+                    debug!("\t\tQueuing order-independent instanced draw {batch_entity:?}");
+                    if let Ok(mut order_independent_phase) =
+                        query_order_independent_3d.get_mut(view_entity)
+                    {
+                        order_independent_phase.add(OrderIndependent3d {
+                            entity: batch_entity,
+                            draw_function,
+                            pipeline,
+                            distance: view_distance + layer_distance,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Opaque draws don't depend on order within a layer,
+        // so group them by pipeline to minimize pipeline switches.
+        // The phase sort is stable, and will preserve this order between equal distances
+        group_by_pipeline(&mut opaque_items, |item| (item.distance, item.pipeline));
+        group_by_pipeline(&mut alpha_mask_items, |item| (item.distance, item.pipeline));
+
+        if let Ok(mut opaque_phase) = query_opaque_3d.get_mut(view_entity) {
+            for item in opaque_items {
+                opaque_phase.add(item);
+            }
+        }
+
+        if let Ok(mut alpha_mask_phase) = query_alpha_mask_3d.get_mut(view_entity) {
+            for item in alpha_mask_items {
+                alpha_mask_phase.add(item);
             }
         }
     }
 }
+
+/// Sorts phase items by distance, then by pipeline.
+///
+/// [`CachedRenderPipelineId`] isn't [`Ord`], so pipelines are ranked by first appearance.
+fn group_by_pipeline<T>(items: &mut [T], key: impl Fn(&T) -> (f32, CachedRenderPipelineId)) {
+    let mut pipeline_ranks = HashMap::<CachedRenderPipelineId, usize>::default();
+    for item in items.iter() {
+        let ranks = pipeline_ranks.len();
+        pipeline_ranks.entry(key(item).1).or_insert(ranks);
+    }
+
+    items.sort_by_key(|item| {
+        let (distance, pipeline) = key(item);
+        (FloatOrd(distance), pipeline_ranks[&pipeline])
+    });
+}
@@ -0,0 +1,125 @@
+use std::hash::Hash;
+
+use bevy::{
+    pbr::{LightEntity, MeshPipelineKey, Shadow},
+    prelude::{debug, error, Commands, Entity, Handle, Query, Res, ResMut, With},
+    render::{
+        render_phase::{DrawFunctions, RenderPhase},
+        render_resource::{CompareFunction, PipelineCache, SpecializedMeshPipelines},
+    },
+};
+
+use crate::instancing::{
+    instance_slice::InstanceSlice,
+    material::{
+        instanced_material_pipeline::{InstancedMaterialPipeline, InstancedMaterialPipelineKey},
+        material_instanced::MaterialInstanced,
+        plugin::{DrawInstancedShadow, InstanceMeta},
+    },
+    mesh_instance::ExtractedInstances,
+    shadow_tile::{ShadowTile, ShadowTileView, ShadowTileViews},
+};
+
+use super::prepare_material_batches::MaterialBatches;
+
+/// Queues the batches prepared for each light's [`ShadowTile`] into the [`Shadow`] phase of bevy's matching shadow view
+///
+/// Bevy queues every extracted mesh entity into its shadow phases, instances included,
+/// drawing each at its entity's transform with the regular mesh pipeline.
+/// Those items are dropped for instances of `M`, which are drawn through their batches instead.
+/// Instances are recognized by their membership of [`ExtractedInstances`] rather than by their material handle,
+/// which [`HybridMaterialPlugin`](crate::prelude::HybridMaterialPlugin) strips before the queue stage.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn system<M: MaterialInstanced>(
+    material_batches: Res<MaterialBatches<M>>,
+    shadow_draw_functions: Res<DrawFunctions<Shadow>>,
+    instanced_material_pipeline: Res<InstancedMaterialPipeline<M>>,
+    shadow_tile_views: Res<ShadowTileViews>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<InstancedMaterialPipeline<M>>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    extracted_instances: Res<ExtractedInstances<M>>,
+    query_instance_slices: Query<(), (With<InstanceSlice>, With<Handle<M>>)>,
+    query_tiles: Query<&InstanceMeta<M>, With<ShadowTile>>,
+    mut query_light_views: Query<(Entity, &LightEntity, &mut RenderPhase<Shadow>)>,
+    mut commands: Commands,
+) where
+    M::Data: Clone + Hash + PartialEq + Eq,
+{
+    debug!("{}", std::any::type_name::<M>());
+
+    let draw_function = shadow_draw_functions
+        .read()
+        .get_id::<DrawInstancedShadow<M>>()
+        .unwrap();
+
+    for (light_view_entity, light_entity, mut shadow_phase) in query_light_views.iter_mut() {
+        debug!("\tLight view {light_view_entity:?}");
+
+        shadow_phase.items.retain(|item| {
+            !(extracted_instances.contains_key(&item.entity)
+                || query_instance_slices.contains(item.entity))
+        });
+
+        let tile_entity = if let Some(tile_entity) = shadow_tile_views.get(light_entity) {
+            tile_entity
+        } else {
+            continue;
+        };
+
+        let instance_meta = if let Ok(instance_meta) = query_tiles.get(tile_entity) {
+            instance_meta
+        } else {
+            continue;
+        };
+
+        for key in instance_meta.batched_instances.keys() {
+            debug!("{key:#?}");
+
+            let material_batch = material_batches.get(&key.material_key).unwrap();
+
+            let pipeline = pipelines.specialize(
+                &mut pipeline_cache,
+                &instanced_material_pipeline,
+                InstancedMaterialPipelineKey {
+                    mesh_key: MeshPipelineKey::from_primitive_topology(
+                        key.mesh_key.primitive_topology,
+                    ),
+                    depth_compare: CompareFunction::GreaterEqual,
+                    write_depth: true,
+                    alpha_to_coverage: false,
+                    order_independent: false,
+                    batch_debug: None,
+                    mirrored: key.mirrored,
+                    shadow: true,
+                    material_key: material_batch.pipeline_key.clone(),
+                },
+                &key.mesh_key.layout,
+            );
+
+            let pipeline = match pipeline {
+                Ok(id) => id,
+                Err(err) => {
+                    error!("{}", err);
+                    continue;
+                }
+            };
+
+            // Batches read their instances from the tile, rather than from bevy's shadow view
+            let batch_entity = commands
+                .spawn((
+                    material_batch.material.clone_weak(),
+                    key.clone(),
+                    ShadowTileView(tile_entity),
+                ))
+                .id();
+
+            debug!("\t\tQueuing instanced shadow draw {batch_entity:?}");
+            shadow_phase.add(Shadow {
+                entity: batch_entity,
+                draw_function,
+                pipeline,
+                distance: 0.0,
+            });
+        }
+    }
+}
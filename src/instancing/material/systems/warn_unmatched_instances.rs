@@ -0,0 +1,48 @@
+use bevy::{
+    prelude::{warn, ChangeTrackers, Entity, Handle, Local, Query, With, Without},
+    utils::HashSet,
+};
+
+use crate::instancing::{
+    instance_slice::InstanceSlice, material::material_instanced::MaterialInstanced,
+    mesh_instance::NotInstanced, render::instance::Instance,
+};
+
+/// Warns about entities using material `M` whose components don't satisfy its instance query,
+/// i.e. a color instance material without an `InstanceColor`, as they would silently not be drawn
+///
+/// Entities are checked when their material handle changes.
+/// Debug builds check every entity each frame, catching required components removed later on.
+/// Each entity is warned about once until it's fixed.
+/// [`InstanceSlice`] entities are skipped, as their instances are written on the GPU.
+#[allow(clippy::type_complexity)]
+pub fn system<M: MaterialInstanced>(
+    query_material: Query<
+        (Entity, ChangeTrackers<Handle<M>>),
+        (
+            With<Handle<M>>,
+            Without<NotInstanced>,
+            Without<InstanceSlice>,
+        ),
+    >,
+    query_instance: Query<<M::Instance as Instance>::Query>,
+    mut warned: Local<HashSet<Entity>>,
+) {
+    warned.retain(|entity| query_material.contains(*entity));
+
+    for (entity, material_tracker) in query_material.iter() {
+        if !cfg!(debug_assertions) && !material_tracker.is_changed() {
+            continue;
+        }
+
+        if query_instance.contains(entity) {
+            warned.remove(&entity);
+        } else if warned.insert(entity) {
+            warn!(
+                "Entity {entity:?} uses {} but is missing components required by {}, and will not be rendered",
+                std::any::type_name::<M>(),
+                std::any::type_name::<M::Instance>(),
+            );
+        }
+    }
+}
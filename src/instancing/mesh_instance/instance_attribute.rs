@@ -0,0 +1,193 @@
+use std::fmt::Debug;
+
+use bevy::{
+    ecs::query::{ROQueryItem, ReadOnlyWorldQuery},
+    math::Mat4,
+    prelude::{default, Component},
+    render::render_resource::{encase::private::WriteInto, ShaderSize, ShaderType},
+};
+
+use crate::prelude::{CulledInstance, GpuMeshInstance, Instance, MeshInstance};
+
+/// Per-instance data stored after the [`GpuMeshInstance`] of a [`MeshInstanceWith`]
+///
+/// Implemented on the component the data is read from, i.e. [`InstanceColor`](crate::prelude::InstanceColor),
+/// so adding per-instance data only takes a GPU representation and a way to extract it.
+/// Pairs of attributes are attributes themselves, and nest to compose more than two.
+pub trait InstanceAttribute: 'static + Send + Sync {
+    /// Data written to the instance buffer after the base instance
+    type GpuAttribute: Debug
+        + Default
+        + Copy
+        + PartialEq
+        + Send
+        + Sync
+        + ShaderType
+        + ShaderSize
+        + WriteInto;
+
+    type Query: ReadOnlyWorldQuery;
+
+    fn extract_attribute(item: ROQueryItem<Self::Query>) -> Self::GpuAttribute;
+
+    /// Scales the opacity of a prepared attribute by `fade`, see [`Instance::fade`]
+    #[allow(unused_variables)]
+    fn fade(attribute: &mut Self::GpuAttribute, fade: f32) {}
+
+    /// Shader defs added to pipelines using [`MeshInstanceWith`] this attribute
+    fn shader_defs() -> Vec<String> {
+        vec![]
+    }
+}
+
+/// GPU-friendly data for a pair of [`InstanceAttribute`]s, laid out one after the other
+#[derive(Debug, Default, Copy, Clone, PartialEq, ShaderType)]
+pub struct GpuInstanceAttributePair<First: ShaderType + ShaderSize, Second: ShaderType + ShaderSize>
+{
+    pub first: First,
+    pub second: Second,
+}
+
+impl<A: InstanceAttribute, B: InstanceAttribute> InstanceAttribute for (A, B) {
+    type GpuAttribute = GpuInstanceAttributePair<A::GpuAttribute, B::GpuAttribute>;
+
+    type Query = (A::Query, B::Query);
+
+    fn extract_attribute((first, second): ROQueryItem<Self::Query>) -> Self::GpuAttribute {
+        GpuInstanceAttributePair {
+            first: A::extract_attribute(first),
+            second: B::extract_attribute(second),
+        }
+    }
+
+    fn fade(attribute: &mut Self::GpuAttribute, fade: f32) {
+        A::fade(&mut attribute.first, fade);
+        B::fade(&mut attribute.second, fade);
+    }
+
+    fn shader_defs() -> Vec<String> {
+        let mut shader_defs = A::shader_defs();
+        shader_defs.extend(B::shader_defs());
+        shader_defs
+    }
+}
+
+/// [`MeshInstance`] extended with the per-instance data of `A`
+///
+/// Shaders see each instance as a struct of the base `InstanceData`
+/// from `indirect_instancing::instance_struct`, followed by `A`'s GPU representation
+/// at its natural WGSL alignment.
+#[derive(Component)]
+pub struct MeshInstanceWith<A: InstanceAttribute> {
+    pub base: MeshInstance,
+    pub attribute: A::GpuAttribute,
+}
+
+impl<A: InstanceAttribute> Debug for MeshInstanceWith<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MeshInstanceWith")
+            .field("base", &self.base)
+            .field("attribute", &self.attribute)
+            .finish()
+    }
+}
+
+impl<A: InstanceAttribute> Default for MeshInstanceWith<A> {
+    fn default() -> Self {
+        Self {
+            base: default(),
+            attribute: default(),
+        }
+    }
+}
+
+impl<A: InstanceAttribute> Clone for MeshInstanceWith<A> {
+    fn clone(&self) -> Self {
+        Self {
+            base: self.base.clone(),
+            attribute: self.attribute,
+        }
+    }
+}
+
+impl<A: InstanceAttribute> PartialEq for MeshInstanceWith<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base && self.attribute == other.attribute
+    }
+}
+
+/// GPU-friendly data for a [`MeshInstanceWith`]
+#[derive(ShaderType, Component)]
+pub struct GpuMeshInstanceWith<A: InstanceAttribute> {
+    pub base: GpuMeshInstance,
+    pub attribute: A::GpuAttribute,
+}
+
+impl<A: InstanceAttribute> Debug for GpuMeshInstanceWith<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuMeshInstanceWith")
+            .field("base", &self.base)
+            .field("attribute", &self.attribute)
+            .finish()
+    }
+}
+
+impl<A: InstanceAttribute> CulledInstance for GpuMeshInstanceWith<A> {
+    const TRANSFORM_OFFSET: u64 = Self::METADATA.offset(0) + GpuMeshInstance::TRANSFORM_OFFSET;
+}
+
+impl<A: InstanceAttribute> Copy for GpuMeshInstanceWith<A> {}
+
+impl<A: InstanceAttribute> Clone for GpuMeshInstanceWith<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A: InstanceAttribute> Default for GpuMeshInstanceWith<A> {
+    fn default() -> Self {
+        Self {
+            base: default(),
+            attribute: default(),
+        }
+    }
+}
+
+impl<A: InstanceAttribute> PartialEq for GpuMeshInstanceWith<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base && self.attribute == other.attribute
+    }
+}
+
+impl<A: InstanceAttribute> Instance for MeshInstanceWith<A> {
+    type ExtractedInstance = Self;
+    type PreparedInstance = GpuMeshInstanceWith<A>;
+
+    type Query = (<MeshInstance as Instance>::Query, A::Query);
+
+    fn extract_instance((base, attribute): ROQueryItem<Self::Query>) -> Self::ExtractedInstance {
+        MeshInstanceWith {
+            base: MeshInstance::extract_instance(base),
+            attribute: A::extract_attribute(attribute),
+        }
+    }
+
+    fn prepare_instance(instance: &Self::ExtractedInstance, mesh: u32) -> Self::PreparedInstance {
+        GpuMeshInstanceWith {
+            base: MeshInstance::prepare_instance(&instance.base, mesh),
+            attribute: instance.attribute,
+        }
+    }
+
+    fn transform(instance: &Self::ExtractedInstance) -> Mat4 {
+        instance.base.transform
+    }
+
+    fn fade(instance: &mut Self::PreparedInstance, fade: f32) {
+        A::fade(&mut instance.attribute, fade);
+    }
+
+    fn shader_defs() -> Vec<String> {
+        A::shader_defs()
+    }
+}
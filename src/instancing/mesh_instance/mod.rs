@@ -1,51 +1,181 @@
+pub mod instance_attribute;
 pub mod mesh_instance_bundle;
 
-use crate::prelude::Instance;
+use std::hash::Hash;
+
+use crate::prelude::{CulledInstance, Instance, InstanceUniformLength};
 use bevy::{
-    ecs::{query::ROQueryItem, system::lifetimeless::Read},
-    math::Mat4,
+    ecs::{
+        query::{QueryItem, ROQueryItem},
+        reflect::ReflectComponent,
+        system::lifetimeless::Read,
+    },
+    math::{Mat4, Vec3},
     prelude::{
-        default, Commands, Component, ComputedVisibility, Entity, GlobalTransform, Handle, Mesh,
-        Query,
+        default, Component, ComputedVisibility, Deref, DerefMut, Entity, GlobalTransform, Handle,
+        Mesh, Query, Reflect, ResMut, Resource, With, Without,
     },
-    render::{render_resource::ShaderType, Extract},
+    render::{
+        extract_component::ExtractComponent,
+        primitives::Aabb,
+        render_resource::{ShaderSize, ShaderType},
+        Extract,
+    },
+    utils::HashMap,
 };
 
 use super::material::material_instanced::MaterialInstanced;
 
+/// Per-instance seed for procedural variation in shaders
+///
+/// Instances without this component are seeded with their entity index.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component)]
+pub struct InstanceSeed(pub u32);
+
+/// Forces instances into a separate batch per group,
+/// even if they would otherwise be drawn together
+///
+/// Instances without this component belong to group 0.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deref, DerefMut, Component, Reflect,
+)]
+#[reflect(Component)]
+pub struct ForceBatchGroup(pub u32);
+
+impl ExtractComponent for ForceBatchGroup {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Per-instance depth bias applied in the vertex shader, i.e. to keep coplanar decals from z-fighting
+///
+/// Pipeline-level depth bias can't vary per instance, so instanced vertex shaders instead pull
+/// vertices towards the camera along the view ray by `constant + slope_scale * slope` world units,
+/// where `slope` is the tangent of the angle between the surface and the view direction.
+/// The slope term separates coplanar geometry further at grazing angles, where z-fighting is worst.
+/// Instances without this component aren't biased.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct InstanceDepthBias {
+    /// Constant offset towards the camera, in world units
+    pub constant: f32,
+    /// Offset towards the camera per unit of surface slope relative to the view direction
+    pub slope_scale: f32,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Component)]
 pub struct MeshInstance {
     pub mesh: Handle<Mesh>,
     pub transform: Mat4,
+    pub seed: u32,
+    pub depth_bias: InstanceDepthBias,
+}
+
+impl MeshInstance {
+    /// Converts this instance into its GPU representation, drawing the mesh at index `mesh` of its batch
+    ///
+    /// Independent of the ECS, i.e. for baking instance buffers offline.
+    ///
+    /// Singular transforms, such as those of hidden instances, get a zero inverse-transpose
+    /// instead of the non-finite one their inverse would produce.
+    pub fn to_gpu(&self, mesh: u32) -> GpuMeshInstance {
+        let inverse_transpose_model = if self.transform.determinant() != 0.0 {
+            self.transform.inverse().transpose()
+        } else {
+            Mat4::ZERO
+        };
+
+        GpuMeshInstance {
+            mesh,
+            seed: self.seed,
+            depth_bias: self.depth_bias.constant,
+            depth_bias_slope_scale: self.depth_bias.slope_scale,
+            transform: self.transform,
+            inverse_transpose_model,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, ShaderType, Component)]
 pub struct GpuMeshInstance {
     #[size(4)]
     pub mesh: u32,
+    #[size(4)]
+    pub seed: u32,
+    #[size(4)]
+    pub depth_bias: f32,
+    #[size(4)]
+    pub depth_bias_slope_scale: f32,
     #[size(64)]
     pub transform: Mat4,
     #[size(64)]
     pub inverse_transpose_model: Mat4,
 }
 
+impl CulledInstance for GpuMeshInstance {
+    const TRANSFORM_OFFSET: u64 = Self::METADATA.offset(4);
+}
+
+impl GpuMeshInstance {
+    /// Float fields in declaration order
+    fn floats(&self) -> impl Iterator<Item = f32> {
+        [self.depth_bias, self.depth_bias_slope_scale]
+            .into_iter()
+            .chain(self.transform.to_cols_array())
+            .chain(self.inverse_transpose_model.to_cols_array())
+    }
+}
+
+// Structural, comparing floats by their bits so instances can be hashed and totally ordered,
+// i.e. `0.0` and `-0.0` differ while identical NaNs are equal
 impl PartialEq for GpuMeshInstance {
     fn eq(&self, other: &Self) -> bool {
         self.mesh == other.mesh
+            && self.seed == other.seed
+            && self
+                .floats()
+                .map(f32::to_bits)
+                .eq(other.floats().map(f32::to_bits))
     }
 }
 
 impl Eq for GpuMeshInstance {}
 
+impl Hash for GpuMeshInstance {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.mesh.hash(state);
+        self.seed.hash(state);
+        for float in self.floats() {
+            float.to_bits().hash(state);
+        }
+    }
+}
+
 impl PartialOrd for GpuMeshInstance {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.mesh.partial_cmp(&other.mesh)
+        Some(self.cmp(other))
     }
 }
 
+/// Orders by mesh, then seed, then each float field in declaration order by [`f32::total_cmp`]
 impl Ord for GpuMeshInstance {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.mesh.cmp(&other.mesh)
+        self.mesh
+            .cmp(&other.mesh)
+            .then(self.seed.cmp(&other.seed))
+            .then_with(|| {
+                self.floats()
+                    .zip(other.floats())
+                    .map(|(lhs, rhs)| lhs.total_cmp(&rhs))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
     }
 }
 
@@ -53,44 +183,60 @@ impl Default for GpuMeshInstance {
     fn default() -> Self {
         Self {
             mesh: default(),
+            seed: default(),
+            depth_bias: default(),
+            depth_bias_slope_scale: default(),
             transform: Mat4::ZERO,
             inverse_transpose_model: Mat4::ZERO,
         }
     }
 }
 
+// Layout is mirrored by hand in `instance_struct.wgsl` and the structs embedding this one
+const _: () = assert!(
+    GpuMeshInstance::SHADER_SIZE.get() == 144,
+    "GpuMeshInstance size changed, update the #[size] of embedding structs"
+);
+const _: () = assert!(
+    MeshInstance::UNIFORM_BUFFER_LENGTH.get() == 113,
+    "Uniform buffer length changed, update the Instances array in instance_struct.wgsl"
+);
+
 impl Instance for MeshInstance {
     type ExtractedInstance = Self;
     type PreparedInstance = GpuMeshInstance;
 
     type Query = (
+        Entity,
         Read<Handle<Mesh>>,
         Read<GlobalTransform>,
         Read<ComputedVisibility>,
+        Option<Read<InstanceSeed>>,
+        Option<Read<InstanceDepthBias>>,
     );
 
+    /// Reads the entity's [`GlobalTransform`], so instances parented to other entities
+    /// (instanced or not) are rendered at their propagated world-space transform.
     fn extract_instance<'w>(
-        (mesh, transform, visibility): ROQueryItem<Self::Query>,
+        (entity, mesh, transform, visibility, seed, depth_bias): ROQueryItem<Self::Query>,
     ) -> Self::ExtractedInstance {
         let transform = if visibility.is_visible() {
             transform.compute_matrix()
         } else {
-            Mat4::ZERO
+            // Collapse hidden instances to a point at the origin
+            Mat4::from_scale(Vec3::ZERO)
         };
 
         MeshInstance {
             mesh: mesh.clone_weak(),
             transform,
+            seed: seed.map(|seed| seed.0).unwrap_or_else(|| entity.index()),
+            depth_bias: depth_bias.copied().unwrap_or_default(),
         }
     }
 
     fn prepare_instance(instance: &Self::ExtractedInstance, mesh: u32) -> Self::PreparedInstance {
-        GpuMeshInstance {
-            mesh,
-            transform: instance.transform,
-            inverse_transpose_model: instance.transform.inverse().transpose(),
-            ..default()
-        }
+        instance.to_gpu(mesh)
     }
 
     fn transform(instance: &Self::ExtractedInstance) -> Mat4 {
@@ -98,21 +244,279 @@ impl Instance for MeshInstance {
     }
 }
 
-/// Tag type for material-independent identification of instances
-#[derive(Debug, Default, Copy, Clone, Component)]
-pub struct ExtractedInstance;
+/// Instance of material `M` extracted into [`ExtractedInstances`]
+pub struct ExtractedMeshInstance<M: MaterialInstanced> {
+    pub instance: <M::Instance as Instance>::ExtractedInstance,
+    /// Main-world [`Aabb`] of the instance
+    ///
+    /// Bevy computes an [`Aabb`] for each mesh entity that lacks one, so users can attach their own
+    /// to override the bounds of an instance, i.e. one whose vertex shader displaces it beyond its mesh.
+    /// GPU instance culling tests against these bounds, falling back to the mesh's when absent.
+    pub aabb: Option<Aabb>,
+}
+
+/// Instances of material `M` extracted this frame, keyed by their entity
+///
+/// Refilled in place every frame instead of being inserted onto render world entities,
+/// so extraction costs no per-instance commands and the arena keeps its capacity between frames.
+#[derive(Resource, Deref, DerefMut)]
+pub struct ExtractedInstances<M: MaterialInstanced>(pub HashMap<Entity, ExtractedMeshInstance<M>>);
+
+impl<M: MaterialInstanced> Default for ExtractedInstances<M> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+/// Opts an entity out of instanced rendering,
+/// i.e. to draw it through bevy's regular material pipeline instead
+#[derive(Debug, Default, Copy, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct NotInstanced;
+
+/// Excludes an instance from the main pass while keeping it in shadow passes,
+/// i.e. for low-poly shadow proxies standing in for a detailed visible mesh
+///
+/// The inverse of bevy's `NotShadowCaster`.
+/// Marked instances are batched separately and never queued into the main pass' phases,
+/// but are still drawn into the shadow maps of the lights whose tiles they're visible to.
+#[derive(Debug, Default, Copy, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct ShadowOnly;
+
+impl ExtractComponent for ShadowOnly {
+    type Query = Read<Self>;
 
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Opts an instance out of per-instance frustum culling, mirroring bevy's `NoFrustumCulling`
+///
+/// Instances are culled on the CPU by their bounding sphere when batched for each view,
+/// and on the GPU by [`InstanceCullingPlugin`](crate::prelude::InstanceCullingPlugin) where added.
+/// Marked instances are treated as unbounded by both,
+/// i.e. for instances whose vertex shader moves them outside their bounds.
+#[derive(Debug, Default, Copy, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct NoInstanceCulling;
+
+impl ExtractComponent for NoInstanceCulling {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Extracts instances of material `M` into [`ExtractedInstances`]
+///
+/// Their render world entities are still spawned by the extraction of their material
+/// and mesh handles, which batches and views refer to them through.
+///
+/// Only entities holding a `Handle<M>` are extracted,
+/// so each material's arena holds its own instances rather than every mesh entity in the world.
+///
+/// The arena is cleared before it's refilled, so instances despawned in the main world
+/// never linger in the next frame's view or batch data.
+#[allow(clippy::type_complexity)]
 pub fn extract_mesh_instances<M: MaterialInstanced>(
-    query_mesh_instance: Extract<Query<(Entity, <M::Instance as Instance>::Query)>>,
-    mut commands: Commands,
+    query_mesh_instance: Extract<
+        Query<
+            (Entity, <M::Instance as Instance>::Query, Option<&Aabb>),
+            (With<Handle<M>>, Without<NotInstanced>),
+        >,
+    >,
+    mut extracted_instances: ResMut<ExtractedInstances<M>>,
 ) {
-    for (entity, item) in query_mesh_instance.iter() {
-        commands.insert_or_spawn_batch([(
+    extracted_instances.clear();
+    extracted_instances.extend(query_mesh_instance.iter().map(|(entity, item, aabb)| {
+        (
             entity,
-            (
-                ExtractedInstance,
-                <M::Instance as Instance>::extract_instance(item),
+            ExtractedMeshInstance {
+                instance: <M::Instance as Instance>::extract_instance(item),
+                aabb: aabb.cloned(),
+            },
+        )
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::AssetPlugin,
+        hierarchy::{BuildWorldChildren, HierarchyPlugin},
+        math::{Mat4, Quat, Vec3, Vec4},
+        prelude::{
+            default, AddAsset, App, Camera, Handle, Mesh, SpatialBundle, Stage, SystemStage,
+            Transform, World,
+        },
+        render::{
+            primitives::Frustum,
+            view::{VisibilityPlugin, VisibleEntities},
+            MainWorld,
+        },
+        time::TimePlugin,
+        transform::TransformPlugin,
+    };
+
+    use crate::prelude::{BasicMaterial, MeshInstanceBundle};
+
+    use super::{extract_mesh_instances, ExtractedInstances, InstanceDepthBias, MeshInstance};
+
+    #[test]
+    fn to_gpu_inverts_and_transposes_transform() {
+        let instance = MeshInstance {
+            transform: Mat4::from_scale_rotation_translation(
+                Vec3::new(2.0, 4.0, 8.0),
+                Quat::IDENTITY,
+                Vec3::new(1.0, 2.0, 3.0),
             ),
-        )])
+            seed: 7,
+            depth_bias: InstanceDepthBias {
+                constant: 0.5,
+                slope_scale: 2.0,
+            },
+            ..default()
+        };
+
+        let gpu = instance.to_gpu(3);
+
+        assert_eq!(gpu.mesh, 3);
+        assert_eq!(gpu.seed, 7);
+        assert_eq!(gpu.depth_bias, 0.5);
+        assert_eq!(gpu.depth_bias_slope_scale, 2.0);
+        assert_eq!(gpu.transform, instance.transform);
+
+        let expected = Mat4::from_cols(
+            Vec4::new(0.5, 0.0, 0.0, -0.5),
+            Vec4::new(0.0, 0.25, 0.0, -0.5),
+            Vec4::new(0.0, 0.0, 0.125, -0.375),
+            Vec4::W,
+        );
+        assert!(gpu.inverse_transpose_model.abs_diff_eq(expected, 1e-6));
+    }
+
+    #[test]
+    fn to_gpu_zeroes_singular_inverse_transpose() {
+        for transform in [Mat4::ZERO, Mat4::from_scale(Vec3::new(1.0, 0.0, 1.0))] {
+            let gpu = MeshInstance {
+                transform,
+                ..default()
+            }
+            .to_gpu(0);
+
+            assert_eq!(gpu.inverse_transpose_model, Mat4::ZERO);
+        }
+    }
+
+    /// Runs a frame of `app`, then extracts its instances the way the render app would
+    fn update_and_extract(app: &mut App, render_world: &mut World) -> Mat4 {
+        app.update();
+
+        let mut main_world = MainWorld::default();
+        std::mem::swap(&mut *main_world, &mut app.world);
+        render_world.insert_resource(main_world);
+
+        SystemStage::single_threaded()
+            .with_system(extract_mesh_instances::<BasicMaterial>)
+            .run(render_world);
+
+        let mut main_world = render_world.remove_resource::<MainWorld>().unwrap();
+        std::mem::swap(&mut *main_world, &mut app.world);
+
+        let extracted_instances = render_world.resource::<ExtractedInstances<BasicMaterial>>();
+        assert_eq!(extracted_instances.len(), 1);
+        extracted_instances
+            .values()
+            .next()
+            .unwrap()
+            .instance
+            .transform
+    }
+
+    /// App propagating transforms and visibility to instances, with a view for them to be visible to
+    fn instancing_app() -> App {
+        let mut app = App::new();
+        app.add_plugin(TimePlugin)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(VisibilityPlugin);
+
+        // Instances are only visible while some view can see them
+        app.world.spawn((
+            Camera::default(),
+            Frustum::default(),
+            VisibleEntities::default(),
+        ));
+
+        app
+    }
+
+    #[test]
+    fn child_instance_extracts_propagated_transform() {
+        let mut app = instancing_app();
+
+        let parent_transform = Transform::from_xyz(1.0, 2.0, 3.0);
+        let child_transform = Transform::from_xyz(0.0, 1.0, 0.0).with_scale(Vec3::splat(2.0));
+
+        let parent = app
+            .world
+            .spawn(SpatialBundle::from_transform(parent_transform))
+            .with_children(|parent| {
+                parent.spawn(MeshInstanceBundle::<BasicMaterial> {
+                    spatial_bundle: SpatialBundle::from_transform(child_transform),
+                    ..default()
+                });
+            })
+            .id();
+
+        let mut render_world = World::new();
+        render_world.init_resource::<ExtractedInstances<BasicMaterial>>();
+
+        assert_eq!(
+            update_and_extract(&mut app, &mut render_world),
+            parent_transform.compute_matrix() * child_transform.compute_matrix()
+        );
+
+        // Moving the parent moves the extracted child along with it
+        let parent_transform = Transform::from_xyz(-4.0, 0.0, 0.0);
+        *app.world.get_mut::<Transform>(parent).unwrap() = parent_transform;
+
+        assert_eq!(
+            update_and_extract(&mut app, &mut render_world),
+            parent_transform.compute_matrix() * child_transform.compute_matrix()
+        );
+    }
+    #[test]
+    fn extracts_only_instances_of_material() {
+        let mut app = instancing_app();
+
+        let instance_transform = Transform::from_xyz(1.0, 0.0, 0.0);
+        app.world.spawn(MeshInstanceBundle::<BasicMaterial> {
+            spatial_bundle: SpatialBundle::from_transform(instance_transform),
+            ..default()
+        });
+
+        // A mesh entity without a `Handle<BasicMaterial>`, i.e. one drawn by another material
+        app.world.spawn((
+            SpatialBundle::from_transform(Transform::from_xyz(-1.0, 0.0, 0.0)),
+            Handle::<Mesh>::default(),
+        ));
+
+        let mut render_world = World::new();
+        render_world.init_resource::<ExtractedInstances<BasicMaterial>>();
+
+        assert_eq!(
+            update_and_extract(&mut app, &mut render_world),
+            instance_transform.compute_matrix()
+        );
     }
 }
@@ -1,7 +1,21 @@
+pub mod batch_debug;
+pub mod clip_plane;
+pub mod compact_instance;
+pub mod draw_call_count;
+pub mod draw_distance;
+pub mod eviction;
+pub mod gpu_timing;
 pub mod indirect;
+pub mod instance_index;
+pub mod instance_data;
 pub mod instance_slice;
 pub mod material;
 pub mod mesh_instance;
+pub mod ndc_instance;
+pub mod oit;
 pub mod plugin;
 pub mod render;
+pub mod shadow_tile;
+pub mod sort;
 pub mod instance_compute;
+pub mod instance_culling;
@@ -0,0 +1,50 @@
+use bevy::{ecs::query::ROQueryItem, math::Mat4, prelude::Component};
+
+use crate::prelude::{GpuMeshInstance, Instance, MeshInstance};
+
+/// Mesh instance positioned directly in normalized device coordinates
+///
+/// The entity's [`GlobalTransform`](bevy::prelude::GlobalTransform) is used as the
+/// clip-space transform, bypassing the view projection. X and Y span `-1..1`
+/// across the viewport and Z is reversed, so `1.0` is the near plane.
+///
+/// Since bevy's frustum culling and instance culling assume world-space bounds,
+/// entities using this instance type should also carry
+/// [`NoFrustumCulling`](bevy::render::view::NoFrustumCulling) and
+/// [`NoInstanceCulling`](crate::prelude::NoInstanceCulling).
+#[derive(Debug, Default, Clone, PartialEq, Component)]
+pub struct NdcInstance {
+    pub base: MeshInstance,
+}
+
+impl Instance for NdcInstance {
+    type ExtractedInstance = Self;
+    type PreparedInstance = GpuMeshInstance;
+
+    type Query = <MeshInstance as Instance>::Query;
+
+    fn extract_instance(instance: ROQueryItem<Self::Query>) -> Self::ExtractedInstance {
+        NdcInstance {
+            base: MeshInstance::extract_instance(instance),
+        }
+    }
+
+    fn prepare_instance(instance: &Self::ExtractedInstance, mesh: u32) -> Self::PreparedInstance {
+        GpuMeshInstance {
+            mesh,
+            seed: instance.base.seed,
+            depth_bias: instance.base.depth_bias.constant,
+            depth_bias_slope_scale: instance.base.depth_bias.slope_scale,
+            transform: instance.base.transform,
+            inverse_transpose_model: Mat4::IDENTITY,
+        }
+    }
+
+    fn transform(instance: &Self::ExtractedInstance) -> Mat4 {
+        instance.base.transform
+    }
+
+    fn shader_defs() -> Vec<String> {
+        vec![String::from("NDC_INSTANCE")]
+    }
+}
@@ -0,0 +1,540 @@
+use bevy::{
+    asset::load_internal_asset,
+    core_pipeline::{
+        core_3d::{self, Camera3d},
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    },
+    ecs::query::QueryState,
+    prelude::{
+        default, App, Camera, Color, Commands, Component, Entity, FromWorld, HandleUntyped, Msaa,
+        Plugin, Query, Res, ResMut, Resource, Shader, With, World,
+    },
+    reflect::TypeUuid,
+    render::{
+        camera::ExtractedCamera,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo, SlotType},
+        render_phase::{
+            CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions, EntityPhaseItem,
+            PhaseItem, RenderPhase, TrackedRenderPass,
+        },
+        render_resource::{
+            BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType,
+            BlendComponent, BlendFactor, BlendOperation, BlendState, CachedRenderPipelineId,
+            ColorTargetState, ColorWrites, Extent3d, FragmentState, LoadOp, MultisampleState,
+            Operations, PipelineCache, PrimitiveState, RenderPassColorAttachment,
+            RenderPassDepthStencilAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
+            ShaderStages, SpecializedRenderPipeline, SpecializedRenderPipelines, TextureDescriptor,
+            TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+            TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::{CachedTexture, TextureCache},
+        view::{ExtractedView, ViewDepthTexture, ViewTarget},
+        Extract, RenderApp, RenderStage,
+    },
+    utils::FloatOrd,
+};
+
+/// `indirect_instancing::oit`, providing the `OitOutput` fragment output
+/// of materials drawn with order-independent transparency
+pub const OIT_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 5720813946250117093);
+
+pub const OIT_RESOLVE_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 16402837709148365031);
+
+/// Name of the `core_3d` graph node accumulating and resolving [`OrderIndependent3d`] items
+pub const ORDER_INDEPENDENT_PASS: &str = "instanced_order_independent_pass";
+
+/// Format of the weighted premultiplied color accumulation target
+pub const OIT_ACCUM_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Format of the revealage target, the product of `1 - alpha` over every fragment
+pub const OIT_REVEALAGE_FORMAT: TextureFormat = TextureFormat::R16Float;
+
+/// Weighted blended order-independent transparency for instanced materials,
+/// see [`MaterialInstanced::order_independent_transparency`](crate::prelude::MaterialInstanced::order_independent_transparency)
+///
+/// Transparent batches are accumulated into two targets after the main pass,
+/// then composited over the view target by a full-screen resolve pass.
+/// Added by [`IndirectRenderingPlugin`](crate::prelude::IndirectRenderingPlugin).
+#[derive(Debug, Default, Copy, Clone)]
+pub struct OrderIndependentTransparencyPlugin;
+
+impl Plugin for OrderIndependentTransparencyPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, OIT_SHADER_HANDLE, "oit.wgsl", Shader::from_wgsl);
+
+        load_internal_asset!(
+            app,
+            OIT_RESOLVE_SHADER_HANDLE,
+            "oit_resolve.wgsl",
+            Shader::from_wgsl
+        );
+
+        let render_app = if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+        } else {
+            return;
+        };
+
+        render_app
+            .init_resource::<DrawFunctions<OrderIndependent3d>>()
+            .init_resource::<OitResolvePipeline>()
+            .init_resource::<SpecializedRenderPipelines<OitResolvePipeline>>()
+            .add_system_to_stage(RenderStage::Extract, extract_order_independent_phases)
+            // Runs after queueing, so targets are only allocated for views with items to draw
+            .add_system_to_stage(RenderStage::PhaseSort, prepare_oit_targets);
+
+        let order_independent_pass = OrderIndependentPassNode::new(&mut render_app.world);
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        if let Some(core_3d) = render_graph.get_sub_graph_mut(core_3d::graph::NAME) {
+            let input_node_id = core_3d.input_node().unwrap().id;
+            core_3d.add_node(ORDER_INDEPENDENT_PASS, order_independent_pass);
+            core_3d
+                .add_slot_edge(
+                    input_node_id,
+                    core_3d::graph::input::VIEW_ENTITY,
+                    ORDER_INDEPENDENT_PASS,
+                    OrderIndependentPassNode::IN_VIEW,
+                )
+                .unwrap();
+            core_3d
+                .add_node_edge(core_3d::graph::node::MAIN_PASS, ORDER_INDEPENDENT_PASS)
+                .unwrap();
+            core_3d
+                .add_node_edge(ORDER_INDEPENDENT_PASS, core_3d::graph::node::TONEMAPPING)
+                .unwrap();
+        }
+    }
+}
+
+/// Instanced batch drawn with weighted blended order-independent transparency
+///
+/// Fragments are accumulated commutatively, so items are drawn in queue order without sorting.
+pub struct OrderIndependent3d {
+    pub distance: f32,
+    pub pipeline: CachedRenderPipelineId,
+    pub entity: Entity,
+    pub draw_function: DrawFunctionId,
+}
+
+impl PhaseItem for OrderIndependent3d {
+    type SortKey = FloatOrd;
+
+    #[inline]
+    fn sort_key(&self) -> Self::SortKey {
+        FloatOrd(self.distance)
+    }
+
+    #[inline]
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+}
+
+impl EntityPhaseItem for OrderIndependent3d {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for OrderIndependent3d {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
+pub fn extract_order_independent_phases(
+    cameras_3d: Extract<Query<(Entity, &Camera), With<Camera3d>>>,
+    mut commands: Commands,
+) {
+    for (entity, camera) in cameras_3d.iter() {
+        if camera.is_active {
+            commands
+                .get_or_spawn(entity)
+                .insert(RenderPhase::<OrderIndependent3d>::default());
+        }
+    }
+}
+
+/// Full-screen pipeline compositing a view's accumulation targets over its view target
+#[derive(Resource)]
+pub struct OitResolvePipeline {
+    pub layout: BindGroupLayout,
+}
+
+impl FromWorld for OitResolvePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: false },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("oit resolve bind group layout"),
+            entries: &[texture_entry(0), texture_entry(1)],
+        });
+
+        OitResolvePipeline { layout }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct OitResolvePipelineKey {
+    pub format: TextureFormat,
+    pub samples: u32,
+}
+
+impl SpecializedRenderPipeline for OitResolvePipeline {
+    type Key = OitResolvePipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("oit resolve pipeline".into()),
+            layout: Some(vec![self.layout.clone()]),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: OIT_RESOLVE_SHADER_HANDLE.typed(),
+                shader_defs: vec![],
+                entry_point: "resolve".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: key.samples,
+                ..default()
+            },
+        }
+    }
+}
+
+/// Color targets of instanced materials drawn in the order-independent pass,
+/// matching the `OitOutput` fragment output
+pub fn oit_color_targets() -> Vec<Option<ColorTargetState>> {
+    let additive = BlendComponent {
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::One,
+        operation: BlendOperation::Add,
+    };
+
+    let revealage = BlendComponent {
+        src_factor: BlendFactor::Zero,
+        dst_factor: BlendFactor::OneMinusSrc,
+        operation: BlendOperation::Add,
+    };
+
+    vec![
+        Some(ColorTargetState {
+            format: OIT_ACCUM_FORMAT,
+            blend: Some(BlendState {
+                color: additive,
+                alpha: additive,
+            }),
+            write_mask: ColorWrites::ALL,
+        }),
+        Some(ColorTargetState {
+            format: OIT_REVEALAGE_FORMAT,
+            blend: Some(BlendState {
+                color: revealage,
+                alpha: revealage,
+            }),
+            write_mask: ColorWrites::ALL,
+        }),
+    ]
+}
+
+/// Accumulation targets and resolve state of a view with [`OrderIndependent3d`] items
+#[derive(Component)]
+pub struct ViewOitTargets {
+    pub accum: CachedTexture,
+    pub revealage: CachedTexture,
+    /// Single-sampled accumulation and revealage targets, when multisampling
+    pub sampled: Option<(CachedTexture, CachedTexture)>,
+    pub resolve_pipeline: CachedRenderPipelineId,
+    pub resolve_bind_group: BindGroup,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_oit_targets(
+    render_device: Res<RenderDevice>,
+    msaa: Res<Msaa>,
+    resolve_pipeline: Res<OitResolvePipeline>,
+    mut texture_cache: ResMut<TextureCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<OitResolvePipeline>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    query_views: Query<(
+        Entity,
+        &ExtractedCamera,
+        &ViewTarget,
+        &RenderPhase<OrderIndependent3d>,
+    )>,
+    mut commands: Commands,
+) {
+    for (entity, camera, target, phase) in query_views.iter() {
+        if phase.items.is_empty() {
+            continue;
+        }
+
+        let size = if let Some(size) = camera.physical_target_size {
+            size
+        } else {
+            continue;
+        };
+
+        let mut texture = |label, format, sample_count, usage| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width: size.x,
+                        height: size.y,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage,
+                },
+            )
+        };
+
+        // Multisampled targets can't be bound to the resolve pass directly,
+        // so they're resolved into single-sampled copies at the end of the accumulation pass
+        let sampled_usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING;
+        let (accum, revealage, sampled) = if msaa.samples > 1 {
+            (
+                texture(
+                    "oit_accum_multisampled_texture",
+                    OIT_ACCUM_FORMAT,
+                    msaa.samples,
+                    TextureUsages::RENDER_ATTACHMENT,
+                ),
+                texture(
+                    "oit_revealage_multisampled_texture",
+                    OIT_REVEALAGE_FORMAT,
+                    msaa.samples,
+                    TextureUsages::RENDER_ATTACHMENT,
+                ),
+                Some((
+                    texture("oit_accum_texture", OIT_ACCUM_FORMAT, 1, sampled_usage),
+                    texture(
+                        "oit_revealage_texture",
+                        OIT_REVEALAGE_FORMAT,
+                        1,
+                        sampled_usage,
+                    ),
+                )),
+            )
+        } else {
+            (
+                texture("oit_accum_texture", OIT_ACCUM_FORMAT, 1, sampled_usage),
+                texture(
+                    "oit_revealage_texture",
+                    OIT_REVEALAGE_FORMAT,
+                    1,
+                    sampled_usage,
+                ),
+                None,
+            )
+        };
+
+        let (accum_view, revealage_view) = match &sampled {
+            Some((accum, revealage)) => (&accum.default_view, &revealage.default_view),
+            None => (&accum.default_view, &revealage.default_view),
+        };
+
+        let resolve_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("oit resolve bind group"),
+            layout: &resolve_pipeline.layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(accum_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(revealage_view),
+                },
+            ],
+        });
+
+        let resolve_pipeline = pipelines.specialize(
+            &mut pipeline_cache,
+            &resolve_pipeline,
+            OitResolvePipelineKey {
+                format: target.main_texture_format(),
+                samples: msaa.samples,
+            },
+        );
+
+        commands.entity(entity).insert(ViewOitTargets {
+            accum,
+            revealage,
+            sampled,
+            resolve_pipeline,
+            resolve_bind_group,
+        });
+    }
+}
+
+/// Accumulates a view's [`OrderIndependent3d`] items, then composites them over its target
+#[allow(clippy::type_complexity)]
+pub struct OrderIndependentPassNode {
+    query: QueryState<
+        (
+            &'static ExtractedCamera,
+            &'static RenderPhase<OrderIndependent3d>,
+            &'static ViewTarget,
+            &'static ViewDepthTexture,
+            &'static ViewOitTargets,
+        ),
+        With<ExtractedView>,
+    >,
+}
+
+impl OrderIndependentPassNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: world.query_filtered(),
+        }
+    }
+}
+
+impl Node for OrderIndependentPassNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let (camera, phase, target, depth, oit_targets) =
+            match self.query.get_manual(world, view_entity) {
+                Ok(query) => query,
+                Err(_) => return Ok(()),
+            };
+
+        if phase.items.is_empty() {
+            return Ok(());
+        }
+
+        // Skip accumulating until there's a pipeline to resolve it with
+        let resolve_pipeline = if let Some(resolve_pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(oit_targets.resolve_pipeline)
+        {
+            resolve_pipeline
+        } else {
+            return Ok(());
+        };
+
+        {
+            let pass_descriptor = RenderPassDescriptor {
+                label: Some("instanced_oit_accumulate_pass"),
+                color_attachments: &[
+                    Some(RenderPassColorAttachment {
+                        view: &oit_targets.accum.default_view,
+                        resolve_target: oit_targets
+                            .sampled
+                            .as_ref()
+                            .map(|(accum, _)| &*accum.default_view),
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::NONE.into()),
+                            store: true,
+                        },
+                    }),
+                    Some(RenderPassColorAttachment {
+                        view: &oit_targets.revealage.default_view,
+                        resolve_target: oit_targets
+                            .sampled
+                            .as_ref()
+                            .map(|(_, revealage)| &*revealage.default_view),
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::WHITE.into()),
+                            store: true,
+                        },
+                    }),
+                ],
+                // Opaque geometry occludes transparent fragments, which don't write depth.
+                // Stored as a workaround for https://github.com/bevyengine/bevy/issues/3776
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &depth.view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            };
+
+            let render_pass = render_context
+                .command_encoder
+                .begin_render_pass(&pass_descriptor);
+            let mut tracked_pass = TrackedRenderPass::new(render_pass);
+            if let Some(viewport) = camera.viewport.as_ref() {
+                tracked_pass.set_camera_viewport(viewport);
+            }
+
+            let draw_functions = world.resource::<DrawFunctions<OrderIndependent3d>>();
+            let mut draw_functions = draw_functions.write();
+            for item in &phase.items {
+                let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
+                draw_function.draw(world, &mut tracked_pass, view_entity, item);
+            }
+        }
+
+        {
+            let pass_descriptor = RenderPassDescriptor {
+                label: Some("instanced_oit_resolve_pass"),
+                color_attachments: &[Some(target.get_color_attachment(Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                }))],
+                depth_stencil_attachment: None,
+            };
+
+            let render_pass = render_context
+                .command_encoder
+                .begin_render_pass(&pass_descriptor);
+            let mut tracked_pass = TrackedRenderPass::new(render_pass);
+            if let Some(viewport) = camera.viewport.as_ref() {
+                tracked_pass.set_camera_viewport(viewport);
+            }
+
+            tracked_pass.set_render_pipeline(resolve_pipeline);
+            tracked_pass.set_bind_group(0, &oit_targets.resolve_bind_group, &[]);
+            tracked_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
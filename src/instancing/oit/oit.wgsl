@@ -0,0 +1,25 @@
+#define_import_path indirect_instancing::oit
+
+// Outputs of materials drawn with weighted blended order-independent transparency,
+// accumulated into the view's OIT targets and composited by the resolve pass
+struct OitOutput {
+    @location(0) accum: vec4<f32>,
+    @location(1) revealage: f32,
+};
+
+// Depth weight from McGuire & Bavoil 2013, equation 9,
+// favoring surfaces near the camera so they dominate the blend
+fn oit_weight(view_depth: f32, alpha: f32) -> f32 {
+    let a = view_depth / 5.0;
+    let b = view_depth / 200.0;
+    let b3 = b * b * b;
+    return alpha * clamp(10.0 / (1e-5 + a * a + b3 * b3), 1e-2, 3e3);
+}
+
+// Weights a straight-alpha color by its distance from the camera
+fn oit_output(color: vec4<f32>, view_depth: f32) -> OitOutput {
+    var out: OitOutput;
+    out.accum = vec4<f32>(color.rgb * color.a, color.a) * oit_weight(view_depth, color.a);
+    out.revealage = color.a;
+    return out;
+}
@@ -1,16 +1,26 @@
 use bevy::{
     asset::load_internal_asset,
-    prelude::{App, HandleUntyped, IntoSystemDescriptor, Plugin, Shader},
+    prelude::{warn, App, CoreStage, HandleUntyped, IntoSystemDescriptor, Plugin, Shader},
     reflect::TypeUuid,
     render::{
-        extract_component::ExtractComponentPlugin, render_asset::PrepareAssetLabel, RenderApp,
-        RenderStage,
+        extract_component::ExtractComponentPlugin, extract_resource::ExtractResourcePlugin,
+        render_asset::PrepareAssetLabel, renderer::RenderDevice, RenderApp, RenderStage,
     },
 };
 
 use crate::{
     instancing::material::systems::prepare_mesh_batches::{self, MeshBatches},
-    prelude::{InstanceSlice, InstancedMeshPipeline},
+    prelude::{
+        finish_draw_call_count, prepare_view_clip_planes, storage_buffers_available,
+        sync_instance_indices, sync_instance_slice_ranges, AllocatedInstanceSliceRanges,
+        AlwaysOnTop, CompactInstancePlugin, DrawDistanceFade, ForceBatchGroup, InstanceBatchDebug,
+        InstanceClipPlane, InstanceComputeGroup, InstanceDepthBias, InstanceEvictionSettings,
+        InstanceFade, InstanceIndexSettings, InstanceIndices, InstanceLayer, InstanceSeed,
+        InstanceShadowTilePlugin, InstanceSlice, InstanceSliceRange, InstanceSortSettings,
+        InstancedMeshPipeline, InstancingDrawCallCount, MaxDrawDistance, NoInstanceCulling,
+        NotInstanced, OrderIndependentTransparencyPlugin, PublishedInstanceIndices, ShadowOnly,
+        SharedInstanceSlicePlugin, SingleInstanceSettings, UniformSplitSettings, ViewClipPlanes,
+    },
 };
 
 pub const INSTANCED_MESH_SHADER_HANDLE: HandleUntyped =
@@ -22,6 +32,26 @@ pub const INSTANCE_STRUCT_HANDLE: HandleUntyped =
 pub const INDIRECT_STRUCT_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 7281773422344927676);
 
+pub const INSTANCE_CLIP_PLANE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 3318542697318840917);
+
+/// `indirect_instancing::instance_depth_bias`, applying [`InstanceDepthBias`] in vertex shaders
+pub const INSTANCE_DEPTH_BIAS_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2690349151846732714);
+
+/// `indirect_instancing::instance_count`, exposing the total instance count
+/// of the batch being drawn to instanced shaders as `instance_count`
+pub const INSTANCE_COUNT_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 9164021837490415523);
+
+/// `indirect_instancing::flat_normal`, deriving face normals for meshes without a normal attribute,
+/// which instanced pipelines specialize with the `FLAT_NORMALS` shader def
+pub const FLAT_NORMAL_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 5830174295563012947);
+
+pub const BATCH_DEBUG_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 11904165287342261907);
+
 /// Plugin encapsulating instanced mesh rendering
 #[derive(Debug, Default, Copy, Clone)]
 pub struct IndirectRenderingPlugin;
@@ -49,16 +79,127 @@ impl Plugin for IndirectRenderingPlugin {
             Shader::from_wgsl
         );
 
-        app.register_type::<InstanceSlice>();
+        load_internal_asset!(
+            app,
+            INSTANCE_CLIP_PLANE_HANDLE,
+            "render/shaders/instance_clip_plane.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            INSTANCE_DEPTH_BIAS_HANDLE,
+            "render/shaders/instance_depth_bias.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            INSTANCE_COUNT_HANDLE,
+            "render/shaders/instance_count.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            FLAT_NORMAL_HANDLE,
+            "render/shaders/flat_normal.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            BATCH_DEBUG_SHADER_HANDLE,
+            "render/shaders/batch_debug.wgsl",
+            Shader::from_wgsl
+        );
+
+        if let Some(render_device) = app.world.get_resource::<RenderDevice>() {
+            if !storage_buffers_available(render_device) {
+                warn!("Storage buffers aren't supported by this device, so instance slices won't be computed or drawn");
+            }
+        }
+
+        app.add_plugin(OrderIndependentTransparencyPlugin)
+            .add_plugin(SharedInstanceSlicePlugin)
+            .add_plugin(CompactInstancePlugin)
+            .add_plugin(InstanceShadowTilePlugin);
+
+        app.register_type::<InstanceSlice>()
+            .register_type::<InstanceSeed>()
+            .register_type::<InstanceDepthBias>()
+            .register_type::<InstanceLayer>()
+            .register_type::<AlwaysOnTop>()
+            .register_type::<ForceBatchGroup>()
+            .register_type::<InstanceClipPlane>()
+            .register_type::<InstanceSliceRange>()
+            .register_type::<InstanceComputeGroup>()
+            .register_type::<NotInstanced>()
+            .register_type::<ShadowOnly>()
+            .register_type::<NoInstanceCulling>()
+            .register_type::<InstanceFade>();
+
+        app.add_plugin(ExtractComponentPlugin::<InstanceSlice>::default())
+            .add_plugin(ExtractComponentPlugin::<InstanceLayer>::default())
+            .add_plugin(ExtractComponentPlugin::<AlwaysOnTop>::default())
+            .add_plugin(ExtractComponentPlugin::<ForceBatchGroup>::default())
+            .add_plugin(ExtractComponentPlugin::<InstanceClipPlane>::default())
+            .add_plugin(ExtractComponentPlugin::<InstanceComputeGroup>::default())
+            .add_plugin(ExtractComponentPlugin::<ShadowOnly>::default())
+            .add_plugin(ExtractComponentPlugin::<NoInstanceCulling>::default())
+            .add_plugin(ExtractComponentPlugin::<InstanceFade>::default());
+
+        app.init_resource::<InstanceSortSettings>()
+            .add_plugin(ExtractResourcePlugin::<InstanceSortSettings>::default());
+
+        app.init_resource::<InstanceEvictionSettings>()
+            .add_plugin(ExtractResourcePlugin::<InstanceEvictionSettings>::default());
+
+        app.init_resource::<UniformSplitSettings>()
+            .add_plugin(ExtractResourcePlugin::<UniformSplitSettings>::default());
+
+        app.init_resource::<SingleInstanceSettings>()
+            .add_plugin(ExtractResourcePlugin::<SingleInstanceSettings>::default());
+
+        let allocated_ranges = AllocatedInstanceSliceRanges::default();
+        app.insert_resource(allocated_ranges.clone())
+            .add_system_to_stage(CoreStage::First, sync_instance_slice_ranges);
+
+        app.init_resource::<InstanceIndexSettings>()
+            .add_plugin(ExtractResourcePlugin::<InstanceIndexSettings>::default());
+
+        let published_indices = PublishedInstanceIndices::default();
+        app.init_resource::<InstanceIndices>()
+            .insert_resource(published_indices.clone())
+            .add_system_to_stage(CoreStage::First, sync_instance_indices);
+
+        app.init_resource::<MaxDrawDistance>()
+            .add_plugin(ExtractResourcePlugin::<MaxDrawDistance>::default());
+
+        app.init_resource::<DrawDistanceFade>()
+            .add_plugin(ExtractResourcePlugin::<DrawDistanceFade>::default());
 
-        app.add_plugin(ExtractComponentPlugin::<InstanceSlice>::default());
+        app.init_resource::<InstanceBatchDebug>()
+            .add_plugin(ExtractResourcePlugin::<InstanceBatchDebug>::default());
 
         app.sub_app_mut(RenderApp)
             .init_resource::<InstancedMeshPipeline>()
             .init_resource::<MeshBatches>()
+            .init_resource::<InstanceSortSettings>()
+            .init_resource::<InstanceEvictionSettings>()
+            .init_resource::<InstanceBatchDebug>()
+            .init_resource::<InstanceIndexSettings>()
+            .init_resource::<MaxDrawDistance>()
+            .init_resource::<DrawDistanceFade>()
+            .init_resource::<ViewClipPlanes>()
+            .init_resource::<InstancingDrawCallCount>()
+            .insert_resource(allocated_ranges)
+            .insert_resource(published_indices)
             .add_system_to_stage(
                 RenderStage::Prepare,
                 prepare_mesh_batches::system.after(PrepareAssetLabel::AssetPrepare),
-            );
+            )
+            .add_system_to_stage(RenderStage::Prepare, prepare_view_clip_planes)
+            .add_system_to_stage(RenderStage::Cleanup, finish_draw_call_count);
     }
 }
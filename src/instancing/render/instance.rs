@@ -22,10 +22,46 @@ pub trait Instance {
         + WriteInto;
     type Query: ReadOnlyWorldQuery;
 
+    /// Upper bound on the number of prepared instances a single entity can expand into
+    const MAX_EXPANSION: usize = 1;
+
     fn extract_instance(instance: ROQueryItem<Self::Query>) -> Self::ExtractedInstance;
     fn prepare_instance(instance: &Self::ExtractedInstance, mesh: u32) -> Self::PreparedInstance;
 
     fn transform(instance: &Self::ExtractedInstance) -> Mat4;
+
+    /// Number of prepared instances this entity expands into (i.e. segments of a trail).
+    /// Clamped to [`Instance::MAX_EXPANSION`].
+    #[allow(unused_variables)]
+    fn expansion(instance: &Self::ExtractedInstance) -> usize {
+        1
+    }
+
+    /// Prepares the `index`th of the instances this entity expands into
+    #[allow(unused_variables)]
+    fn prepare_expanded_instance(
+        instance: &Self::ExtractedInstance,
+        mesh: u32,
+        index: usize,
+    ) -> Self::PreparedInstance {
+        Self::prepare_instance(instance, mesh)
+    }
+
+    /// Scales the opacity of a prepared instance by `fade`, in `0.0..1.0`,
+    /// i.e. when it nears the [`MaxDrawDistance`](crate::prelude::MaxDrawDistance).
+    /// Defaults to doing nothing, for instance types without a color.
+    #[allow(unused_variables)]
+    fn fade(instance: &mut Self::PreparedInstance, fade: f32) {}
+
+    /// Shader defs added to the vertex and fragment stages of pipelines using this instance type
+    fn shader_defs() -> Vec<String> {
+        vec![]
+    }
+}
+
+/// The number of prepared instances an extracted instance occupies in the instance buffer
+pub fn expanded_len<T: Instance>(instance: &T::ExtractedInstance) -> usize {
+    T::expansion(instance).min(T::MAX_EXPANSION)
 }
 
 pub trait InstanceUniformLength: Instance {
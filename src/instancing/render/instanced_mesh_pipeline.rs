@@ -1,12 +1,12 @@
 use bevy::{
     pbr::{MeshPipeline, MeshPipelineKey},
-    prelude::{FromWorld, Shader, World, Resource},
+    prelude::{FromWorld, Mesh, Resource, Shader, Vec4, World},
     render::{
-        mesh::MeshVertexBufferLayout,
+        mesh::{MeshVertexAttribute, MeshVertexBufferLayout},
         render_resource::{
             BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
-            BufferBindingType, RenderPipelineDescriptor, ShaderStages, SpecializedMeshPipeline,
-            SpecializedMeshPipelineError,
+            BufferBindingType, RenderPipelineDescriptor, ShaderStages, ShaderType,
+            SpecializedMeshPipeline, SpecializedMeshPipelineError, VertexFormat,
         },
         renderer::RenderDevice,
     },
@@ -14,6 +14,13 @@ use bevy::{
 
 use crate::prelude::INSTANCED_MESH_SHADER_HANDLE;
 
+/// Secondary texture coordinates, i.e. for lightmaps
+///
+/// Meshes carrying this attribute expose it to instanced shaders at location 7,
+/// guarded by the `VERTEX_UVS_1` shader def.
+pub const ATTRIBUTE_UV_1: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_Uv_1", 2094650241, VertexFormat::Float32x2);
+
 /// Pipeline for rendering instanced meshes
 #[derive(Clone, Resource)]
 pub struct InstancedMeshPipeline {
@@ -35,16 +42,40 @@ impl FromWorld for InstancedMeshPipeline {
         let bind_group_layout =
             render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
                 label: Some("instanced mesh bind group"),
-                entries: &[BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::VERTEX,
-                    ty: BindingType::Buffer {
-                        ty: instance_buffer_binding_type,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::VERTEX,
+                        ty: BindingType::Buffer {
+                            ty: instance_buffer_binding_type,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // View clip plane
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(Vec4::min_size()),
+                        },
+                        count: None,
+                    },
+                    // Batch instance count
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::VERTEX_FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(u32::min_size()),
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                ],
             });
 
         InstancedMeshPipeline {
@@ -65,6 +96,36 @@ impl SpecializedMeshPipeline for InstancedMeshPipeline {
     ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
         let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
 
+        // Append attributes the mesh pipeline doesn't know about,
+        // after its own (which end at the skinning attributes)
+        if layout.contains(ATTRIBUTE_UV_1) {
+            let uv_1 = layout.get_layout(&[ATTRIBUTE_UV_1.at_shader_location(7)])?;
+            descriptor.vertex.buffers[0]
+                .attributes
+                .extend(uv_1.attributes);
+
+            let shader_def = String::from("VERTEX_UVS_1");
+            descriptor.vertex.shader_defs.push(shader_def.clone());
+            descriptor
+                .fragment
+                .as_mut()
+                .unwrap()
+                .shader_defs
+                .push(shader_def);
+        }
+
+        // Meshes without normals are shaded with face normals derived in the fragment shader
+        if !layout.contains(Mesh::ATTRIBUTE_NORMAL) {
+            let shader_def = String::from("FLAT_NORMALS");
+            descriptor.vertex.shader_defs.push(shader_def.clone());
+            descriptor
+                .fragment
+                .as_mut()
+                .unwrap()
+                .shader_defs
+                .push(shader_def);
+        }
+
         descriptor.label = Some(
             if key.contains(MeshPipelineKey::TRANSPARENT_MAIN_PASS) {
                 "transparent_instanced_mesh_pipeline"
@@ -0,0 +1,46 @@
+#ifdef ORDER_INDEPENDENT_TRANSPARENCY
+#import indirect_instancing::oit
+#endif
+
+fn batch_color() -> vec3<f32> {
+    var color = vec3<f32>(1.0, 1.0, 1.0);
+#ifdef BATCH_DEBUG_0
+    color = vec3<f32>(0.9, 0.1, 0.1);
+#endif
+#ifdef BATCH_DEBUG_1
+    color = vec3<f32>(0.1, 0.8, 0.1);
+#endif
+#ifdef BATCH_DEBUG_2
+    color = vec3<f32>(0.1, 0.3, 0.9);
+#endif
+#ifdef BATCH_DEBUG_3
+    color = vec3<f32>(0.9, 0.8, 0.1);
+#endif
+#ifdef BATCH_DEBUG_4
+    color = vec3<f32>(0.8, 0.1, 0.8);
+#endif
+#ifdef BATCH_DEBUG_5
+    color = vec3<f32>(0.1, 0.8, 0.8);
+#endif
+#ifdef BATCH_DEBUG_6
+    color = vec3<f32>(0.9, 0.5, 0.1);
+#endif
+#ifdef BATCH_DEBUG_7
+    color = vec3<f32>(0.5, 0.5, 0.5);
+#endif
+    return color;
+}
+
+// Only reads the position builtin, so it can follow any material's vertex stage
+#ifdef ORDER_INDEPENDENT_TRANSPARENCY
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> OitOutput {
+    // Fully opaque, so the batch color isn't mixed with others when resolved
+    return oit_output(vec4<f32>(batch_color(), 1.0), 1.0);
+}
+#else
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return vec4<f32>(batch_color(), 1.0);
+}
+#endif
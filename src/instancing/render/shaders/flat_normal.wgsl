@@ -0,0 +1,11 @@
+#define_import_path indirect_instancing::flat_normal
+
+// World-space face normal from screen-space derivatives of a fragment's world position,
+// for meshes without a normal attribute, which are specialized with the FLAT_NORMALS def
+//
+// Only meaningful for triangle topologies, and must be called from uniform control flow.
+// Framebuffer Y points down, hence dpdy before dpdx to face the normal towards the camera
+// for front faces.
+fn flat_normal(world_position: vec3<f32>) -> vec3<f32> {
+    return normalize(cross(dpdy(world_position), dpdx(world_position)));
+}
@@ -0,0 +1,12 @@
+#define_import_path indirect_instancing::instance_clip_plane
+
+@group(2)
+@binding(1)
+var<uniform> clip_plane: vec4<f32>;
+
+// Discards fragments on the far side of the view's InstanceClipPlane
+fn clip_instance(world_position: vec4<f32>) {
+    if dot(clip_plane, vec4<f32>(world_position.xyz, 1.0)) < 0.0 {
+        discard;
+    }
+}
@@ -0,0 +1,9 @@
+#define_import_path indirect_instancing::instance_count
+
+// Total number of instances in the batch being drawn,
+// i.e. for spreading an effect across all of its instances.
+// Without storage buffer support, batches are split into uniform buffer chunks,
+// so instance_index only spans its own chunk
+@group(2)
+@binding(2)
+var<uniform> instance_count: u32;
@@ -0,0 +1,31 @@
+#define_import_path indirect_instancing::instance_depth_bias
+
+// Pulls a world position towards the camera by an instance's InstanceDepthBias,
+// returning the position to project in its place
+//
+// The offset is `constant + slope_scale * slope` world units along the view ray,
+// where `slope` is the tangent of the angle between the surface and the view direction,
+// so coplanar surfaces seen at grazing angles are separated further.
+// Moving along the view ray leaves the projected position, and thus rasterization, unchanged.
+fn instance_depth_bias(
+    world_position: vec3<f32>,
+    world_normal: vec3<f32>,
+    camera_position: vec3<f32>,
+    constant: f32,
+    slope_scale: f32,
+) -> vec3<f32> {
+    if constant == 0.0 && slope_scale == 0.0 {
+        return world_position;
+    }
+
+    let to_camera = camera_position - world_position;
+    let distance = length(to_camera);
+    let view_direction = to_camera / distance;
+
+    let n_dot_v = clamp(abs(dot(normalize(world_normal), view_direction)), 1e-3, 1.0);
+    let slope = min(sqrt(1.0 - n_dot_v * n_dot_v) / n_dot_v, 16.0);
+
+    // Never push geometry past the camera
+    let bias = min(constant + slope_scale * slope, distance * 0.5);
+    return world_position + view_direction * bias;
+}
@@ -3,6 +3,12 @@
 struct InstanceData {
     @size(4)
     mesh: u32,
+    @size(4)
+    seed: u32,
+    @size(4)
+    depth_bias: f32,
+    @size(4)
+    depth_bias_slope_scale: f32,
     @size(64)
     transform: mat4x4<f32>,
     @size(64)
@@ -1,5 +1,8 @@
 #import bevy_pbr::mesh_view_bindings
 #import indirect_instancing::instance_struct
+#import indirect_instancing::instance_clip_plane
+#import indirect_instancing::instance_depth_bias
+#import bevy_core_pipeline::tonemapping
 
 #ifdef NO_STORAGE_BUFFERS_SUPPORT
 @group(2)
@@ -14,8 +17,13 @@ var<storage> instances: Instances;
 struct Vertex {
     @builtin(instance_index) instance: u32,
     @location(0) vertex: vec3<f32>,
+#ifndef FLAT_NORMALS
     @location(1) normal: vec3<f32>,
+#endif
     @location(2) uv: vec2<f32>,
+#ifdef VERTEX_UVS_1
+    @location(7) uv_1: vec2<f32>,
+#endif
 };
 
 struct VertexOutput {
@@ -23,6 +31,9 @@ struct VertexOutput {
     @location(0) world_position: vec4<f32>,
     @location(1) vertex: vec3<f32>,
     @location(2) normal: vec3<f32>,
+#ifdef VERTEX_UVS_1
+    @location(3) uv_1: vec2<f32>,
+#endif
 };
 
 @vertex
@@ -31,13 +42,54 @@ fn vertex(in: Vertex) -> VertexOutput {
 
     var out: VertexOutput;
     out.world_position = instance.transform * vec4<f32>(in.vertex, 1.0);
-    out.clip_position = view.view_proj * out.world_position;
+#ifdef NDC_INSTANCE
+    // Instance transform maps directly into clip space
+    out.clip_position = out.world_position;
+#else
+#ifdef FLAT_NORMALS
+    // Depth bias falls back to its constant term without vertex normals
+    let world_normal = view.world_position - out.world_position.xyz;
+#else
+    let world_normal = (instance.inverse_transpose_model * vec4<f32>(in.normal, 0.0)).xyz;
+#endif
+    let biased_position = instance_depth_bias(
+        out.world_position.xyz,
+        world_normal,
+        view.world_position,
+        instance.depth_bias,
+        instance.depth_bias_slope_scale,
+    );
+    out.clip_position = view.view_proj * vec4<f32>(biased_position, 1.0);
+#endif
     out.vertex = in.vertex;
+#ifdef FLAT_NORMALS
+    // Replaced with a face normal in the fragment shader
+    out.normal = vec3<f32>(0.0);
+#else
     out.normal = in.normal;
+#endif
+#ifdef VERTEX_UVS_1
+    out.uv_1 = in.uv_1;
+#endif
     return out;
 }
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+    clip_instance(in.world_position);
+
+    var output_color = vec4<f32>(1.0, 0.0, 1.0, 1.0);
+
+    // Match the view's output transform, as bevy's own meshes do
+#ifdef TONEMAP_IN_SHADER
+    output_color = vec4<f32>(reinhard_luminance(output_color.rgb), output_color.a);
+#endif
+#ifdef DEBAND_DITHER
+    var output_rgb = output_color.rgb;
+    output_rgb = pow(output_rgb, vec3<f32>(1.0 / 2.2));
+    output_rgb = output_rgb + screen_space_dither(in.clip_position.xy);
+    output_rgb = pow(output_rgb, vec3<f32>(2.2));
+    output_color = vec4<f32>(output_rgb, output_color.a);
+#endif
+    return output_color;
 }
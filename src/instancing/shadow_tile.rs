@@ -0,0 +1,354 @@
+use bevy::{
+    math::{Mat4, UVec4, Vec3, Vec4},
+    pbr::{
+        CubemapVisibleEntities, DirectionalLight, DirectionalLightShadowMap, LightEntity,
+        PointLight, PointLightShadowMap, SpotLight,
+    },
+    prelude::{
+        App, Commands, Component, ComputedVisibility, CoreStage, Entity, GlobalTransform, Local,
+        Plugin, Query, Res, ResMut, Resource, Transform, With, Without,
+    },
+    render::{
+        camera::CameraProjection,
+        view::{ExtractedView, VisibleEntities},
+        Extract, RenderApp, RenderStage,
+    },
+    utils::HashMap,
+};
+
+/// Near plane of point and spot light shadow projections, matching bevy's
+const POINT_LIGHT_NEAR_Z: f32 = 0.1;
+
+/// Target and up directions of each point light cube map face, in bevy's face order
+const CUBE_MAP_FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::NEG_X, Vec3::NEG_Y),
+    (Vec3::X, Vec3::NEG_Y),
+    (Vec3::NEG_Y, Vec3::Z),
+    (Vec3::Y, Vec3::NEG_Z),
+    (Vec3::NEG_Z, Vec3::NEG_Y),
+    (Vec3::Z, Vec3::NEG_Y),
+];
+
+/// One tile of the shadow atlas, i.e. a directional or spot light's shadow map,
+/// or a single face of a point light's cube map
+///
+/// Bevy spawns its shadow views anew every frame during [`RenderStage::Prepare`],
+/// too late for instances to be batched for them. Each tile is instead given a persistent view
+/// mirroring the shadow view's transform, projection and visible entities,
+/// so instance buffers are prepared per tile and kept between frames like those of cameras.
+///
+/// Tile views are never drawn into directly; shadow passes look them up through [`ShadowTileViews`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Component)]
+pub struct ShadowTile {
+    pub light: Entity,
+    /// Cube map face of point lights, 0 otherwise
+    pub face_index: usize,
+}
+
+impl From<&LightEntity> for ShadowTile {
+    fn from(light_entity: &LightEntity) -> Self {
+        match *light_entity {
+            LightEntity::Directional { light_entity } | LightEntity::Spot { light_entity } => {
+                ShadowTile {
+                    light: light_entity,
+                    face_index: 0,
+                }
+            }
+            LightEntity::Point {
+                light_entity,
+                face_index,
+            } => ShadowTile {
+                light: light_entity,
+                face_index,
+            },
+        }
+    }
+}
+
+/// Render world view entities of the shadow tiles extracted this frame
+#[derive(Debug, Default, Clone, Resource)]
+pub struct ShadowTileViews(pub HashMap<ShadowTile, Entity>);
+
+impl ShadowTileViews {
+    /// Returns the tile view standing in for the bevy shadow view of `light_entity`
+    pub fn get(&self, light_entity: &LightEntity) -> Option<Entity> {
+        self.0.get(&ShadowTile::from(light_entity)).copied()
+    }
+}
+
+/// Tile view whose instances a batch queued into a [`Shadow`](bevy::pbr::Shadow) phase draws,
+/// standing in for the bevy shadow view the phase belongs to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+pub struct ShadowTileView(pub Entity);
+
+/// Prepares instances of every instanced material for each tile of the shadow atlas
+///
+/// Tiles are kept for every light with shadows enabled. Lights beyond bevy's shadow map limits
+/// still have their tiles prepared, though bevy renders no shadow views for them.
+/// Entities marked `NotShadowCaster`, and instances of materials that don't
+/// [cast shadows](crate::prelude::MaterialInstanced::cast_shadows), are left out of every tile.
+///
+/// Added by [`IndirectRenderingPlugin`](crate::prelude::IndirectRenderingPlugin).
+#[derive(Debug, Default, Copy, Clone)]
+pub struct InstanceShadowTilePlugin;
+
+impl Plugin for InstanceShadowTilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PostUpdate, sync_shadow_tiles);
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<ShadowTileViews>()
+                .add_system_to_stage(RenderStage::Extract, extract_shadow_tiles);
+        }
+    }
+}
+
+/// Spawns a [`ShadowTile`] entity per tile of each shadow-casting light,
+/// and despawns those of lights that no longer cast shadows
+pub fn sync_shadow_tiles(
+    mut light_tiles: Local<HashMap<Entity, Vec<Entity>>>,
+    query_directional: Query<(Entity, &DirectionalLight)>,
+    query_point: Query<(Entity, &PointLight)>,
+    query_spot: Query<(Entity, &SpotLight)>,
+    mut commands: Commands,
+) {
+    let directional_tiles = query_directional
+        .iter()
+        .filter(|(_, light)| light.shadows_enabled)
+        .map(|(entity, _)| (entity, 1));
+
+    let point_tiles = query_point
+        .iter()
+        .filter(|(_, light)| light.shadows_enabled)
+        .map(|(entity, _)| (entity, CUBE_MAP_FACES.len()));
+
+    let spot_tiles = query_spot
+        .iter()
+        .filter(|(_, light)| light.shadows_enabled)
+        .map(|(entity, _)| (entity, 1));
+
+    let tile_counts = directional_tiles
+        .chain(point_tiles)
+        .chain(spot_tiles)
+        .collect::<HashMap<_, _>>();
+
+    light_tiles.retain(|light, tiles| {
+        if tile_counts.get(light) == Some(&tiles.len()) {
+            return true;
+        }
+
+        for tile in tiles.iter() {
+            commands.entity(*tile).despawn();
+        }
+        false
+    });
+
+    for (light, tile_count) in tile_counts {
+        light_tiles.entry(light).or_insert_with(|| {
+            (0..tile_count)
+                .map(|face_index| commands.spawn(ShadowTile { light, face_index }).id())
+                .collect()
+        });
+    }
+}
+
+/// Extracts a view of each [`ShadowTile`] of a visible light,
+/// computed the same way as bevy's shadow views
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn extract_shadow_tiles(
+    directional_light_shadow_map: Extract<Res<DirectionalLightShadowMap>>,
+    point_light_shadow_map: Extract<Res<PointLightShadowMap>>,
+    query_tiles: Extract<Query<(Entity, &ShadowTile)>>,
+    query_directional: Extract<
+        Query<
+            (
+                &DirectionalLight,
+                &VisibleEntities,
+                &GlobalTransform,
+                &ComputedVisibility,
+            ),
+            Without<SpotLight>,
+        >,
+    >,
+    query_point: Extract<
+        Query<
+            (
+                &CubemapVisibleEntities,
+                &GlobalTransform,
+                &ComputedVisibility,
+            ),
+            With<PointLight>,
+        >,
+    >,
+    query_spot: Extract<
+        Query<(
+            &SpotLight,
+            &VisibleEntities,
+            &GlobalTransform,
+            &ComputedVisibility,
+        )>,
+    >,
+    mut shadow_tile_views: ResMut<ShadowTileViews>,
+    mut commands: Commands,
+) {
+    shadow_tile_views.0.clear();
+
+    let directional_size = directional_light_shadow_map.size as u32;
+    let point_size = point_light_shadow_map.size as u32;
+
+    for (tile_entity, tile) in query_tiles.iter() {
+        let extracted = if let Ok((light, visible_entities, transform, visibility)) =
+            query_directional.get(tile.light)
+        {
+            visibility.is_visible().then(|| {
+                (
+                    ExtractedView {
+                        projection: light.shadow_projection.get_projection_matrix(),
+                        transform: *transform,
+                        hdr: false,
+                        viewport: UVec4::new(0, 0, directional_size, directional_size),
+                    },
+                    visible_entities.clone(),
+                )
+            })
+        } else if let Ok((cubemap_visible_entities, transform, visibility)) =
+            query_point.get(tile.light)
+        {
+            visibility.is_visible().then(|| {
+                // Translation only, so faces stay aligned with the world axes
+                let (target, up) = CUBE_MAP_FACES[tile.face_index];
+                let view_translation = GlobalTransform::from_translation(transform.translation());
+                let view_rotation = Transform::IDENTITY.looking_at(target, up);
+
+                (
+                    ExtractedView {
+                        projection: Mat4::perspective_infinite_reverse_rh(
+                            std::f32::consts::FRAC_PI_2,
+                            1.0,
+                            POINT_LIGHT_NEAR_Z,
+                        ),
+                        transform: view_translation * view_rotation,
+                        hdr: false,
+                        viewport: UVec4::new(0, 0, point_size, point_size),
+                    },
+                    cubemap_visible_entities.get(tile.face_index).clone(),
+                )
+            })
+        } else if let Ok((light, visible_entities, transform, visibility)) =
+            query_spot.get(tile.light)
+        {
+            visibility.is_visible().then(|| {
+                (
+                    ExtractedView {
+                        // Spot light shadow maps are allocated from the directional light atlas
+                        projection: Mat4::perspective_infinite_reverse_rh(
+                            light.outer_angle * 2.0,
+                            1.0,
+                            POINT_LIGHT_NEAR_Z,
+                        ),
+                        transform: spot_light_view_matrix(transform).into(),
+                        hdr: false,
+                        viewport: UVec4::new(0, 0, directional_size, directional_size),
+                    },
+                    visible_entities.clone(),
+                )
+            })
+        } else {
+            None
+        };
+
+        let (view, visible_entities) = match extracted {
+            Some(extracted) => extracted,
+            None => continue,
+        };
+
+        commands
+            .get_or_spawn(tile_entity)
+            .insert((*tile, view, visible_entities));
+
+        shadow_tile_views.0.insert(*tile, tile_entity);
+    }
+}
+
+/// View matrix of a spot light's shadow map, reproducing bevy's basis construction
+/// so that tiles match the shadow views it renders
+fn spot_light_view_matrix(transform: &GlobalTransform) -> Mat4 {
+    let fwd_dir = transform.back().extend(0.0);
+
+    let sign = 1f32.copysign(fwd_dir.z);
+    let a = -1.0 / (fwd_dir.z + sign);
+    let b = fwd_dir.x * fwd_dir.y * a;
+    let up_dir = Vec4::new(
+        1.0 + sign * fwd_dir.x * fwd_dir.x * a,
+        sign * b,
+        -sign * fwd_dir.x,
+        0.0,
+    );
+    let right_dir = Vec4::new(-b, -sign - fwd_dir.y * fwd_dir.y * a, fwd_dir.y, 0.0);
+
+    Mat4::from_cols(
+        right_dir,
+        up_dir,
+        fwd_dir,
+        transform.translation().extend(1.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        pbr::{DirectionalLight, PointLight, SpotLight},
+        prelude::{default, Entity, Stage, SystemStage, World},
+    };
+
+    use super::{sync_shadow_tiles, ShadowTile};
+
+    fn tile_count(world: &mut World, light: Entity) -> usize {
+        world
+            .query::<&ShadowTile>()
+            .iter(world)
+            .filter(|tile| tile.light == light)
+            .count()
+    }
+
+    #[test]
+    fn tiles_follow_light_shadows() {
+        let mut world = World::new();
+        let mut stage = SystemStage::single_threaded().with_system(sync_shadow_tiles);
+
+        let point = world
+            .spawn(PointLight {
+                shadows_enabled: true,
+                ..default()
+            })
+            .id();
+        let spot = world
+            .spawn(SpotLight {
+                shadows_enabled: true,
+                ..default()
+            })
+            .id();
+        let directional = world
+            .spawn(DirectionalLight {
+                shadows_enabled: true,
+                ..default()
+            })
+            .id();
+
+        stage.run(&mut world);
+        assert_eq!(tile_count(&mut world, point), 6);
+        assert_eq!(tile_count(&mut world, spot), 1);
+        assert_eq!(tile_count(&mut world, directional), 1);
+
+        world.get_mut::<PointLight>(point).unwrap().shadows_enabled = false;
+        world.get_mut::<SpotLight>(spot).unwrap().shadows_enabled = false;
+        world
+            .get_mut::<DirectionalLight>(directional)
+            .unwrap()
+            .shadows_enabled = false;
+
+        stage.run(&mut world);
+        assert_eq!(world.query::<&ShadowTile>().iter(&world).count(), 0);
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::{
+    ecs::{reflect::ReflectComponent, system::lifetimeless::Read},
+    prelude::{Component, Deref, DerefMut, Resource},
+    reflect::Reflect,
+    render::{extract_component::ExtractComponent, extract_resource::ExtractResource},
+};
+
+/// Explicit draw order layer for deterministic 2.5D sorting
+///
+/// Instances are drawn in ascending layer order regardless of their depth,
+/// and sorted by view distance within a layer as usual.
+/// Instances without this component are drawn on layer 0.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deref, DerefMut, Component, Reflect,
+)]
+#[reflect(Component)]
+pub struct InstanceLayer(pub i32);
+
+impl ExtractComponent for InstanceLayer {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Draws instances after all others and over any geometry, i.e. for selection highlights
+///
+/// Marked instances are routed to the transparent phase regardless of their material's alpha mode,
+/// neither test nor write depth, and are drawn after every other transparent item.
+/// [`InstanceLayer`] still orders marked instances among themselves.
+#[derive(Debug, Default, Copy, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct AlwaysOnTop;
+
+impl ExtractComponent for AlwaysOnTop {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Controls how instances are sorted within a batch
+#[derive(Debug, Copy, Clone, Resource, ExtractResource)]
+pub struct InstanceSortSettings {
+    /// Batches with at least this many instances are sorted with an O(n) radix sort
+    /// over a quantized (mesh, depth) key instead of a comparison sort
+    pub radix_threshold: usize,
+}
+
+impl Default for InstanceSortSettings {
+    fn default() -> Self {
+        Self {
+            radix_threshold: 4096,
+        }
+    }
+}
+
+/// Maps an `f32` to a `u32` whose unsigned ordering matches the float's total ordering
+pub fn depth_key(depth: f32) -> u32 {
+    let bits = depth.to_bits();
+    if bits & 0x8000_0000 != 0 {
+        !bits
+    } else {
+        bits | 0x8000_0000
+    }
+}
+
+/// Stable least-significant-digit radix sort over a 64-bit key
+///
+/// Passes whose digit is identical across all items are skipped,
+/// so keys that only use their low bits are cheap to sort.
+pub fn radix_sort_by_key<T: Copy>(items: &mut Vec<T>, key: impl Fn(&T) -> u64) {
+    let mut keyed = items
+        .iter()
+        .map(|item| (key(item), *item))
+        .collect::<Vec<_>>();
+
+    let mut scratch = keyed.clone();
+
+    for pass in 0..8 {
+        let shift = pass * 8;
+
+        let mut counts = [0usize; 256];
+        for (key, _) in keyed.iter() {
+            counts[((key >> shift) & 0xFF) as usize] += 1;
+        }
+
+        if counts.contains(&keyed.len()) {
+            continue;
+        }
+
+        let mut offsets = [0usize; 256];
+        let mut offset = 0;
+        for (digit, count) in counts.iter().enumerate() {
+            offsets[digit] = offset;
+            offset += count;
+        }
+
+        for item in keyed.iter() {
+            let digit = ((item.0 >> shift) & 0xFF) as usize;
+            scratch[offsets[digit]] = *item;
+            offsets[digit] += 1;
+        }
+
+        std::mem::swap(&mut keyed, &mut scratch);
+    }
+
+    items.clear();
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
@@ -2,5 +2,7 @@ pub mod materials;
 pub mod instancing;
 pub mod prelude;
 pub mod colored_mesh_instance;
-
-//pub mod compute;
+pub mod pbr_mesh_instance;
+pub mod point_cloud_instance;
+pub mod instanced_shapes;
+pub mod instanced_scene;
@@ -2,7 +2,7 @@ pub mod plugin;
 
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
-    reflect::TypeUuid,
+    reflect::{std_traits::ReflectDefault, Reflect, TypeUuid},
     render::{
         render_asset::{PrepareAssetError, RenderAsset},
         render_resource::{
@@ -18,8 +18,9 @@ use crate::{
     prelude::{InstancedMaterialPipeline, MeshInstance},
 };
 
-#[derive(Debug, Default, Clone, TypeUuid)]
+#[derive(Debug, Default, Clone, TypeUuid, Reflect)]
 #[uuid = "40d95476-3236-4c43-a1c9-1f0645ca762a"]
+#[reflect(Default, Debug)]
 pub struct BasicMaterial;
 
 impl AsBindGroup for BasicMaterial {
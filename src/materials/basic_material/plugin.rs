@@ -14,7 +14,8 @@ pub struct BasicMaterialPlugin;
 
 impl Plugin for BasicMaterialPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_asset::<BasicMaterial>()
+        app.register_type::<BasicMaterial>()
+            .add_asset::<BasicMaterial>()
             .add_plugin(InstancedMaterialPlugin::<BasicMaterial>::default());
 
         app.world
@@ -22,4 +23,3 @@ impl Plugin for BasicMaterialPlugin {
             .set_untracked(Handle::<BasicMaterial>::default(), BasicMaterial::default());
     }
 }
-
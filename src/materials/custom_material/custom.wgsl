@@ -1,5 +1,11 @@
 #import bevy_pbr::mesh_view_bindings
 #import indirect_instancing::color_instance_struct
+#import indirect_instancing::instance_clip_plane
+#import indirect_instancing::instance_depth_bias
+#import indirect_instancing::flat_normal
+#ifdef ORDER_INDEPENDENT_TRANSPARENCY
+#import indirect_instancing::oit
+#endif
 
 #ifdef NO_STORAGE_BUFFERS_SUPPORT
 @group(2)
@@ -14,7 +20,9 @@ var<storage> instances: ColorInstances;
 struct VertexInput {
     @builtin(instance_index) instance: u32,
     @location(0) vertex: vec3<f32>,
+#ifndef FLAT_NORMALS
     @location(1) normal: vec3<f32>,
+#endif
     @location(2) uv: vec2<f32>,
 };
 
@@ -32,9 +40,27 @@ fn vertex(in: VertexInput) -> VertexOutput {
 
     var out: VertexOutput;
     out.world_position = instance.base.transform * vec4<f32>(in.vertex, 1.0);
-    out.clip_position = view.view_proj * out.world_position;
+#ifdef FLAT_NORMALS
+    // Depth bias falls back to its constant term without vertex normals
+    let world_normal = view.world_position - out.world_position.xyz;
+#else
+    let world_normal = (instance.base.inverse_transpose_model * vec4<f32>(in.normal, 0.0)).xyz;
+#endif
+    let biased_position = instance_depth_bias(
+        out.world_position.xyz,
+        world_normal,
+        view.world_position,
+        instance.base.depth_bias,
+        instance.base.depth_bias_slope_scale,
+    );
+    out.clip_position = view.view_proj * vec4<f32>(biased_position, 1.0);
     out.vertex = in.vertex;
+#ifdef FLAT_NORMALS
+    // Replaced with a face normal in the fragment shader
+    out.normal = vec3<f32>(0.0);
+#else
     out.normal = in.normal;
+#endif
     out.color = instance.color;
     return out;
 }
@@ -47,8 +73,21 @@ fn luminance(v: vec3<f32>) -> f32 {
 }
 
 @fragment
+#ifdef ORDER_INDEPENDENT_TRANSPARENCY
+fn fragment(in: VertexOutput) -> OitOutput {
+#else
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+#endif
     let grad_size = fwidth(in.world_position.xyz);
+#ifdef FLAT_NORMALS
+    let normal = flat_normal(in.world_position.xyz);
+#else
+    let normal = in.normal;
+#endif
+
+    // Discard after taking derivatives, which require uniform control flow
+    clip_instance(in.world_position);
+
     let margin_max = 0.5 - margin_size;
     let margin_min = -margin_max;
     let stripe_axis = 
@@ -61,11 +100,11 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     let grad = mix(0.33, 1.0, height_fac);
 
     let diagonal_fac = 1.0 - abs(dot(in.vertex.xz, vec2<f32>(1.0)));
-    let diagonal_fac = max(diagonal_fac, 1.0 - in.normal.y);
+    let diagonal_fac = max(diagonal_fac, 1.0 - normal.y);
     let diagonal_fac = mix(0.33, 1.0, diagonal_fac);
 
     let directional_light = lights.directional_lights[0];
-    let directional_fac = dot(in.normal, directional_light.direction_to_light);
+    let directional_fac = dot(normal, directional_light.direction_to_light);
     let directional_color = directional_light.color * directional_fac;
 
     let ambient = 0.3;
@@ -78,5 +117,10 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     );
     let color = color * luminance(color.xyz);
 
+#ifdef ORDER_INDEPENDENT_TRANSPARENCY
+    let view_depth = distance(in.world_position.xyz, view.world_position);
+    return oit_output(vec4<f32>(color, in.color.a), view_depth);
+#else
     return vec4<f32>(color, in.color.a);
+#endif
 }
@@ -2,7 +2,7 @@ use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     pbr::AlphaMode,
     prelude::{default, AssetServer},
-    reflect::TypeUuid,
+    reflect::{std_traits::ReflectDefault, Reflect, TypeUuid},
     render::{
         mesh::MeshVertexBufferLayout,
         render_asset::{PrepareAssetError, RenderAsset},
@@ -21,12 +21,18 @@ use crate::{
     },
 };
 
-#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[derive(Debug, Clone, AsBindGroup, TypeUuid, Reflect)]
 #[uuid = "6dc3b9fc-fcfd-4149-8f20-5d3a1573e5da"]
 #[bind_group_data(CustomMaterialKey)]
+#[reflect(Default, Debug)]
 pub struct CustomMaterial {
     pub alpha_mode: AlphaMode,
+    /// Not reflected, as [`Face`] is a `wgpu` type
+    #[reflect(ignore)]
     pub cull_mode: Option<Face>,
+    /// Draw [`AlphaMode::Blend`] instances with weighted blended order-independent transparency
+    /// instead of sorting their batches back-to-front
+    pub order_independent_transparency: bool,
 }
 
 impl Default for CustomMaterial {
@@ -34,6 +40,7 @@ impl Default for CustomMaterial {
         Self {
             alpha_mode: default(),
             cull_mode: Some(Face::Back),
+            order_independent_transparency: false,
         }
     }
 }
@@ -131,4 +138,8 @@ impl MaterialInstanced for CustomMaterial {
     fn alpha_mode(&self) -> AlphaMode {
         self.alpha_mode
     }
+
+    fn order_independent_transparency(&self) -> bool {
+        self.order_independent_transparency
+    }
 }
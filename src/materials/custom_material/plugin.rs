@@ -17,7 +17,8 @@ impl Plugin for CustomMaterialPlugin {
 
         app.register_type::<InstanceColor>();
 
-        app.add_asset::<CustomMaterial>()
+        app.register_type::<CustomMaterial>()
+            .add_asset::<CustomMaterial>()
             .add_plugin(InstancedMaterialPlugin::<CustomMaterial>::default());
 
         if !app.is_plugin_added::<ColorInstancePlugin>() {
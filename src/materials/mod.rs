@@ -1,3 +1,6 @@
 pub mod basic_material;
 pub mod custom_material;
+pub mod pbr_material;
+pub mod screen_material;
+pub mod standard_material;
 pub mod texture_material;
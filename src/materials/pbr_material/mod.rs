@@ -0,0 +1,2 @@
+pub mod pbr_instanced_material;
+pub mod plugin;
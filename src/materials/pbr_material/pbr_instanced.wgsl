@@ -0,0 +1,191 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_types
+#import bevy_pbr::pbr_types
+#import indirect_instancing::instance_struct
+#import indirect_instancing::color_instance_struct
+#import indirect_instancing::pbr_instance_struct
+#import indirect_instancing::instance_clip_plane
+#import indirect_instancing::instance_depth_bias
+#import indirect_instancing::flat_normal
+
+@group(1)
+@binding(0)
+var<uniform> material: StandardMaterial;
+
+@group(1)
+@binding(1)
+var base_color_texture: texture_2d<f32>;
+
+@group(1)
+@binding(2)
+var base_color_sampler: sampler;
+
+@group(1)
+@binding(3)
+var normal_map_texture: texture_2d<f32>;
+
+@group(1)
+@binding(4)
+var normal_map_sampler: sampler;
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+@group(2)
+@binding(0)
+var<uniform> in_instances: PbrInstances;
+#else
+@group(2)
+@binding(0)
+var<storage> in_instances: PbrInstances;
+#endif
+
+// Stands in for the per-mesh uniform read by bevy's pbr function,
+// which instanced draws don't bind
+var<private> mesh: Mesh;
+
+// Imported after the bindings they read
+#import bevy_pbr::utils
+#import bevy_pbr::clustered_forward
+#import bevy_pbr::lighting
+#import bevy_pbr::shadows
+#import bevy_pbr::pbr_functions
+
+struct VertexInput {
+    @builtin(instance_index) instance: u32,
+    @location(0) vertex: vec3<f32>,
+#ifndef FLAT_NORMALS
+    @location(1) normal: vec3<f32>,
+#endif
+#ifdef VERTEX_UVS
+    @location(2) uv: vec2<f32>,
+#endif
+#ifdef VERTEX_TANGENTS
+    @location(3) tangent: vec4<f32>,
+#endif
+#ifdef VERTEX_COLORS
+    @location(4) color: vec4<f32>,
+#endif
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) world_tangent: vec4<f32>,
+    @location(4) color: vec4<f32>,
+    @location(5) metallic_roughness: vec2<f32>,
+    @location(6) @interpolate(flat) has_metallic_roughness: u32,
+};
+
+@vertex
+fn vertex(in: VertexInput) -> VertexOutput {
+    let instance = in_instances.instances[in.instance];
+
+    var out: VertexOutput;
+    out.world_position = instance.base.base.transform * vec4<f32>(in.vertex, 1.0);
+#ifdef FLAT_NORMALS
+    // Replaced with a face normal in the fragment shader,
+    // and depth bias falls back to its constant term
+    out.world_normal = vec3<f32>(0.0);
+    let bias_normal = view.world_position - out.world_position.xyz;
+#else
+    out.world_normal = normalize(
+        (instance.base.base.inverse_transpose_model * vec4<f32>(in.normal, 0.0)).xyz
+    );
+    let bias_normal = out.world_normal;
+#endif
+    let biased_position = instance_depth_bias(
+        out.world_position.xyz,
+        bias_normal,
+        view.world_position,
+        instance.base.base.depth_bias,
+        instance.base.base.depth_bias_slope_scale,
+    );
+    out.clip_position = view.view_proj * vec4<f32>(biased_position, 1.0);
+#ifdef VERTEX_UVS
+    out.uv = in.uv;
+#endif
+#ifdef VERTEX_TANGENTS
+    out.world_tangent = vec4<f32>(
+        (instance.base.base.transform * vec4<f32>(in.tangent.xyz, 0.0)).xyz,
+        in.tangent.w,
+    );
+#endif
+    // Instance tint multiplies the material's base color
+#ifdef VERTEX_COLORS
+    out.color = instance.base.color * in.color;
+#else
+    out.color = instance.base.color;
+#endif
+    out.metallic_roughness = vec2<f32>(instance.metallic, instance.perceptual_roughness);
+    out.has_metallic_roughness = instance.has_metallic_roughness;
+    return out;
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> @location(0) vec4<f32> {
+    mesh.flags = MESH_FLAGS_SHADOW_RECEIVER_BIT;
+
+#ifdef FLAT_NORMALS
+    let world_normal = flat_normal(in.world_position.xyz);
+#else
+    let world_normal = in.world_normal;
+#endif
+
+    var output_color = material.base_color * in.color;
+#ifdef VERTEX_UVS
+    if (material.flags & STANDARD_MATERIAL_FLAGS_BASE_COLOR_TEXTURE_BIT) != 0u {
+        output_color = output_color * textureSample(base_color_texture, base_color_sampler, in.uv);
+    }
+#endif
+
+    var pbr_input = pbr_input_new();
+    pbr_input.material = material;
+    pbr_input.material.base_color = output_color;
+    // Per-instance metallic and roughness override the material's
+    if in.has_metallic_roughness != 0u {
+        pbr_input.material.metallic = in.metallic_roughness.x;
+        pbr_input.material.perceptual_roughness = in.metallic_roughness.y;
+    }
+    pbr_input.frag_coord = in.clip_position;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = prepare_world_normal(
+        world_normal,
+        (material.flags & STANDARD_MATERIAL_FLAGS_DOUBLE_SIDED_BIT) != 0u,
+        is_front,
+    );
+    pbr_input.is_orthographic = view.projection[3].w == 1.0;
+    pbr_input.N = apply_normal_mapping(
+        material.flags,
+        pbr_input.world_normal,
+#ifdef VERTEX_TANGENTS
+#ifdef STANDARDMATERIAL_NORMAL_MAP
+        in.world_tangent,
+#endif
+#endif
+#ifdef VERTEX_UVS
+        in.uv,
+#endif
+    );
+    pbr_input.V = calculate_view(in.world_position, pbr_input.is_orthographic);
+
+    // Discard after sampling, which requires uniform control flow
+    clip_instance(in.world_position);
+
+    output_color = pbr(pbr_input);
+
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color);
+#endif
+#ifdef DEBAND_DITHER
+    var output_rgb = output_color.rgb;
+    output_rgb = pow(output_rgb, vec3<f32>(1.0 / 2.2));
+    output_rgb = output_rgb + screen_space_dither(in.clip_position.xy);
+    output_rgb = pow(output_rgb, vec3<f32>(2.2));
+    output_color = vec4<f32>(output_rgb, output_color.a);
+#endif
+    return output_color;
+}
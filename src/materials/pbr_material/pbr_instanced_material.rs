@@ -0,0 +1,244 @@
+use bevy::{
+    pbr::{AlphaMode, StandardMaterialFlags, StandardMaterialUniform},
+    prelude::{AssetServer, Color, Handle, Image},
+    reflect::{std_traits::ReflectDefault, Reflect, TypeUuid},
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupShaderType, Face, RenderPipelineDescriptor, ShaderRef,
+            SpecializedMeshPipelineError, TextureFormat,
+        },
+    },
+};
+
+use crate::{
+    instancing::material::material_instanced::AsBatch,
+    prelude::{InstancedMaterialPipeline, MaterialInstanced, PbrMeshInstance},
+};
+
+use super::plugin::PBR_INSTANCED_SHADER_HANDLE;
+
+/// Physically based material lit with bevy's PBR functions, after `StandardMaterial`
+///
+/// The [`InstanceColor`](crate::prelude::InstanceColor) of each instance multiplies `base_color`,
+/// and its [`InstanceMetallicRoughness`](crate::prelude::InstanceMetallicRoughness), where present,
+/// overrides `metallic` and `perceptual_roughness`.
+/// Normal maps require meshes with tangents and UVs.
+#[derive(Debug, Clone, AsBindGroup, TypeUuid, Reflect)]
+#[uuid = "b1e57c0a-6d2f-4a8e-9c43-5f8a0d7e2b19"]
+#[bind_group_data(PbrInstancedMaterialKey)]
+#[uniform(0, StandardMaterialUniform)]
+#[reflect(Default, Debug)]
+pub struct PbrInstancedMaterial {
+    pub base_color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub base_color_texture: Option<Handle<Image>>,
+    pub emissive: Color,
+    /// Linear perceptual roughness, clamped to [0.089, 1.0] in the shader
+    pub perceptual_roughness: f32,
+    /// From [0.0, 1.0], dielectric to pure metallic
+    pub metallic: f32,
+    /// Specular intensity for non-metals, where 0.5 maps to 4% reflectance
+    pub reflectance: f32,
+    #[texture(3)]
+    #[sampler(4)]
+    pub normal_map_texture: Option<Handle<Image>>,
+    /// Flips the normal map's green channel, for maps authored for DirectX
+    pub flip_normal_map_y: bool,
+    /// Lights back faces as if they faced the viewer, for use with a `cull_mode` of [`None`]
+    pub double_sided: bool,
+    /// Not reflected, as [`Face`] is a `wgpu` type
+    #[reflect(ignore)]
+    pub cull_mode: Option<Face>,
+    pub alpha_mode: AlphaMode,
+}
+
+impl Default for PbrInstancedMaterial {
+    /// Matches the defaults of bevy's `StandardMaterial`
+    fn default() -> Self {
+        Self {
+            base_color: Color::rgb(1.0, 1.0, 1.0),
+            base_color_texture: None,
+            emissive: Color::BLACK,
+            perceptual_roughness: 0.089,
+            metallic: 0.01,
+            reflectance: 0.5,
+            normal_map_texture: None,
+            flip_normal_map_y: false,
+            double_sided: false,
+            cull_mode: Some(Face::Back),
+            alpha_mode: AlphaMode::Opaque,
+        }
+    }
+}
+
+impl PbrInstancedMaterial {
+    fn flags(&self) -> StandardMaterialFlags {
+        let mut flags = StandardMaterialFlags::NONE;
+        if self.base_color_texture.is_some() {
+            flags |= StandardMaterialFlags::BASE_COLOR_TEXTURE;
+        }
+        if self.double_sided {
+            flags |= StandardMaterialFlags::DOUBLE_SIDED;
+        }
+        if self.normal_map_texture.is_some() && self.flip_normal_map_y {
+            flags |= StandardMaterialFlags::FLIP_NORMAL_MAP_Y;
+        }
+        flags |= match self.alpha_mode {
+            AlphaMode::Opaque => StandardMaterialFlags::ALPHA_MODE_OPAQUE,
+            AlphaMode::Mask(_) => StandardMaterialFlags::ALPHA_MODE_MASK,
+            AlphaMode::Blend => StandardMaterialFlags::ALPHA_MODE_BLEND,
+        };
+        flags
+    }
+
+    fn alpha_cutoff(&self) -> f32 {
+        match self.alpha_mode {
+            AlphaMode::Mask(cutoff) => cutoff,
+            _ => 0.5,
+        }
+    }
+}
+
+impl AsBindGroupShaderType<StandardMaterialUniform> for PbrInstancedMaterial {
+    fn as_bind_group_shader_type(&self, images: &RenderAssets<Image>) -> StandardMaterialUniform {
+        let mut flags = self.flags();
+
+        if let Some(texture) = self
+            .normal_map_texture
+            .as_ref()
+            .and_then(|normal_map| images.get(normal_map))
+        {
+            if let TextureFormat::Rg8Unorm
+            | TextureFormat::Rg16Unorm
+            | TextureFormat::Bc5RgUnorm
+            | TextureFormat::EacRg11Unorm = texture.texture_format
+            {
+                flags |= StandardMaterialFlags::TWO_COMPONENT_NORMAL_MAP;
+            }
+        }
+
+        StandardMaterialUniform {
+            base_color: self.base_color.as_linear_rgba_f32().into(),
+            emissive: self.emissive.as_linear_rgba_f32().into(),
+            roughness: self.perceptual_roughness,
+            metallic: self.metallic,
+            reflectance: self.reflectance,
+            flags: flags.bits(),
+            alpha_cutoff: self.alpha_cutoff(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct PbrInstancedMaterialKey {
+    pub normal_map: bool,
+    pub cull_mode: Option<Face>,
+}
+
+impl PartialOrd for PbrInstancedMaterialKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PbrInstancedMaterialKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match self.normal_map.cmp(&other.normal_map) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        self.cull_mode
+            .map(|cull_mode| cull_mode as usize)
+            .cmp(&other.cull_mode.map(|cull_mode| cull_mode as usize))
+    }
+}
+
+impl From<&PbrInstancedMaterial> for PbrInstancedMaterialKey {
+    fn from(material: &PbrInstancedMaterial) -> Self {
+        PbrInstancedMaterialKey {
+            normal_map: material.normal_map_texture.is_some(),
+            cull_mode: material.cull_mode,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PbrInstancedMaterialBatchKey {
+    pub base_color_texture: Option<Handle<Image>>,
+    pub normal_map_texture: Option<Handle<Image>>,
+    /// Bit patterns of the uniform values, which are bound once for the whole batch
+    pub uniform: [u32; 13],
+    pub pipeline_key: PbrInstancedMaterialKey,
+}
+
+impl From<&PbrInstancedMaterial> for PbrInstancedMaterialBatchKey {
+    fn from(material: &PbrInstancedMaterial) -> Self {
+        let [r, g, b, a] = material.base_color.as_linear_rgba_f32();
+        let [er, eg, eb, ea] = material.emissive.as_linear_rgba_f32();
+
+        PbrInstancedMaterialBatchKey {
+            base_color_texture: material.base_color_texture.as_ref().map(Handle::clone_weak),
+            normal_map_texture: material.normal_map_texture.as_ref().map(Handle::clone_weak),
+            uniform: [
+                r.to_bits(),
+                g.to_bits(),
+                b.to_bits(),
+                a.to_bits(),
+                er.to_bits(),
+                eg.to_bits(),
+                eb.to_bits(),
+                ea.to_bits(),
+                material.perceptual_roughness.to_bits(),
+                material.metallic.to_bits(),
+                material.reflectance.to_bits(),
+                material.flags().bits(),
+                material.alpha_cutoff().to_bits(),
+            ],
+            pipeline_key: material.into(),
+        }
+    }
+}
+
+impl AsBatch for PbrInstancedMaterial {
+    type BatchKey = PbrInstancedMaterialBatchKey;
+}
+
+impl MaterialInstanced for PbrInstancedMaterial {
+    type Instance = PbrMeshInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        PBR_INSTANCED_SHADER_HANDLE.typed().into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        PBR_INSTANCED_SHADER_HANDLE.typed().into()
+    }
+
+    fn specialize(
+        _pipeline: &InstancedMaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        key: Self::Data,
+        _layout: &MeshVertexBufferLayout,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = key.cull_mode;
+        if key.normal_map {
+            descriptor
+                .fragment
+                .as_mut()
+                .unwrap()
+                .shader_defs
+                .push("STANDARDMATERIAL_NORMAL_MAP".into());
+        }
+        if let Some(label) = &mut descriptor.label {
+            *label = format!("pbr_{}", *label).into();
+        }
+        Ok(())
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
@@ -0,0 +1,38 @@
+use bevy::{
+    asset::load_internal_asset,
+    prelude::{AddAsset, Assets, Handle, HandleUntyped, Plugin, Shader},
+    reflect::TypeUuid,
+};
+
+use crate::prelude::{InstancedMaterialPlugin, PbrInstancePlugin, PbrInstancedMaterial};
+
+pub const PBR_INSTANCED_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 7613250894213360947);
+
+pub struct PbrInstancedMaterialPlugin;
+
+impl Plugin for PbrInstancedMaterialPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        load_internal_asset!(
+            app,
+            PBR_INSTANCED_SHADER_HANDLE,
+            "pbr_instanced.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<PbrInstancedMaterial>()
+            .add_asset::<PbrInstancedMaterial>()
+            .add_plugin(InstancedMaterialPlugin::<PbrInstancedMaterial>::default());
+
+        if !app.is_plugin_added::<PbrInstancePlugin>() {
+            app.add_plugin(PbrInstancePlugin);
+        }
+
+        app.world
+            .resource_mut::<Assets<PbrInstancedMaterial>>()
+            .set_untracked(
+                Handle::<PbrInstancedMaterial>::default(),
+                PbrInstancedMaterial::default(),
+            );
+    }
+}
@@ -0,0 +1,244 @@
+pub mod plugin;
+
+use bevy::{
+    pbr::AlphaMode,
+    prelude::{default, AssetServer, Assets, Commands, Handle, Image, Res, ResMut, Resource},
+    reflect::{std_traits::ReflectDefault, Reflect, TypeUuid},
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, CommandEncoderDescriptor, Extent3d, Face, ImageCopyTexture, Origin3d,
+            RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError, TextureAspect,
+            TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        Extract,
+    },
+};
+
+use crate::{
+    instancing::material::material_instanced::AsBatch,
+    prelude::{
+        ColorMeshInstance, InstancedMaterialPipeline, MaterialInstanced, SCREEN_SHADER_HANDLE,
+    },
+};
+
+/// Displays one of several render targets per instance, i.e. for a bank of monitors
+/// showing different camera feeds
+///
+/// Each frame, the feeds are copied into the layers of `screens`,
+/// a 2D array texture created by [`ScreenMaterial::new`].
+/// Instances display the feed indexed by their [`InstanceSeed`](crate::prelude::InstanceSeed)
+/// (or [`InstanceData::tag`](crate::prelude::InstanceData::tag)), wrapping around the feed count.
+///
+/// Feeds must share the size and format of `screens`, and include [`TextureUsages::COPY_SRC`](bevy::render::render_resource::TextureUsages::COPY_SRC).
+/// Copies are issued before the frame's cameras render, so screens lag their feeds by a frame.
+#[derive(Debug, Clone, AsBindGroup, TypeUuid, Reflect)]
+#[uuid = "9b0f7e52-3c41-4f58-a0c3-6d2b8e1f47a9"]
+#[bind_group_data(ScreenMaterialKey)]
+#[reflect(Default, Debug)]
+pub struct ScreenMaterial {
+    #[texture(0, dimension = "2d_array")]
+    #[sampler(1)]
+    pub screens: Handle<Image>,
+    pub feeds: Vec<Handle<Image>>,
+    pub alpha_mode: AlphaMode,
+    /// Not reflected, as [`Face`] is a `wgpu` type
+    #[reflect(ignore)]
+    pub cull_mode: Option<Face>,
+}
+
+impl Default for ScreenMaterial {
+    fn default() -> Self {
+        Self {
+            screens: default(),
+            feeds: default(),
+            alpha_mode: default(),
+            cull_mode: Some(Face::Back),
+        }
+    }
+}
+
+impl ScreenMaterial {
+    /// Creates a material displaying `feeds`, allocating an array texture
+    /// with one `width` x `height` layer per feed
+    pub fn new(
+        feeds: Vec<Handle<Image>>,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        images: &mut Assets<Image>,
+    ) -> Self {
+        let mut screens = Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: feeds.len().max(1) as u32,
+            },
+            TextureDimension::D2,
+            &vec![0; format.describe().block_size as usize],
+            format,
+        );
+
+        screens.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..default()
+        });
+
+        ScreenMaterial {
+            screens: images.add(screens),
+            feeds,
+            ..default()
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct ScreenMaterialKey {
+    pub cull_mode: Option<Face>,
+}
+
+impl PartialOrd for ScreenMaterialKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScreenMaterialKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.cull_mode
+            .map(|cull_mode| cull_mode as usize)
+            .cmp(&other.cull_mode.map(|cull_mode| cull_mode as usize))
+    }
+}
+
+impl From<&ScreenMaterial> for ScreenMaterialKey {
+    fn from(screen_material: &ScreenMaterial) -> Self {
+        ScreenMaterialKey {
+            cull_mode: screen_material.cull_mode,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScreenMaterialBatchKey {
+    pub screens: Handle<Image>,
+    pub key: ScreenMaterialKey,
+}
+
+impl From<&ScreenMaterial> for ScreenMaterialBatchKey {
+    fn from(screen_material: &ScreenMaterial) -> Self {
+        ScreenMaterialBatchKey {
+            screens: screen_material.screens.clone_weak(),
+            key: screen_material.into(),
+        }
+    }
+}
+
+impl AsBatch for ScreenMaterial {
+    type BatchKey = ScreenMaterialBatchKey;
+}
+
+impl MaterialInstanced for ScreenMaterial {
+    type Instance = ColorMeshInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        SCREEN_SHADER_HANDLE.typed().into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        SCREEN_SHADER_HANDLE.typed().into()
+    }
+
+    fn specialize(
+        _pipeline: &InstancedMaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        key: Self::Data,
+        _layout: &MeshVertexBufferLayout,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = key.cull_mode;
+        if let Some(label) = &mut descriptor.label {
+            *label = format!("screen_{}", *label).into();
+        }
+        Ok(())
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
+
+/// Screen array textures and the feeds to copy into their layers
+#[derive(Debug, Default, Resource)]
+pub struct ExtractedScreenFeeds(pub Vec<(Handle<Image>, Vec<Handle<Image>>)>);
+
+pub fn extract_screen_feeds(
+    screen_materials: Extract<Res<Assets<ScreenMaterial>>>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(ExtractedScreenFeeds(
+        screen_materials
+            .iter()
+            .map(|(_, material)| (material.screens.clone_weak(), material.feeds.clone()))
+            .collect(),
+    ));
+}
+
+/// Copies each feed into its layer of the screen array texture
+pub fn queue_screen_feeds(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    gpu_images: Res<RenderAssets<Image>>,
+    mut screen_feeds: ResMut<ExtractedScreenFeeds>,
+) {
+    let mut command_encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("screen feed encoder"),
+    });
+
+    for (screens, feeds) in screen_feeds.0.drain(..) {
+        let screens = if let Some(screens) = gpu_images.get(&screens) {
+            screens
+        } else {
+            continue;
+        };
+
+        for (layer, feed) in feeds.iter().enumerate() {
+            let feed = if let Some(feed) = gpu_images.get(feed) {
+                feed
+            } else {
+                continue;
+            };
+
+            if feed.size != screens.size || feed.texture_format != screens.texture_format {
+                continue;
+            }
+
+            command_encoder.copy_texture_to_texture(
+                ImageCopyTexture {
+                    texture: &feed.texture,
+                    mip_level: 0,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                ImageCopyTexture {
+                    texture: &screens.texture,
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                Extent3d {
+                    width: feed.size.x as u32,
+                    height: feed.size.y as u32,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+    }
+
+    render_queue.submit([command_encoder.finish()]);
+}
@@ -0,0 +1,44 @@
+use bevy::{
+    asset::load_internal_asset,
+    prelude::{AddAsset, Assets, Handle, HandleUntyped, Plugin, Shader},
+    reflect::TypeUuid,
+    render::{RenderApp, RenderStage},
+};
+
+use crate::prelude::{
+    extract_screen_feeds, queue_screen_feeds, ColorInstancePlugin, ExtractedScreenFeeds,
+    InstancedMaterialPlugin, ScreenMaterial,
+};
+
+pub const SCREEN_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6158379124470915283);
+
+pub struct ScreenMaterialPlugin;
+
+impl Plugin for ScreenMaterialPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        load_internal_asset!(app, SCREEN_SHADER_HANDLE, "screen.wgsl", Shader::from_wgsl);
+
+        app.register_type::<ScreenMaterial>()
+            .add_asset::<ScreenMaterial>()
+            .add_plugin(InstancedMaterialPlugin::<ScreenMaterial>::default());
+
+        if !app.is_plugin_added::<ColorInstancePlugin>() {
+            app.add_plugin(ColorInstancePlugin);
+        }
+
+        app.world
+            .resource_mut::<Assets<ScreenMaterial>>()
+            .set_untracked(
+                Handle::<ScreenMaterial>::default(),
+                ScreenMaterial::default(),
+            );
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<ExtractedScreenFeeds>()
+                .add_system_to_stage(RenderStage::Extract, extract_screen_feeds)
+                .add_system_to_stage(RenderStage::Queue, queue_screen_feeds);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#import bevy_pbr::mesh_view_bindings
+#import indirect_instancing::instance_struct
+#import indirect_instancing::color_instance_struct
+#import indirect_instancing::instance_clip_plane
+#import indirect_instancing::instance_depth_bias
+#import bevy_core_pipeline::tonemapping
+
+@group(1)
+@binding(0)
+var in_screens: texture_2d_array<f32>;
+
+@group(1)
+@binding(1)
+var in_sampler: sampler;
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+@group(2)
+@binding(0)
+var<uniform> in_instances: ColorInstances;
+#else
+@group(2)
+@binding(0)
+var<storage> in_instances: ColorInstances;
+#endif
+
+struct VertexInput {
+    @builtin(instance_index) instance: u32,
+    @location(0) vertex: vec3<f32>,
+#ifndef FLAT_NORMALS
+    @location(1) normal: vec3<f32>,
+#endif
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) @interpolate(flat) layer: u32,
+};
+
+@vertex
+fn vertex(in: VertexInput) -> VertexOutput {
+    let instance = in_instances.instances[in.instance];
+
+    var out: VertexOutput;
+    out.world_position = instance.base.transform * vec4<f32>(in.vertex, 1.0);
+#ifdef FLAT_NORMALS
+    // Depth bias falls back to its constant term without vertex normals
+    let world_normal = view.world_position - out.world_position.xyz;
+#else
+    let world_normal = (instance.base.inverse_transpose_model * vec4<f32>(in.normal, 0.0)).xyz;
+#endif
+    let biased_position = instance_depth_bias(
+        out.world_position.xyz,
+        world_normal,
+        view.world_position,
+        instance.base.depth_bias,
+        instance.base.depth_bias_slope_scale,
+    );
+    out.clip_position = view.view_proj * vec4<f32>(biased_position, 1.0);
+    out.uv = in.uv;
+    out.color = instance.color;
+    // Select a feed by instance seed
+    out.layer = instance.base.seed % u32(textureNumLayers(in_screens));
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let screen = textureSample(in_screens, in_sampler, in.uv, i32(in.layer));
+
+    // Discard after sampling, which requires uniform control flow
+    clip_instance(in.world_position);
+
+    // Screens are emissive, so aren't lit
+    var output_color = vec4<f32>(screen.rgb * in.color.rgb, in.color.a);
+
+#ifdef TONEMAP_IN_SHADER
+    output_color = vec4<f32>(reinhard_luminance(output_color.rgb), output_color.a);
+#endif
+#ifdef DEBAND_DITHER
+    var output_rgb = output_color.rgb;
+    output_rgb = pow(output_rgb, vec3<f32>(1.0 / 2.2));
+    output_rgb = output_rgb + screen_space_dither(in.clip_position.xy);
+    output_rgb = pow(output_rgb, vec3<f32>(2.2));
+    output_color = vec4<f32>(output_rgb, output_color.a);
+#endif
+    return output_color;
+}
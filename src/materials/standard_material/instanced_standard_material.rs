@@ -0,0 +1,208 @@
+use bevy::{
+    pbr::{AlphaMode, StandardMaterial, StandardMaterialFlags},
+    prelude::{AssetServer, Deref, DerefMut, Handle, Image},
+    reflect::{std_traits::ReflectDefault, Reflect, TypeUuid},
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupError, BindGroupLayout, PreparedBindGroup,
+            RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+        renderer::RenderDevice,
+        texture::FallbackImage,
+    },
+};
+
+use crate::{
+    instancing::material::material_instanced::AsBatch,
+    prelude::{
+        InstancedMaterialPipeline, MaterialInstanced, MeshInstance, PbrInstancedMaterialKey,
+    },
+};
+
+use super::plugin::STANDARD_INSTANCED_SHADER_HANDLE;
+
+/// Instanced adapter for bevy's [`StandardMaterial`]
+///
+/// Binds the wrapped material exactly as bevy does, so its textures, uniform, alpha mode,
+/// cull mode and depth bias carry over unchanged.
+/// Instances are plain [`MeshInstance`]s, so existing `PbrBundle`s need no extra components.
+///
+/// [`InstancedStandardMaterialPlugin`](crate::prelude::InstancedStandardMaterialPlugin)
+/// creates these from the `Handle<StandardMaterial>`s of existing entities.
+#[derive(Debug, Default, Clone, TypeUuid, Reflect, Deref, DerefMut)]
+#[uuid = "5c0e2a71-93d4-4b6f-a1e8-2f7d6c9b4a30"]
+#[reflect(Default, Debug)]
+pub struct InstancedStandardMaterial(pub StandardMaterial);
+
+impl From<StandardMaterial> for InstancedStandardMaterial {
+    fn from(material: StandardMaterial) -> Self {
+        InstancedStandardMaterial(material)
+    }
+}
+
+impl AsBindGroup for InstancedStandardMaterial {
+    type Data = PbrInstancedMaterialKey;
+
+    fn as_bind_group(
+        &self,
+        layout: &BindGroupLayout,
+        render_device: &RenderDevice,
+        images: &RenderAssets<Image>,
+        fallback_image: &FallbackImage,
+    ) -> Result<PreparedBindGroup<Self>, AsBindGroupError> {
+        let prepared = self
+            .0
+            .as_bind_group(layout, render_device, images, fallback_image)?;
+
+        Ok(PreparedBindGroup {
+            bindings: prepared.bindings,
+            bind_group: prepared.bind_group,
+            data: (&self.0).into(),
+        })
+    }
+
+    fn bind_group_layout(render_device: &RenderDevice) -> BindGroupLayout {
+        StandardMaterial::bind_group_layout(render_device)
+    }
+}
+
+impl From<&StandardMaterial> for PbrInstancedMaterialKey {
+    fn from(material: &StandardMaterial) -> Self {
+        PbrInstancedMaterialKey {
+            normal_map: material.normal_map_texture.is_some(),
+            cull_mode: material.cull_mode,
+        }
+    }
+}
+
+/// Uniform flags of a [`StandardMaterial`], short of those that depend on its prepared textures
+fn standard_material_flags(material: &StandardMaterial) -> StandardMaterialFlags {
+    let mut flags = StandardMaterialFlags::NONE;
+    if material.base_color_texture.is_some() {
+        flags |= StandardMaterialFlags::BASE_COLOR_TEXTURE;
+    }
+    if material.emissive_texture.is_some() {
+        flags |= StandardMaterialFlags::EMISSIVE_TEXTURE;
+    }
+    if material.metallic_roughness_texture.is_some() {
+        flags |= StandardMaterialFlags::METALLIC_ROUGHNESS_TEXTURE;
+    }
+    if material.occlusion_texture.is_some() {
+        flags |= StandardMaterialFlags::OCCLUSION_TEXTURE;
+    }
+    if material.double_sided {
+        flags |= StandardMaterialFlags::DOUBLE_SIDED;
+    }
+    if material.unlit {
+        flags |= StandardMaterialFlags::UNLIT;
+    }
+    if material.normal_map_texture.is_some() && material.flip_normal_map_y {
+        flags |= StandardMaterialFlags::FLIP_NORMAL_MAP_Y;
+    }
+    flags |= match material.alpha_mode {
+        AlphaMode::Opaque => StandardMaterialFlags::ALPHA_MODE_OPAQUE,
+        AlphaMode::Mask(_) => StandardMaterialFlags::ALPHA_MODE_MASK,
+        AlphaMode::Blend => StandardMaterialFlags::ALPHA_MODE_BLEND,
+    };
+    flags
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InstancedStandardMaterialBatchKey {
+    pub base_color_texture: Option<Handle<Image>>,
+    pub emissive_texture: Option<Handle<Image>>,
+    pub metallic_roughness_texture: Option<Handle<Image>>,
+    pub occlusion_texture: Option<Handle<Image>>,
+    /// Also decides whether the normal map is two-component, which is derived from its format
+    pub normal_map_texture: Option<Handle<Image>>,
+    /// Bit patterns of the uniform values, which are bound once for the whole batch
+    pub uniform: [u32; 13],
+    pub depth_bias: u32,
+    pub pipeline_key: PbrInstancedMaterialKey,
+}
+
+impl From<&InstancedStandardMaterial> for InstancedStandardMaterialBatchKey {
+    fn from(InstancedStandardMaterial(material): &InstancedStandardMaterial) -> Self {
+        let [r, g, b, a] = material.base_color.as_linear_rgba_f32();
+        let [er, eg, eb, ea] = material.emissive.as_linear_rgba_f32();
+        let alpha_cutoff = match material.alpha_mode {
+            AlphaMode::Mask(cutoff) => cutoff,
+            _ => 0.5,
+        };
+
+        InstancedStandardMaterialBatchKey {
+            base_color_texture: material.base_color_texture.as_ref().map(Handle::clone_weak),
+            emissive_texture: material.emissive_texture.as_ref().map(Handle::clone_weak),
+            metallic_roughness_texture: material
+                .metallic_roughness_texture
+                .as_ref()
+                .map(Handle::clone_weak),
+            occlusion_texture: material.occlusion_texture.as_ref().map(Handle::clone_weak),
+            normal_map_texture: material.normal_map_texture.as_ref().map(Handle::clone_weak),
+            uniform: [
+                r.to_bits(),
+                g.to_bits(),
+                b.to_bits(),
+                a.to_bits(),
+                er.to_bits(),
+                eg.to_bits(),
+                eb.to_bits(),
+                ea.to_bits(),
+                material.perceptual_roughness.to_bits(),
+                material.metallic.to_bits(),
+                material.reflectance.to_bits(),
+                standard_material_flags(material).bits(),
+                alpha_cutoff.to_bits(),
+            ],
+            depth_bias: material.depth_bias.to_bits(),
+            pipeline_key: material.into(),
+        }
+    }
+}
+
+impl AsBatch for InstancedStandardMaterial {
+    type BatchKey = InstancedStandardMaterialBatchKey;
+}
+
+impl MaterialInstanced for InstancedStandardMaterial {
+    type Instance = MeshInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        STANDARD_INSTANCED_SHADER_HANDLE.typed().into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        STANDARD_INSTANCED_SHADER_HANDLE.typed().into()
+    }
+
+    fn specialize(
+        _pipeline: &InstancedMaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        key: Self::Data,
+        _layout: &MeshVertexBufferLayout,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = key.cull_mode;
+        if key.normal_map {
+            descriptor
+                .fragment
+                .as_mut()
+                .unwrap()
+                .shader_defs
+                .push("STANDARDMATERIAL_NORMAL_MAP".into());
+        }
+        if let Some(label) = &mut descriptor.label {
+            *label = format!("standard_{}", *label).into();
+        }
+        Ok(())
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn depth_bias(&self) -> f32 {
+        self.depth_bias
+    }
+}
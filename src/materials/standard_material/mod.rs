@@ -0,0 +1,2 @@
+pub mod instanced_standard_material;
+pub mod plugin;
@@ -0,0 +1,155 @@
+use bevy::{
+    asset::load_internal_asset,
+    pbr::StandardMaterial,
+    prelude::{
+        AddAsset, AssetEvent, Assets, Changed, Commands, CoreStage, Entity, EventReader, Handle,
+        HandleUntyped, IntoSystemDescriptor, Local, Or, Plugin, Query, Res, ResMut, Shader, With,
+        Without,
+    },
+    reflect::TypeUuid,
+    render::{RenderApp, RenderStage},
+    utils::HashMap,
+};
+
+use crate::{
+    instancing::{
+        material::systems::{prepare_batched_instances, prepare_instance_slice_targets},
+        mesh_instance::ExtractedInstances,
+    },
+    prelude::{InstancedMaterialPlugin, InstancedStandardMaterial, NotInstanced},
+};
+
+pub const STANDARD_INSTANCED_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2891046573318260419);
+
+/// Instances entities using bevy's [`StandardMaterial`], i.e. `PbrBundle`s and loaded glTF scenes
+///
+/// Each entity with a `Handle<StandardMaterial>` is given a handle to an
+/// [`InstancedStandardMaterial`] copy of its material, which follows modifications to the original.
+/// The original handle is kept, but stripped in the render world once the entity has been batched,
+/// so each entity is drawn exactly once. Entities still cast shadows through bevy's shadow pass.
+///
+/// Entities marked [`NotInstanced`] are left to bevy's material pipeline.
+pub struct InstancedStandardMaterialPlugin;
+
+impl Plugin for InstancedStandardMaterialPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        load_internal_asset!(
+            app,
+            STANDARD_INSTANCED_SHADER_HANDLE,
+            "standard_instanced.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<InstancedStandardMaterial>()
+            .add_asset::<InstancedStandardMaterial>()
+            .add_plugin(InstancedMaterialPlugin::<InstancedStandardMaterial>::default())
+            .add_system_to_stage(CoreStage::PostUpdate, instance_standard_materials);
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_system_to_stage(
+                RenderStage::Prepare,
+                hide_instances_from_standard_material_pipeline
+                    .after(prepare_batched_instances::system::<InstancedStandardMaterial>)
+                    .after(prepare_instance_slice_targets::system::<InstancedStandardMaterial>),
+            );
+        }
+    }
+}
+
+/// Gives entities using a [`StandardMaterial`] the matching [`InstancedStandardMaterial`],
+/// creating one per standard material and keeping it in sync
+#[allow(clippy::type_complexity)]
+pub fn instance_standard_materials(
+    mut instanced_handles: Local<
+        HashMap<Handle<StandardMaterial>, Handle<InstancedStandardMaterial>>,
+    >,
+    mut asset_events: EventReader<AssetEvent<StandardMaterial>>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut instanced_materials: ResMut<Assets<InstancedStandardMaterial>>,
+    query_standard: Query<
+        (Entity, &Handle<StandardMaterial>),
+        (
+            Without<NotInstanced>,
+            Or<(
+                Changed<Handle<StandardMaterial>>,
+                Without<Handle<InstancedStandardMaterial>>,
+            )>,
+        ),
+    >,
+    query_not_instanced: Query<
+        Entity,
+        (
+            With<NotInstanced>,
+            With<Handle<StandardMaterial>>,
+            With<Handle<InstancedStandardMaterial>>,
+        ),
+    >,
+    mut commands: Commands,
+) {
+    for event in asset_events.iter() {
+        match event {
+            AssetEvent::Created { .. } => (),
+            AssetEvent::Modified { handle } => {
+                if let (Some(material), Some(instanced_material)) = (
+                    standard_materials.get(handle),
+                    instanced_handles
+                        .get(handle)
+                        .and_then(|instanced| instanced_materials.get_mut(instanced)),
+                ) {
+                    instanced_material.0 = material.clone();
+                }
+            }
+            AssetEvent::Removed { handle } => {
+                instanced_handles.remove(handle);
+            }
+        }
+    }
+
+    for (entity, handle) in query_standard.iter() {
+        let instanced = match instanced_handles.get(handle) {
+            Some(instanced) => instanced.clone(),
+            None => {
+                // Retried every frame until the standard material has loaded
+                let material = match standard_materials.get(handle) {
+                    Some(material) => material,
+                    None => continue,
+                };
+
+                let instanced = instanced_materials.add(material.clone().into());
+                instanced_handles.insert(handle.clone_weak(), instanced.clone());
+                instanced
+            }
+        };
+
+        commands.entity(entity).insert(instanced);
+    }
+
+    for entity in query_not_instanced.iter() {
+        commands
+            .entity(entity)
+            .remove::<Handle<InstancedStandardMaterial>>();
+    }
+}
+
+/// Strips the standard material handle from instanced entities,
+/// so bevy's material pipeline doesn't queue them
+#[allow(clippy::type_complexity)]
+pub fn hide_instances_from_standard_material_pipeline(
+    extracted_instances: Res<ExtractedInstances<InstancedStandardMaterial>>,
+    query_instance: Query<
+        Entity,
+        (
+            With<Handle<StandardMaterial>>,
+            With<Handle<InstancedStandardMaterial>>,
+        ),
+    >,
+    mut commands: Commands,
+) {
+    for entity in query_instance
+        .iter()
+        .filter(|entity| extracted_instances.contains_key(entity))
+    {
+        commands.entity(entity).remove::<Handle<StandardMaterial>>();
+    }
+}
@@ -20,7 +20,8 @@ impl Plugin for TextureMaterialPlugin {
             Shader::from_wgsl
         );
 
-        app.add_asset::<TextureMaterial>()
+        app.register_type::<TextureMaterial>()
+            .add_asset::<TextureMaterial>()
             .add_plugin(InstancedMaterialPlugin::<TextureMaterial>::default());
 
         if !app.is_plugin_added::<ColorInstancePlugin>() {
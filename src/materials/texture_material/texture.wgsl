@@ -1,6 +1,10 @@
 #import bevy_pbr::mesh_view_bindings
 #import indirect_instancing::instance_struct
 #import indirect_instancing::color_instance_struct
+#import indirect_instancing::instance_clip_plane
+#import indirect_instancing::instance_depth_bias
+#import indirect_instancing::flat_normal
+#import bevy_core_pipeline::tonemapping
 
 @group(1)
 @binding(0)
@@ -23,8 +27,13 @@ var<storage> in_instances: ColorInstances;
 struct VertexInput {
     @builtin(instance_index) instance: u32,
     @location(0) vertex: vec3<f32>,
+#ifndef FLAT_NORMALS
     @location(1) normal: vec3<f32>,
+#endif
     @location(2) uv: vec2<f32>,
+#ifdef VERTEX_COLORS
+    @location(4) color: vec4<f32>,
+#endif
 };
 
 struct VertexOutput {
@@ -42,11 +51,34 @@ fn vertex(in: VertexInput) -> VertexOutput {
 
     var out: VertexOutput;
     out.world_position = instance.base.transform * vec4<f32>(in.vertex, 1.0);
-    out.clip_position = view.view_proj * out.world_position;
+#ifdef FLAT_NORMALS
+    // Depth bias falls back to its constant term without vertex normals
+    let world_normal = view.world_position - out.world_position.xyz;
+#else
+    let world_normal = (instance.base.inverse_transpose_model * vec4<f32>(in.normal, 0.0)).xyz;
+#endif
+    let biased_position = instance_depth_bias(
+        out.world_position.xyz,
+        world_normal,
+        view.world_position,
+        instance.base.depth_bias,
+        instance.base.depth_bias_slope_scale,
+    );
+    out.clip_position = view.view_proj * vec4<f32>(biased_position, 1.0);
     out.vertex = in.vertex;
+#ifdef FLAT_NORMALS
+    // Replaced with a face normal in the fragment shader
+    out.normal = vec3<f32>(0.0);
+#else
     out.normal = in.normal;
+#endif
     out.uv = in.uv;
+#ifdef VERTEX_COLORS
+    // Vertex color modulates the instance tint, i.e. for atlased foliage
+    out.color = instance.color * in.color;
+#else
     out.color = instance.color;
+#endif
     return out;
 }
 
@@ -55,9 +87,24 @@ fn luminance(v: vec3<f32>) -> f32 {
 }
 
 @fragment
-fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> @location(0) vec4<f32> {
+#ifdef FLAT_NORMALS
+    var normal = flat_normal(in.world_position.xyz);
+#else
+    var normal = in.normal;
+#endif
+#ifdef TWO_SIDED_LIGHTING
+    // Light back faces as if they faced the viewer
+    if !is_front {
+        normal = -normal;
+    }
+#endif
+
     let directional_light = lights.directional_lights[0];
-    let directional_fac = dot(in.normal, directional_light.direction_to_light);
+    let directional_fac = dot(normal, directional_light.direction_to_light);
     let directional_color = directional_light.color * directional_fac;
 
     let ambient = 0.3;
@@ -65,6 +112,9 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let tex = textureSample(in_texture, in_sampler, in.uv);
 
+    // Discard after sampling, which requires uniform control flow
+    clip_instance(in.world_position);
+
     let tint = in.color.xyz * clamp(
         directional_color.xyz,
         vec3<f32>(ambient),
@@ -73,5 +123,17 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let color = tex.rgb * tint * directional_color.xyz;
 
-    return vec4<f32>(color, in.color.a);
+    var output_color = vec4<f32>(color, tex.a * in.color.a);
+
+#ifdef TONEMAP_IN_SHADER
+    output_color = vec4<f32>(reinhard_luminance(output_color.rgb), output_color.a);
+#endif
+#ifdef DEBAND_DITHER
+    var output_rgb = output_color.rgb;
+    output_rgb = pow(output_rgb, vec3<f32>(1.0 / 2.2));
+    output_rgb = output_rgb + screen_space_dither(in.clip_position.xy);
+    output_rgb = pow(output_rgb, vec3<f32>(2.2));
+    output_color = vec4<f32>(output_rgb, output_color.a);
+#endif
+    return output_color;
 }
@@ -2,7 +2,7 @@ use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     pbr::AlphaMode,
     prelude::{default, AssetServer, Handle, Image},
-    reflect::TypeUuid,
+    reflect::{std_traits::ReflectDefault, Reflect, TypeUuid},
     render::{
         mesh::MeshVertexBufferLayout,
         render_asset::{PrepareAssetError, RenderAsset, RenderAssets},
@@ -21,14 +21,21 @@ use crate::{
 
 use super::plugin::TEXTURE_SHADER_HANDLE;
 
-#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[derive(Debug, Clone, AsBindGroup, TypeUuid, Reflect)]
 #[uuid = "335058d3-aa56-4b1b-b0aa-cf483b2c6ca4"]
 #[bind_group_data(TextureMaterialPipelineKey)]
+#[reflect(Default, Debug)]
 pub struct TextureMaterial {
     #[texture(0)]
     #[sampler(1)]
     pub texture: Handle<Image>,
     pub alpha_mode: AlphaMode,
+    /// Converts texture alpha into MSAA sample coverage, i.e. for foliage cards
+    pub alpha_to_coverage: bool,
+    /// Lights back faces as if they faced the viewer, i.e. for foliage cards drawn without culling
+    pub two_sided_lighting: bool,
+    /// Not reflected, as [`Face`] is a `wgpu` type
+    #[reflect(ignore)]
     pub cull_mode: Option<Face>,
 }
 
@@ -37,6 +44,8 @@ impl Default for TextureMaterial {
         Self {
             texture: default(),
             alpha_mode: default(),
+            alpha_to_coverage: false,
+            two_sided_lighting: false,
             cull_mode: Some(Face::Back),
         }
     }
@@ -99,21 +108,35 @@ impl RenderAsset for TextureMaterial {
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct TextureMaterialPipelineKey {
     pub cull_mode: Option<Face>,
+    pub two_sided_lighting: bool,
 }
 
 impl PartialOrd for TextureMaterialPipelineKey {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.cull_mode
+        match self
+            .cull_mode
             .map(|cull_mode| cull_mode as usize)
             .partial_cmp(&other.cull_mode.map(|cull_mode| cull_mode as usize))
+        {
+            Some(core::cmp::Ordering::Equal) => {}
+            ord => return ord,
+        }
+        self.two_sided_lighting
+            .partial_cmp(&other.two_sided_lighting)
     }
 }
 
 impl Ord for TextureMaterialPipelineKey {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.cull_mode
+        match self
+            .cull_mode
             .map(|cull_mode| cull_mode as usize)
             .cmp(&other.cull_mode.map(|cull_mode| cull_mode as usize))
+        {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        self.two_sided_lighting.cmp(&other.two_sided_lighting)
     }
 }
 
@@ -121,6 +144,7 @@ impl From<&TextureMaterial> for TextureMaterialPipelineKey {
     fn from(texture_material: &TextureMaterial) -> Self {
         TextureMaterialPipelineKey {
             cull_mode: texture_material.cull_mode,
+            two_sided_lighting: texture_material.two_sided_lighting,
         }
     }
 }
@@ -128,7 +152,7 @@ impl From<&TextureMaterial> for TextureMaterialPipelineKey {
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct TextureMaterialBatchKey {
     pub texture: Handle<Image>,
-    pub cull_mode: Option<Face>,
+    pub pipeline_key: TextureMaterialPipelineKey,
 }
 
 impl PartialOrd for TextureMaterialBatchKey {
@@ -137,9 +161,7 @@ impl PartialOrd for TextureMaterialBatchKey {
             Some(core::cmp::Ordering::Equal) => {}
             ord => return ord,
         }
-        self.cull_mode
-            .map(|cull_mode| cull_mode as usize)
-            .partial_cmp(&other.cull_mode.map(|cull_mode| cull_mode as usize))
+        self.pipeline_key.partial_cmp(&other.pipeline_key)
     }
 }
 
@@ -149,9 +171,7 @@ impl Ord for TextureMaterialBatchKey {
             core::cmp::Ordering::Equal => {}
             ord => return ord,
         }
-        self.cull_mode
-            .map(|cull_mode| cull_mode as usize)
-            .cmp(&other.cull_mode.map(|cull_mode| cull_mode as usize))
+        self.pipeline_key.cmp(&other.pipeline_key)
     }
 }
 
@@ -159,7 +179,7 @@ impl From<&TextureMaterial> for TextureMaterialBatchKey {
     fn from(texture_material: &TextureMaterial) -> Self {
         TextureMaterialBatchKey {
             texture: texture_material.texture.clone_weak(),
-            cull_mode: texture_material.cull_mode,
+            pipeline_key: texture_material.into(),
         }
     }
 }
@@ -186,6 +206,14 @@ impl MaterialInstanced for TextureMaterial {
         _layout: &MeshVertexBufferLayout,
     ) -> Result<(), SpecializedMeshPipelineError> {
         descriptor.primitive.cull_mode = key.cull_mode;
+        if key.two_sided_lighting {
+            descriptor
+                .fragment
+                .as_mut()
+                .unwrap()
+                .shader_defs
+                .push("TWO_SIDED_LIGHTING".into());
+        }
         if let Some(label) = &mut descriptor.label {
             *label = format!("custom_{}", *label).into();
         }
@@ -195,4 +223,8 @@ impl MaterialInstanced for TextureMaterial {
     fn alpha_mode(&self) -> AlphaMode {
         self.alpha_mode
     }
+
+    fn alpha_to_coverage(&self) -> bool {
+        self.alpha_to_coverage
+    }
 }
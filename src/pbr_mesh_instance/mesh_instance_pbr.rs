@@ -0,0 +1,22 @@
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Component, Reflect},
+};
+
+/// Per-instance PBR surface properties, overriding those of the instance's material
+#[derive(Debug, Copy, Clone, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct InstanceMetallicRoughness {
+    pub metallic: f32,
+    pub perceptual_roughness: f32,
+}
+
+impl Default for InstanceMetallicRoughness {
+    /// Matches the defaults of bevy's `StandardMaterial`
+    fn default() -> Self {
+        Self {
+            metallic: 0.01,
+            perceptual_roughness: 0.089,
+        }
+    }
+}
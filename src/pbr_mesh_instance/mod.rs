@@ -0,0 +1,146 @@
+pub mod mesh_instance_pbr;
+pub mod plugin;
+
+use bevy::{
+    ecs::{query::ROQueryItem, system::lifetimeless::Read},
+    math::Mat4,
+    prelude::{default, Component},
+    render::render_resource::{ShaderSize, ShaderType},
+};
+
+use crate::prelude::{
+    ColorMeshInstance, CulledInstance, GpuColorMeshInstance, Instance, InstanceMetallicRoughness,
+    InstanceUniformLength,
+};
+
+/// Color mesh instance with per-instance metallic and roughness,
+/// the instance type of [`PbrInstancedMaterial`](crate::prelude::PbrInstancedMaterial)
+///
+/// Entities without an [`InstanceMetallicRoughness`] use those of their material.
+#[derive(Debug, Default, Clone, PartialEq, Component)]
+pub struct PbrMeshInstance {
+    pub base: ColorMeshInstance,
+    pub metallic_roughness: Option<InstanceMetallicRoughness>,
+}
+
+/// GPU-friendly data for a single PBR mesh instance
+#[derive(Debug, Copy, Clone, PartialEq, ShaderType, Component)]
+pub struct GpuPbrMeshInstance {
+    #[size(160)]
+    pub base: GpuColorMeshInstance,
+    #[size(4)]
+    pub metallic: f32,
+    #[size(4)]
+    pub perceptual_roughness: f32,
+    /// Nonzero when `metallic` and `perceptual_roughness` override the material's
+    #[size(8)]
+    pub has_metallic_roughness: u32,
+}
+
+impl Default for GpuPbrMeshInstance {
+    fn default() -> Self {
+        Self {
+            base: default(),
+            metallic: 0.0,
+            perceptual_roughness: 0.0,
+            has_metallic_roughness: 0,
+        }
+    }
+}
+
+// Layout is mirrored by hand in `pbr_instance_struct.wgsl`
+const _: () = assert!(
+    GpuColorMeshInstance::SHADER_SIZE.get() == 160,
+    "GpuColorMeshInstance size changed, update GpuPbrMeshInstance::base's #[size]"
+);
+const _: () = assert!(
+    GpuPbrMeshInstance::SHADER_SIZE.get() == 176,
+    "GpuPbrMeshInstance size changed, update pbr_instance_struct.wgsl"
+);
+const _: () = assert!(
+    PbrMeshInstance::UNIFORM_BUFFER_LENGTH.get() == 93,
+    "Uniform buffer length changed, update the PbrInstances array in pbr_instance_struct.wgsl"
+);
+
+impl CulledInstance for GpuPbrMeshInstance {
+    const TRANSFORM_OFFSET: u64 = Self::METADATA.offset(0) + GpuColorMeshInstance::TRANSFORM_OFFSET;
+}
+
+impl Instance for PbrMeshInstance {
+    type ExtractedInstance = Self;
+    type PreparedInstance = GpuPbrMeshInstance;
+
+    type Query = (
+        <ColorMeshInstance as Instance>::Query,
+        Option<Read<InstanceMetallicRoughness>>,
+    );
+
+    fn extract_instance(
+        (base, metallic_roughness): ROQueryItem<Self::Query>,
+    ) -> Self::ExtractedInstance {
+        PbrMeshInstance {
+            base: ColorMeshInstance::extract_instance(base),
+            metallic_roughness: metallic_roughness.copied(),
+        }
+    }
+
+    fn prepare_instance(instance: &Self::ExtractedInstance, mesh: u32) -> Self::PreparedInstance {
+        let base = ColorMeshInstance::prepare_instance(&instance.base, mesh);
+
+        match instance.metallic_roughness {
+            Some(metallic_roughness) => GpuPbrMeshInstance {
+                base,
+                metallic: metallic_roughness.metallic,
+                perceptual_roughness: metallic_roughness.perceptual_roughness,
+                has_metallic_roughness: 1,
+            },
+            None => GpuPbrMeshInstance { base, ..default() },
+        }
+    }
+
+    fn transform(instance: &Self::ExtractedInstance) -> Mat4 {
+        instance.base.base.transform
+    }
+
+    fn fade(instance: &mut Self::PreparedInstance, fade: f32) {
+        ColorMeshInstance::fade(&mut instance.base, fade);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{math::Vec4, prelude::default};
+
+    use crate::prelude::{ColorMeshInstance, Instance, InstanceMetallicRoughness};
+
+    use super::PbrMeshInstance;
+
+    #[test]
+    fn prepare_instance_carries_metallic_roughness() {
+        let instance = PbrMeshInstance {
+            base: ColorMeshInstance {
+                attribute: Vec4::new(1.0, 0.5, 0.25, 1.0),
+                ..default()
+            },
+            metallic_roughness: Some(InstanceMetallicRoughness {
+                metallic: 0.75,
+                perceptual_roughness: 0.3,
+            }),
+        };
+
+        let prepared = PbrMeshInstance::prepare_instance(&instance, 2);
+
+        assert_eq!(prepared.base.base.mesh, 2);
+        assert_eq!(prepared.base.attribute, instance.base.attribute);
+        assert_eq!(prepared.metallic, 0.75);
+        assert_eq!(prepared.perceptual_roughness, 0.3);
+        assert_eq!(prepared.has_metallic_roughness, 1);
+    }
+
+    #[test]
+    fn prepare_instance_defers_to_material_without_metallic_roughness() {
+        let prepared = PbrMeshInstance::prepare_instance(&PbrMeshInstance::default(), 0);
+
+        assert_eq!(prepared.has_metallic_roughness, 0);
+    }
+}
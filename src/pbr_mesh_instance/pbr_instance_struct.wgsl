@@ -0,0 +1,24 @@
+#import indirect_instancing::color_instance_struct
+#define_import_path indirect_instancing::pbr_instance_struct
+
+struct PbrInstanceData {
+    @size(160)
+    base: ColorInstanceData,
+    @size(4)
+    metallic: f32,
+    @size(4)
+    perceptual_roughness: f32,
+    // Nonzero when metallic and perceptual_roughness override the material's
+    @size(8)
+    has_metallic_roughness: u32,
+};
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+struct PbrInstances {
+    instances: array<PbrInstanceData, 93>,
+};
+#else
+struct PbrInstances {
+    instances: array<PbrInstanceData>,
+};
+#endif
@@ -0,0 +1,31 @@
+use bevy::{
+    asset::load_internal_asset,
+    prelude::{HandleUntyped, Plugin, Shader},
+    reflect::TypeUuid,
+};
+
+use crate::prelude::{ColorInstancePlugin, InstanceMetallicRoughness};
+
+pub const PBR_INSTANCE_STRUCT_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 4370316624870261537);
+
+/// Registers [`PbrMeshInstance`](crate::prelude::PbrMeshInstance) support,
+/// including the `indirect_instancing::pbr_instance_struct` shader import
+pub struct PbrInstancePlugin;
+
+impl Plugin for PbrInstancePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        load_internal_asset!(
+            app,
+            PBR_INSTANCE_STRUCT_HANDLE,
+            "pbr_instance_struct.wgsl",
+            Shader::from_wgsl
+        );
+
+        if !app.is_plugin_added::<ColorInstancePlugin>() {
+            app.add_plugin(ColorInstancePlugin);
+        }
+
+        app.register_type::<InstanceMetallicRoughness>();
+    }
+}
@@ -0,0 +1,115 @@
+pub mod plugin;
+pub mod point_cloud_bundle;
+pub mod point_cloud_material;
+
+use bevy::{
+    ecs::{query::ROQueryItem, reflect::ReflectComponent, system::lifetimeless::Read},
+    math::{Mat4, Vec3, Vec4},
+    prelude::{
+        default, Color, Component, ComputedVisibility, Deref, DerefMut, GlobalTransform, Reflect,
+    },
+    render::render_resource::{ShaderSize, ShaderType},
+};
+
+use crate::prelude::{Instance, InstanceColor, InstanceUniformLength};
+
+/// World-space diameter of a point sprite
+#[derive(Debug, Copy, Clone, PartialEq, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component)]
+pub struct InstancePointSize(pub f32);
+
+impl Default for InstancePointSize {
+    fn default() -> Self {
+        InstancePointSize(1.0)
+    }
+}
+
+/// Point expanded to a camera-facing quad in the vertex shader
+///
+/// Entities without an [`InstanceColor`] are white,
+/// and those without an [`InstancePointSize`] use its default.
+#[derive(Debug, Default, Clone, PartialEq, Component)]
+pub struct PointCloudInstance {
+    pub position: Vec3,
+    pub size: f32,
+    pub color: Vec4,
+}
+
+/// GPU-friendly data for a single point sprite
+///
+/// Unlike the mesh instance types, this doesn't carry a transform,
+/// so it can't be used with [`InstanceCullingPlugin`](crate::prelude::InstanceCullingPlugin).
+#[derive(Debug, Copy, Clone, PartialEq, ShaderType, Component)]
+pub struct GpuPointCloudInstance {
+    pub mesh: u32,
+    pub size: f32,
+    pub position: Vec3,
+    pub color: Vec4,
+}
+
+impl Default for GpuPointCloudInstance {
+    fn default() -> Self {
+        Self {
+            mesh: default(),
+            size: 0.0,
+            position: Vec3::ZERO,
+            color: Vec4::ZERO,
+        }
+    }
+}
+
+// Layout is mirrored by hand in `point_cloud_instance_struct.wgsl`
+const _: () = assert!(
+    GpuPointCloudInstance::SHADER_SIZE.get() == 48,
+    "GpuPointCloudInstance size changed, update point_cloud_instance_struct.wgsl"
+);
+const _: () = assert!(
+    PointCloudInstance::UNIFORM_BUFFER_LENGTH.get() == 341,
+    "Uniform buffer length changed, update the PointCloudInstances array in point_cloud_instance_struct.wgsl"
+);
+
+impl Instance for PointCloudInstance {
+    type ExtractedInstance = Self;
+    type PreparedInstance = GpuPointCloudInstance;
+
+    type Query = (
+        Read<GlobalTransform>,
+        Read<ComputedVisibility>,
+        Option<Read<InstanceColor>>,
+        Option<Read<InstancePointSize>>,
+    );
+
+    fn extract_instance(
+        (transform, visibility, color, size): ROQueryItem<Self::Query>,
+    ) -> Self::ExtractedInstance {
+        let color = color.map(|color| color.0).unwrap_or(Color::WHITE);
+
+        PointCloudInstance {
+            position: transform.translation(),
+            // Invisible points collapse to nothing, as with mesh instances
+            size: if visibility.is_visible() {
+                size.copied().unwrap_or_default().0
+            } else {
+                0.0
+            },
+            color: Vec4::new(color.r(), color.g(), color.b(), color.a()),
+        }
+    }
+
+    fn prepare_instance(instance: &Self::ExtractedInstance, mesh: u32) -> Self::PreparedInstance {
+        GpuPointCloudInstance {
+            mesh,
+            size: instance.size,
+            position: instance.position,
+            color: instance.color,
+        }
+    }
+
+    fn transform(instance: &Self::ExtractedInstance) -> Mat4 {
+        Mat4::from_translation(instance.position)
+    }
+
+    fn fade(instance: &mut Self::PreparedInstance, fade: f32) {
+        instance.color.w *= fade;
+    }
+}
@@ -0,0 +1,79 @@
+use bevy::{
+    asset::load_internal_asset,
+    prelude::{AddAsset, Assets, Handle, HandleUntyped, Mesh, Plugin, Shader},
+    reflect::TypeUuid,
+    render::mesh::PrimitiveTopology,
+};
+
+use crate::prelude::{
+    ColorInstancePlugin, InstancePointSize, InstancedMaterialPlugin, PointCloudMaterial,
+};
+
+pub const POINT_CLOUD_INSTANCE_STRUCT_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 9318560411728943661);
+
+pub const POINT_CLOUD_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 15647207342081693410);
+
+/// Six-vertex mesh drawn once per point sprite
+///
+/// Its positions only bound the sprite for frustum culling;
+/// [`PointCloudMaterial`] doesn't read them.
+pub const POINT_SPRITE_MESH_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Mesh::TYPE_UUID, 6241370153390837277);
+
+/// Registers [`PointCloudMaterial`] and the point sprite mesh,
+/// including the `indirect_instancing::point_cloud_instance_struct` shader import
+pub struct PointCloudPlugin;
+
+impl Plugin for PointCloudPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        load_internal_asset!(
+            app,
+            POINT_CLOUD_INSTANCE_STRUCT_HANDLE,
+            "point_cloud_instance_struct.wgsl",
+            Shader::from_wgsl
+        );
+
+        load_internal_asset!(
+            app,
+            POINT_CLOUD_SHADER_HANDLE,
+            "point_cloud.wgsl",
+            Shader::from_wgsl
+        );
+
+        if !app.is_plugin_added::<ColorInstancePlugin>() {
+            app.add_plugin(ColorInstancePlugin);
+        }
+
+        app.register_type::<InstancePointSize>();
+
+        app.register_type::<PointCloudMaterial>()
+            .add_asset::<PointCloudMaterial>()
+            .add_plugin(InstancedMaterialPlugin::<PointCloudMaterial>::default());
+
+        app.world
+            .resource_mut::<Assets<PointCloudMaterial>>()
+            .set_untracked(
+                Handle::<PointCloudMaterial>::default(),
+                PointCloudMaterial::default(),
+            );
+
+        let mut point_sprite_mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        point_sprite_mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [-0.5, -0.5, 0.0],
+                [0.5, -0.5, 0.0],
+                [0.5, 0.5, 0.0],
+                [-0.5, -0.5, 0.0],
+                [0.5, 0.5, 0.0],
+                [-0.5, 0.5, 0.0],
+            ],
+        );
+
+        app.world
+            .resource_mut::<Assets<Mesh>>()
+            .set_untracked(POINT_SPRITE_MESH_HANDLE, point_sprite_mesh);
+    }
+}
@@ -0,0 +1,79 @@
+#import bevy_pbr::mesh_view_bindings
+#import indirect_instancing::point_cloud_instance_struct
+#import indirect_instancing::instance_clip_plane
+#import bevy_core_pipeline::tonemapping
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+@group(2)
+@binding(0)
+var<uniform> instances: PointCloudInstances;
+#else
+@group(2)
+@binding(0)
+var<storage> instances: PointCloudInstances;
+#endif
+
+struct VertexInput {
+    @builtin(instance_index) instance: u32,
+    @builtin(vertex_index) vertex: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) world_position: vec4<f32>,
+};
+
+@vertex
+fn vertex(in: VertexInput) -> VertexOutput {
+    let instance = instances.instances[in.instance];
+
+    // Two triangles spanning a unit quad
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-0.5, -0.5),
+        vec2<f32>(0.5, -0.5),
+        vec2<f32>(0.5, 0.5),
+        vec2<f32>(-0.5, -0.5),
+        vec2<f32>(0.5, 0.5),
+        vec2<f32>(-0.5, 0.5),
+    );
+    let corner = corners[in.vertex % 6u];
+
+    // Face the camera using its world-space right and up axes
+    let right = view.view[0].xyz;
+    let up = view.view[1].xyz;
+    let world_position = instance.position + (right * corner.x + up * corner.y) * instance.size;
+
+    var out: VertexOutput;
+    out.clip_position = view.view_proj * vec4<f32>(world_position, 1.0);
+    out.corner = corner;
+    out.color = instance.color;
+    out.world_position = vec4<f32>(world_position, 1.0);
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    clip_instance(in.world_position);
+
+    // Round off the quad
+    if (length(in.corner) > 0.5) {
+        discard;
+    }
+
+    var output_color = in.color;
+
+    // Match the view's output transform, as bevy's own meshes do
+#ifdef TONEMAP_IN_SHADER
+    output_color = vec4<f32>(reinhard_luminance(output_color.rgb), output_color.a);
+#endif
+#ifdef DEBAND_DITHER
+    var output_rgb = output_color.rgb;
+    output_rgb = pow(output_rgb, vec3<f32>(1.0 / 2.2));
+    output_rgb = output_rgb + screen_space_dither(in.clip_position.xy);
+    output_rgb = pow(output_rgb, vec3<f32>(2.2));
+    output_color = vec4<f32>(output_rgb, output_color.a);
+#endif
+    return output_color;
+}
@@ -0,0 +1,28 @@
+use bevy::prelude::{default, Bundle, Handle, Mesh, SpatialBundle};
+
+use crate::prelude::{
+    InstanceColor, InstancePointSize, PointCloudMaterial, POINT_SPRITE_MESH_HANDLE,
+};
+
+/// Components to create a point sprite
+#[derive(Bundle)]
+pub struct PointCloudBundle {
+    pub material: Handle<PointCloudMaterial>,
+    pub mesh: Handle<Mesh>,
+    pub color: InstanceColor,
+    pub size: InstancePointSize,
+    #[bundle]
+    pub spatial_bundle: SpatialBundle,
+}
+
+impl Default for PointCloudBundle {
+    fn default() -> Self {
+        Self {
+            material: default(),
+            mesh: POINT_SPRITE_MESH_HANDLE.typed(),
+            color: default(),
+            size: default(),
+            spatial_bundle: default(),
+        }
+    }
+}
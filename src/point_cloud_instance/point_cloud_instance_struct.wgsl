@@ -0,0 +1,18 @@
+#define_import_path indirect_instancing::point_cloud_instance_struct
+
+struct PointCloudInstanceData {
+    mesh: u32,
+    size: f32,
+    position: vec3<f32>,
+    color: vec4<f32>,
+};
+
+#ifdef NO_STORAGE_BUFFERS_SUPPORT
+struct PointCloudInstances {
+    instances: array<PointCloudInstanceData, 341>,
+};
+#else
+struct PointCloudInstances {
+    instances: array<PointCloudInstanceData>,
+};
+#endif
@@ -0,0 +1,80 @@
+use bevy::{
+    pbr::AlphaMode,
+    prelude::{default, AssetServer},
+    reflect::{std_traits::ReflectDefault, Reflect, TypeUuid},
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+};
+
+use crate::{
+    instancing::material::material_instanced::AsBatch,
+    prelude::{
+        InstancedMaterialPipeline, MaterialInstanced, PointCloudInstance, POINT_CLOUD_SHADER_HANDLE,
+    },
+};
+
+/// Renders [`PointCloudInstance`]s as round, camera-facing sprites
+///
+/// Sprite corners are derived from `vertex_index`, so the pipeline binds no vertex buffer.
+/// Intended for use with [`POINT_SPRITE_MESH_HANDLE`](crate::prelude::POINT_SPRITE_MESH_HANDLE),
+/// which only provides the vertex count of a quad.
+#[derive(Debug, Clone, AsBindGroup, TypeUuid, Reflect)]
+#[uuid = "53bdeac8-c1e1-471f-9769-2ca018d48ceb"]
+#[reflect(Default, Debug)]
+pub struct PointCloudMaterial {
+    pub alpha_mode: AlphaMode,
+}
+
+impl Default for PointCloudMaterial {
+    fn default() -> Self {
+        Self {
+            alpha_mode: default(),
+        }
+    }
+}
+
+impl From<&PointCloudMaterial> for () {
+    fn from(_: &PointCloudMaterial) -> Self {}
+}
+
+impl AsBatch for PointCloudMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for PointCloudMaterial {
+    type Instance = PointCloudInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        POINT_CLOUD_SHADER_HANDLE.typed().into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        POINT_CLOUD_SHADER_HANDLE.typed().into()
+    }
+
+    fn specialize(
+        _pipeline: &InstancedMaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _key: Self::Data,
+        _layout: &MeshVertexBufferLayout,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = None;
+        if let Some(label) = &mut descriptor.label {
+            *label = format!("point_cloud_{}", *label).into();
+        }
+        Ok(())
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    // Sprites are generated from vertex_index alone
+    fn vertexless() -> bool {
+        true
+    }
+}
@@ -1,23 +1,47 @@
 pub use crate::{
     colored_mesh_instance::{color_instance_bundle::*, mesh_instance_color::*, plugin::*, *},
+    instanced_scene::{plugin::*, *},
+    instanced_shapes::{plugin::*, *},
     instancing::{
+        batch_debug::*,
+        clip_plane::*,
+        compact_instance::*,
+        draw_call_count::*,
+        draw_distance::*,
+        eviction::*,
+        gpu_timing::*,
         indirect::*,
-        instance_slice::{instance_slice_bundle::*, *},
         instance_compute::*,
+        instance_culling::*,
+        instance_data::*,
+        instance_index::*,
+        instance_slice::{
+            instance_seed::*, instance_slice_bundle::*, instance_stream::*,
+            shared_instance_slice::*, *,
+        },
         material::{
-            instanced_material_pipeline::*, plugin::*,
-            set_instanced_material_bind_group::*, material_instanced::*, systems::*, *,
+            hybrid::*, instance_storage_buffer::*, instanced_material_pipeline::*,
+            material_instanced::*, plugin::*, set_instanced_material_bind_group::*, systems::*, *,
         },
-        mesh_instance::{mesh_instance_bundle::*, *},
+        mesh_instance::{instance_attribute::*, mesh_instance_bundle::*, *},
+        ndc_instance::*,
+        oit::*,
         plugin::*,
         render::{instance::*, instanced_mesh_pipeline::*, *},
+        shadow_tile::*,
+        sort::*,
         *,
     },
     materials::{
         basic_material::{plugin::*, *},
         custom_material::{custom_material::*, plugin::*, *},
+        pbr_material::{pbr_instanced_material::*, plugin::*, *},
+        screen_material::{plugin::*, *},
+        standard_material::{instanced_standard_material::*, plugin::*, *},
         texture_material::{plugin::*, texture_material::*, *},
         *,
     },
+    pbr_mesh_instance::{mesh_instance_pbr::*, plugin::*, *},
+    point_cloud_instance::{plugin::*, point_cloud_bundle::*, point_cloud_material::*, *},
     *,
 };
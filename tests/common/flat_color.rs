@@ -0,0 +1,33 @@
+use bevy::{
+    prelude::AssetServer,
+    reflect::TypeUuid,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+
+use bevy_instancing::prelude::{AsBatch, ColorMeshInstance, MaterialInstanced};
+
+/// Unlit material drawing each instance in its [`InstanceColor`](bevy_instancing::prelude::InstanceColor),
+/// so rendered pixels can be compared against exact colors
+#[derive(Debug, Default, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "8f3c2a61-5d4e-4b97-a1c8-3e6f0b9d2a74"]
+pub struct FlatColorMaterial {}
+
+impl From<&FlatColorMaterial> for () {
+    fn from(_: &FlatColorMaterial) -> Self {}
+}
+
+impl AsBatch for FlatColorMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for FlatColorMaterial {
+    type Instance = ColorMeshInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        "shader/flat_color.wgsl".into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        "shader/flat_color.wgsl".into()
+    }
+}
@@ -0,0 +1,5 @@
+// Each test binary compiles this module separately, using only part of it
+#![allow(dead_code)]
+
+pub mod flat_color;
+pub mod readback;
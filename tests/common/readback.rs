@@ -0,0 +1,253 @@
+use std::{
+    num::NonZeroU32,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    prelude::{default, App, Assets, Commands, Handle, Image, PluginGroup, Res, Resource, World},
+    render::{
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        main_graph::node::CAMERA_DRIVER,
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageCopyTexture,
+            ImageDataLayout, MapMode, Origin3d, TextureAspect, TextureDescriptor, TextureDimension,
+            TextureFormat, TextureUsages,
+        },
+        renderer::{RenderContext, RenderDevice},
+        settings::WgpuSettings,
+        RenderApp, RenderStage,
+    },
+    window::WindowPlugin,
+    winit::WinitPlugin,
+    DefaultPlugins,
+};
+
+/// Width and height of the offscreen render target
+///
+/// A row of RGBA8 pixels is 256 bytes, so rows are copied without padding.
+pub const TARGET_SIZE: u32 = 64;
+
+/// Frames to wait for pipelines to compile before giving up
+pub const MAX_FRAMES: u32 = 300;
+
+const IMAGE_READBACK: &str = "image_readback";
+
+/// Offscreen render target shared with the render world
+#[derive(Debug, Clone, Resource, ExtractResource)]
+pub struct ReadbackTarget(pub Handle<Image>);
+
+/// Most recent contents of the render target, as rows of RGBA8 pixels
+#[derive(Debug, Default, Clone, Resource)]
+pub struct ImageReadback(Arc<Mutex<Option<Vec<u8>>>>);
+
+impl ImageReadback {
+    /// Pixel at `x`, `y` from the top left of the render target
+    pub fn pixel_at(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        let offset = (y * TARGET_SIZE + x) as usize * 4;
+        self.0.lock().unwrap().as_ref().map(|image| {
+            [
+                image[offset],
+                image[offset + 1],
+                image[offset + 2],
+                image[offset + 3],
+            ]
+        })
+    }
+
+    /// Middle row of the render target
+    pub fn middle_row(&self) -> Option<Vec<u8>> {
+        let row_size = TARGET_SIZE as usize * 4;
+        let offset = (TARGET_SIZE / 2) as usize * row_size;
+        self.0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|image| image[offset..offset + row_size].to_vec())
+    }
+
+    /// Pixel at `column` of the middle row
+    pub fn pixel(&self, column: u32) -> Option<[u8; 4]> {
+        self.pixel_at(column, TARGET_SIZE / 2)
+    }
+
+    /// Pixel at the center of the render target
+    pub fn center(&self) -> Option<[u8; 4]> {
+        self.pixel(TARGET_SIZE / 2)
+    }
+}
+
+/// Staging buffers copied to during this frame's render graph, awaiting mapping
+#[derive(Default, Resource)]
+struct PendingReadbacks(Mutex<Vec<Buffer>>);
+
+/// Whether bevy's renderer would find an adapter, with the same settings it requests one with
+pub fn has_adapter() -> bool {
+    let settings = WgpuSettings::default();
+    let instance = wgpu::Instance::new(settings.backends.unwrap());
+    futures_lite::future::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: settings.power_preference,
+        ..default()
+    }))
+    .is_some()
+}
+
+/// Windowless app reading back its [`ReadbackTarget`] after every frame
+///
+/// Returns `None` without a GPU adapter to render with, so tests pass vacuously on machines without one.
+pub fn readback_app() -> Option<App> {
+    if !has_adapter() {
+        eprintln!("No GPU adapter available, skipping render test");
+        return None;
+    }
+
+    let mut app = App::default();
+
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                add_primary_window: false,
+                exit_on_all_closed: false,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(ScheduleRunnerPlugin)
+    .add_plugin(ExtractResourcePlugin::<ReadbackTarget>::default());
+
+    let readback = ImageReadback::default();
+    app.insert_resource(readback.clone());
+
+    let render_app = app.sub_app_mut(RenderApp);
+    render_app
+        .insert_resource(readback)
+        .init_resource::<PendingReadbacks>()
+        .add_system_to_stage(RenderStage::Cleanup, map_pending_readbacks);
+
+    let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+    render_graph.add_node(IMAGE_READBACK, ImageReadbackNode);
+    render_graph
+        .add_node_edge(CAMERA_DRIVER, IMAGE_READBACK)
+        .unwrap();
+
+    Some(app)
+}
+
+/// Adds an sRGB render target of [`TARGET_SIZE`] and inserts it as the [`ReadbackTarget`]
+pub fn add_target(images: &mut Assets<Image>, commands: &mut Commands) -> Handle<Image> {
+    let size = Extent3d {
+        width: TARGET_SIZE,
+        height: TARGET_SIZE,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("readback target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    let target = images.add(image);
+    commands.insert_resource(ReadbackTarget(target.clone()));
+    target
+}
+
+/// Copies the render target into a staging buffer
+struct ImageReadbackNode;
+
+impl Node for ImageReadbackNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let target = if let Some(target) = world.get_resource::<ReadbackTarget>() {
+            target
+        } else {
+            return Ok(());
+        };
+
+        let gpu_image =
+            if let Some(gpu_image) = world.resource::<RenderAssets<Image>>().get(&target.0) {
+                gpu_image
+            } else {
+                return Ok(());
+            };
+
+        let buffer = render_context
+            .render_device
+            .create_buffer(&BufferDescriptor {
+                label: Some("image readback buffer"),
+                size: TARGET_SIZE as u64 * TARGET_SIZE as u64 * 4,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        render_context.command_encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture: &gpu_image.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    bytes_per_row: NonZeroU32::new(TARGET_SIZE * 4),
+                    ..default()
+                },
+            },
+            Extent3d {
+                width: TARGET_SIZE,
+                height: TARGET_SIZE,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        world
+            .resource::<PendingReadbacks>()
+            .0
+            .lock()
+            .unwrap()
+            .push(buffer);
+
+        Ok(())
+    }
+}
+
+/// Maps the buffers copied to during the render stage, storing their contents
+/// once the device has finished with them
+fn map_pending_readbacks(
+    render_device: Res<RenderDevice>,
+    pending: Res<PendingReadbacks>,
+    readback: Res<ImageReadback>,
+) {
+    for buffer in pending.0.lock().unwrap().drain(..) {
+        let result = readback.0.clone();
+        let mapped = buffer.clone();
+        render_device.map_buffer(&buffer.slice(..), MapMode::Read, move |status| {
+            if status.is_err() {
+                return;
+            }
+
+            let image = mapped.slice(..).get_mapped_range().to_vec();
+            mapped.unmap();
+
+            *result.lock().unwrap() = Some(image);
+        });
+    }
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    math::{Quat, Vec2, Vec3},
+    prelude::{
+        default, info, shape::Quad, AssetServer, Assets, Camera, Camera3dBundle, Commands,
+        EventWriter, Image, Local, Mesh, OrthographicProjection, Res, ResMut, SpatialBundle,
+        Transform,
+    },
+    reflect::TypeUuid,
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        render_resource::{AsBindGroup, ShaderRef},
+    },
+};
+
+use bevy_instancing::prelude::{
+    AsBatch, CompactMeshInstance, IndirectRenderingPlugin, InstancedMaterialPlugin,
+    MaterialInstanced, MeshInstanceBundle,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+/// Unlit white material drawing compact instances
+#[derive(Debug, Default, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "5e0b3f72-1c94-4d8a-b6e5-9a2d7c4f1e03"]
+struct CompactMaterial {}
+
+impl From<&CompactMaterial> for () {
+    fn from(_: &CompactMaterial) -> Self {}
+}
+
+impl AsBatch for CompactMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for CompactMaterial {
+    type Instance = CompactMeshInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        "shader/compact_instance.wgsl".into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        "shader/compact_instance.wgsl".into()
+    }
+}
+
+// Test that compact instances reconstruct their scale and rotation in the vertex shader,
+// by drawing a wide quad scaled down on the left and rotated upright on the right
+#[test]
+fn compact_instance() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(InstancedMaterialPlugin::<CompactMaterial>::default());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_coverage);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CompactMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    // Two units wide and half a unit tall
+    let mesh = meshes.add(Quad::new(Vec2::new(2.0, 0.5)).into());
+    let material = materials.add(CompactMaterial {});
+
+    let transforms = [
+        // Spans x -1.5..-0.5
+        Transform::from_xyz(-1.0, 0.0, 0.0).with_scale(Vec3::splat(0.5)),
+        // Spans x 0.75..1.25
+        Transform::from_xyz(1.0, 0.0, 0.0).with_rotation(Quat::from_rotation_z(FRAC_PI_2)),
+    ];
+
+    for transform in transforms {
+        commands.spawn(MeshInstanceBundle::<CompactMaterial> {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            spatial_bundle: SpatialBundle {
+                transform,
+                ..default()
+            },
+        });
+    }
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Column of the render target covering world-space `x`
+fn column(x: f32) -> usize {
+    ((x / VIEW_WIDTH + 0.5) * TARGET_SIZE as f32) as usize
+}
+
+fn check_coverage(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    // World-space x of sampled columns, and whether each should be covered
+    let expected = [(-1.75, false), (-1.0, true), (0.5, false), (1.0, true)];
+
+    let row = readback.middle_row();
+    let coverage = row.as_ref().map(|row| {
+        expected
+            .iter()
+            .map(|(x, _)| row[column(*x) * 4] > 0)
+            .collect::<Vec<_>>()
+    });
+
+    let matches = coverage.as_ref().map(|coverage| {
+        coverage
+            .iter()
+            .zip(expected.iter())
+            .all(|(covered, (_, expected))| covered == expected)
+    });
+
+    if matches == Some(true) {
+        info!(
+            "Compact instances covered the expected columns after {} frames",
+            *frame
+        );
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected coverage {expected:?}, got {coverage:?}");
+    }
+}
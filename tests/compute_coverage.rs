@@ -0,0 +1,164 @@
+//! Headless test of instance compute dispatch coverage
+//!
+//! Computes a slice whose length isn't a multiple of the compute workgroup size,
+//! laying each instance out in its own cell of a grid filling the render target,
+//! and fails unless every cell of the slice's range is drawn.
+
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    ecs::system::lifetimeless::Read,
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Commands, Component,
+        EventWriter, Image, Local, Mesh, OrthographicProjection, Res, ResMut, Transform, Vec3,
+    },
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        extract_component::ExtractComponent,
+        render_resource::{AsBindGroup, ShaderRef},
+    },
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, InstanceCompute,
+    InstanceComputePlugin, InstanceSlice, InstanceSliceBundle, MeshInstance,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Deliberately not a multiple of the compute workgroup size of 64
+const INSTANCE_COUNT: u32 = 200;
+
+/// Cells per row and column of the grid
+const GRID_COLUMNS: u32 = 16;
+
+/// Width and height of each cell in pixels
+const CELL_SIZE: u32 = TARGET_SIZE / GRID_COLUMNS;
+
+/// Color output by the default instanced fragment shader
+const EXPECTED_PIXEL: [u8; 4] = [255, 0, 255, 255];
+
+/// Lays the instances of its slice out in a grid of unit cells
+#[derive(Debug, Default, Copy, Clone, Component, AsBindGroup)]
+struct GridInstances {
+    #[uniform(0)]
+    columns: u32,
+}
+
+impl From<&GridInstances> for () {
+    fn from(_: &GridInstances) -> Self {}
+}
+
+impl ExtractComponent for GridInstances {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+impl InstanceCompute for GridInstances {
+    type Instance = MeshInstance;
+
+    fn shader() -> ShaderRef {
+        "shader/compute_grid.wgsl".into()
+    }
+}
+
+// Test that every instance of a slice is computed, including those past the last full workgroup
+#[test]
+fn compute_coverage() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(BasicMaterialPlugin)
+        .add_plugin(InstanceComputePlugin::<GridInstances>::default())
+        .add_startup_system(setup_instancing)
+        .add_system(check_cells);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    commands.spawn((
+        InstanceSliceBundle::<BasicMaterial> {
+            mesh: meshes.add(Quad::default().into()),
+            mesh_instance_slice: InstanceSlice {
+                instance_count: INSTANCE_COUNT as usize,
+            },
+            ..default()
+        },
+        GridInstances {
+            columns: GRID_COLUMNS,
+        },
+    ));
+
+    // One world unit per cell
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(GRID_COLUMNS as f32),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Pixel at the center of the cell of instance `index`
+fn cell_pixel(readback: &ImageReadback, index: u32) -> Option<[u8; 4]> {
+    readback.pixel_at(
+        (index % GRID_COLUMNS) * CELL_SIZE + CELL_SIZE / 2,
+        (index / GRID_COLUMNS) * CELL_SIZE + CELL_SIZE / 2,
+    )
+}
+
+fn check_cells(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let missing = (0..INSTANCE_COUNT)
+        .filter(|index| cell_pixel(&readback, *index) != Some(EXPECTED_PIXEL))
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() {
+        // The cell after the slice's range must stay empty
+        assert_ne!(
+            cell_pixel(&readback, INSTANCE_COUNT),
+            Some(EXPECTED_PIXEL),
+            "Drew past the end of the slice"
+        );
+
+        info!(
+            "All {INSTANCE_COUNT} instances were computed after {} frames",
+            *frame
+        );
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!(
+            "Expected every instance of the slice to be computed and drawn, {} weren't: {missing:?}",
+            missing.len()
+        );
+    }
+}
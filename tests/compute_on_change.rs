@@ -0,0 +1,219 @@
+//! Headless test of computing instance slices only when their uniform changes
+//!
+//! Computes a slice once, then leaves its uniform untouched for a number of frames,
+//! exiting with a panic if the slice is dispatched again or its instances stop being drawn.
+//! Finally marks the uniform as changed, and expects the slice to be dispatched once more.
+//!
+
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    ecs::{change_detection::DetectChanges, system::lifetimeless::Read},
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Commands, Component,
+        EventWriter, Image, Local, Mesh, Query, Res, ResMut, Resource, Transform, Vec3,
+    },
+    render::{
+        camera::RenderTarget,
+        extract_component::ExtractComponent,
+        render_resource::{AsBindGroup, ShaderRef},
+        RenderApp, RenderStage,
+    },
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, InstanceCompute,
+    InstanceComputePlugin, InstanceComputeQueue, InstanceSlice, InstanceSliceBundle, MeshInstance,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES};
+
+const INSTANCE_COUNT: usize = 3;
+
+/// Color output by the default instanced fragment shader
+const EXPECTED_PIXEL: [u8; 4] = [255, 0, 255, 255];
+
+/// Frames to wait after the slice is first drawn, for its output to be cached
+const SETTLE_FRAMES: u32 = 10;
+
+/// Frames during which the uniform is left unchanged, and no dispatches are expected
+const PAUSED_FRAMES: u32 = 60;
+
+/// Frames to wait for a dispatch after the uniform is changed
+const RESUME_FRAMES: u32 = 10;
+
+/// Moves all but the last instance of its slice out of view
+#[derive(Debug, Default, Copy, Clone, Component, AsBindGroup)]
+struct PausedInstances {
+    #[uniform(0)]
+    last_instance: u32,
+}
+
+impl From<&PausedInstances> for () {
+    fn from(_: &PausedInstances) -> Self {}
+}
+
+impl ExtractComponent for PausedInstances {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+impl InstanceCompute for PausedInstances {
+    type Instance = MeshInstance;
+
+    fn shader() -> ShaderRef {
+        "shader/compute_coverage.wgsl".into()
+    }
+}
+
+/// Total compute dispatches queued by the render world
+#[derive(Debug, Default, Clone, Resource)]
+struct DispatchCount(Arc<AtomicUsize>);
+
+// Test that slices with an unchanged uniform keep their output without being dispatched
+#[test]
+fn compute_on_change() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(BasicMaterialPlugin)
+        .add_plugin(InstanceComputePlugin::<PausedInstances>::default().compute_on_change());
+
+    let dispatch_count = DispatchCount::default();
+    app.insert_resource(dispatch_count.clone());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_dispatches);
+
+    app.sub_app_mut(RenderApp)
+        .insert_resource(dispatch_count)
+        .add_system_to_stage(RenderStage::PhaseSort, count_dispatches);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    commands.spawn((
+        InstanceSliceBundle::<BasicMaterial> {
+            mesh: meshes.add(Quad::default().into()),
+            mesh_instance_slice: InstanceSlice {
+                instance_count: INSTANCE_COUNT,
+            },
+            ..default()
+        },
+        PausedInstances {
+            last_instance: INSTANCE_COUNT as u32 - 1,
+        },
+    ));
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn count_dispatches(
+    compute_queue: Option<Res<InstanceComputeQueue<PausedInstances>>>,
+    dispatch_count: Res<DispatchCount>,
+) {
+    if let Some(compute_queue) = compute_queue {
+        dispatch_count
+            .0
+            .fetch_add(compute_queue.dispatch_count(), Ordering::Relaxed);
+    }
+}
+
+fn check_dispatches(
+    readback: Res<ImageReadback>,
+    dispatch_count: Res<DispatchCount>,
+    mut query_instances: Query<&mut PausedInstances>,
+    mut frame: Local<u32>,
+    mut drawn_frame: Local<Option<u32>>,
+    mut paused_dispatches: Local<usize>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let pixel = readback.center();
+    let dispatches = dispatch_count.0.load(Ordering::Relaxed);
+
+    let drawn_frame = match *drawn_frame {
+        Some(drawn_frame) => drawn_frame,
+        None if pixel == Some(EXPECTED_PIXEL) => {
+            info!("Slice was computed after {} frames", *frame);
+            *drawn_frame = Some(*frame);
+            return;
+        }
+        None if *frame > MAX_FRAMES => {
+            panic!("Expected the slice to be computed and drawn at the center pixel {EXPECTED_PIXEL:?}, got {pixel:?}");
+        }
+        None => return,
+    };
+
+    let pause_start = drawn_frame + SETTLE_FRAMES;
+    let pause_end = pause_start + PAUSED_FRAMES;
+
+    if *frame < pause_start {
+        return;
+    }
+
+    if *frame == pause_start {
+        *paused_dispatches = dispatches;
+    }
+
+    if pixel != Some(EXPECTED_PIXEL) {
+        panic!("Expected the slice to stay drawn at the center pixel {EXPECTED_PIXEL:?} on frame {}, got {pixel:?}", *frame);
+    }
+
+    if *frame <= pause_end {
+        if dispatches != *paused_dispatches {
+            panic!(
+                "Expected no dispatches while the uniform is unchanged, got {} by frame {}",
+                dispatches - *paused_dispatches,
+                *frame
+            );
+        }
+
+        if *frame == pause_end {
+            info!("No dispatches during {PAUSED_FRAMES} paused frames, changing uniform");
+            for mut instances in query_instances.iter_mut() {
+                instances.set_changed();
+            }
+        }
+    } else if dispatches > *paused_dispatches {
+        info!(
+            "Slice was dispatched {} frames after its uniform changed",
+            *frame - pause_end
+        );
+        exit.send(AppExit);
+    } else if *frame > pause_end + RESUME_FRAMES {
+        panic!("Expected the slice to be dispatched after its uniform changed");
+    }
+}
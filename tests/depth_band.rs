@@ -0,0 +1,182 @@
+mod common;
+
+use std::ops::Range;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    math::{Vec2, Vec3},
+    prelude::{
+        default, info, shape::Quad, AssetServer, Assets, Camera, Camera3dBundle, Color, Commands,
+        EventWriter, Handle, Image, Local, Mesh, OrthographicProjection, Res, ResMut,
+        SpatialBundle, Transform,
+    },
+    reflect::TypeUuid,
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        render_resource::{AsBindGroup, ShaderRef},
+    },
+};
+
+use bevy_instancing::prelude::{
+    AsBatch, ColorInstanceBundle, ColorInstancePlugin, ColorMeshInstance, IndirectRenderingPlugin,
+    InstanceColor, InstancedMaterialPlugin, MaterialInstanced, MeshInstanceBundle,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+/// Unlit material drawing instance colors into a configurable depth band
+#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "2d7a9c41-8e35-4b6f-a0d2-6c1e5f3b9a87"]
+struct DepthBandMaterial {
+    depth_range: Range<f32>,
+}
+
+impl From<&DepthBandMaterial> for () {
+    fn from(_: &DepthBandMaterial) -> Self {}
+}
+
+impl AsBatch for DepthBandMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for DepthBandMaterial {
+    type Instance = ColorMeshInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        "shader/flat_color.wgsl".into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        "shader/flat_color.wgsl".into()
+    }
+
+    fn depth_range(&self) -> Range<f32> {
+        self.depth_range.clone()
+    }
+}
+
+// Test that instances are drawn within their material's depth band,
+// by compressing a near red quad into the far band, behind a farther blue quad
+// drawn with the full depth range
+#[test]
+fn depth_band() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(ColorInstancePlugin)
+        .add_plugin(InstancedMaterialPlugin::<DepthBandMaterial>::default());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_depth_band);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<DepthBandMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    let full_width = meshes.add(Quad::new(Vec2::splat(VIEW_WIDTH)).into());
+    let half_width = meshes.add(Quad::new(Vec2::new(VIEW_WIDTH * 0.5, VIEW_WIDTH)).into());
+
+    // Reversed Z puts the far plane at 0, so this band lies behind the rest of the scene
+    let background = materials.add(DepthBandMaterial {
+        depth_range: 0.0..0.1,
+    });
+    let foreground = materials.add(DepthBandMaterial {
+        depth_range: 0.0..1.0,
+    });
+
+    let mut spawn_quad = |mesh: Handle<Mesh>,
+                          material: Handle<DepthBandMaterial>,
+                          transform: Transform,
+                          color: Color| {
+        commands.spawn(ColorInstanceBundle::<DepthBandMaterial> {
+            instance_bundle: MeshInstanceBundle {
+                mesh,
+                material,
+                spatial_bundle: SpatialBundle {
+                    transform,
+                    ..default()
+                },
+            },
+            mesh_instance_color: InstanceColor(color),
+        });
+    };
+
+    // Nearest to the camera, but banded behind everything else
+    spawn_quad(
+        full_width,
+        background,
+        Transform::from_xyz(0.0, 0.0, 1.0),
+        Color::RED,
+    );
+
+    // Covers the right half of the view, further away
+    spawn_quad(
+        half_width,
+        foreground,
+        Transform::from_xyz(VIEW_WIDTH * 0.25, 0.0, 0.0),
+        Color::BLUE,
+    );
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn check_depth_band(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let row = readback.middle_row();
+    let pixels = row.as_ref().map(|row| {
+        let pixel = |column: u32| row[column as usize * 4..column as usize * 4 + 3].to_vec();
+        (pixel(TARGET_SIZE / 4), pixel(TARGET_SIZE * 3 / 4))
+    });
+
+    let red = [255, 0, 0].to_vec();
+    let blue = [0, 0, 255].to_vec();
+
+    match &pixels {
+        Some((left, right)) if *left == red && *right == blue => {
+            info!(
+                "Banded instance drawn behind nearer geometry after {} frames: {pixels:?}",
+                *frame
+            );
+            exit.send(AppExit);
+        }
+        Some((left, right)) if *left == red && *right == red => {
+            panic!("Banded instance was drawn at its unbanded depth, in front of the blue quad");
+        }
+        _ if *frame > MAX_FRAMES => {
+            panic!("Expected red on the left and blue on the right, got {pixels:?}");
+        }
+        _ => {}
+    }
+}
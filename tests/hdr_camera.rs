@@ -0,0 +1,84 @@
+//! Headless test of instances drawn by an HDR camera
+//!
+//! HDR views render into a floating-point main texture,
+//! so instanced pipelines must be specialized for its format to be drawn at all.
+
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Commands, EventWriter, Image,
+        Local, Mesh, Res, ResMut, Transform, Vec3,
+    },
+    render::camera::RenderTarget,
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, MeshInstanceBundle,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES};
+
+/// Color output by the default instanced fragment shader
+const EXPECTED_PIXEL: [u8; 4] = [255, 0, 255, 255];
+
+// Test that an HDR camera draws instances through its floating-point main texture
+#[test]
+fn hdr_camera() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(BasicMaterialPlugin)
+        .add_startup_system(setup_instancing)
+        .add_system(check_pixel);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    commands.spawn(MeshInstanceBundle::<BasicMaterial> {
+        mesh: meshes.add(Quad::default().into()),
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            hdr: true,
+            ..default()
+        },
+        // Keeps the shader's output exact through the upscaling pass
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn check_pixel(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let pixel = readback.center();
+
+    if pixel == Some(EXPECTED_PIXEL) {
+        info!("HDR camera drew the instance after {} frames", *frame);
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected center pixel {EXPECTED_PIXEL:?} from an HDR camera, got {pixel:?}");
+    }
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    math::{Vec2, Vec3},
+    prelude::{
+        default, info, shape::Quad, AssetServer, Assets, Camera, Camera3dBundle, Color, Commands,
+        Component, Entity, EventWriter, Image, Local, Mesh, OrthographicProjection, Query, Res,
+        ResMut, SpatialBundle, Transform, With,
+    },
+    reflect::TypeUuid,
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        render_resource::{AsBindGroup, ShaderRef},
+    },
+};
+
+use bevy_instancing::prelude::{
+    AsBatch, IndirectRenderingPlugin, InstanceIndexSettings, InstanceIndices,
+    InstancedMaterialPlugin, MaterialInstanced, MeshInstance, MeshInstanceBundle,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+const INSTANCE_COUNT: usize = 4;
+
+/// Material encoding `instance_index` in the red channel of its output
+#[derive(Debug, Default, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "8f4c2b7e-5a61-4d0e-b3a9-2e7d6c1f9a54"]
+struct InstanceIndexMaterial {}
+
+impl From<&InstanceIndexMaterial> for () {
+    fn from(_: &InstanceIndexMaterial) -> Self {}
+}
+
+impl AsBatch for InstanceIndexMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for InstanceIndexMaterial {
+    type Instance = MeshInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        "shader/instance_index.wgsl".into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        "shader/instance_index.wgsl".into()
+    }
+}
+
+/// Column of the render target an instance is centered on
+#[derive(Debug, Copy, Clone, Component)]
+struct TargetColumn(u32);
+
+// Test that the instance_index each instance is drawn with matches the one published
+// to InstanceIndices, by rendering a row of quads that output their instance_index as color
+#[test]
+fn instance_index() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(InstancedMaterialPlugin::<InstanceIndexMaterial>::default());
+
+    app.insert_resource(InstanceIndexSettings { enabled: true });
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_instance_indices);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<InstanceIndexMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    let mesh = meshes.add(Quad::new(Vec2::splat(0.5)).into());
+    let material = materials.add(InstanceIndexMaterial {});
+
+    // A row of quads, each nearer the camera than the last,
+    // so front-to-back sorting draws them in the reverse of spawn order
+    let spacing = VIEW_WIDTH / INSTANCE_COUNT as f32;
+    for i in 0..INSTANCE_COUNT {
+        let x = (i as f32 + 0.5) * spacing - VIEW_WIDTH * 0.5;
+
+        commands.spawn((
+            MeshInstanceBundle::<InstanceIndexMaterial> {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_xyz(x, 0.0, i as f32 * 0.1),
+                    ..default()
+                },
+            },
+            TargetColumn(((i as f32 + 0.5) / INSTANCE_COUNT as f32 * TARGET_SIZE as f32) as u32),
+        ));
+    }
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Decodes the instance_index output by the material from a pixel's red channel
+fn decode_instance_index(red: u8) -> Option<u32> {
+    (0..INSTANCE_COUNT as u32).min_by_key(|index| {
+        let expected = Color::rgb_linear((index + 1) as f32 / 8.0, 0.0, 0.0).as_rgba_f32()[0];
+        ((expected * 255.0).round() as u8).abs_diff(red)
+    })
+}
+
+fn check_instance_indices(
+    readback: Res<ImageReadback>,
+    instance_indices: Res<InstanceIndices>,
+    query_camera: Query<Entity, With<Camera>>,
+    query_instance: Query<(Entity, &TargetColumn)>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let row = readback.middle_row();
+    let view = query_camera.single();
+
+    let results = query_instance
+        .iter()
+        .map(|(entity, column)| {
+            let drawn = row.as_ref().and_then(|row| {
+                let red = row[column.0 as usize * 4];
+                if red > 0 {
+                    decode_instance_index(red)
+                } else {
+                    None
+                }
+            });
+
+            (entity, drawn, instance_indices.get(view, entity))
+        })
+        .collect::<Vec<_>>();
+
+    if results
+        .iter()
+        .all(|(_, drawn, published)| drawn.is_some() && drawn == published)
+    {
+        info!(
+            "Shader instance_index matches published indices after {} frames: {results:?}",
+            *frame
+        );
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected drawn instance indices to match published ones, got {results:?}");
+    }
+}
@@ -0,0 +1,190 @@
+//! Headless test of mesh batches being rebuilt when an instanced mesh changes
+//!
+//! Draws one instance of a quad on the left of the render target, then moves the quad's vertices
+//! to the right. Instances must follow the new geometry, and the batch's previous vertex buffer
+//! must no longer be held once it's rebuilt.
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    math::Vec2,
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Commands, EventWriter, Handle,
+        Image, Local, Mesh, OrthographicProjection, Res, ResMut, Resource, Transform, Vec3,
+    },
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        mesh::VertexAttributeValues,
+        render_resource::BufferId,
+        RenderApp, RenderStage,
+    },
+};
+
+use bevy_instancing::{
+    instancing::material::systems::prepare_mesh_batches::MeshBatches,
+    prelude::{BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, MeshInstanceBundle},
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+/// Column the quad is first drawn at
+const BEFORE_COLUMN: u32 = 16;
+
+/// Column the quad's vertices are moved to
+const AFTER_COLUMN: u32 = 48;
+
+/// Color output by the default instanced fragment shader
+const EXPECTED_PIXEL: [u8; 4] = [255, 0, 255, 255];
+
+/// Vertex buffers of the render world's mesh batches, as of the last frame
+#[derive(Debug, Default, Clone, Resource)]
+struct BatchVertexBuffers(Arc<Mutex<Vec<BufferId>>>);
+
+/// Handle of the quad drawn by the instance
+#[derive(Debug, Clone, Resource)]
+struct QuadMesh(Handle<Mesh>);
+
+// Test that instances draw a mesh's new geometry once it changes, and the old vertex buffer is released
+#[test]
+fn mesh_rebuild() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(BasicMaterialPlugin)
+        .add_startup_system(setup_instancing)
+        .add_system(check_pixels);
+
+    let batch_vertex_buffers = BatchVertexBuffers::default();
+    app.insert_resource(batch_vertex_buffers.clone());
+    app.sub_app_mut(RenderApp)
+        .insert_resource(batch_vertex_buffers)
+        .add_system_to_stage(RenderStage::Cleanup, read_batch_vertex_buffers);
+
+    app.run()
+}
+
+fn column_x(column: u32) -> f32 {
+    (column as f32 + 0.5) / TARGET_SIZE as f32 * VIEW_WIDTH - VIEW_WIDTH * 0.5
+}
+
+/// Moves the vertices of `mesh` by `x` along the X axis
+fn offset_mesh(mesh: &mut Mesh, x: f32) {
+    if let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    {
+        for position in positions {
+            position[0] += x;
+        }
+    }
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    let mut quad = Mesh::from(Quad::new(Vec2::ONE));
+    offset_mesh(&mut quad, column_x(BEFORE_COLUMN));
+    let quad = meshes.add(quad);
+
+    commands.spawn(MeshInstanceBundle::<BasicMaterial> {
+        mesh: quad.clone(),
+        ..default()
+    });
+    commands.insert_resource(QuadMesh(quad));
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn read_batch_vertex_buffers(
+    mesh_batches: Res<MeshBatches>,
+    batch_vertex_buffers: Res<BatchVertexBuffers>,
+) {
+    *batch_vertex_buffers.0.lock().unwrap() = mesh_batches
+        .values()
+        .flat_map(|batch| batch.vertex_data.buffer())
+        .map(|buffer| buffer.id())
+        .collect();
+}
+
+/// Moves the quad once it's drawn at its first column,
+/// then waits for it to be drawn at the second from a new vertex buffer
+fn check_pixels(
+    readback: Res<ImageReadback>,
+    batch_vertex_buffers: Res<BatchVertexBuffers>,
+    quad: Res<QuadMesh>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut frame: Local<u32>,
+    mut old_vertex_buffers: Local<Option<Vec<BufferId>>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let before = readback.pixel(BEFORE_COLUMN);
+    let after = readback.pixel(AFTER_COLUMN);
+    let vertex_buffers = batch_vertex_buffers.0.lock().unwrap().clone();
+
+    match &*old_vertex_buffers {
+        None => {
+            if before == Some(EXPECTED_PIXEL) && after != Some(EXPECTED_PIXEL) {
+                assert_eq!(vertex_buffers.len(), 1, "Expected a single mesh batch");
+
+                info!(
+                    "Drew the quad at column {BEFORE_COLUMN} after {} frames, moving it",
+                    *frame
+                );
+                offset_mesh(
+                    meshes.get_mut(&quad.0).unwrap(),
+                    column_x(AFTER_COLUMN) - column_x(BEFORE_COLUMN),
+                );
+                *old_vertex_buffers = Some(vertex_buffers);
+            } else if *frame > MAX_FRAMES {
+                panic!("Expected the quad drawn at column {BEFORE_COLUMN}, got {before:?}");
+            }
+        }
+        Some(old) => {
+            if after == Some(EXPECTED_PIXEL) && before != Some(EXPECTED_PIXEL) {
+                assert_eq!(vertex_buffers.len(), 1, "Expected a single mesh batch");
+                assert!(
+                    old.iter().all(|id| !vertex_buffers.contains(id)),
+                    "Mesh batches kept their previous vertex buffer"
+                );
+
+                info!(
+                    "Drew the moved quad at column {AFTER_COLUMN} after {} frames",
+                    *frame
+                );
+                exit.send(AppExit);
+            } else if *frame > MAX_FRAMES * 2 {
+                panic!(
+                    "Expected the moved quad drawn at column {AFTER_COLUMN} only, got {before:?} and {after:?}"
+                );
+            }
+        }
+    }
+}
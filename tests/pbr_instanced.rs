@@ -0,0 +1,135 @@
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    math::{Vec2, Vec3},
+    pbr::AmbientLight,
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Color, Commands, EventWriter,
+        Image, Local, Mesh, OrthographicProjection, Res, ResMut, SpatialBundle, Transform,
+    },
+    render::camera::{Projection, RenderTarget, ScalingMode},
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, IndirectRenderingPlugin, InstanceColor, MeshInstanceBundle,
+    PbrInstancedMaterial, PbrInstancedMaterialPlugin,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+// Test that PbrInstancedMaterial renders through bevy's PBR lighting,
+// with each instance's color multiplying the base color of a shared white material
+#[test]
+fn pbr_instanced() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(PbrInstancedMaterialPlugin);
+
+    // Lit by ambient light alone, so color only comes from the tinted base color
+    app.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 1.0,
+    });
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_tints);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PbrInstancedMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    let mesh = meshes.add(Quad::new(Vec2::splat(VIEW_WIDTH * 0.5)).into());
+
+    // Dielectric with no specular reflectance, so lighting doesn't desaturate the tint
+    let material = materials.add(PbrInstancedMaterial {
+        base_color: Color::WHITE,
+        metallic: 0.0,
+        reflectance: 0.0,
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+
+    for (x, color) in [(-1.0, Color::RED), (1.0, Color::BLUE)] {
+        commands.spawn(ColorInstanceBundle::<PbrInstancedMaterial> {
+            instance_bundle: MeshInstanceBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_xyz(x, 0.0, 0.0),
+                    ..default()
+                },
+            },
+            mesh_instance_color: InstanceColor(color),
+        });
+    }
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Whether `pixel` is lit, and only in the `channel` its instance is tinted with
+fn is_tinted(pixel: &[u8], channel: usize) -> bool {
+    pixel[..3].iter().enumerate().all(|(i, value)| {
+        if i == channel {
+            *value > 64
+        } else {
+            *value < 16
+        }
+    })
+}
+
+fn check_tints(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let row = readback.middle_row();
+    let pixels = row.as_ref().map(|row| {
+        let pixel = |column: u32| row[column as usize * 4..column as usize * 4 + 4].to_vec();
+        (pixel(TARGET_SIZE / 4), pixel(TARGET_SIZE * 3 / 4))
+    });
+
+    match &pixels {
+        Some((red, blue)) if is_tinted(red, 0) && is_tinted(blue, 2) => {
+            info!(
+                "Instance tints multiply the lit base color after {} frames: {pixels:?}",
+                *frame
+            );
+            exit.send(AppExit);
+        }
+        _ if *frame > MAX_FRAMES => {
+            panic!("Expected a red-tinted and a blue-tinted instance, got {pixels:?}");
+        }
+        _ => {}
+    }
+}
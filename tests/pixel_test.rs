@@ -0,0 +1,87 @@
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Color, Commands, EventWriter,
+        Image, Local, Mesh, Res, ResMut, SpatialBundle, Transform, Vec3,
+    },
+    render::camera::RenderTarget,
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, ColorInstancePlugin, IndirectRenderingPlugin, InstanceColor,
+    InstancedMaterialPlugin, MeshInstanceBundle,
+};
+
+use common::{
+    flat_color::FlatColorMaterial,
+    readback::{add_target, readback_app, ImageReadback, MAX_FRAMES},
+};
+
+const EXPECTED_PIXEL: [u8; 4] = [255, 0, 0, 255];
+
+// Test the full instancing pipeline headlessly by rendering a single red quad
+// into an offscreen image and verifying its center pixel
+#[test]
+fn pixel_test() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(ColorInstancePlugin)
+        .add_plugin(InstancedMaterialPlugin::<FlatColorMaterial>::default())
+        .add_startup_system(setup_instancing)
+        .add_system(check_pixel);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<FlatColorMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    commands.spawn(ColorInstanceBundle::<FlatColorMaterial> {
+        instance_bundle: MeshInstanceBundle {
+            mesh: meshes.add(Quad::default().into()),
+            material: materials.add(default()),
+            spatial_bundle: SpatialBundle::default(),
+        },
+        mesh_instance_color: InstanceColor(Color::RED),
+    });
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn check_pixel(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let pixel = readback.center();
+
+    if pixel == Some(EXPECTED_PIXEL) {
+        info!("Center pixel matches after {} frames", *frame);
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected center pixel {EXPECTED_PIXEL:?}, got {pixel:?}");
+    }
+}
@@ -0,0 +1,195 @@
+//! Headless test of a single computed instance slice drawn by two materials
+//!
+//! Computes one instance into a slice, and shares it with a slice of a second material.
+//! Each slice's mesh is offset to its own side of the render target,
+//! so the shared side is only drawn once the source slice's instances are copied into it.
+
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    ecs::system::lifetimeless::Read,
+    math::Vec2,
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Commands, Component,
+        EventWriter, Image, Local, Mesh, OrthographicProjection, Res, ResMut, Transform, Vec3,
+    },
+    reflect::TypeUuid,
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        extract_component::ExtractComponent,
+        mesh::VertexAttributeValues,
+        render_resource::{AsBindGroup, ShaderRef},
+    },
+};
+
+use bevy_instancing::prelude::{
+    AsBatch, BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, InstanceCompute,
+    InstanceComputePlugin, InstanceSlice, InstanceSliceBundle, InstancedMaterialPlugin,
+    MaterialInstanced, MeshInstance, SharedInstanceSlice,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+/// Column of the source slice's quad
+const SOURCE_COLUMN: u32 = 16;
+
+/// Column of the shared slice's quad
+const SHARED_COLUMN: u32 = 48;
+
+/// Color output by the default instanced fragment shader
+const EXPECTED_PIXEL: [u8; 4] = [255, 0, 255, 255];
+
+/// Second material drawing [`MeshInstance`]s with the default shaders,
+/// batched apart from [`BasicMaterial`] since it's a different type
+#[derive(Debug, Default, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "3b8e6d14-7a2c-4f59-9e01-c4d7a5b2f863"]
+struct SharedMaterial {}
+
+impl From<&SharedMaterial> for () {
+    fn from(_: &SharedMaterial) -> Self {}
+}
+
+impl AsBatch for SharedMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for SharedMaterial {
+    type Instance = MeshInstance;
+}
+
+/// Lays the instances of its slice out in a grid of unit cells
+#[derive(Debug, Default, Copy, Clone, Component, AsBindGroup)]
+struct GridInstances {
+    #[uniform(0)]
+    columns: u32,
+}
+
+impl From<&GridInstances> for () {
+    fn from(_: &GridInstances) -> Self {}
+}
+
+impl ExtractComponent for GridInstances {
+    type Query = Read<Self>;
+
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+impl InstanceCompute for GridInstances {
+    type Instance = MeshInstance;
+
+    fn shader() -> ShaderRef {
+        "shader/compute_grid.wgsl".into()
+    }
+}
+
+// Test that a computed slice is drawn by a second material through a SharedInstanceSlice
+#[test]
+fn shared_slice() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(BasicMaterialPlugin)
+        .add_plugin(InstancedMaterialPlugin::<SharedMaterial>::default())
+        .add_plugin(InstanceComputePlugin::<GridInstances>::default())
+        .add_startup_system(setup_instancing)
+        .add_system(check_pixels);
+
+    app.run()
+}
+
+/// Unit quad centered on world-space `x`, so a single computed instance at the origin
+/// draws it there
+fn offset_quad(x: f32) -> Mesh {
+    let mut mesh = Mesh::from(Quad::new(Vec2::ONE));
+    if let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    {
+        for position in positions {
+            position[0] += x;
+        }
+    }
+    mesh
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut shared_materials: ResMut<Assets<SharedMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    let column_x =
+        |column: u32| (column as f32 + 0.5) / TARGET_SIZE as f32 * VIEW_WIDTH - VIEW_WIDTH * 0.5;
+
+    let source = commands
+        .spawn((
+            InstanceSliceBundle::<BasicMaterial> {
+                mesh: meshes.add(offset_quad(column_x(SOURCE_COLUMN))),
+                mesh_instance_slice: InstanceSlice { instance_count: 1 },
+                ..default()
+            },
+            GridInstances { columns: 1 },
+        ))
+        .id();
+
+    commands.spawn((
+        InstanceSliceBundle::<SharedMaterial> {
+            material: shared_materials.add(default()),
+            mesh: meshes.add(offset_quad(column_x(SHARED_COLUMN))),
+            mesh_instance_slice: InstanceSlice { instance_count: 1 },
+            ..default()
+        },
+        SharedInstanceSlice(source),
+    ));
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn check_pixels(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let source = readback.pixel(SOURCE_COLUMN);
+    let shared = readback.pixel(SHARED_COLUMN);
+
+    if source == Some(EXPECTED_PIXEL) && shared == Some(EXPECTED_PIXEL) {
+        info!(
+            "Both materials drew the computed instance after {} frames",
+            *frame
+        );
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!(
+            "Expected the source and shared slices to draw {EXPECTED_PIXEL:?}, got {source:?} and {shared:?}"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Headless test of the texture material's vertex color, instance color and texture product
+//!
+//! Each source halves a different channel of an otherwise white instance,
+//! so the drawn color is only an even gray if all three are multiplied together.
+
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    pbr::{DirectionalLight, DirectionalLightBundle},
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Color, Commands, EventWriter,
+        Image, Local, Mesh, Res, ResMut, SpatialBundle, Transform, Vec3,
+    },
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use bevy_instancing::prelude::{
+    ColorInstanceBundle, IndirectRenderingPlugin, InstanceColor, MeshInstanceBundle,
+    TextureMaterial, TextureMaterialPlugin,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES};
+
+/// Halves the texture's green channel, in linear space
+const TEXTURE_COLOR: [u8; 4] = [255, 128, 255, 255];
+
+/// Halves the vertex color's red channel
+const VERTEX_COLOR: [f32; 4] = [0.5, 1.0, 1.0, 1.0];
+
+/// Halves the instance color's blue channel
+const INSTANCE_COLOR: Color = Color::rgb_linear(1.0, 1.0, 0.5);
+
+/// Directional light illuminance giving a light color of 1.0 after bevy's fixed exposure
+const ILLUMINANCE: f32 = 4800.0;
+
+/// Linear 0.5 scaled by the shader's 0.6 maximum light tint, as sRGB
+const EXPECTED_PIXEL: [u8; 4] = [149, 149, 149, 255];
+
+/// Largest per-channel difference allowed from [`EXPECTED_PIXEL`], for rounding
+const TOLERANCE: u8 = 2;
+
+// Test that the texture material multiplies vertex colors, instance colors and the texture together
+#[test]
+fn texture_tint() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(TextureMaterialPlugin)
+        .add_startup_system(setup_instancing)
+        .add_system(check_pixel);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<TextureMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    let texture = images.add(Image::new_fill(
+        Extent3d::default(),
+        TextureDimension::D2,
+        &TEXTURE_COLOR,
+        TextureFormat::Rgba8Unorm,
+    ));
+
+    let mut quad = Mesh::from(Quad::default());
+    quad.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![VERTEX_COLOR; 4]);
+
+    commands.spawn(ColorInstanceBundle::<TextureMaterial> {
+        instance_bundle: MeshInstanceBundle {
+            mesh: meshes.add(quad),
+            material: materials.add(TextureMaterial {
+                texture,
+                ..default()
+            }),
+            spatial_bundle: SpatialBundle::default(),
+        },
+        mesh_instance_color: InstanceColor(INSTANCE_COLOR),
+    });
+
+    // Faces the quad head on, so it's lit at full strength
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: ILLUMINANCE,
+            shadows_enabled: false,
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 1.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn check_pixel(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let pixel = readback.center();
+
+    let matches = pixel.map_or(false, |pixel| {
+        pixel
+            .iter()
+            .zip(EXPECTED_PIXEL.iter())
+            .all(|(lhs, rhs)| lhs.abs_diff(*rhs) <= TOLERANCE)
+    });
+
+    if matches {
+        info!(
+            "Vertex, instance and texture colors multiplied into {pixel:?} after {} frames",
+            *frame
+        );
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected center pixel {EXPECTED_PIXEL:?}, got {pixel:?}");
+    }
+}
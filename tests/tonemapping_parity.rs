@@ -0,0 +1,149 @@
+//! Headless test of instances matching bevy's own meshes under tonemapping
+//!
+//! Draws an unlit magenta bevy mesh next to an instance of the default instanced shader,
+//! which outputs the same magenta, through a camera tonemapping in the fragment shader.
+//! Both must come out tonemapped, and to the same color.
+
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    math::Vec2,
+    pbr::{PbrBundle, StandardMaterial},
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Color, Commands, EventWriter,
+        Image, Local, Mesh, OrthographicProjection, Res, ResMut, SpatialBundle, Transform, Vec3,
+    },
+    render::camera::{Projection, RenderTarget, ScalingMode},
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, MeshInstanceBundle,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+/// Column of the regular mesh
+const MESH_COLUMN: u32 = 16;
+
+/// Column of the instance
+const INSTANCE_COLUMN: u32 = 48;
+
+/// Color output by the default instanced fragment shader before tonemapping
+const UNTONEMAPPED_PIXEL: [u8; 4] = [255, 0, 255, 255];
+
+/// Largest per-channel difference allowed between the two, for rounding
+const TOLERANCE: u8 = 1;
+
+// Test that instances are tonemapped the same way as bevy's own meshes
+#[test]
+fn tonemapping_parity() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(BasicMaterialPlugin)
+        .add_startup_system(setup_instancing)
+        .add_system(check_pixels);
+
+    app.run()
+}
+
+fn column_x(column: u32) -> f32 {
+    (column as f32 + 0.5) / TARGET_SIZE as f32 * VIEW_WIDTH - VIEW_WIDTH * 0.5
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    let quad = meshes.add(Quad::new(Vec2::ONE).into());
+
+    commands.spawn(PbrBundle {
+        mesh: quad.clone(),
+        material: standard_materials.add(StandardMaterial {
+            base_color: Color::rgb_linear(1.0, 0.0, 1.0),
+            unlit: true,
+            ..default()
+        }),
+        transform: Transform::from_xyz(column_x(MESH_COLUMN), 0.0, 0.0),
+        ..default()
+    });
+
+    commands.spawn(MeshInstanceBundle::<BasicMaterial> {
+        mesh: quad,
+        spatial_bundle: SpatialBundle::from_transform(Transform::from_xyz(
+            column_x(INSTANCE_COLUMN),
+            0.0,
+            0.0,
+        )),
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        // Dithering would add per-pixel noise to both
+        tonemapping: Tonemapping::Enabled {
+            deband_dither: false,
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn check_pixels(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let mesh = readback.pixel(MESH_COLUMN);
+    let instance = readback.pixel(INSTANCE_COLUMN);
+
+    // Magenta has no green, unlike the clear color
+    let drawn = |pixel: &[u8; 4]| pixel[1] == 0;
+
+    match (mesh, instance) {
+        (Some(mesh), Some(instance)) if drawn(&mesh) && drawn(&instance) => {
+            assert_ne!(
+                mesh, UNTONEMAPPED_PIXEL,
+                "Expected the regular mesh to be tonemapped"
+            );
+            assert!(
+                mesh.iter()
+                    .zip(instance.iter())
+                    .all(|(lhs, rhs)| lhs.abs_diff(*rhs) <= TOLERANCE),
+                "Expected the instance to match the regular mesh {mesh:?} under tonemapping, got {instance:?}"
+            );
+
+            info!(
+                "Instance matched the regular mesh as {instance:?} after {} frames",
+                *frame
+            );
+            exit.send(AppExit);
+        }
+        _ if *frame > MAX_FRAMES => {
+            panic!("Expected both the regular mesh and the instance drawn, got {mesh:?} and {instance:?}");
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::{clear_color::ClearColorConfig, core_3d::Camera3d, tonemapping::Tonemapping},
+    math::{Vec2, Vec3},
+    pbr::{AlphaMode, PbrBundle, StandardMaterial},
+    prelude::{
+        default, info, shape::Quad, AssetServer, Assets, Camera, Camera3dBundle, Color, Commands,
+        EventWriter, Image, Local, Mesh, OrthographicProjection, Res, ResMut, SpatialBundle,
+        Transform,
+    },
+    reflect::TypeUuid,
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        render_resource::{AsBindGroup, ShaderRef},
+    },
+};
+
+use bevy_instancing::prelude::{
+    AsBatch, ForceBatchGroup, IndirectRenderingPlugin, InstancedMaterialPlugin, MaterialInstanced,
+    MeshInstance, MeshInstanceBundle,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+/// Column of the instanced quad behind the regular transparent quad
+const BEHIND_COLUMN: usize = 16;
+
+/// Column of the instanced quad in front of the regular transparent quad
+const IN_FRONT_COLUMN: usize = 48;
+
+/// Unlit half-transparent red material
+#[derive(Debug, Default, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "d2a7f5c1-93b4-4e86-8c1d-5b0e7a3f6c29"]
+struct FlatBlendMaterial {}
+
+impl From<&FlatBlendMaterial> for () {
+    fn from(_: &FlatBlendMaterial) -> Self {}
+}
+
+impl AsBatch for FlatBlendMaterial {
+    type BatchKey = ();
+}
+
+impl MaterialInstanced for FlatBlendMaterial {
+    type Instance = MeshInstance;
+
+    fn vertex_shader(_: &AssetServer) -> ShaderRef {
+        "shader/flat_blend.wgsl".into()
+    }
+
+    fn fragment_shader(_: &AssetServer) -> ShaderRef {
+        "shader/flat_blend.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+// Test that transparent instanced batches interleave with regular transparent meshes,
+// by drawing one batch behind a blue quad and another in front of it.
+// The batch in front also holds an instance far behind the quad, so it only
+// sorts correctly when queued at the distance of its nearest instance
+#[test]
+fn transparent_interleave() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(InstancedMaterialPlugin::<FlatBlendMaterial>::default());
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_blend_order);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<FlatBlendMaterial>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    // Regular transparent quad spanning both columns
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Quad::new(Vec2::new(3.0, 1.0)).into()),
+        material: standard_materials.add(StandardMaterial {
+            base_color: Color::rgba(0.0, 0.0, 1.0, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        ..default()
+    });
+
+    let mesh = meshes.add(Quad::new(Vec2::splat(0.5)).into());
+    let material = materials.add(FlatBlendMaterial {});
+
+    let column_x =
+        |column: usize| (column as f32 + 0.5) / TARGET_SIZE as f32 * VIEW_WIDTH - VIEW_WIDTH * 0.5;
+
+    for (position, group) in [
+        // Batch behind the quad
+        (Vec3::new(column_x(BEHIND_COLUMN), 0.0, -1.0), 0),
+        // Batch in front of the quad, with a far instance above it
+        (Vec3::new(column_x(IN_FRONT_COLUMN), 0.0, 1.0), 1),
+        (Vec3::new(0.0, 1.5, -8.0), 1),
+    ] {
+        commands.spawn((
+            MeshInstanceBundle::<FlatBlendMaterial> {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                spatial_bundle: SpatialBundle {
+                    transform: Transform::from_translation(position),
+                    ..default()
+                },
+            },
+            ForceBatchGroup(group),
+        ));
+    }
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        camera_3d: Camera3d {
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// Red and blue channels of the pixel at `column`
+fn red_blue(row: &[u8], column: usize) -> (u8, u8) {
+    (row[column * 4], row[column * 4 + 2])
+}
+
+fn check_blend_order(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let row = readback.middle_row();
+    let pixels = row
+        .as_ref()
+        .map(|row| (red_blue(row, BEHIND_COLUMN), red_blue(row, IN_FRONT_COLUMN)));
+
+    // Whichever color is blended last dominates its pixel
+    let ordered = pixels.map_or(false, |((behind_r, behind_b), (front_r, front_b))| {
+        behind_r > 0 && behind_b > behind_r && front_b > 0 && front_r > front_b
+    });
+
+    if ordered {
+        info!(
+            "Instanced batches interleaved with the transparent quad after {} frames: {pixels:?}",
+            *frame
+        );
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!(
+            "Expected blue over red behind the quad and red over blue in front of it, \
+            got (red, blue) {pixels:?}"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    math::{Vec2, Vec3},
+    prelude::{
+        default, info, shape::Quad, Assets, Camera, Camera3dBundle, Commands, EventWriter, Handle,
+        Image, Local, Mesh, OrthographicProjection, Res, ResMut, SpatialBundle, Transform,
+    },
+    render::{
+        camera::{Projection, RenderTarget, ScalingMode},
+        mesh::MeshVertexAttribute,
+        render_resource::VertexFormat,
+    },
+};
+
+use bevy_instancing::prelude::{
+    BasicMaterial, BasicMaterialPlugin, IndirectRenderingPlugin, MeshInstanceBundle,
+};
+
+use common::readback::{add_target, readback_app, ImageReadback, MAX_FRAMES, TARGET_SIZE};
+
+/// Width of the orthographic view in world units
+const VIEW_WIDTH: f32 = 4.0;
+
+/// Column of the quad with only the standard attributes
+const STANDARD_COLUMN: usize = 16;
+
+/// Column of the quad with an extra interleaved attribute
+const CUSTOM_COLUMN: usize = 48;
+
+/// Color output by the default instanced fragment shader
+const EXPECTED_PIXEL: [u8; 4] = [255, 0, 255, 255];
+
+/// Extra per-vertex attribute, unused by the shader but interleaved into the vertex buffer
+const ATTRIBUTE_CUSTOM: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_Custom", 2147520913, VertexFormat::Float32x4);
+
+// Test that meshes with different interleaved vertex layouts render correctly side by side,
+// by drawing a quad with an extra custom attribute next to a standard one.
+// Their vertex strides differ, so they can't share a vertex buffer
+#[test]
+fn vertex_layout() {
+    let mut app = if let Some(app) = readback_app() {
+        app
+    } else {
+        return;
+    };
+
+    app.add_plugin(IndirectRenderingPlugin)
+        .add_plugin(BasicMaterialPlugin);
+
+    app.add_startup_system(setup_instancing);
+    app.add_system(check_pixels);
+
+    app.run()
+}
+
+fn setup_instancing(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    let target = add_target(&mut images, &mut commands);
+
+    let mesh_standard: Mesh = Quad::new(Vec2::splat(0.5)).into();
+
+    let mut mesh_custom = mesh_standard.clone();
+    mesh_custom.insert_attribute(ATTRIBUTE_CUSTOM, vec![[1.0f32, 2.0, 3.0, 4.0]; 4]);
+
+    let column_x =
+        |column: usize| (column as f32 + 0.5) / TARGET_SIZE as f32 * VIEW_WIDTH - VIEW_WIDTH * 0.5;
+
+    for (mesh, column) in [
+        (mesh_standard, STANDARD_COLUMN),
+        (mesh_custom, CUSTOM_COLUMN),
+    ] {
+        commands.spawn(MeshInstanceBundle::<BasicMaterial> {
+            mesh: meshes.add(mesh),
+            material: Handle::<BasicMaterial>::default(),
+            spatial_bundle: SpatialBundle {
+                transform: Transform::from_xyz(column_x(column), 0.0, 0.0),
+                ..default()
+            },
+        });
+    }
+
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target),
+            ..default()
+        },
+        projection: Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+            ..default()
+        }),
+        tonemapping: Tonemapping::Disabled,
+        transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn check_pixels(
+    readback: Res<ImageReadback>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+
+    let row = readback.middle_row();
+    let pixels = row.as_ref().map(|row| {
+        [STANDARD_COLUMN, CUSTOM_COLUMN].map(|column| {
+            let pixel = &row[column * 4..column * 4 + 4];
+            [pixel[0], pixel[1], pixel[2], pixel[3]]
+        })
+    });
+
+    if pixels == Some([EXPECTED_PIXEL; 2]) {
+        info!("Both vertex layouts rendered after {} frames", *frame);
+        exit.send(AppExit);
+    } else if *frame > MAX_FRAMES {
+        panic!("Expected both quads to output {EXPECTED_PIXEL:?}, got {pixels:?}");
+    }
+}